@@ -0,0 +1,47 @@
+--
+-- Moves avatars from `users.mini_pfp_file_id` and `users.pfp_file_id` into
+-- `user_pfp_variants`, as the 1x density of the builtin `mini` and `normal` sizes.
+-- Only needed for databases created before avatar variants
+--
+
+USE `neor`;
+
+CREATE TABLE IF NOT EXISTS `user_pfp_variants` (
+  `id` bigint(20) UNSIGNED NOT NULL AUTO_INCREMENT,
+  `user_id` bigint(20) UNSIGNED NOT NULL,
+  `size` varchar(32) NOT NULL,
+  `density` int(10) UNSIGNED NOT NULL,
+  `file_id` bigint(20) UNSIGNED NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `user_id_size_density` (`user_id`,`size`,`density`),
+  KEY `file_id` (`file_id`),
+  CONSTRAINT `user_pfp_variants_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`) ON DELETE CASCADE ON UPDATE CASCADE,
+  CONSTRAINT `user_pfp_variants_ibfk_2` FOREIGN KEY (`file_id`) REFERENCES `files` (`id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+CREATE OR REPLACE VIEW `user_pfps` AS
+  SELECT
+    `user_pfp_variants`.`user_id`,
+    `user_pfp_variants`.`size`,
+    GROUP_CONCAT(CONCAT(`user_pfp_variants`.`density`, ':', `files`.`id`, '.', `files`.`extension`) ORDER BY `user_pfp_variants`.`density`) AS `variants`
+  FROM `user_pfp_variants`
+    JOIN `files` ON `user_pfp_variants`.`file_id` = `files`.`id`
+  GROUP BY `user_pfp_variants`.`user_id`, `user_pfp_variants`.`size`;
+
+INSERT IGNORE INTO `user_pfp_variants` (`user_id`, `size`, `density`, `file_id`)
+  SELECT `id`, 'mini', 1, `mini_pfp_file_id`
+  FROM `users`
+  WHERE `mini_pfp_file_id` IS NOT NULL;
+
+INSERT IGNORE INTO `user_pfp_variants` (`user_id`, `size`, `density`, `file_id`)
+  SELECT `id`, 'normal', 1, `pfp_file_id`
+  FROM `users`
+  WHERE `pfp_file_id` IS NOT NULL;
+
+ALTER TABLE `users`
+  DROP FOREIGN KEY `users_ibfk_1`,
+  DROP FOREIGN KEY `users_ibfk_2`;
+
+ALTER TABLE `users`
+  DROP COLUMN `mini_pfp_file_id`,
+  DROP COLUMN `pfp_file_id`;
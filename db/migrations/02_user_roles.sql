@@ -0,0 +1,50 @@
+--
+-- Moves `users.role` into `user_roles`, giving every user the builtin roles their
+-- `role` set contained. Only needed for databases created before roles were stored
+-- in the database, run after `01_pfp_variants.sql`
+--
+
+USE `neor`;
+
+CREATE TABLE IF NOT EXISTS `roles` (
+  `id` bigint(20) UNSIGNED NOT NULL AUTO_INCREMENT,
+  `name` varchar(64) NOT NULL,
+  `can_post` tinyint(1) NOT NULL DEFAULT 0,
+  `can_comment` tinyint(1) NOT NULL DEFAULT 0,
+  `can_reply` tinyint(1) NOT NULL DEFAULT 0,
+  `can_edit_posts` tinyint(1) NOT NULL DEFAULT 0,
+  `can_edit_comments` tinyint(1) NOT NULL DEFAULT 0,
+  `can_edit_self` tinyint(1) NOT NULL DEFAULT 0,
+  `can_anonymise_posts` tinyint(1) NOT NULL DEFAULT 0,
+  `can_anonymise_comments` tinyint(1) NOT NULL DEFAULT 0,
+  `can_delete_posts` tinyint(1) NOT NULL DEFAULT 0,
+  `can_delete_comments` tinyint(1) NOT NULL DEFAULT 0,
+  `can_admin` tinyint(1) NOT NULL DEFAULT 0,
+  `created_at` datetime NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `name` (`name`)
+) ENGINE=InnoDB AUTO_INCREMENT=6 DEFAULT CHARSET=utf8mb4;
+
+INSERT IGNORE INTO `roles` (`id`, `name`, `can_post`, `can_comment`, `can_reply`, `can_edit_posts`, `can_edit_comments`, `can_edit_self`, `can_anonymise_posts`, `can_anonymise_comments`, `can_delete_posts`, `can_delete_comments`, `can_admin`, `created_at`) VALUES
+(1, 'Admin', 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, NOW()),
+(2, 'Mod', 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, NOW()),
+(3, 'Member', 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, NOW()),
+(4, 'Banned', 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, NOW()),
+(5, 'Unverified', 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, NOW());
+
+CREATE TABLE IF NOT EXISTS `user_roles` (
+  `user_id` bigint(20) UNSIGNED NOT NULL,
+  `role_id` bigint(20) UNSIGNED NOT NULL,
+  PRIMARY KEY (`user_id`,`role_id`),
+  KEY `role_id` (`role_id`),
+  CONSTRAINT `user_roles_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`) ON DELETE CASCADE ON UPDATE CASCADE,
+  CONSTRAINT `user_roles_ibfk_2` FOREIGN KEY (`role_id`) REFERENCES `roles` (`id`) ON DELETE CASCADE ON UPDATE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+INSERT IGNORE INTO `user_roles` (`user_id`, `role_id`)
+  SELECT `users`.`id`, `roles`.`id`
+  FROM `users`
+    JOIN `roles` ON FIND_IN_SET(`roles`.`name`, `users`.`role`);
+
+ALTER TABLE `users`
+  DROP COLUMN `role`;
@@ -0,0 +1,23 @@
+--
+-- Adds soft deletion to `posts` and `comments`, nothing existing counts as deleted.
+-- Only needed for databases created before deleted content was kept,
+-- run after `02_user_roles.sql`
+--
+
+USE `neor`;
+
+ALTER TABLE `posts`
+  ADD COLUMN `deleted_at` datetime DEFAULT NULL AFTER `modified_at`,
+  ADD COLUMN `deleted_by_user_id` bigint(20) UNSIGNED DEFAULT NULL AFTER `deleted_at`,
+  ADD COLUMN `deleted_reason` varchar(512) NOT NULL DEFAULT '' AFTER `deleted_by_user_id`,
+  ADD KEY `deleted_by_user_id` (`deleted_by_user_id`),
+  ADD KEY `deleted_at` (`deleted_at`),
+  ADD CONSTRAINT `posts_ibfk_2` FOREIGN KEY (`deleted_by_user_id`) REFERENCES `users` (`id`) ON DELETE SET NULL ON UPDATE CASCADE;
+
+ALTER TABLE `comments`
+  ADD COLUMN `deleted_at` datetime DEFAULT NULL AFTER `modified_at`,
+  ADD COLUMN `deleted_by_user_id` bigint(20) UNSIGNED DEFAULT NULL AFTER `deleted_at`,
+  ADD COLUMN `deleted_reason` varchar(512) NOT NULL DEFAULT '' AFTER `deleted_by_user_id`,
+  ADD KEY `deleted_by_user_id` (`deleted_by_user_id`),
+  ADD KEY `deleted_at` (`deleted_at`),
+  ADD CONSTRAINT `comments_ibfk_4` FOREIGN KEY (`deleted_by_user_id`) REFERENCES `users` (`id`) ON DELETE SET NULL ON UPDATE CASCADE;
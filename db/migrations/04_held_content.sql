@@ -0,0 +1,23 @@
+--
+-- Adds approval and shadow-bans. Everything existing stays visible: no post is pending,
+-- nothing is shadowed and no user or tag requires approval.
+-- Only needed for databases created before content could be held back,
+-- run after `03_soft_delete.sql`
+--
+
+USE `neor`;
+
+ALTER TABLE `posts`
+  ADD COLUMN `is_pending` tinyint(1) NOT NULL DEFAULT 0 AFTER `deleted_reason`,
+  ADD COLUMN `is_shadowed` tinyint(1) NOT NULL DEFAULT 0 AFTER `is_pending`,
+  ADD KEY `is_pending` (`is_pending`);
+
+ALTER TABLE `comments`
+  ADD COLUMN `is_shadowed` tinyint(1) NOT NULL DEFAULT 0 AFTER `deleted_reason`;
+
+ALTER TABLE `users`
+  ADD COLUMN `is_shadow_banned` tinyint(1) NOT NULL DEFAULT 0 AFTER `joined_at`,
+  ADD COLUMN `requires_approval` tinyint(1) NOT NULL DEFAULT 0 AFTER `is_shadow_banned`;
+
+ALTER TABLE `tags`
+  ADD COLUMN `requires_approval` tinyint(1) NOT NULL DEFAULT 0 AFTER `created_at`;
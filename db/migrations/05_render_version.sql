@@ -0,0 +1,16 @@
+--
+-- Records which renderer version produced `markdown_content`. Existing rows get `0`,
+-- which no configuration renders with, so `neor rerender` or `/admin` renders them all again.
+-- Only needed for databases created before the renderer version was kept,
+-- run after `04_held_content.sql`
+--
+
+USE `neor`;
+
+ALTER TABLE `posts`
+  ADD COLUMN `render_version` int(10) UNSIGNED NOT NULL DEFAULT 0 AFTER `markdown_content`,
+  ADD KEY `render_version` (`render_version`);
+
+ALTER TABLE `comments`
+  ADD COLUMN `render_version` int(10) UNSIGNED NOT NULL DEFAULT 0 AFTER `markdown_content`,
+  ADD KEY `render_version` (`render_version`);
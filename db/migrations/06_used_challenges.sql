@@ -0,0 +1,16 @@
+--
+-- Challenges are no longer stored when they're asked, only the answered ones are
+-- remembered. Only needed for databases created before challenges were signed,
+-- run after `05_render_version.sql`
+--
+
+USE `neor`;
+
+DROP TABLE IF EXISTS `challenges`;
+
+CREATE TABLE IF NOT EXISTS `used_challenges` (
+  `id` varchar(36) NOT NULL,
+  `used_at` datetime NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `used_at` (`used_at`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -25,9 +25,61 @@ USE `neor`;
 
 -- --------------------------------------------------------
 
+--
+-- Table structure for table `api_tokens`
+--
+-- Personal tokens for `/api/v1`, only the SHA-256 of a token is kept.
+-- `scopes` is a comma separated list of `read`, `post`, `comment` and `moderate`,
+-- a token with no `expires_at` never expires
+--
+
+CREATE TABLE IF NOT EXISTS `api_tokens` (
+  `id` bigint(20) UNSIGNED NOT NULL AUTO_INCREMENT,
+  `user_id` bigint(20) UNSIGNED NOT NULL,
+  `name` varchar(64) NOT NULL,
+  `token_hash` char(64) NOT NULL,
+  `scopes` varchar(64) NOT NULL,
+  `created_at` datetime NOT NULL,
+  `expires_at` datetime DEFAULT NULL,
+  `last_used_at` datetime DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `token_hash` (`token_hash`),
+  KEY `user_id` (`user_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+-- --------------------------------------------------------
+
+--
+-- Table structure for table `bans`
+--
+-- `previous_roles` are the role names given back when the ban is lifted,
+-- a ban with no `expires_at` is permanent
+--
+
+CREATE TABLE IF NOT EXISTS `bans` (
+  `id` bigint(20) UNSIGNED NOT NULL AUTO_INCREMENT,
+  `user_id` bigint(20) UNSIGNED NOT NULL,
+  `reason` varchar(512) NOT NULL,
+  `previous_roles` varchar(512) NOT NULL,
+  `banned_by_user_id` bigint(20) UNSIGNED DEFAULT NULL,
+  `banned_at` datetime NOT NULL,
+  `expires_at` datetime DEFAULT NULL,
+  `lifted_by_user_id` bigint(20) UNSIGNED DEFAULT NULL,
+  `lifted_at` datetime DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  KEY `user_id_lifted_at` (`user_id`,`lifted_at`),
+  KEY `banned_by_user_id` (`banned_by_user_id`),
+  KEY `lifted_by_user_id` (`lifted_by_user_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+-- --------------------------------------------------------
+
 --
 -- Table structure for table `comments`
 --
+-- `render_version` is the `render::version` `markdown_content` was rendered with,
+-- `jobs::rerender` renders rows with any other version again from `content`
+--
 
 CREATE TABLE IF NOT EXISTS `comments` (
   `id` bigint(20) UNSIGNED NOT NULL AUTO_INCREMENT,
@@ -35,17 +87,71 @@ CREATE TABLE IF NOT EXISTS `comments` (
   `reply_to_comment_id` bigint(20) UNSIGNED DEFAULT NULL,
   `content` text NOT NULL,
   `markdown_content` text NOT NULL,
+  `render_version` int(10) UNSIGNED NOT NULL DEFAULT 0,
   `posted_by_user_id` bigint(20) UNSIGNED DEFAULT NULL,
   `posted_at` datetime NOT NULL,
   `modified_at` datetime DEFAULT NULL,
+  `deleted_at` datetime DEFAULT NULL,
+  `deleted_by_user_id` bigint(20) UNSIGNED DEFAULT NULL,
+  `deleted_reason` varchar(512) NOT NULL DEFAULT '',
+  `is_shadowed` tinyint(1) NOT NULL DEFAULT 0,
   PRIMARY KEY (`id`),
   KEY `post_id` (`post_id`),
   KEY `reply_to_comment_id` (`reply_to_comment_id`),
-  KEY `posted_by_user_id` (`posted_by_user_id`)
+  KEY `posted_by_user_id` (`posted_by_user_id`),
+  KEY `deleted_by_user_id` (`deleted_by_user_id`),
+  KEY `deleted_at` (`deleted_at`),
+  KEY `render_version` (`render_version`)
 ) ENGINE=InnoDB AUTO_INCREMENT=79 DEFAULT CHARSET=utf8mb4;
 
 -- --------------------------------------------------------
 
+--
+-- Table structure for table `comment_revisions`
+--
+-- Every version of an edited comment, the first row is how it was before the first edit.
+-- `edited_by_user_id` is unknown for versions from before revisions were kept
+--
+
+CREATE TABLE IF NOT EXISTS `comment_revisions` (
+  `id` bigint(20) UNSIGNED NOT NULL AUTO_INCREMENT,
+  `comment_id` bigint(20) UNSIGNED NOT NULL,
+  `content` text NOT NULL,
+  `edited_by_user_id` bigint(20) UNSIGNED DEFAULT NULL,
+  `created_at` datetime NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `comment_id` (`comment_id`),
+  KEY `edited_by_user_id` (`edited_by_user_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+-- --------------------------------------------------------
+
+--
+-- Table structure for table `drafts`
+--
+-- Unfinished posts, saved as they're written. `tags` is kept as typed and only
+-- checked on publishing. With `publish_at` set `jobs::scheduled_posts` publishes the draft
+-- then, if that fails it's unscheduled and `last_error` says why
+--
+
+CREATE TABLE IF NOT EXISTS `drafts` (
+  `id` bigint(20) UNSIGNED NOT NULL AUTO_INCREMENT,
+  `user_id` bigint(20) UNSIGNED NOT NULL,
+  `title` varchar(256) NOT NULL DEFAULT '',
+  `description` varchar(512) NOT NULL DEFAULT '',
+  `tags` text NOT NULL,
+  `content` text NOT NULL,
+  `publish_at` datetime DEFAULT NULL,
+  `last_error` varchar(512) DEFAULT NULL,
+  `created_at` datetime NOT NULL,
+  `updated_at` datetime NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `user_id` (`user_id`),
+  KEY `publish_at` (`publish_at`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+-- --------------------------------------------------------
+
 --
 -- Table structure for table `files`
 --
@@ -63,9 +169,34 @@ CREATE TABLE IF NOT EXISTS `files` (
 
 -- --------------------------------------------------------
 
+--
+-- Table structure for table `mod_actions`
+--
+
+CREATE TABLE IF NOT EXISTS `mod_actions` (
+  `id` bigint(20) UNSIGNED NOT NULL AUTO_INCREMENT,
+  `actor_user_id` bigint(20) UNSIGNED DEFAULT NULL,
+  `action` varchar(32) NOT NULL,
+  `target_type` varchar(16) NOT NULL,
+  `target_id` bigint(20) UNSIGNED NOT NULL,
+  `target_label` varchar(256) NOT NULL,
+  `reason` varchar(512) NOT NULL,
+  `details` varchar(512) NOT NULL,
+  `created_at` datetime NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `actor_user_id` (`actor_user_id`),
+  KEY `action` (`action`),
+  KEY `target_type_target_id` (`target_type`,`target_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+-- --------------------------------------------------------
+
 --
 -- Table structure for table `posts`
 --
+-- `render_version` is the `render::version` `markdown_content` was rendered with,
+-- `jobs::rerender` renders rows with any other version again from `content`
+--
 
 CREATE TABLE IF NOT EXISTS `posts` (
   `id` bigint(20) UNSIGNED NOT NULL AUTO_INCREMENT,
@@ -73,15 +204,47 @@ CREATE TABLE IF NOT EXISTS `posts` (
   `description` varchar(512) NOT NULL,
   `content` text NOT NULL,
   `markdown_content` text NOT NULL,
+  `render_version` int(10) UNSIGNED NOT NULL DEFAULT 0,
   `posted_by_user_id` bigint(20) UNSIGNED DEFAULT NULL,
   `posted_at` datetime NOT NULL,
   `modified_at` datetime DEFAULT NULL,
+  `deleted_at` datetime DEFAULT NULL,
+  `deleted_by_user_id` bigint(20) UNSIGNED DEFAULT NULL,
+  `deleted_reason` varchar(512) NOT NULL DEFAULT '',
+  `is_pending` tinyint(1) NOT NULL DEFAULT 0,
+  `is_shadowed` tinyint(1) NOT NULL DEFAULT 0,
   PRIMARY KEY (`id`),
-  KEY `posted_by_user_id` (`posted_by_user_id`)
+  KEY `posted_by_user_id` (`posted_by_user_id`),
+  KEY `deleted_by_user_id` (`deleted_by_user_id`),
+  KEY `deleted_at` (`deleted_at`),
+  KEY `is_pending` (`is_pending`),
+  KEY `render_version` (`render_version`)
 ) ENGINE=InnoDB AUTO_INCREMENT=3831 DEFAULT CHARSET=utf8mb4;
 
 -- --------------------------------------------------------
 
+--
+-- Table structure for table `post_revisions`
+--
+-- Every version of an edited post, the first row is how it was before the first edit.
+-- `edited_by_user_id` is unknown for versions from before revisions were kept
+--
+
+CREATE TABLE IF NOT EXISTS `post_revisions` (
+  `id` bigint(20) UNSIGNED NOT NULL AUTO_INCREMENT,
+  `post_id` bigint(20) UNSIGNED NOT NULL,
+  `title` varchar(256) NOT NULL,
+  `description` varchar(512) NOT NULL,
+  `content` text NOT NULL,
+  `edited_by_user_id` bigint(20) UNSIGNED DEFAULT NULL,
+  `created_at` datetime NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `post_id` (`post_id`),
+  KEY `edited_by_user_id` (`edited_by_user_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+-- --------------------------------------------------------
+
 --
 -- Table structure for table `post_tags`
 --
@@ -97,6 +260,69 @@ CREATE TABLE IF NOT EXISTS `post_tags` (
 
 -- --------------------------------------------------------
 
+--
+-- Table structure for table `reports`
+--
+
+CREATE TABLE IF NOT EXISTS `reports` (
+  `id` bigint(20) UNSIGNED NOT NULL AUTO_INCREMENT,
+  `target_type` varchar(16) NOT NULL,
+  `target_id` bigint(20) UNSIGNED NOT NULL,
+  `category` varchar(32) NOT NULL,
+  `details` varchar(512) NOT NULL,
+  `reported_by_user_id` bigint(20) UNSIGNED DEFAULT NULL,
+  `reported_at` datetime NOT NULL,
+  `resolution` varchar(16) DEFAULT NULL,
+  `resolved_by_user_id` bigint(20) UNSIGNED DEFAULT NULL,
+  `resolved_at` datetime DEFAULT NULL,
+  `is_automatic` tinyint(1) NOT NULL DEFAULT 0,
+  PRIMARY KEY (`id`),
+  KEY `target_type_target_id` (`target_type`,`target_id`),
+  KEY `reported_by_user_id` (`reported_by_user_id`),
+  KEY `resolved_by_user_id` (`resolved_by_user_id`),
+  KEY `resolved_at` (`resolved_at`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+-- --------------------------------------------------------
+
+--
+-- Table structure for table `roles`
+--
+
+CREATE TABLE IF NOT EXISTS `roles` (
+  `id` bigint(20) UNSIGNED NOT NULL AUTO_INCREMENT,
+  `name` varchar(64) NOT NULL,
+  `can_post` tinyint(1) NOT NULL DEFAULT 0,
+  `can_comment` tinyint(1) NOT NULL DEFAULT 0,
+  `can_reply` tinyint(1) NOT NULL DEFAULT 0,
+  `can_edit_posts` tinyint(1) NOT NULL DEFAULT 0,
+  `can_edit_comments` tinyint(1) NOT NULL DEFAULT 0,
+  `can_edit_self` tinyint(1) NOT NULL DEFAULT 0,
+  `can_anonymise_posts` tinyint(1) NOT NULL DEFAULT 0,
+  `can_anonymise_comments` tinyint(1) NOT NULL DEFAULT 0,
+  `can_delete_posts` tinyint(1) NOT NULL DEFAULT 0,
+  `can_delete_comments` tinyint(1) NOT NULL DEFAULT 0,
+  `can_admin` tinyint(1) NOT NULL DEFAULT 0,
+  `created_at` datetime NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `name` (`name`)
+) ENGINE=InnoDB AUTO_INCREMENT=6 DEFAULT CHARSET=utf8mb4;
+
+--
+-- Dumping data for table `roles`
+--
+-- Builtin roles, the code refers to them by name
+--
+
+INSERT IGNORE INTO `roles` (`id`, `name`, `can_post`, `can_comment`, `can_reply`, `can_edit_posts`, `can_edit_comments`, `can_edit_self`, `can_anonymise_posts`, `can_anonymise_comments`, `can_delete_posts`, `can_delete_comments`, `can_admin`, `created_at`) VALUES
+(1, 'Admin', 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, NOW()),
+(2, 'Mod', 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, NOW()),
+(3, 'Member', 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, NOW()),
+(4, 'Banned', 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, NOW()),
+(5, 'Unverified', 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, NOW());
+
+-- --------------------------------------------------------
+
 --
 -- Table structure for table `tags`
 --
@@ -106,6 +332,7 @@ CREATE TABLE IF NOT EXISTS `tags` (
   `name` varchar(128) NOT NULL,
   `created_by_user_id` bigint(20) UNSIGNED DEFAULT NULL,
   `created_at` datetime NOT NULL,
+  `requires_approval` tinyint(1) NOT NULL DEFAULT 0,
   PRIMARY KEY (`id`),
   UNIQUE KEY `name` (`name`),
   KEY `created_by_user_id` (`created_by_user_id`)
@@ -122,34 +349,170 @@ CREATE TABLE IF NOT EXISTS `users` (
   `username` varchar(128) NOT NULL,
   `email` varchar(320) NOT NULL,
   `password` varchar(255) NOT NULL,
-  `role` set('Admin','Mod','Member','Banned','Unverified') NOT NULL,
   `session` varchar(36) NOT NULL,
   `code` varchar(6) DEFAULT NULL,
-  `mini_pfp_file_id` bigint(20) UNSIGNED DEFAULT NULL,
-  `pfp_file_id` bigint(20) UNSIGNED DEFAULT NULL,
   `name` varchar(256) NOT NULL,
   `description` varchar(512) NOT NULL,
   `joined_at` datetime NOT NULL,
+  `is_shadow_banned` tinyint(1) NOT NULL DEFAULT 0,
+  `requires_approval` tinyint(1) NOT NULL DEFAULT 0,
   PRIMARY KEY (`id`),
   UNIQUE KEY `username` (`username`),
   UNIQUE KEY `email` (`email`),
   UNIQUE KEY `session` (`session`),
-  UNIQUE KEY `code` (`code`),
-  KEY `pfp_file_id` (`pfp_file_id`),
-  KEY `mini_pfp_file_id` (`mini_pfp_file_id`)
+  UNIQUE KEY `code` (`code`)
 ) ENGINE=InnoDB AUTO_INCREMENT=9 DEFAULT CHARSET=utf8mb4;
 
+-- --------------------------------------------------------
+
+--
+-- Table structure for table `user_roles`
+--
+
+CREATE TABLE IF NOT EXISTS `user_roles` (
+  `user_id` bigint(20) UNSIGNED NOT NULL,
+  `role_id` bigint(20) UNSIGNED NOT NULL,
+  PRIMARY KEY (`user_id`,`role_id`),
+  KEY `role_id` (`role_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+-- --------------------------------------------------------
+
+--
+-- Table structure for table `user_pfp_variants`
+--
+
+CREATE TABLE IF NOT EXISTS `user_pfp_variants` (
+  `id` bigint(20) UNSIGNED NOT NULL AUTO_INCREMENT,
+  `user_id` bigint(20) UNSIGNED NOT NULL,
+  `size` varchar(32) NOT NULL,
+  `density` int(10) UNSIGNED NOT NULL,
+  `file_id` bigint(20) UNSIGNED NOT NULL,
+  PRIMARY KEY (`id`),
+  UNIQUE KEY `user_id_size_density` (`user_id`,`size`,`density`),
+  KEY `file_id` (`file_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+-- --------------------------------------------------------
+
+--
+-- Table structure for table `used_challenges`
+--
+-- Nonces of the challenges that were answered, kept until they'd have expired
+--
+
+CREATE TABLE IF NOT EXISTS `used_challenges` (
+  `id` varchar(36) NOT NULL,
+  `used_at` datetime NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `used_at` (`used_at`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+-- --------------------------------------------------------
+
+--
+-- Table structure for table `webhooks`
+--
+-- Endpoints that get a signed JSON `POST` for each of their `events`,
+-- a comma separated list like `post.created,user.banned`.
+-- With a `tag` only post and comment events on posts with that tag are sent
+--
+
+CREATE TABLE IF NOT EXISTS `webhooks` (
+  `id` bigint(20) UNSIGNED NOT NULL AUTO_INCREMENT,
+  `url` varchar(512) NOT NULL,
+  `secret` varchar(64) NOT NULL,
+  `events` varchar(128) NOT NULL,
+  `tag` varchar(64) DEFAULT NULL,
+  `is_active` tinyint(1) NOT NULL DEFAULT 1,
+  `created_by_user_id` bigint(20) UNSIGNED DEFAULT NULL,
+  `created_at` datetime NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `created_by_user_id` (`created_by_user_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+-- --------------------------------------------------------
+
+--
+-- Table structure for table `webhook_deliveries`
+--
+-- One row per event and webhook, doubling as the queue of the delivery worker
+-- and the delivery log. `status` is `pending` until it's `succeeded` or out of attempts
+--
+
+CREATE TABLE IF NOT EXISTS `webhook_deliveries` (
+  `id` bigint(20) UNSIGNED NOT NULL AUTO_INCREMENT,
+  `webhook_id` bigint(20) UNSIGNED NOT NULL,
+  `event` varchar(32) NOT NULL,
+  `payload` mediumtext NOT NULL,
+  `status` varchar(16) NOT NULL,
+  `attempt_count` int(10) UNSIGNED NOT NULL DEFAULT 0,
+  `response_status` smallint(5) UNSIGNED DEFAULT NULL,
+  `last_error` varchar(512) DEFAULT NULL,
+  `created_at` datetime NOT NULL,
+  `next_attempt_at` datetime NOT NULL,
+  `finished_at` datetime DEFAULT NULL,
+  PRIMARY KEY (`id`),
+  KEY `webhook_id` (`webhook_id`),
+  KEY `status_next_attempt_at` (`status`,`next_attempt_at`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
+
+-- --------------------------------------------------------
+
+--
+-- View structure for view `user_pfps`
+--
+-- Aggregates all densities of a size into `density:filename` pairs
+--
+
+CREATE OR REPLACE VIEW `user_pfps` AS
+  SELECT
+    `user_pfp_variants`.`user_id`,
+    `user_pfp_variants`.`size`,
+    GROUP_CONCAT(CONCAT(`user_pfp_variants`.`density`, ':', `files`.`id`, '.', `files`.`extension`) ORDER BY `user_pfp_variants`.`density`) AS `variants`
+  FROM `user_pfp_variants`
+    JOIN `files` ON `user_pfp_variants`.`file_id` = `files`.`id`
+  GROUP BY `user_pfp_variants`.`user_id`, `user_pfp_variants`.`size`;
+
 --
 -- Constraints for dumped tables
 --
 
+--
+-- Constraints for table `api_tokens`
+--
+ALTER TABLE `api_tokens`
+  ADD CONSTRAINT `api_tokens_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`) ON DELETE CASCADE ON UPDATE CASCADE;
+
+--
+-- Constraints for table `bans`
+--
+ALTER TABLE `bans`
+  ADD CONSTRAINT `bans_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`) ON DELETE CASCADE ON UPDATE CASCADE,
+  ADD CONSTRAINT `bans_ibfk_2` FOREIGN KEY (`banned_by_user_id`) REFERENCES `users` (`id`) ON DELETE SET NULL ON UPDATE CASCADE,
+  ADD CONSTRAINT `bans_ibfk_3` FOREIGN KEY (`lifted_by_user_id`) REFERENCES `users` (`id`) ON DELETE SET NULL ON UPDATE CASCADE;
+
 --
 -- Constraints for table `comments`
 --
 ALTER TABLE `comments`
   ADD CONSTRAINT `comments_ibfk_1` FOREIGN KEY (`posted_by_user_id`) REFERENCES `users` (`id`) ON DELETE SET NULL ON UPDATE CASCADE,
   ADD CONSTRAINT `comments_ibfk_2` FOREIGN KEY (`post_id`) REFERENCES `posts` (`id`) ON DELETE CASCADE ON UPDATE CASCADE,
-  ADD CONSTRAINT `comments_ibfk_3` FOREIGN KEY (`reply_to_comment_id`) REFERENCES `comments` (`id`) ON DELETE SET NULL ON UPDATE CASCADE;
+  ADD CONSTRAINT `comments_ibfk_3` FOREIGN KEY (`reply_to_comment_id`) REFERENCES `comments` (`id`) ON DELETE SET NULL ON UPDATE CASCADE,
+  ADD CONSTRAINT `comments_ibfk_4` FOREIGN KEY (`deleted_by_user_id`) REFERENCES `users` (`id`) ON DELETE SET NULL ON UPDATE CASCADE;
+
+--
+-- Constraints for table `comment_revisions`
+--
+ALTER TABLE `comment_revisions`
+  ADD CONSTRAINT `comment_revisions_ibfk_1` FOREIGN KEY (`comment_id`) REFERENCES `comments` (`id`) ON DELETE CASCADE ON UPDATE CASCADE,
+  ADD CONSTRAINT `comment_revisions_ibfk_2` FOREIGN KEY (`edited_by_user_id`) REFERENCES `users` (`id`) ON DELETE SET NULL ON UPDATE CASCADE;
+
+--
+-- Constraints for table `drafts`
+--
+ALTER TABLE `drafts`
+  ADD CONSTRAINT `drafts_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`) ON DELETE CASCADE ON UPDATE CASCADE;
 
 --
 -- Constraints for table `files`
@@ -157,11 +520,25 @@ ALTER TABLE `comments`
 ALTER TABLE `files`
   ADD CONSTRAINT `files_ibfk_1` FOREIGN KEY (`uploaded_by_user_id`) REFERENCES `users` (`id`) ON DELETE CASCADE ON UPDATE CASCADE;
 
+--
+-- Constraints for table `mod_actions`
+--
+ALTER TABLE `mod_actions`
+  ADD CONSTRAINT `mod_actions_ibfk_1` FOREIGN KEY (`actor_user_id`) REFERENCES `users` (`id`) ON DELETE SET NULL ON UPDATE CASCADE;
+
 --
 -- Constraints for table `posts`
 --
 ALTER TABLE `posts`
-  ADD CONSTRAINT `posts_ibfk_1` FOREIGN KEY (`posted_by_user_id`) REFERENCES `users` (`id`) ON DELETE SET NULL ON UPDATE CASCADE;
+  ADD CONSTRAINT `posts_ibfk_1` FOREIGN KEY (`posted_by_user_id`) REFERENCES `users` (`id`) ON DELETE SET NULL ON UPDATE CASCADE,
+  ADD CONSTRAINT `posts_ibfk_2` FOREIGN KEY (`deleted_by_user_id`) REFERENCES `users` (`id`) ON DELETE SET NULL ON UPDATE CASCADE;
+
+--
+-- Constraints for table `post_revisions`
+--
+ALTER TABLE `post_revisions`
+  ADD CONSTRAINT `post_revisions_ibfk_1` FOREIGN KEY (`post_id`) REFERENCES `posts` (`id`) ON DELETE CASCADE ON UPDATE CASCADE,
+  ADD CONSTRAINT `post_revisions_ibfk_2` FOREIGN KEY (`edited_by_user_id`) REFERENCES `users` (`id`) ON DELETE SET NULL ON UPDATE CASCADE;
 
 --
 -- Constraints for table `post_tags`
@@ -170,6 +547,13 @@ ALTER TABLE `post_tags`
   ADD CONSTRAINT `post_tags_ibfk_1` FOREIGN KEY (`tag_id`) REFERENCES `tags` (`id`) ON DELETE CASCADE ON UPDATE CASCADE,
   ADD CONSTRAINT `post_tags_ibfk_2` FOREIGN KEY (`post_id`) REFERENCES `posts` (`id`) ON DELETE CASCADE ON UPDATE CASCADE;
 
+--
+-- Constraints for table `reports`
+--
+ALTER TABLE `reports`
+  ADD CONSTRAINT `reports_ibfk_1` FOREIGN KEY (`reported_by_user_id`) REFERENCES `users` (`id`) ON DELETE SET NULL ON UPDATE CASCADE,
+  ADD CONSTRAINT `reports_ibfk_2` FOREIGN KEY (`resolved_by_user_id`) REFERENCES `users` (`id`) ON DELETE SET NULL ON UPDATE CASCADE;
+
 --
 -- Constraints for table `tags`
 --
@@ -177,11 +561,30 @@ ALTER TABLE `tags`
   ADD CONSTRAINT `tags_ibfk_1` FOREIGN KEY (`created_by_user_id`) REFERENCES `users` (`id`) ON DELETE SET NULL ON UPDATE CASCADE;
 
 --
--- Constraints for table `users`
+-- Constraints for table `user_roles`
+--
+ALTER TABLE `user_roles`
+  ADD CONSTRAINT `user_roles_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`) ON DELETE CASCADE ON UPDATE CASCADE,
+  ADD CONSTRAINT `user_roles_ibfk_2` FOREIGN KEY (`role_id`) REFERENCES `roles` (`id`) ON DELETE CASCADE ON UPDATE CASCADE;
+
+--
+-- Constraints for table `user_pfp_variants`
+--
+ALTER TABLE `user_pfp_variants`
+  ADD CONSTRAINT `user_pfp_variants_ibfk_1` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`) ON DELETE CASCADE ON UPDATE CASCADE,
+  ADD CONSTRAINT `user_pfp_variants_ibfk_2` FOREIGN KEY (`file_id`) REFERENCES `files` (`id`) ON DELETE CASCADE ON UPDATE CASCADE;
+
+--
+-- Constraints for table `webhooks`
+--
+ALTER TABLE `webhooks`
+  ADD CONSTRAINT `webhooks_ibfk_1` FOREIGN KEY (`created_by_user_id`) REFERENCES `users` (`id`) ON DELETE SET NULL ON UPDATE CASCADE;
+
+--
+-- Constraints for table `webhook_deliveries`
 --
-ALTER TABLE `users`
-  ADD CONSTRAINT `users_ibfk_1` FOREIGN KEY (`mini_pfp_file_id`) REFERENCES `files` (`id`) ON DELETE SET NULL ON UPDATE CASCADE,
-  ADD CONSTRAINT `users_ibfk_2` FOREIGN KEY (`pfp_file_id`) REFERENCES `files` (`id`) ON DELETE SET NULL ON UPDATE CASCADE;
+ALTER TABLE `webhook_deliveries`
+  ADD CONSTRAINT `webhook_deliveries_ibfk_1` FOREIGN KEY (`webhook_id`) REFERENCES `webhooks` (`id`) ON DELETE CASCADE ON UPDATE CASCADE;
 COMMIT;
 
 /*!40101 SET CHARACTER_SET_CLIENT=@OLD_CHARACTER_SET_CLIENT */;
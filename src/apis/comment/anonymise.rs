@@ -70,7 +70,7 @@ async fn service_inner(
     user: User,
     request: Request,
 ) -> Result<HttpResponse, ErrorKind> {
-    let comment = fetch_comment_by_id(&state.db_pool, request.comment_id, &user)
+    let comment = fetch_comment_by_id(&state.db_pool, request.comment_id, &user, &state.config)
         .await?
         .ok_or(ErrorKind::UserCantAnonymiseComment)?;
 
@@ -86,7 +86,7 @@ async fn service_inner(
         return Err(ErrorKind::InvalidConfirmation);
     }
 
-    anonymise_comment(&state.db_pool, request.comment_id, &user).await?;
+    anonymise_comment(&state.db_pool, request.comment_id).await?;
 
     let location = format!(
         "/post/{}?start_id={}#{}",
@@ -100,11 +100,7 @@ async fn service_inner(
     Ok(response)
 }
 
-async fn anonymise_comment(
-    db_pool: &MySqlPool,
-    post_id: Id,
-    user: &User,
-) -> sqlx::Result<MySqlQueryResult> {
+async fn anonymise_comment(db_pool: &MySqlPool, post_id: Id) -> sqlx::Result<MySqlQueryResult> {
     sqlx::query!(
         "
         UPDATE comments
@@ -112,10 +108,8 @@ async fn anonymise_comment(
             posted_by_user_id = NULL
         WHERE
             id = ?
-            AND posted_by_user_id = ?
         ",
-        post_id,
-        user.id
+        post_id
     )
     .execute(db_pool)
     .await
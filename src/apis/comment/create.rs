@@ -1,7 +1,16 @@
+use crate::config::MarkdownConfig;
+use crate::db::{check_comment_spam, fetch_account_age_days, insert_automatic_report};
+use crate::flash::Flash;
+use crate::policy::{self, Action, Resource};
+use crate::rate_limit::{self, Action as RateLimitAction, Requester};
+use crate::render;
 use crate::session::auth;
 use crate::session::User;
+use crate::spam::{Action as SpamAction, Rule as SpamRule};
 use crate::types::comment::content::Content;
 use crate::types::id::Id;
+use crate::types::mod_action::TargetType;
+use crate::webhook;
 use crate::State;
 use actix_web::{
     http::{header, StatusCode},
@@ -10,7 +19,9 @@ use actix_web::{
     HttpRequest, HttpResponse, ResponseError,
 };
 use serde::Deserialize;
-use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use sqlx::mysql::{MySql, MySqlPool, MySqlQueryResult};
+use sqlx::Transaction;
+use std::net::IpAddr;
 use thiserror::Error;
 
 #[derive(Debug, Error, Copy, Clone)]
@@ -23,14 +34,20 @@ pub struct Error {
 
 #[derive(Debug, Error, Copy, Clone)]
 pub enum ErrorKind {
+    #[error("You are banned")]
+    UserIsBanned,
     #[error("You are not allowed to comment")]
     UserCantComment,
+    #[error("You are commenting too often, try again later")]
+    RateLimited,
     #[error("Reply and original comment must be on the same post")]
     InvalidReply,
     #[error("Post not found")]
     PostNotFound,
     #[error("Invalid content")]
     InvalidContent,
+    #[error("{0}")]
+    Spam(SpamRule),
     #[error("Server error")]
     Server,
 }
@@ -59,22 +76,68 @@ pub async fn service(
     let post_id = request.post_id;
     let reply_to_comment_id = request.reply_to_comment_id;
 
-    service_inner(state, request, user)
-        .await
-        .map_err(|err| Error::new(err, post_id, reply_to_comment_id))
+    let ip = rate_limit::client_ip(&req, state.config.rate_limit.is_behind_proxy);
+
+    let flash = Flash::new().field("content", &request.content);
+
+    let comment_id = match service_inner(state.clone(), request, user, ip).await {
+        Ok(comment_id) => comment_id,
+        Err(kind) => {
+            let err = Error::new(kind, post_id, reply_to_comment_id);
+
+            return Ok(state
+                .flash
+                .redirect(&err.as_location(), flash.error(kind.field(), err)));
+        }
+    };
+
+    let location = format!("/post/{post_id}?start_id={comment_id}#{comment_id}");
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, location))
+        .finish();
+
+    Ok(response)
 }
 
-async fn service_inner(
+// Also used by `/api/v1/posts/{id}/comments`, returns the id of the new comment
+pub async fn service_inner(
     state: Data<State>,
     request: Request,
     user: User,
-) -> Result<HttpResponse, ErrorKind> {
-    if !user.role.can_comment() {
+    ip: Option<IpAddr>,
+) -> Result<Id, ErrorKind> {
+    if user.ban.is_some() {
+        return Err(ErrorKind::UserIsBanned);
+    }
+
+    if !policy::can(&user, Action::Create, Resource::Comments, &state.config) {
+        return Err(ErrorKind::UserCantComment);
+    }
+
+    if request.reply_to_comment_id.is_some()
+        && !policy::can(&user, Action::Create, Resource::Replies, &state.config)
+    {
         return Err(ErrorKind::UserCantComment);
     }
 
+    let requester = Requester {
+        user_id: Some(user.id),
+        account_age_days: fetch_account_age_days(&state.db_pool, user.id).await?,
+        ip,
+    };
+
+    if state
+        .rate_limiter
+        .check(RateLimitAction::Comment, &requester)
+        .is_err()
+    {
+        return Err(ErrorKind::RateLimited);
+    }
+
     // Checks that post with id `request.post_id` exists
-    // and that reply and original comment share the same `post_id`
+    // and that reply and original comment share the same `post_id`,
+    // removed posts and comments can't be replied to
     match request.reply_to_comment_id {
         Some(comment_id) => match fetch_comment_post_id(&state.db_pool, comment_id).await? {
             Some(post_id) => {
@@ -95,37 +158,64 @@ async fn service_inner(
 
     let content = Content::parse(request.content).map_err(|_| ErrorKind::InvalidContent)?;
 
+    // Moderators aren't checked
+    let spam_rule = if policy::can(&user, Action::View, Resource::Held, &state.config) {
+        None
+    } else {
+        check_comment_spam(
+            &state.db_pool,
+            &state.spam_filter,
+            &state.config,
+            user.id,
+            content.as_ref(),
+            None,
+        )
+        .await?
+    };
+
+    if let Some(rule) = spam_rule {
+        if state.spam_filter.action == SpamAction::Reject {
+            return Err(ErrorKind::Spam(rule));
+        }
+    }
+
+    let mut tx = state.db_pool.begin().await?;
+
     let comment_insert_result = insert_comment(
-        &state.db_pool,
+        &mut tx,
         request.post_id,
         request.reply_to_comment_id,
         &content,
         &user,
+        &state.config.markdown,
     )
     .await?;
 
     let comment_id = comment_insert_result.last_insert_id();
 
-    let location = format!(
-        "/post/{}?start_id={comment_id}#{comment_id}",
-        request.post_id
-    );
+    // Comments can't be held back, so moderators are told about them instead
+    if let Some(rule) = spam_rule {
+        insert_automatic_report(&mut tx, TargetType::Comment, comment_id, rule).await?;
+    }
 
-    let response = HttpResponse::SeeOther()
-        .append_header((header::LOCATION, location))
-        .finish();
+    // Queued before the commit, a comment that's saved but answered with an error would be
+    // posted twice by a retry
+    webhook::comment_created(&mut tx, &state.config.domain, comment_id).await?;
 
-    Ok(response)
+    tx.commit().await?;
+
+    Ok(comment_id)
 }
 
 async fn insert_comment(
-    db_pool: &MySqlPool,
+    tx: &mut Transaction<'_, MySql>,
     post_id: Id,
     reply_to_comment_id: Option<Id>,
     content: &Content,
     user: &User,
+    markdown_config: &MarkdownConfig,
 ) -> sqlx::Result<MySqlQueryResult> {
-    let markdown_content = markdown::to_html(content.as_ref());
+    let markdown_content = render::markdown(content.as_ref(), markdown_config);
 
     sqlx::query!(
         "
@@ -135,9 +225,11 @@ async fn insert_comment(
             reply_to_comment_id,
             content,
             markdown_content,
+            render_version,
             posted_by_user_id,
             posted_at,
-            modified_at
+            modified_at,
+            is_shadowed
         )
         VALUES
         (
@@ -146,17 +238,21 @@ async fn insert_comment(
             ?,
             ?,
             ?,
+            ?,
             NOW(),
-            NULL
+            NULL,
+            (SELECT is_shadow_banned FROM users WHERE id = ?)
         )
         ",
         post_id,
         reply_to_comment_id,
         content.as_ref(),
         markdown_content,
+        render::version(markdown_config),
+        user.id,
         user.id
     )
-    .execute(db_pool)
+    .execute(&mut *tx)
     .await
 }
 
@@ -170,10 +266,14 @@ async fn fetch_comment_post_id(db_pool: &MySqlPool, comment_id: Id) -> sqlx::Res
         Comment,
         "
         SELECT
-            post_id
+            comments.post_id
         FROM comments
+            JOIN posts ON comments.post_id = posts.id
         WHERE
-            id = ?
+            comments.id = ?
+            AND comments.deleted_at IS NULL
+            AND posts.deleted_at IS NULL
+            AND posts.is_pending = 0
         ",
         comment_id,
     )
@@ -190,6 +290,8 @@ async fn fetch_post_exists(db_pool: &MySqlPool, post_id: Id) -> sqlx::Result<boo
         FROM posts
         WHERE
             id = ?
+            AND deleted_at IS NULL
+            AND is_pending = 0
         ",
         post_id
     )
@@ -204,6 +306,16 @@ impl From<sqlx::Error> for ErrorKind {
     }
 }
 
+impl ErrorKind {
+    // The form field to highlight
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            Self::InvalidContent | Self::Spam(_) => Some("content"),
+            _ => None,
+        }
+    }
+}
+
 impl Error {
     pub fn new(kind: ErrorKind, post_id: Id, reply_to_comment_id: Option<Id>) -> Self {
         Self {
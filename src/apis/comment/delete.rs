@@ -1,7 +1,9 @@
-use crate::db::fetch_comment_by_id;
+use crate::db::{fetch_comment_by_id, insert_mod_action, resolve_reports, soft_delete_comment};
 use crate::session::auth;
 use crate::session::User;
 use crate::types::id::Id;
+use crate::types::mod_action::{reason::Reason, Kind, NewModAction, TargetType};
+use crate::types::report::Resolution;
 use crate::State;
 use actix_web::{
     http::{header, StatusCode},
@@ -10,7 +12,6 @@ use actix_web::{
     HttpRequest, HttpResponse, ResponseError,
 };
 use serde::Deserialize;
-use sqlx::mysql::{MySqlPool, MySqlQueryResult};
 use thiserror::Error;
 
 #[derive(Debug, Error, Copy, Clone)]
@@ -32,6 +33,8 @@ pub enum ErrorKind {
     UserCantDeleteComment,
     #[error("Commenter's username and confirmation string do not match")]
     InvalidConfirmation,
+    #[error("Invalid reason")]
+    InvalidReason,
     #[error("Server error")]
     Server,
 }
@@ -40,6 +43,7 @@ pub enum ErrorKind {
 pub struct Request {
     pub comment_id: Id,
     pub confirm: String,
+    pub reason: Option<String>,
 }
 
 #[post("/api/comment/delete")]
@@ -58,19 +62,28 @@ pub async fn service(
         return Ok(response);
     };
 
-    let post_id = request.comment_id;
+    let comment_id = request.comment_id;
 
-    service_inner(state, user, request)
+    let post_id = service_inner(state, user, request)
         .await
-        .map_err(|err| Error::new(err, post_id))
+        .map_err(|err| Error::new(err, comment_id))?;
+
+    let location = format!("/post/{post_id}?start_id={comment_id}#{comment_id}");
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, location))
+        .finish();
+
+    Ok(response)
 }
 
-async fn service_inner(
+// Also used by `/api/v1/comments/{id}`, returns the id of the post commented on
+pub async fn service_inner(
     state: Data<State>,
     user: User,
     request: Request,
-) -> Result<HttpResponse, ErrorKind> {
-    let comment = fetch_comment_by_id(&state.db_pool, request.comment_id, &user)
+) -> Result<Id, ErrorKind> {
+    let comment = fetch_comment_by_id(&state.db_pool, request.comment_id, &user, &state.config)
         .await?
         .ok_or(ErrorKind::UserCantDeleteComment)?;
 
@@ -78,41 +91,52 @@ async fn service_inner(
         return Err(ErrorKind::UserCantDeleteComment);
     }
 
-    if comment
+    let commenter = comment
         .posted_by
         .as_ref()
         .map(|posted_by| posted_by.username.as_str())
-        .unwrap_or("Anonymous")
-        != request.confirm
-    {
+        .unwrap_or("Anonymous");
+
+    if commenter != request.confirm {
         return Err(ErrorKind::InvalidConfirmation);
     }
 
-    delete_comment(&state.db_pool, request.comment_id).await?;
+    let reason =
+        Reason::parse(request.reason.unwrap_or_default()).map_err(|_| ErrorKind::InvalidReason)?;
 
-    let location = format!(
-        "/post/{}?start_id={}#{}",
-        comment.post_id, request.comment_id, request.comment_id
-    );
+    let target_label = format!("Comment by {commenter} on post {}", comment.post_id);
 
-    let response = HttpResponse::SeeOther()
-        .append_header((header::LOCATION, location))
-        .finish();
+    let mut tx = state.db_pool.begin().await?;
 
-    Ok(response)
-}
+    insert_mod_action(
+        &mut tx,
+        &NewModAction {
+            actor_user_id: user.id,
+            kind: Kind::DeleteComment,
+            target_type: TargetType::Comment,
+            target_id: comment.id,
+            target_label: &target_label,
+            reason: &reason,
+            details: "",
+        },
+    )
+    .await?;
+
+    soft_delete_comment(&mut tx, request.comment_id, user.id, &reason).await?;
 
-async fn delete_comment(db_pool: &MySqlPool, post_id: Id) -> sqlx::Result<MySqlQueryResult> {
-    sqlx::query!(
-        "
-        DELETE FROM comments
-        WHERE
-            id = ?
-        ",
-        post_id,
+    // Deleting is the answer to whatever the item was reported for
+    resolve_reports(
+        &mut tx,
+        TargetType::Comment,
+        request.comment_id,
+        Resolution::Resolved,
+        user.id,
     )
-    .execute(db_pool)
-    .await
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(comment.post_id)
 }
 
 impl From<sqlx::Error> for ErrorKind {
@@ -1,5 +1,11 @@
+use crate::config::MarkdownConfig;
+use crate::db::{check_comment_spam, insert_comment_revision, insert_original_comment_revision};
+use crate::flash::Flash;
+use crate::policy::{self, Action, Authored, Resource};
+use crate::render;
 use crate::session::auth;
 use crate::session::User;
+use crate::spam::Rule as SpamRule;
 use crate::types::comment::content::Content;
 use crate::types::id::Id;
 use crate::State;
@@ -9,8 +15,10 @@ use actix_web::{
     web::{Data, Form},
     HttpRequest, HttpResponse, ResponseError,
 };
+use chrono::NaiveDateTime;
 use serde::Deserialize;
-use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use sqlx::mysql::{MySql, MySqlPool, MySqlQueryResult};
+use sqlx::Transaction;
 use thiserror::Error;
 
 #[derive(Debug, Error, Copy, Clone)]
@@ -26,6 +34,8 @@ pub enum ErrorKind {
     UserCantEditComments,
     #[error("Invalid content")]
     InvalidContent,
+    #[error("{0}")]
+    Spam(SpamRule),
     #[error("Server error")]
     Server,
 }
@@ -54,49 +64,96 @@ pub async fn service(
 
     let comment_id = request.id;
 
-    service_inner(state, request, user)
-        .await
-        .map_err(|err| Error::new(err, comment_id))
+    let flash = Flash::new().field("content", &request.content);
+
+    let post_id = match service_inner(state.clone(), request, user).await {
+        Ok(post_id) => post_id,
+        Err(kind) => {
+            let err = Error::new(kind, comment_id);
+
+            return Ok(state
+                .flash
+                .redirect(&err.as_location(), flash.error(kind.field(), err)));
+        }
+    };
+
+    let location = format!("/post/{post_id}?start_id={comment_id}#{comment_id}");
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, location))
+        .finish();
+
+    Ok(response)
 }
 
-async fn service_inner(
+// Also used by `/api/v1/comments/{id}`, returns the id of the post commented on
+pub async fn service_inner(
     state: Data<State>,
     request: Request,
     user: User,
-) -> Result<HttpResponse, ErrorKind> {
-    if !user.role.can_post() {
+) -> Result<Id, ErrorKind> {
+    let Some(comment) = fetch_comment_by_id(&state.db_pool, request.id).await? else {
+        return Err(ErrorKind::UserCantEditComments);
+    };
+
+    let authored = Authored {
+        posted_by_user_id: comment.posted_by_user_id,
+        posted_at: comment.posted_at,
+    };
+
+    if !policy::can(
+        &user,
+        Action::Edit,
+        Resource::Comment(authored),
+        &state.config,
+    ) {
         return Err(ErrorKind::UserCantEditComments);
     }
 
     let content = Content::parse(request.content).map_err(|_| ErrorKind::InvalidContent)?;
 
-    let comment_update_result = update_comment(&state.db_pool, request.id, &content, &user).await?;
+    // There is nothing to hold an edit back with, so it's rejected whatever `spam.action` is
+    if !policy::can(&user, Action::View, Resource::Held, &state.config) {
+        let spam_rule = check_comment_spam(
+            &state.db_pool,
+            &state.spam_filter,
+            &state.config,
+            user.id,
+            content.as_ref(),
+            Some(comment.id),
+        )
+        .await?;
+
+        if let Some(rule) = spam_rule {
+            return Err(ErrorKind::Spam(rule));
+        }
+    }
+
+    let mut tx = state.db_pool.begin().await?;
+
+    insert_original_comment_revision(&mut tx, request.id).await?;
+
+    let comment_update_result =
+        update_comment(&mut tx, request.id, &content, &state.config.markdown).await?;
 
     if comment_update_result.rows_affected() == 0 {
         return Err(ErrorKind::UserCantEditComments);
     }
 
-    let comment = fetch_comment_by_id(&state.db_pool, request.id).await?;
-
-    let location = format!(
-        "/post/{}?start_id={}#{}",
-        comment.post_id, comment.id, comment.id
-    );
+    insert_comment_revision(&mut tx, request.id, user.id).await?;
 
-    let response = HttpResponse::SeeOther()
-        .append_header((header::LOCATION, location))
-        .finish();
+    tx.commit().await?;
 
-    Ok(response)
+    Ok(comment.post_id)
 }
 
 async fn update_comment(
-    db_pool: &MySqlPool,
+    tx: &mut Transaction<'_, MySql>,
     comment_id: Id,
     content: &Content,
-    user: &User,
+    markdown_config: &MarkdownConfig,
 ) -> sqlx::Result<MySqlQueryResult> {
-    let markdown_content = markdown::to_html(content.as_ref());
+    let markdown_content = render::markdown(content.as_ref(), markdown_config);
 
     sqlx::query!(
         "
@@ -104,17 +161,18 @@ async fn update_comment(
         SET
             content = ?,
             markdown_content = ?,
+            render_version = ?,
             modified_at = NOW()
         WHERE
             id = ?
-            AND posted_by_user_id = ?
+            AND deleted_at IS NULL
         ",
         content.as_ref(),
         markdown_content,
-        comment_id,
-        user.id
+        render::version(markdown_config),
+        comment_id
     )
-    .execute(db_pool)
+    .execute(&mut *tx)
     .await
 }
 
@@ -122,22 +180,26 @@ async fn update_comment(
 struct Comment {
     pub id: Id,
     pub post_id: Id,
+    pub posted_by_user_id: Option<Id>,
+    pub posted_at: NaiveDateTime,
 }
 
-async fn fetch_comment_by_id(db_pool: &MySqlPool, comment_id: Id) -> sqlx::Result<Comment> {
+async fn fetch_comment_by_id(db_pool: &MySqlPool, comment_id: Id) -> sqlx::Result<Option<Comment>> {
     sqlx::query_as!(
         Comment,
         "
         SELECT
             id,
-            post_id
+            post_id,
+            posted_by_user_id,
+            posted_at
         FROM comments
         WHERE
             id = ?
         ",
         comment_id
     )
-    .fetch_one(db_pool)
+    .fetch_optional(db_pool)
     .await
 }
 
@@ -147,6 +209,16 @@ impl From<sqlx::Error> for ErrorKind {
     }
 }
 
+impl ErrorKind {
+    // The form field to highlight
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            Self::InvalidContent | Self::Spam(_) => Some("content"),
+            _ => None,
+        }
+    }
+}
+
 impl Error {
     pub fn new(kind: ErrorKind, comment_id: Id) -> Self {
         Self { kind, comment_id }
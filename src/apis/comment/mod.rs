@@ -2,3 +2,4 @@ pub mod anonymise;
 pub mod create;
 pub mod delete;
 pub mod edit;
+pub mod restore;
@@ -0,0 +1,95 @@
+use crate::session::auth;
+use crate::types::id::Id;
+use crate::State;
+use actix_web::{
+    http::{header, StatusCode},
+    post,
+    web::{Data, Form},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::Deserialize;
+use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use thiserror::Error;
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("Draft not found")]
+    DraftNotFound,
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub id: Id,
+}
+
+#[post("/api/draft/delete")]
+pub async fn service(
+    state: Data<State>,
+    req: HttpRequest,
+    Form(request): Form<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/post/create"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    if delete_draft(&state.db_pool, request.id, user.id)
+        .await?
+        .rows_affected()
+        == 0
+    {
+        return Err(Error::DraftNotFound);
+    }
+
+    let location = format!("/user/{}#drafts", user.username);
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, location))
+        .finish();
+
+    Ok(response)
+}
+
+async fn delete_draft(db_pool: &MySqlPool, id: Id, user_id: Id) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        DELETE FROM drafts
+        WHERE
+            id = ?
+            AND user_id = ?
+        ",
+        id,
+        user_id
+    )
+    .execute(db_pool)
+    .await
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl Error {
+    pub fn as_location(&self) -> String {
+        format!("/post/create?error={self}")
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SEE_OTHER
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        HttpResponse::build(self.status_code())
+            .append_header((header::LOCATION, self.as_location()))
+            .finish()
+    }
+}
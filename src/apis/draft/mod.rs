@@ -0,0 +1,4 @@
+pub mod delete;
+pub mod save;
+pub mod schedule;
+pub mod unschedule;
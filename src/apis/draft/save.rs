@@ -0,0 +1,301 @@
+use crate::config::Config;
+use crate::flash::Flash;
+use crate::session::auth;
+use crate::types::id::Id;
+use crate::types::post::{
+    content::CONTENT_MAX_CHAR_COUNT, description::DESCRIPTION_MAX_CHAR_COUNT,
+    tags::tags_max_char_count, title::TITLE_MAX_CHAR_COUNT,
+};
+use crate::State;
+use actix_web::{
+    http::{header, StatusCode},
+    post,
+    web::{Data, Form},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use thiserror::Error;
+
+pub const MAX_DRAFTS_PER_USER: i64 = 50;
+
+#[derive(Debug, Copy, Clone, Error)]
+#[error("{kind}")]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub draft_id: Option<Id>,
+}
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum ErrorKind {
+    #[error("Draft not found")]
+    DraftNotFound,
+    #[error("The draft is too long")]
+    TooLong,
+    #[error("You have too many drafts, publish or delete some first")]
+    TooManyDrafts,
+    #[error("Server error")]
+    Server,
+}
+
+// Drafts don't have to be valid posts yet, they only have to fit
+#[derive(Debug, Clone, Deserialize)]
+pub struct Request {
+    pub draft_id: Option<Id>,
+    pub title: String,
+    pub description: String,
+    pub tags: String,
+    pub content: String,
+}
+
+impl Request {
+    pub fn to_flash(&self) -> Flash {
+        Flash::new()
+            .field("title", &self.title)
+            .field("description", &self.description)
+            .field("tags", &self.tags)
+            .field("content", &self.content)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    id: Id,
+}
+
+// Autosave asks for JSON and gets the id of the draft back,
+// the "Save draft" button of the form is sent back to the form
+#[post("/api/draft/save")]
+pub async fn service(
+    state: Data<State>,
+    req: HttpRequest,
+    Form(request): Form<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/post/create"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    let is_json = accepts_json(&req);
+
+    let id = match save(&state.db_pool, &state.config, user.id, &request).await {
+        Ok(id) => id,
+        // Autosave only needs to know it failed
+        Err(kind) if is_json => return Err(Error::new(kind, request.draft_id)),
+        Err(kind) => {
+            let err = Error::new(kind, request.draft_id);
+            // None of its errors are about a single field
+            let flash = request.to_flash();
+
+            return Ok(state.flash.redirect(&err.as_location(), flash));
+        }
+    };
+
+    if is_json {
+        return Ok(HttpResponse::Ok().json(Response { id }));
+    }
+
+    let location = format!("/post/create?draft_id={id}");
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, location))
+        .finish();
+
+    Ok(response)
+}
+
+// Also used when creating or scheduling a post fails, so nothing typed is lost.
+// Creates a new draft unless `draft_id` is one of the user's
+pub async fn save(
+    db_pool: &MySqlPool,
+    config: &Config,
+    user_id: Id,
+    request: &Request,
+) -> Result<Id, ErrorKind> {
+    let is_too_long = request.title.chars().count() > TITLE_MAX_CHAR_COUNT
+        || request.description.chars().count() > DESCRIPTION_MAX_CHAR_COUNT
+        || request.tags.chars().count() > tags_max_char_count(config.tags.max_count)
+        || request.content.chars().count() > CONTENT_MAX_CHAR_COUNT;
+
+    if is_too_long {
+        return Err(ErrorKind::TooLong);
+    }
+
+    match request.draft_id {
+        Some(id) => {
+            if !fetch_is_own_draft(db_pool, id, user_id).await? {
+                return Err(ErrorKind::DraftNotFound);
+            }
+
+            update_draft(db_pool, id, request).await?;
+
+            Ok(id)
+        }
+        None => {
+            if fetch_draft_count(db_pool, user_id).await? >= MAX_DRAFTS_PER_USER {
+                return Err(ErrorKind::TooManyDrafts);
+            }
+
+            let result = insert_draft(db_pool, user_id, request).await?;
+
+            Ok(result.last_insert_id())
+        }
+    }
+}
+
+fn accepts_json(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/json"))
+}
+
+async fn fetch_is_own_draft(db_pool: &MySqlPool, id: Id, user_id: Id) -> sqlx::Result<bool> {
+    #[derive(Debug)]
+    struct Count {
+        count: i64,
+    }
+
+    sqlx::query_as!(
+        Count,
+        "
+        SELECT
+            COUNT(*) AS `count!`
+        FROM drafts
+        WHERE
+            id = ?
+            AND user_id = ?
+        ",
+        id,
+        user_id
+    )
+    .fetch_one(db_pool)
+    .await
+    .map(|result| result.count > 0)
+}
+
+async fn fetch_draft_count(db_pool: &MySqlPool, user_id: Id) -> sqlx::Result<i64> {
+    #[derive(Debug)]
+    struct Count {
+        count: i64,
+    }
+
+    sqlx::query_as!(
+        Count,
+        "
+        SELECT
+            COUNT(*) AS `count!`
+        FROM drafts
+        WHERE
+            user_id = ?
+        ",
+        user_id
+    )
+    .fetch_one(db_pool)
+    .await
+    .map(|result| result.count)
+}
+
+async fn insert_draft(
+    db_pool: &MySqlPool,
+    user_id: Id,
+    request: &Request,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        INSERT INTO drafts
+        (
+            user_id,
+            title,
+            description,
+            tags,
+            content,
+            publish_at,
+            last_error,
+            created_at,
+            updated_at
+        )
+        VALUES
+        (
+            ?,
+            ?,
+            ?,
+            ?,
+            ?,
+            NULL,
+            NULL,
+            NOW(),
+            NOW()
+        )
+        ",
+        user_id,
+        request.title,
+        request.description,
+        request.tags,
+        request.content
+    )
+    .execute(db_pool)
+    .await
+}
+
+// A schedule is kept, the draft is published as it is when it's due
+async fn update_draft(
+    db_pool: &MySqlPool,
+    id: Id,
+    request: &Request,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        UPDATE drafts
+        SET
+            title = ?,
+            description = ?,
+            tags = ?,
+            content = ?,
+            updated_at = NOW()
+        WHERE
+            id = ?
+        ",
+        request.title,
+        request.description,
+        request.tags,
+        request.content,
+        id
+    )
+    .execute(db_pool)
+    .await
+}
+
+impl From<sqlx::Error> for ErrorKind {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, draft_id: Option<Id>) -> Self {
+        Self { kind, draft_id }
+    }
+
+    pub fn as_location(&self) -> String {
+        match self.draft_id {
+            Some(draft_id) => format!("/post/create?draft_id={draft_id}&error={self}"),
+            None => format!("/post/create?error={self}"),
+        }
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SEE_OTHER
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        HttpResponse::build(self.status_code())
+            .append_header((header::LOCATION, self.as_location()))
+            .finish()
+    }
+}
@@ -0,0 +1,193 @@
+use super::save::{self, ErrorKind as SaveErrorKind};
+use crate::apis::post::create::{self, Error as PostCreateError};
+use crate::rate_limit;
+use crate::session::{auth, User};
+use crate::types::draft::parse_publish_at;
+use crate::types::id::Id;
+use crate::State;
+use actix_web::{
+    http::{header, StatusCode},
+    post,
+    web::{Data, Form},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use chrono::{NaiveDateTime, Utc};
+use serde::Deserialize;
+use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use std::net::IpAddr;
+use thiserror::Error;
+
+#[derive(Debug, Copy, Clone, Error)]
+#[error("{kind}")]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub draft_id: Option<Id>,
+}
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum ErrorKind {
+    #[error("{0}")]
+    Draft(SaveErrorKind),
+    #[error("{0}")]
+    Post(PostCreateError),
+    #[error("Invalid publishing time")]
+    InvalidPublishAt,
+    #[error("The publishing time has to be in the future")]
+    PublishAtInPast,
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub draft_id: Option<Id>,
+    pub title: String,
+    pub description: String,
+    pub tags: String,
+    pub content: String,
+    pub publish_at: String,
+    pub challenge_id: Option<String>,
+    pub challenge_answer: Option<String>,
+}
+
+// The draft is saved first, then checked like a post being created right now.
+// Spam and approval are only decided once it's published
+#[post("/api/draft/schedule")]
+pub async fn service(
+    state: Data<State>,
+    req: HttpRequest,
+    Form(request): Form<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/post/create"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    let draft = save::Request {
+        draft_id: request.draft_id,
+        title: request.title.clone(),
+        description: request.description.clone(),
+        tags: request.tags.clone(),
+        content: request.content.clone(),
+    };
+    let publish_at = request.publish_at.clone();
+
+    let ip = rate_limit::client_ip(&req, state.config.rate_limit.is_behind_proxy);
+
+    if let Err(err) = service_inner(&state, &draft, request, &user, ip).await {
+        let flash = draft
+            .to_flash()
+            .field("publish_at", &publish_at)
+            .error(err.kind.field(), err);
+
+        return Ok(state.flash.redirect(&err.as_location(), flash));
+    }
+
+    let location = format!("/user/{}#drafts", user.username);
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, location))
+        .finish();
+
+    Ok(response)
+}
+
+async fn service_inner(
+    state: &State,
+    draft: &save::Request,
+    request: Request,
+    user: &User,
+    ip: Option<IpAddr>,
+) -> Result<(), Error> {
+    let draft_id = save::save(&state.db_pool, &state.config, user.id, draft)
+        .await
+        .map_err(|kind| Error::new(ErrorKind::Draft(kind), request.draft_id))?;
+
+    let error = |kind| Error::new(kind, Some(draft_id));
+
+    let publish_at =
+        parse_publish_at(&request.publish_at).map_err(|_| error(ErrorKind::InvalidPublishAt))?;
+
+    if publish_at <= Utc::now().naive_utc() {
+        return Err(error(ErrorKind::PublishAtInPast));
+    }
+
+    let post = create::Request {
+        title: request.title,
+        description: request.description,
+        tags: request.tags,
+        content: request.content,
+        challenge_id: request.challenge_id,
+        challenge_answer: request.challenge_answer,
+        draft_id: Some(draft_id),
+    };
+
+    create::prepare(state, post, user, ip)
+        .await
+        .map_err(|err| error(ErrorKind::Post(err)))?;
+
+    schedule_draft(&state.db_pool, draft_id, publish_at)
+        .await
+        .map_err(|_| error(ErrorKind::Server))?;
+
+    Ok(())
+}
+
+async fn schedule_draft(
+    db_pool: &MySqlPool,
+    id: Id,
+    publish_at: NaiveDateTime,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        UPDATE drafts
+        SET
+            publish_at = ?,
+            last_error = NULL
+        WHERE
+            id = ?
+        ",
+        publish_at,
+        id
+    )
+    .execute(db_pool)
+    .await
+}
+
+impl ErrorKind {
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            Self::Post(err) => err.field(),
+            Self::InvalidPublishAt | Self::PublishAtInPast => Some("publish_at"),
+            _ => None,
+        }
+    }
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, draft_id: Option<Id>) -> Self {
+        Self { kind, draft_id }
+    }
+
+    pub fn as_location(&self) -> String {
+        match self.draft_id {
+            Some(draft_id) => format!("/post/create?draft_id={draft_id}&error={self}"),
+            None => format!("/post/create?error={self}"),
+        }
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SEE_OTHER
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        HttpResponse::build(self.status_code())
+            .append_header((header::LOCATION, self.as_location()))
+            .finish()
+    }
+}
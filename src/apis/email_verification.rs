@@ -1,4 +1,6 @@
-use crate::types::user::role::Role as UserRole;
+use crate::db::{delete_user_role, insert_user_role};
+use crate::types::id::Id;
+use crate::types::user::role::{MEMBER, UNVERIFIED};
 use crate::State;
 use actix_web::{
     http::{header, StatusCode},
@@ -7,7 +9,8 @@ use actix_web::{
     HttpResponse, ResponseError,
 };
 use serde::Deserialize;
-use sqlx::mysql::MySqlPool;
+use sqlx::mysql::{MySql, MySqlPool};
+use sqlx::Transaction;
 use thiserror::Error;
 
 #[derive(Debug, Error, Copy, Clone)]
@@ -43,23 +46,60 @@ pub async fn service(
 }
 
 async fn verify_email(db_pool: &MySqlPool, code: &str) -> sqlx::Result<bool> {
+    let mut tx = db_pool.begin().await?;
+
+    let Some(user) = fetch_unverified_user_by_code(&mut tx, code).await? else {
+        return Ok(false);
+    };
+
     sqlx::query!(
         "
         UPDATE users
         SET
-            code = NULL,
-            role = ?
+            code = NULL
+        WHERE
+            id = ?
+        ",
+        user.id
+    )
+    .execute(&mut tx)
+    .await?;
+
+    delete_user_role(&mut tx, user.id, UNVERIFIED).await?;
+    insert_user_role(&mut tx, user.id, MEMBER).await?;
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+#[derive(Debug)]
+struct UnverifiedUser {
+    pub id: Id,
+}
+
+async fn fetch_unverified_user_by_code(
+    tx: &mut Transaction<'_, MySql>,
+    code: &str,
+) -> sqlx::Result<Option<UnverifiedUser>> {
+    sqlx::query_as!(
+        UnverifiedUser,
+        "
+        SELECT
+            users.id
+        FROM users
+            JOIN user_roles ON users.id = user_roles.user_id
+            JOIN roles ON user_roles.role_id = roles.id
         WHERE
-            code = ?
-            AND role = ?
+            users.code = ?
+            AND roles.name = ?
+        FOR UPDATE
         ",
-        UserRole::Member.as_str(),
         code,
-        UserRole::default().as_str(),
+        UNVERIFIED
     )
-    .execute(db_pool)
+    .fetch_optional(&mut *tx)
     .await
-    .map(|result| result.rows_affected() == 1)
 }
 
 impl From<sqlx::Error> for Error {
@@ -0,0 +1,57 @@
+use crate::render;
+use crate::session::auth;
+use crate::types::post::content::CONTENT_MAX_CHAR_COUNT;
+use crate::State;
+use actix_web::{
+    http::StatusCode,
+    post,
+    web::{Data, Form},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("Sign in to preview")]
+    Unauthorized,
+    #[error("The content is too long")]
+    TooLong,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub content: String,
+}
+
+// Renders the way a post or comment would be saved, for the preview on their forms
+#[post("/api/markdown/preview")]
+pub async fn service(
+    state: Data<State>,
+    req: HttpRequest,
+    Form(request): Form<Request>,
+) -> Result<HttpResponse, Error> {
+    if auth(&state.db_pool, &req).await.is_err() {
+        return Err(Error::Unauthorized);
+    }
+
+    // Posts allow the longest content
+    if request.content.chars().count() > CONTENT_MAX_CHAR_COUNT {
+        return Err(Error::TooLong);
+    }
+
+    let html = render::markdown(&request.content, &state.config.markdown);
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(html))
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::TooLong => StatusCode::PAYLOAD_TOO_LARGE,
+        }
+    }
+}
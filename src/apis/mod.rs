@@ -1,11 +1,21 @@
 pub mod comment;
+pub mod draft;
 pub mod email_verification;
+pub mod markdown;
+pub mod moderation;
 pub mod password_change;
 pub mod password_reset;
 pub mod post;
+pub mod report;
+pub mod rerender;
+pub mod role;
 pub mod sign_in;
 pub mod sign_up;
+pub mod tag;
+pub mod token;
 pub mod user;
+pub mod v1;
+pub mod webhook;
 
 pub fn is_checked(checkbox: Option<String>) -> bool {
     match checkbox {
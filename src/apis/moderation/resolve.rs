@@ -0,0 +1,276 @@
+use crate::db::{
+    ban_user, fetch_account_by_username, fetch_comment_by_id, fetch_contentless_post_by_id,
+    insert_mod_action, resolve_reports, soft_delete_comment, soft_delete_post,
+};
+use crate::policy::{self, Action, Resource};
+use crate::session::auth;
+use crate::session::User;
+use crate::types::ban;
+use crate::types::id::Id;
+use crate::types::lifetime::Lifetime;
+use crate::types::mod_action::{reason::Reason, Kind, NewModAction, TargetType};
+use crate::types::report::Resolution;
+use crate::types::user::Preview as UserPreview;
+use crate::webhook;
+use crate::State;
+use actix_web::{
+    http::{header, StatusCode},
+    post,
+    web::{Data, Form},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("You are not allowed to resolve these reports")]
+    UserCantResolve,
+    #[error("You are not allowed to delete this")]
+    UserCantDelete,
+    #[error("You are not allowed to ban this user")]
+    UserCantBan,
+    #[error("Invalid action")]
+    InvalidAction,
+    #[error("Invalid reason")]
+    InvalidReason,
+    #[error("Invalid ban duration")]
+    InvalidBanDuration,
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub target_type: String,
+    pub target_id: Id,
+    // One of `resolve`, `dismiss`, `delete` or `ban`
+    pub action: String,
+    pub ban_duration: Option<String>,
+    pub reason: Option<String>,
+}
+
+// The reported item as far as resolving is concerned
+struct Target {
+    label: String,
+    posted_by: Option<UserPreview>,
+    is_resolvable: bool,
+    is_deletable: bool,
+}
+
+#[post("/api/mod/queue/resolve")]
+pub async fn service(
+    state: Data<State>,
+    req: HttpRequest,
+    Form(request): Form<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/mod/queue"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    let target_type =
+        TargetType::from_str(&request.target_type).map_err(|_| Error::UserCantResolve)?;
+
+    let target = fetch_target(&state, target_type, request.target_id, &user)
+        .await?
+        .ok_or(Error::UserCantResolve)?;
+
+    if !target.is_resolvable {
+        return Err(Error::UserCantResolve);
+    }
+
+    let reason =
+        Reason::parse(request.reason.unwrap_or_default()).map_err(|_| Error::InvalidReason)?;
+
+    let mut tx = state.db_pool.begin().await?;
+
+    let resolution = match request.action.as_str() {
+        "resolve" => Resolution::Resolved,
+        "dismiss" => Resolution::Dismissed,
+        "delete" => {
+            if !target.is_deletable {
+                return Err(Error::UserCantDelete);
+            }
+
+            let kind = match target_type {
+                TargetType::Post => {
+                    soft_delete_post(&mut tx, request.target_id, user.id, &reason).await?;
+
+                    webhook::post_deleted(
+                        &mut tx,
+                        &state.config.domain,
+                        request.target_id,
+                        &user,
+                        &reason,
+                    )
+                    .await?;
+
+                    Kind::DeletePost
+                }
+                _ => {
+                    soft_delete_comment(&mut tx, request.target_id, user.id, &reason).await?;
+
+                    Kind::DeleteComment
+                }
+            };
+
+            insert_mod_action(
+                &mut tx,
+                &NewModAction {
+                    actor_user_id: user.id,
+                    kind,
+                    target_type,
+                    target_id: request.target_id,
+                    target_label: &target.label,
+                    reason: &reason,
+                    details: "",
+                },
+            )
+            .await?;
+
+            Resolution::Resolved
+        }
+        "ban" => {
+            let Some(posted_by) = &target.posted_by else {
+                return Err(Error::UserCantBan);
+            };
+
+            let Some(account) =
+                fetch_account_by_username(&state.db_pool, &posted_by.username).await?
+            else {
+                return Err(Error::UserCantBan);
+            };
+
+            if !policy::can(&user, Action::Admin, Resource::User(account), &state.config) {
+                return Err(Error::UserCantBan);
+            }
+
+            let ban_duration = Lifetime::parse(request.ban_duration.as_deref().unwrap_or_default())
+                .map_err(|_| Error::InvalidBanDuration)?;
+
+            ban_user(&mut tx, account.id, user.id, &reason, ban_duration).await?;
+
+            webhook::user_banned(
+                &mut tx,
+                &state.config.domain,
+                account.id,
+                &posted_by.username,
+                &user,
+                &reason,
+                ban_duration,
+            )
+            .await?;
+
+            insert_mod_action(
+                &mut tx,
+                &NewModAction {
+                    actor_user_id: user.id,
+                    kind: Kind::BanUser,
+                    target_type: TargetType::User,
+                    target_id: account.id,
+                    target_label: &posted_by.username,
+                    reason: &reason,
+                    details: &format!("{}, {}", ban::duration_label(ban_duration), target.label),
+                },
+            )
+            .await?;
+
+            Resolution::Resolved
+        }
+        _ => return Err(Error::InvalidAction),
+    };
+
+    resolve_reports(&mut tx, target_type, request.target_id, resolution, user.id).await?;
+
+    insert_mod_action(
+        &mut tx,
+        &NewModAction {
+            actor_user_id: user.id,
+            kind: match resolution {
+                Resolution::Resolved => Kind::ResolveReports,
+                Resolution::Dismissed => Kind::DismissReports,
+            },
+            target_type,
+            target_id: request.target_id,
+            target_label: &target.label,
+            reason: &reason,
+            details: "",
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, "/mod/queue"))
+        .finish();
+
+    Ok(response)
+}
+
+async fn fetch_target(
+    state: &State,
+    target_type: TargetType,
+    target_id: Id,
+    user: &User,
+) -> sqlx::Result<Option<Target>> {
+    let target = match target_type {
+        TargetType::Post => {
+            fetch_contentless_post_by_id(&state.db_pool, target_id, user, &state.config)
+                .await?
+                .map(|post| Target {
+                    label: post.title,
+                    posted_by: post.posted_by,
+                    is_resolvable: post.is_resolvable,
+                    is_deletable: post.is_deletable,
+                })
+        }
+        TargetType::Comment => fetch_comment_by_id(&state.db_pool, target_id, user, &state.config)
+            .await?
+            .map(|comment| Target {
+                label: format!(
+                    "Comment by {} on post {}",
+                    comment
+                        .posted_by
+                        .as_ref()
+                        .map(|posted_by| posted_by.username.as_str())
+                        .unwrap_or("Anonymous"),
+                    comment.post_id
+                ),
+                posted_by: comment.posted_by,
+                is_resolvable: comment.is_resolvable,
+                is_deletable: comment.is_deletable,
+            }),
+        TargetType::User | TargetType::Tag | TargetType::Role => None,
+    };
+
+    Ok(target)
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl Error {
+    pub fn as_location(&self) -> String {
+        format!("/mod/queue?error={self}")
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SEE_OTHER
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        HttpResponse::build(self.status_code())
+            .append_header((header::LOCATION, self.as_location()))
+            .finish()
+    }
+}
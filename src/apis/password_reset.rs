@@ -44,7 +44,7 @@ pub async fn service(
     }
 
     send_email(
-        &state.domain,
+        &state.config.domain,
         state.email_from.clone(),
         &state.mailer,
         &request.email,
@@ -70,7 +70,7 @@ async fn service_inner(
     user: User,
     request: Request,
 ) -> Result<HttpResponse, ErrorKind> {
-    let post = fetch_contentless_post_by_id(&state.db_pool, request.post_id, &user)
+    let post = fetch_contentless_post_by_id(&state.db_pool, request.post_id, &user, &state.config)
         .await?
         .ok_or(ErrorKind::UserCantAnonymisePost)?;
 
@@ -82,7 +82,7 @@ async fn service_inner(
         return Err(ErrorKind::InvalidConfirmation);
     }
 
-    anonymise_post(&state.db_pool, request.post_id, &user).await?;
+    anonymise_post(&state.db_pool, request.post_id).await?;
 
     let location = format!("/post/{}", request.post_id);
 
@@ -93,11 +93,7 @@ async fn service_inner(
     Ok(response)
 }
 
-async fn anonymise_post(
-    db_pool: &MySqlPool,
-    post_id: Id,
-    user: &User,
-) -> sqlx::Result<MySqlQueryResult> {
+async fn anonymise_post(db_pool: &MySqlPool, post_id: Id) -> sqlx::Result<MySqlQueryResult> {
     sqlx::query!(
         "
         UPDATE posts
@@ -105,10 +101,8 @@ async fn anonymise_post(
             posted_by_user_id = NULL
         WHERE
             id = ?
-            AND posted_by_user_id = ?
         ",
-        post_id,
-        user.id
+        post_id
     )
     .execute(db_pool)
     .await
@@ -0,0 +1,140 @@
+use crate::db::{fetch_contentless_post_by_id, insert_mod_action};
+use crate::session::auth;
+use crate::session::User;
+use crate::types::id::Id;
+use crate::types::mod_action::{reason::Reason, Kind, NewModAction, TargetType};
+use crate::webhook;
+use crate::State;
+use actix_web::{
+    http::{header, StatusCode},
+    post,
+    web::{Data, Form},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::Deserialize;
+use sqlx::mysql::{MySql, MySqlQueryResult};
+use sqlx::Transaction;
+use thiserror::Error;
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("You are not allowed to approve this post")]
+    UserCantApprovePost,
+    #[error("Invalid reason")]
+    InvalidReason,
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub post_id: Id,
+    pub reason: Option<String>,
+}
+
+#[post("/api/post/approve")]
+pub async fn service(
+    state: Data<State>,
+    req: HttpRequest,
+    Form(request): Form<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/mod/approvals"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    service_inner(state, user, request).await
+}
+
+async fn service_inner(
+    state: Data<State>,
+    user: User,
+    request: Request,
+) -> Result<HttpResponse, Error> {
+    let post = fetch_contentless_post_by_id(&state.db_pool, request.post_id, &user, &state.config)
+        .await?
+        .ok_or(Error::UserCantApprovePost)?;
+
+    if !post.is_approvable {
+        return Err(Error::UserCantApprovePost);
+    }
+
+    let reason =
+        Reason::parse(request.reason.unwrap_or_default()).map_err(|_| Error::InvalidReason)?;
+
+    let mut tx = state.db_pool.begin().await?;
+
+    insert_mod_action(
+        &mut tx,
+        &NewModAction {
+            actor_user_id: user.id,
+            kind: Kind::ApprovePost,
+            target_type: TargetType::Post,
+            target_id: post.id,
+            target_label: &post.title,
+            reason: &reason,
+            details: "",
+        },
+    )
+    .await?;
+
+    approve_post(&mut tx, request.post_id).await?;
+
+    // Announced only now that everyone can see it
+    webhook::post_created(&mut tx, &state.config.domain, request.post_id).await?;
+
+    tx.commit().await?;
+
+    let location = format!("/post/{}", request.post_id);
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, location))
+        .finish();
+
+    Ok(response)
+}
+
+async fn approve_post(
+    tx: &mut Transaction<'_, MySql>,
+    post_id: Id,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        UPDATE posts
+        SET
+            is_pending = 0
+        WHERE
+            id = ?
+        ",
+        post_id
+    )
+    .execute(&mut *tx)
+    .await
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl Error {
+    pub fn as_location(&self) -> String {
+        format!("/mod/approvals?error={self}")
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SEE_OTHER
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        HttpResponse::build(self.status_code())
+            .append_header((header::LOCATION, self.as_location()))
+            .finish()
+    }
+}
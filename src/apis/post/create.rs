@@ -1,7 +1,20 @@
-use crate::db::insert_post_tags;
+use crate::apis::draft::save as draft_save;
+use crate::config::{Config, MarkdownConfig};
+use crate::db::{
+    check_post_spam, fetch_account_age_days, fetch_requires_approval, insert_automatic_report,
+    insert_post_tags,
+};
+use crate::policy::{self, Action, Authored, Resource};
+use crate::rate_limit::{self, Action as RateLimitAction, Requester};
+use crate::render;
 use crate::session::auth;
 use crate::session::User;
+use crate::spam::{self, Action as SpamAction, Filter as SpamFilter, Rule as SpamRule};
+use crate::types::challenge::Challenge;
+use crate::types::id::Id;
+use crate::types::mod_action::TargetType;
 use crate::types::post::{content::Content, description::Description, tags::Tags, title::Title};
+use crate::webhook;
 use crate::State;
 use actix_web::{
     http::{header, StatusCode},
@@ -10,13 +23,21 @@ use actix_web::{
     HttpRequest, HttpResponse, ResponseError,
 };
 use serde::Deserialize;
-use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use sqlx::mysql::{MySql, MySqlPool, MySqlQueryResult};
+use sqlx::Transaction;
+use std::net::IpAddr;
 use thiserror::Error;
 
 #[derive(Debug, Error, Copy, Clone)]
 pub enum Error {
+    #[error("You are banned")]
+    UserIsBanned,
     #[error("You are not allowed to post")]
     UserCantPost,
+    #[error("You are posting too often, try again later")]
+    RateLimited,
+    #[error("Wrong answer to the question, please try again")]
+    WrongChallengeAnswer,
     #[error("Invalid title")]
     InvalidTitle,
     #[error("Invalid description")]
@@ -25,6 +46,8 @@ pub enum Error {
     InvalidTags,
     #[error("Invalid content")]
     InvalidContent,
+    #[error("{0}")]
+    Spam(SpamRule),
     #[error("Server error")]
     Server,
 }
@@ -35,6 +58,36 @@ pub struct Request {
     pub description: String,
     pub tags: String,
     pub content: String,
+    pub challenge_id: Option<String>,
+    pub challenge_answer: Option<String>,
+    // Deleted once the post is created
+    pub draft_id: Option<Id>,
+}
+
+// A post that passed validation, published right away or when its draft is due
+#[derive(Debug)]
+pub struct NewPost {
+    pub title: Title,
+    pub description: Description,
+    pub tags: Tags,
+    pub content: Content,
+}
+
+impl NewPost {
+    pub fn parse(
+        title: String,
+        description: String,
+        tags: String,
+        content: String,
+        config: &Config,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            title: Title::parse(title).map_err(|_| Error::InvalidTitle)?,
+            description: Description::parse(description).map_err(|_| Error::InvalidDescription)?,
+            tags: Tags::parse(tags, config.tags.max_count).map_err(|_| Error::InvalidTags)?,
+            content: Content::parse(content).map_err(|_| Error::InvalidContent)?,
+        })
+    }
 }
 
 #[post("/api/post/create")]
@@ -54,28 +107,42 @@ pub async fn service(
         }
     };
 
-    if !user.role.can_post() {
-        return Err(Error::UserCantPost);
-    }
+    let ip = rate_limit::client_ip(&req, state.config.rate_limit.is_behind_proxy);
 
-    let title = Title::parse(request.title).map_err(|_| Error::InvalidTitle)?;
-    let description =
-        Description::parse(request.description).map_err(|_| Error::InvalidDescription)?;
-    let tags = Tags::parse(request.tags).map_err(|_| Error::InvalidTags)?;
-    let content = Content::parse(request.content).map_err(|_| Error::InvalidContent)?;
+    let user_id = user.id;
+    let draft = draft_save::Request {
+        draft_id: request.draft_id,
+        title: request.title.clone(),
+        description: request.description.clone(),
+        tags: request.tags.clone(),
+        content: request.content.clone(),
+    };
 
-    let post_insert_result =
-        insert_post(&state.db_pool, &title, &description, &content, &user).await?;
+    let post_id = match service_inner(state.clone(), request, user, ip).await {
+        Ok(post_id) => post_id,
+        // The form comes back filled in either way, what was typed is only kept as a draft
+        // when the post itself needs fixing
+        Err(err) => {
+            let draft_id = if err.keeps_draft() {
+                draft_save::save(&state.db_pool, &state.config, user_id, &draft)
+                    .await
+                    .ok()
+            } else {
+                draft.draft_id
+            };
 
-    insert_post_tags(
-        &state.db_pool,
-        &tags,
-        post_insert_result.last_insert_id(),
-        user.id,
-    )
-    .await?;
+            let location = match draft_id {
+                Some(draft_id) => format!("/post/create?draft_id={draft_id}&error={err}"),
+                None => err.as_location(),
+            };
+
+            let flash = draft.to_flash().error(err.field(), err);
+
+            return Ok(state.flash.redirect(&location, flash));
+        }
+    };
 
-    let location = format!("/post/{}", post_insert_result.last_insert_id());
+    let location = format!("/post/{post_id}");
 
     let response = HttpResponse::SeeOther()
         .append_header((header::LOCATION, location))
@@ -84,14 +151,175 @@ pub async fn service(
     Ok(response)
 }
 
-async fn insert_post(
+// Also used by `/api/v1/posts`, returns the id of the new post
+pub async fn service_inner(
+    state: Data<State>,
+    request: Request,
+    user: User,
+    ip: Option<IpAddr>,
+) -> Result<Id, Error> {
+    let draft_id = request.draft_id;
+
+    let post = prepare(&state, request, &user, ip).await?;
+
+    let post_id = publish(
+        &state.db_pool,
+        &state.spam_filter,
+        &state.config,
+        &user,
+        &post,
+    )
+    .await?;
+
+    // Left behind rather than failing a post that is already up
+    if let Some(draft_id) = draft_id {
+        if let Err(e) = delete_draft(&state.db_pool, draft_id, user.id).await {
+            eprintln!("Failed to delete draft {draft_id} of post {post_id}: {e}");
+        }
+    }
+
+    Ok(post_id)
+}
+
+// Everything checked when the post is submitted, also used when it's scheduled instead
+pub async fn prepare(
+    state: &State,
+    request: Request,
+    user: &User,
+    ip: Option<IpAddr>,
+) -> Result<NewPost, Error> {
+    check_can_post(user, &state.config)?;
+
+    let requester = Requester {
+        user_id: Some(user.id),
+        account_age_days: fetch_account_age_days(&state.db_pool, user.id).await?,
+        ip,
+    };
+
+    if state
+        .rate_limiter
+        .check(RateLimitAction::Post, &requester)
+        .is_err()
+    {
+        return Err(Error::RateLimited);
+    }
+
+    if Challenge::is_required_for_post(&state.db_pool, user, &state.config).await? {
+        let (Some(id), Some(answer)) = (&request.challenge_id, &request.challenge_answer) else {
+            return Err(Error::WrongChallengeAnswer);
+        };
+
+        if !Challenge::verify(
+            &state.db_pool,
+            id,
+            answer,
+            state.config.challenge.expire_minutes,
+        )
+        .await?
+        {
+            return Err(Error::WrongChallengeAnswer);
+        }
+    }
+
+    NewPost::parse(
+        request.title,
+        request.description,
+        request.tags,
+        request.content,
+        &state.config,
+    )
+}
+
+pub fn check_can_post(user: &User, config: &Config) -> Result<(), Error> {
+    if user.ban.is_some() {
+        return Err(Error::UserIsBanned);
+    }
+
+    if !policy::can(user, Action::Create, Resource::Posts, config) {
+        return Err(Error::UserCantPost);
+    }
+
+    Ok(())
+}
+
+// Spam and approval are only decided here, so scheduled posts get the rules of when they go up
+pub async fn publish(
     db_pool: &MySqlPool,
+    spam_filter: &SpamFilter,
+    config: &Config,
+    user: &User,
+    post: &NewPost,
+) -> Result<Id, Error> {
+    let NewPost {
+        title,
+        description,
+        tags,
+        content,
+    } = post;
+
+    // Those who could approve the post themselves skip the wait
+    let authored = Authored {
+        posted_by_user_id: Some(user.id),
+        posted_at: chrono::Utc::now().naive_utc(),
+    };
+    let can_approve = policy::can(user, Action::Approve, Resource::Post(authored), config);
+
+    // Moderators aren't checked, they could approve the post anyway
+    let spam_rule = if can_approve {
+        None
+    } else {
+        let text = spam::post_text(title.as_ref(), description.as_ref(), content.as_ref());
+
+        check_post_spam(db_pool, spam_filter, config, user.id, &text, None).await?
+    };
+
+    if let Some(rule) = spam_rule {
+        if spam_filter.action == SpamAction::Reject {
+            return Err(Error::Spam(rule));
+        }
+    }
+
+    let is_pending = !can_approve
+        && (spam_rule.is_some() || fetch_requires_approval(db_pool, user.id, tags).await?);
+
+    // All or nothing, a post without its tags or webhooks would be published again by a retry
+    let mut tx = db_pool.begin().await?;
+
+    let post_id = insert_post(
+        &mut tx,
+        title,
+        description,
+        content,
+        is_pending,
+        user,
+        &config.markdown,
+    )
+    .await?
+    .last_insert_id();
+
+    insert_post_tags(&mut tx, tags, post_id, user.id).await?;
+
+    if let Some(rule) = spam_rule {
+        insert_automatic_report(&mut tx, TargetType::Post, post_id, rule).await?;
+    }
+
+    webhook::post_created(&mut tx, &config.domain, post_id).await?;
+
+    tx.commit().await?;
+
+    Ok(post_id)
+}
+
+async fn insert_post(
+    tx: &mut Transaction<'_, MySql>,
     title: &Title,
     description: &Description,
     content: &Content,
+    is_pending: bool,
     user: &User,
+    markdown_config: &MarkdownConfig,
 ) -> sqlx::Result<MySqlQueryResult> {
-    let markdown_content = markdown::to_html(content.as_ref());
+    let markdown_content = render::markdown(content.as_ref(), markdown_config);
 
     sqlx::query!(
         "
@@ -101,9 +329,12 @@ async fn insert_post(
             description,
             content,
             markdown_content,
+            render_version,
             posted_by_user_id,
             posted_at,
-            modified_at
+            modified_at,
+            is_pending,
+            is_shadowed
         )
         VALUES
         (
@@ -112,16 +343,41 @@ async fn insert_post(
             ?,
             ?,
             ?,
+            ?,
             NOW(),
-            NULL
+            NULL,
+            ?,
+            (SELECT is_shadow_banned FROM users WHERE id = ?)
         )
         ",
         title.as_ref(),
         description.as_ref(),
         content.as_ref(),
         markdown_content,
+        render::version(markdown_config),
+        user.id,
+        is_pending,
         user.id
     )
+    .execute(&mut *tx)
+    .await
+}
+
+async fn delete_draft(
+    db_pool: &MySqlPool,
+    draft_id: Id,
+    user_id: Id,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        DELETE FROM drafts
+        WHERE
+            id = ?
+            AND user_id = ?
+        ",
+        draft_id,
+        user_id
+    )
     .execute(db_pool)
     .await
 }
@@ -136,6 +392,34 @@ impl Error {
     pub fn as_location(&self) -> String {
         format!("/post/create?error={self}")
     }
+
+    // The form field to highlight
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            Self::WrongChallengeAnswer => Some("challenge_answer"),
+            Self::InvalidTitle => Some("title"),
+            Self::InvalidDescription => Some("description"),
+            Self::InvalidTags => Some("tags"),
+            Self::InvalidContent => Some("content"),
+            _ => None,
+        }
+    }
+
+    // Saving a draft only helps with what the author can fix in the post
+    fn keeps_draft(&self) -> bool {
+        match self {
+            Self::InvalidTitle
+            | Self::InvalidDescription
+            | Self::InvalidTags
+            | Self::InvalidContent
+            | Self::Spam(_) => true,
+            Self::UserIsBanned
+            | Self::UserCantPost
+            | Self::RateLimited
+            | Self::WrongChallengeAnswer
+            | Self::Server => false,
+        }
+    }
 }
 
 impl ResponseError for Error {
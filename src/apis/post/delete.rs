@@ -1,7 +1,12 @@
-use crate::db::fetch_contentless_post_by_id;
+use crate::db::{
+    fetch_contentless_post_by_id, insert_mod_action, resolve_reports, soft_delete_post,
+};
 use crate::session::auth;
 use crate::session::User;
 use crate::types::id::Id;
+use crate::types::mod_action::{reason::Reason, Kind, NewModAction, TargetType};
+use crate::types::report::Resolution;
+use crate::webhook;
 use crate::State;
 use actix_web::{
     http::{header, StatusCode},
@@ -10,7 +15,6 @@ use actix_web::{
     HttpRequest, HttpResponse, ResponseError,
 };
 use serde::Deserialize;
-use sqlx::mysql::{MySqlPool, MySqlQueryResult};
 use thiserror::Error;
 
 #[derive(Debug, Error, Copy, Clone)]
@@ -32,6 +36,8 @@ pub enum ErrorKind {
     UserCantDeletePost,
     #[error("Post title and confirmation string do not match")]
     InvalidConfirmation,
+    #[error("Invalid reason")]
+    InvalidReason,
     #[error("Server error")]
     Server,
 }
@@ -40,6 +46,7 @@ pub enum ErrorKind {
 pub struct Request {
     pub post_id: Id,
     pub confirm: String,
+    pub reason: Option<String>,
 }
 
 #[post("/api/post/delete")]
@@ -62,15 +69,24 @@ pub async fn service(
 
     service_inner(state, user, request)
         .await
-        .map_err(|err| Error::new(err, post_id))
+        .map_err(|err| Error::new(err, post_id))?;
+
+    let location = format!("/post/{post_id}");
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, location))
+        .finish();
+
+    Ok(response)
 }
 
-async fn service_inner(
+// Also used by `/api/v1/posts/{id}`
+pub async fn service_inner(
     state: Data<State>,
     user: User,
     request: Request,
-) -> Result<HttpResponse, ErrorKind> {
-    let post = fetch_contentless_post_by_id(&state.db_pool, request.post_id, &user)
+) -> Result<(), ErrorKind> {
+    let post = fetch_contentless_post_by_id(&state.db_pool, request.post_id, &user, &state.config)
         .await?
         .ok_or(ErrorKind::UserCantDeletePost)?;
 
@@ -82,28 +98,49 @@ async fn service_inner(
         return Err(ErrorKind::InvalidConfirmation);
     }
 
-    delete_post(&state.db_pool, request.post_id).await?;
+    let reason =
+        Reason::parse(request.reason.unwrap_or_default()).map_err(|_| ErrorKind::InvalidReason)?;
+
+    let mut tx = state.db_pool.begin().await?;
+
+    insert_mod_action(
+        &mut tx,
+        &NewModAction {
+            actor_user_id: user.id,
+            kind: Kind::DeletePost,
+            target_type: TargetType::Post,
+            target_id: post.id,
+            target_label: &post.title,
+            reason: &reason,
+            details: "",
+        },
+    )
+    .await?;
 
-    let location = format!("/post/{}", request.post_id);
+    soft_delete_post(&mut tx, request.post_id, user.id, &reason).await?;
 
-    let response = HttpResponse::SeeOther()
-        .append_header((header::LOCATION, location))
-        .finish();
+    webhook::post_deleted(
+        &mut tx,
+        &state.config.domain,
+        request.post_id,
+        &user,
+        &reason,
+    )
+    .await?;
+
+    // Deleting is the answer to whatever the item was reported for
+    resolve_reports(
+        &mut tx,
+        TargetType::Post,
+        request.post_id,
+        Resolution::Resolved,
+        user.id,
+    )
+    .await?;
 
-    Ok(response)
-}
+    tx.commit().await?;
 
-async fn delete_post(db_pool: &MySqlPool, post_id: Id) -> sqlx::Result<MySqlQueryResult> {
-    sqlx::query!(
-        "
-        DELETE FROM posts
-        WHERE
-            id = ?
-        ",
-        post_id,
-    )
-    .execute(db_pool)
-    .await
+    Ok(())
 }
 
 impl From<sqlx::Error> for ErrorKind {
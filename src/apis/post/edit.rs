@@ -1,6 +1,14 @@
-use crate::db::insert_post_tags;
+use crate::config::MarkdownConfig;
+use crate::db::{
+    check_post_spam, fetch_raw_contentless_post_by_id, insert_original_post_revision,
+    insert_post_revision, insert_post_tags,
+};
+use crate::flash::Flash;
+use crate::policy::{self, Action, Authored, Resource};
+use crate::render;
 use crate::session::auth;
 use crate::session::User;
+use crate::spam::{self, Rule as SpamRule};
 use crate::types::id::Id;
 use crate::types::post::{content::Content, description::Description, tags::Tags, title::Title};
 use crate::State;
@@ -11,7 +19,8 @@ use actix_web::{
     HttpRequest, HttpResponse, ResponseError,
 };
 use serde::Deserialize;
-use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use sqlx::mysql::{MySql, MySqlQueryResult};
+use sqlx::Transaction;
 use thiserror::Error;
 
 #[derive(Debug, Error, Copy, Clone)]
@@ -39,6 +48,8 @@ pub enum ErrorKind {
     InvalidTags,
     #[error("Invalid content")]
     InvalidContent,
+    #[error("{0}")]
+    Spam(SpamRule),
     #[error("Server error")]
     Server,
 }
@@ -69,34 +80,85 @@ pub async fn service(
     };
 
     let post_id = request.id;
+    let flash = Flash::new()
+        .field("title", &request.title)
+        .field("description", &request.description)
+        .field("tags", &request.tags)
+        .field("content", &request.content);
+
+    if let Err(kind) = service_inner(state.clone(), user, request).await {
+        let err = Error::new(kind, post_id);
+
+        return Ok(state
+            .flash
+            .redirect(&err.as_location(), flash.error(kind.field(), err)));
+    }
+
+    let location = format!("/post/{post_id}");
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, location))
+        .finish();
 
-    service_inner(state, user, request)
-        .await
-        .map_err(|err| Error::new(err, post_id))
+    Ok(response)
 }
 
-async fn service_inner(
+// Also used by `/api/v1/posts/{id}`
+pub async fn service_inner(
     state: Data<State>,
     user: User,
     request: Request,
-) -> Result<HttpResponse, ErrorKind> {
-    if !user.role.can_edit_posts() {
+) -> Result<(), ErrorKind> {
+    let Some(post) = fetch_raw_contentless_post_by_id(&state.db_pool, request.id).await? else {
+        return Err(ErrorKind::UserCantEditPost);
+    };
+
+    let authored = Authored {
+        posted_by_user_id: post.posted_by_user_id,
+        posted_at: post.posted_at,
+    };
+
+    if !policy::can(&user, Action::Edit, Resource::Post(authored), &state.config) {
         return Err(ErrorKind::UserCantEditPost);
     }
 
     let title = Title::parse(request.title).map_err(|_| ErrorKind::InvalidTitle)?;
     let description =
         Description::parse(request.description).map_err(|_| ErrorKind::InvalidDescription)?;
-    let tags = Tags::parse(request.tags).map_err(|_| ErrorKind::InvalidTags)?;
+    let tags = Tags::parse(request.tags, state.config.tags.max_count)
+        .map_err(|_| ErrorKind::InvalidTags)?;
     let content = Content::parse(request.content).map_err(|_| ErrorKind::InvalidContent)?;
 
+    // There is nothing to hold an edit back with, so it's rejected whatever `spam.action` is
+    if !policy::can(&user, Action::View, Resource::Held, &state.config) {
+        let text = spam::post_text(title.as_ref(), description.as_ref(), content.as_ref());
+
+        let spam_rule = check_post_spam(
+            &state.db_pool,
+            &state.spam_filter,
+            &state.config,
+            user.id,
+            &text,
+            Some(request.id),
+        )
+        .await?;
+
+        if let Some(rule) = spam_rule {
+            return Err(ErrorKind::Spam(rule));
+        }
+    }
+
+    let mut tx = state.db_pool.begin().await?;
+
+    insert_original_post_revision(&mut tx, request.id).await?;
+
     let update_post_result = update_post(
-        &state.db_pool,
+        &mut tx,
         request.id,
         &title,
         &description,
         &content,
-        &user,
+        &state.config.markdown,
     )
     .await?;
 
@@ -104,26 +166,24 @@ async fn service_inner(
         return Err(ErrorKind::UserCantEditPost);
     }
 
-    update_post_tags(&state.db_pool, &tags, request.id, user.id).await?;
+    insert_post_revision(&mut tx, request.id, user.id).await?;
 
-    let location = format!("/post/{}", request.id);
+    update_post_tags(&mut tx, &tags, request.id, user.id).await?;
 
-    let response = HttpResponse::SeeOther()
-        .append_header((header::LOCATION, location))
-        .finish();
+    tx.commit().await?;
 
-    Ok(response)
+    Ok(())
 }
 
 async fn update_post(
-    db_pool: &MySqlPool,
+    tx: &mut Transaction<'_, MySql>,
     id: Id,
     title: &Title,
     description: &Description,
     content: &Content,
-    user: &User,
+    markdown_config: &MarkdownConfig,
 ) -> sqlx::Result<MySqlQueryResult> {
-    let markdown_content = markdown::to_html(content.as_ref());
+    let markdown_content = render::markdown(content.as_ref(), markdown_config);
 
     sqlx::query!(
         "
@@ -133,35 +193,39 @@ async fn update_post(
             description = ?,
             content = ?,
             markdown_content = ?,
+            render_version = ?,
             modified_at = NOW()
         WHERE
             id = ?
-            AND posted_by_user_id = ?
+            AND deleted_at IS NULL
         ",
         title.as_ref(),
         description.as_ref(),
         content.as_ref(),
         markdown_content,
-        id,
-        user.id
+        render::version(markdown_config),
+        id
     )
-    .execute(db_pool)
+    .execute(&mut *tx)
     .await
 }
 
 async fn update_post_tags(
-    db_pool: &MySqlPool,
+    tx: &mut Transaction<'_, MySql>,
     tags: &Tags,
     post_id: Id,
     user_id: Id,
 ) -> sqlx::Result<()> {
-    delete_post_tags(db_pool, post_id).await?;
-    insert_post_tags(db_pool, tags, post_id, user_id).await?;
+    delete_post_tags(tx, post_id).await?;
+    insert_post_tags(tx, tags, post_id, user_id).await?;
 
     Ok(())
 }
 
-async fn delete_post_tags(db_pool: &MySqlPool, id: Id) -> sqlx::Result<MySqlQueryResult> {
+async fn delete_post_tags(
+    tx: &mut Transaction<'_, MySql>,
+    id: Id,
+) -> sqlx::Result<MySqlQueryResult> {
     sqlx::query!(
         "
         DELETE FROM post_tags
@@ -170,7 +234,7 @@ async fn delete_post_tags(db_pool: &MySqlPool, id: Id) -> sqlx::Result<MySqlQuer
         ",
         id
     )
-    .execute(db_pool)
+    .execute(&mut *tx)
     .await
 }
 
@@ -180,6 +244,19 @@ impl From<sqlx::Error> for ErrorKind {
     }
 }
 
+impl ErrorKind {
+    // The form field to highlight
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            Self::InvalidTitle => Some("title"),
+            Self::InvalidDescription => Some("description"),
+            Self::InvalidTags => Some("tags"),
+            Self::InvalidContent => Some("content"),
+            _ => None,
+        }
+    }
+}
+
 impl Error {
     pub fn as_location(&self) -> String {
         format!("/post/{}/edit?error={self}", self.post_id)
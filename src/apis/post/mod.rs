@@ -1,4 +1,6 @@
 pub mod anonymise;
+pub mod approve;
 pub mod create;
 pub mod delete;
 pub mod edit;
+pub mod restore;
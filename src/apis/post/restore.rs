@@ -0,0 +1,157 @@
+use crate::db::{fetch_contentless_post_by_id, insert_mod_action};
+use crate::session::auth;
+use crate::session::User;
+use crate::types::id::Id;
+use crate::types::mod_action::{reason::Reason, Kind, NewModAction, TargetType};
+use crate::State;
+use actix_web::{
+    http::{header, StatusCode},
+    post,
+    web::{Data, Form},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::Deserialize;
+use sqlx::mysql::{MySql, MySqlQueryResult};
+use sqlx::Transaction;
+use thiserror::Error;
+
+#[derive(Debug, Error, Copy, Clone)]
+#[error("{kind}")]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub post_id: Id,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, post_id: Id) -> Self {
+        Self { kind, post_id }
+    }
+}
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum ErrorKind {
+    #[error("You are not allowed to restore this post")]
+    UserCantRestorePost,
+    #[error("Invalid reason")]
+    InvalidReason,
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub post_id: Id,
+    pub reason: Option<String>,
+}
+
+#[post("/api/post/restore")]
+pub async fn service(
+    state: Data<State>,
+    req: HttpRequest,
+    Form(request): Form<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(user) = auth(&state.db_pool, &req).await else {
+        let location = format!("/sign-in?back=/post/{}/restore", request.post_id);
+
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, location))
+            .finish();
+
+        return Ok(response);
+    };
+
+    let post_id = request.post_id;
+
+    service_inner(state, user, request)
+        .await
+        .map_err(|err| Error::new(err, post_id))
+}
+
+async fn service_inner(
+    state: Data<State>,
+    user: User,
+    request: Request,
+) -> Result<HttpResponse, ErrorKind> {
+    let post = fetch_contentless_post_by_id(&state.db_pool, request.post_id, &user, &state.config)
+        .await?
+        .ok_or(ErrorKind::UserCantRestorePost)?;
+
+    if !post.is_restorable {
+        return Err(ErrorKind::UserCantRestorePost);
+    }
+
+    let reason =
+        Reason::parse(request.reason.unwrap_or_default()).map_err(|_| ErrorKind::InvalidReason)?;
+
+    let mut tx = state.db_pool.begin().await?;
+
+    insert_mod_action(
+        &mut tx,
+        &NewModAction {
+            actor_user_id: user.id,
+            kind: Kind::RestorePost,
+            target_type: TargetType::Post,
+            target_id: post.id,
+            target_label: &post.title,
+            reason: &reason,
+            details: "",
+        },
+    )
+    .await?;
+
+    restore_post(&mut tx, request.post_id).await?;
+
+    tx.commit().await?;
+
+    let location = format!("/post/{}", request.post_id);
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, location))
+        .finish();
+
+    Ok(response)
+}
+
+async fn restore_post(
+    tx: &mut Transaction<'_, MySql>,
+    post_id: Id,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        UPDATE posts
+        SET
+            deleted_at = NULL,
+            deleted_by_user_id = NULL,
+            deleted_reason = ''
+        WHERE
+            id = ?
+        ",
+        post_id
+    )
+    .execute(&mut *tx)
+    .await
+}
+
+impl From<sqlx::Error> for ErrorKind {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl Error {
+    pub fn as_location(&self) -> String {
+        format!("/post/{}/restore?error={self}", self.post_id)
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SEE_OTHER
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        HttpResponse::build(self.status_code())
+            .append_header((header::LOCATION, self.as_location()))
+            .finish()
+    }
+}
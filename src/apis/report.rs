@@ -0,0 +1,251 @@
+use crate::db::{fetch_comment_by_id, fetch_contentless_post_by_id};
+use crate::session::auth;
+use crate::session::User;
+use crate::types::id::Id;
+use crate::types::mod_action::TargetType;
+use crate::types::report::{details::Details, Category};
+use crate::State;
+use actix_web::{
+    http::{header, StatusCode},
+    post,
+    web::{Data, Form},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::Deserialize;
+use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use thiserror::Error;
+
+#[derive(Debug, Error, Clone)]
+#[error("{kind}")]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub target_type: String,
+    pub target_id: Id,
+}
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum ErrorKind {
+    #[error("You are not allowed to report this")]
+    UserCantReport,
+    #[error("You have already reported this")]
+    AlreadyReported,
+    #[error("Invalid category")]
+    InvalidCategory,
+    #[error("Invalid details")]
+    InvalidDetails,
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub target_type: String,
+    pub target_id: Id,
+    pub category: String,
+    pub details: Option<String>,
+}
+
+#[post("/api/report")]
+pub async fn service(
+    state: Data<State>,
+    req: HttpRequest,
+    Form(request): Form<Request>,
+) -> Result<HttpResponse, Error> {
+    let target_type = request.target_type.clone();
+    let target_id = request.target_id;
+
+    let Ok(user) = auth(&state.db_pool, &req).await else {
+        let location = format!("/sign-in?back={}", report_location(&target_type, target_id));
+
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, location))
+            .finish();
+
+        return Ok(response);
+    };
+
+    service_inner(state, user, request)
+        .await
+        .map_err(|kind| Error {
+            kind,
+            target_type,
+            target_id,
+        })
+}
+
+async fn service_inner(
+    state: Data<State>,
+    user: User,
+    request: Request,
+) -> Result<HttpResponse, ErrorKind> {
+    let target_type =
+        TargetType::from_str(&request.target_type).map_err(|_| ErrorKind::UserCantReport)?;
+
+    // Where to go back to once the report is filed
+    let location = match target_type {
+        TargetType::Post => {
+            let post = fetch_contentless_post_by_id(
+                &state.db_pool,
+                request.target_id,
+                &user,
+                &state.config,
+            )
+            .await?
+            .ok_or(ErrorKind::UserCantReport)?;
+
+            if !post.is_reportable {
+                return Err(ErrorKind::UserCantReport);
+            }
+
+            format!("/post/{}", post.id)
+        }
+        TargetType::Comment => {
+            let comment =
+                fetch_comment_by_id(&state.db_pool, request.target_id, &user, &state.config)
+                    .await?
+                    .ok_or(ErrorKind::UserCantReport)?;
+
+            if !comment.is_reportable {
+                return Err(ErrorKind::UserCantReport);
+            }
+
+            format!(
+                "/post/{}?start_id={}#{}",
+                comment.post_id, comment.id, comment.id
+            )
+        }
+        TargetType::User | TargetType::Tag | TargetType::Role => {
+            return Err(ErrorKind::UserCantReport)
+        }
+    };
+
+    let category = Category::from_str(&request.category).map_err(|_| ErrorKind::InvalidCategory)?;
+    let details = Details::parse(request.details.unwrap_or_default())
+        .map_err(|_| ErrorKind::InvalidDetails)?;
+
+    if fetch_has_open_report(&state.db_pool, target_type, request.target_id, user.id).await? {
+        return Err(ErrorKind::AlreadyReported);
+    }
+
+    insert_report(
+        &state.db_pool,
+        target_type,
+        request.target_id,
+        category,
+        &details,
+        user.id,
+    )
+    .await?;
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, location))
+        .finish();
+
+    Ok(response)
+}
+
+async fn fetch_has_open_report(
+    db_pool: &MySqlPool,
+    target_type: TargetType,
+    target_id: Id,
+    user_id: Id,
+) -> sqlx::Result<bool> {
+    #[derive(Debug)]
+    struct Report {
+        id: Id,
+    }
+
+    sqlx::query_as!(
+        Report,
+        "
+        SELECT
+            id
+        FROM reports
+        WHERE
+            target_type = ?
+            AND target_id = ?
+            AND reported_by_user_id = ?
+            AND resolved_at IS NULL
+        LIMIT 1
+        ",
+        target_type.as_str(),
+        target_id,
+        user_id
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map(|result| result.map(|report| report.id).is_some())
+}
+
+async fn insert_report(
+    db_pool: &MySqlPool,
+    target_type: TargetType,
+    target_id: Id,
+    category: Category,
+    details: &Details,
+    user_id: Id,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        INSERT INTO reports
+        (
+            target_type,
+            target_id,
+            category,
+            details,
+            reported_by_user_id,
+            reported_at
+        )
+        VALUES
+        (
+            ?,
+            ?,
+            ?,
+            ?,
+            ?,
+            NOW()
+        )
+        ",
+        target_type.as_str(),
+        target_id,
+        category.as_str(),
+        details.as_ref(),
+        user_id
+    )
+    .execute(db_pool)
+    .await
+}
+
+fn report_location(target_type: &str, target_id: Id) -> String {
+    match TargetType::from_str(target_type) {
+        Ok(TargetType::Comment) => format!("/comment/{target_id}/report"),
+        _ => format!("/post/{target_id}/report"),
+    }
+}
+
+impl From<sqlx::Error> for ErrorKind {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl Error {
+    pub fn as_location(&self) -> String {
+        format!(
+            "{}?error={self}",
+            report_location(&self.target_type, self.target_id)
+        )
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SEE_OTHER
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        HttpResponse::build(self.status_code())
+            .append_header((header::LOCATION, self.as_location()))
+            .finish()
+    }
+}
@@ -0,0 +1,71 @@
+use crate::jobs;
+use crate::policy::{self, Action, Resource};
+use crate::session::auth;
+use crate::State;
+use actix_web::{
+    http::{header, StatusCode},
+    post,
+    web::Data,
+    HttpRequest, HttpResponse, ResponseError,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("You are not allowed to re-render content")]
+    UserCantRerender,
+    #[error("Content is already being re-rendered")]
+    AlreadyRunning,
+}
+
+// Starts re-rendering outdated posts and comments, the dashboard shows how far it got
+#[post("/api/rerender")]
+pub async fn service(state: Data<State>, req: HttpRequest) -> Result<HttpResponse, Error> {
+    let Ok(user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/admin"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    if !policy::can(&user, Action::Admin, Resource::Site, &state.config) {
+        return Err(Error::UserCantRerender);
+    }
+
+    if !state.rerender.try_start() {
+        return Err(Error::AlreadyRunning);
+    }
+
+    let db_pool = state.db_pool.clone();
+    let config = state.config.markdown.clone();
+    let status = state.rerender.clone();
+
+    tokio::spawn(async move {
+        jobs::rerender::run(&db_pool, &config, &status, |_| {}).await;
+    });
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, "/admin"))
+        .finish();
+
+    Ok(response)
+}
+
+impl Error {
+    pub fn as_location(&self) -> String {
+        format!("/admin?error={self}")
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SEE_OTHER
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        HttpResponse::build(self.status_code())
+            .append_header((header::LOCATION, self.as_location()))
+            .finish()
+    }
+}
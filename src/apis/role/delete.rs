@@ -0,0 +1,127 @@
+use crate::db::{fetch_roles, insert_mod_action};
+use crate::policy::{self, Action, Resource};
+use crate::session::auth;
+use crate::types::id::Id;
+use crate::types::mod_action::{reason::Reason, Kind, NewModAction, TargetType};
+use crate::State;
+use actix_web::{
+    http::{header, StatusCode},
+    post,
+    web::{Data, Form},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::Deserialize;
+use sqlx::mysql::{MySql, MySqlQueryResult};
+use sqlx::Transaction;
+use thiserror::Error;
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("You are not allowed to manage roles")]
+    UserCantManageRoles,
+    #[error("Role not found")]
+    RoleNotFound,
+    #[error("Builtin roles can't be deleted")]
+    CantDeleteBuiltinRole,
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub id: Id,
+}
+
+#[post("/api/role/delete")]
+pub async fn service(
+    state: Data<State>,
+    req: HttpRequest,
+    Form(request): Form<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/admin/roles"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    if !policy::can(&user, Action::Admin, Resource::Site, &state.config) {
+        return Err(Error::UserCantManageRoles);
+    }
+
+    let roles = fetch_roles(&state.db_pool).await?;
+
+    let role = roles
+        .iter()
+        .find(|role| role.id == request.id)
+        .ok_or(Error::RoleNotFound)?;
+
+    if role.is_builtin {
+        return Err(Error::CantDeleteBuiltinRole);
+    }
+
+    let mut tx = state.db_pool.begin().await?;
+
+    // Assignments go with it through `ON DELETE CASCADE`
+    delete_role(&mut tx, role.id).await?;
+
+    insert_mod_action(
+        &mut tx,
+        &NewModAction {
+            actor_user_id: user.id,
+            kind: Kind::DeleteRole,
+            target_type: TargetType::Role,
+            target_id: role.id,
+            target_label: &role.name,
+            reason: &Reason::default(),
+            details: "",
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, "/admin/roles"))
+        .finish();
+
+    Ok(response)
+}
+
+async fn delete_role(tx: &mut Transaction<'_, MySql>, id: Id) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        DELETE FROM roles
+        WHERE
+            id = ?
+        ",
+        id
+    )
+    .execute(&mut *tx)
+    .await
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl Error {
+    pub fn as_location(&self) -> String {
+        format!("/admin/roles?error={self}")
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SEE_OTHER
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        HttpResponse::build(self.status_code())
+            .append_header((header::LOCATION, self.as_location()))
+            .finish()
+    }
+}
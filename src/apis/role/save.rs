@@ -0,0 +1,282 @@
+use crate::apis::is_checked;
+use crate::db::{fetch_roles, insert_mod_action};
+use crate::policy::{self, Action, Resource};
+use crate::session::auth;
+use crate::types::id::Id;
+use crate::types::mod_action::{reason::Reason, Kind, NewModAction, TargetType};
+use crate::types::user::role::{name::Name, Permissions, ADMIN};
+use crate::State;
+use actix_web::{
+    http::{header, StatusCode},
+    post,
+    web::{Data, Form},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::Deserialize;
+use sqlx::mysql::{MySql, MySqlQueryResult};
+use sqlx::Transaction;
+use thiserror::Error;
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("You are not allowed to manage roles")]
+    UserCantManageRoles,
+    #[error("Invalid role name")]
+    InvalidName,
+    #[error("Role name already taken")]
+    NameTaken,
+    #[error("Role not found")]
+    RoleNotFound,
+    #[error("Builtin roles can't be renamed")]
+    CantRenameBuiltinRole,
+    #[error("The Admin role must keep the admin permission")]
+    CantDemoteAdminRole,
+    #[error("The permissions of this role can't be changed")]
+    CantChangeFixedRole,
+    #[error("Server error")]
+    Server,
+}
+
+// No `id` creates a new role
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub id: Option<Id>,
+    pub name: String,
+
+    pub can_post: Option<String>,
+    pub can_comment: Option<String>,
+    pub can_reply: Option<String>,
+    pub can_edit_posts: Option<String>,
+    pub can_edit_comments: Option<String>,
+    pub can_edit_self: Option<String>,
+    pub can_anonymise_posts: Option<String>,
+    pub can_anonymise_comments: Option<String>,
+    pub can_delete_posts: Option<String>,
+    pub can_delete_comments: Option<String>,
+    pub can_admin: Option<String>,
+}
+
+#[post("/api/role/save")]
+pub async fn service(
+    state: Data<State>,
+    req: HttpRequest,
+    Form(request): Form<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/admin/roles"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    if !policy::can(&user, Action::Admin, Resource::Site, &state.config) {
+        return Err(Error::UserCantManageRoles);
+    }
+
+    let name = Name::parse(request.name).map_err(|_| Error::InvalidName)?;
+
+    let permissions = Permissions {
+        can_post: is_checked(request.can_post),
+        can_comment: is_checked(request.can_comment),
+        can_reply: is_checked(request.can_reply),
+        can_edit_posts: is_checked(request.can_edit_posts),
+        can_edit_comments: is_checked(request.can_edit_comments),
+        can_edit_self: is_checked(request.can_edit_self),
+        can_anonymise_posts: is_checked(request.can_anonymise_posts),
+        can_anonymise_comments: is_checked(request.can_anonymise_comments),
+        can_delete_posts: is_checked(request.can_delete_posts),
+        can_delete_comments: is_checked(request.can_delete_comments),
+        can_admin: is_checked(request.can_admin),
+    };
+
+    let roles = fetch_roles(&state.db_pool).await?;
+
+    if roles
+        .iter()
+        .any(|role| role.name == name.as_ref() && Some(role.id) != request.id)
+    {
+        return Err(Error::NameTaken);
+    }
+
+    let mut tx = state.db_pool.begin().await?;
+
+    let (kind, id) = match request.id {
+        Some(id) => {
+            let role = roles
+                .iter()
+                .find(|role| role.id == id)
+                .ok_or(Error::RoleNotFound)?;
+
+            if role.is_builtin && role.name != name.as_ref() {
+                return Err(Error::CantRenameBuiltinRole);
+            }
+
+            // Otherwise nobody could ever admin again
+            if role.name == ADMIN && !permissions.can_admin {
+                return Err(Error::CantDemoteAdminRole);
+            }
+
+            if role.has_fixed_permissions && permissions != role.permissions {
+                return Err(Error::CantChangeFixedRole);
+            }
+
+            update_role(&mut tx, id, &name, &permissions).await?;
+
+            (Kind::EditRole, id)
+        }
+        None => {
+            let id = insert_role(&mut tx, &name, &permissions)
+                .await?
+                .last_insert_id();
+
+            (Kind::CreateRole, id)
+        }
+    };
+
+    insert_mod_action(
+        &mut tx,
+        &NewModAction {
+            actor_user_id: user.id,
+            kind,
+            target_type: TargetType::Role,
+            target_id: id,
+            target_label: name.as_ref(),
+            reason: &Reason::default(),
+            details: &permissions.names().join(", "),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, "/admin/roles"))
+        .finish();
+
+    Ok(response)
+}
+
+async fn insert_role(
+    tx: &mut Transaction<'_, MySql>,
+    name: &Name,
+    permissions: &Permissions,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        INSERT INTO roles
+        (
+            name,
+            can_post,
+            can_comment,
+            can_reply,
+            can_edit_posts,
+            can_edit_comments,
+            can_edit_self,
+            can_anonymise_posts,
+            can_anonymise_comments,
+            can_delete_posts,
+            can_delete_comments,
+            can_admin,
+            created_at
+        )
+        VALUES
+        (
+            ?,
+            ?,
+            ?,
+            ?,
+            ?,
+            ?,
+            ?,
+            ?,
+            ?,
+            ?,
+            ?,
+            ?,
+            NOW()
+        )
+        ",
+        name.as_ref(),
+        permissions.can_post,
+        permissions.can_comment,
+        permissions.can_reply,
+        permissions.can_edit_posts,
+        permissions.can_edit_comments,
+        permissions.can_edit_self,
+        permissions.can_anonymise_posts,
+        permissions.can_anonymise_comments,
+        permissions.can_delete_posts,
+        permissions.can_delete_comments,
+        permissions.can_admin
+    )
+    .execute(&mut *tx)
+    .await
+}
+
+async fn update_role(
+    tx: &mut Transaction<'_, MySql>,
+    id: Id,
+    name: &Name,
+    permissions: &Permissions,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        UPDATE roles
+        SET
+            name = ?,
+            can_post = ?,
+            can_comment = ?,
+            can_reply = ?,
+            can_edit_posts = ?,
+            can_edit_comments = ?,
+            can_edit_self = ?,
+            can_anonymise_posts = ?,
+            can_anonymise_comments = ?,
+            can_delete_posts = ?,
+            can_delete_comments = ?,
+            can_admin = ?
+        WHERE
+            id = ?
+        ",
+        name.as_ref(),
+        permissions.can_post,
+        permissions.can_comment,
+        permissions.can_reply,
+        permissions.can_edit_posts,
+        permissions.can_edit_comments,
+        permissions.can_edit_self,
+        permissions.can_anonymise_posts,
+        permissions.can_anonymise_comments,
+        permissions.can_delete_posts,
+        permissions.can_delete_comments,
+        permissions.can_admin,
+        id
+    )
+    .execute(&mut *tx)
+    .await
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl Error {
+    pub fn as_location(&self) -> String {
+        format!("/admin/roles?error={self}")
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SEE_OTHER
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        HttpResponse::build(self.status_code())
+            .append_header((header::LOCATION, self.as_location()))
+            .finish()
+    }
+}
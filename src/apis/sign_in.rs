@@ -38,12 +38,26 @@ pub async fn service(
     state: Data<State>,
     Form(request): Form<Request>,
 ) -> Result<HttpResponse, Error> {
-    let email = Email::parse(request.email).map_err(|_| Error::InvalidEmailOrPassword)?;
-    let password = Password::parse(request.password).map_err(|_| Error::InvalidEmailOrPassword)?;
-
     let remember_me = is_checked(request.remember_me);
 
-    let user = User::fetch_by_email(&state.db_pool, &email)
+    let user = verify(&state.db_pool, request.email, request.password).await?;
+
+    let location = request.back.unwrap_or("/".to_owned());
+
+    let response = HttpResponse::SeeOther()
+        .set_session(&state.config.domain, &user.session, remember_me)
+        .append_header((header::LOCATION, location))
+        .finish();
+
+    Ok(response)
+}
+
+// Also used by `/api/v1/auth/sign-in`
+pub async fn verify(db_pool: &MySqlPool, email: String, password: String) -> Result<User, Error> {
+    let email = Email::parse(email).map_err(|_| Error::InvalidEmailOrPassword)?;
+    let password = Password::parse(password).map_err(|_| Error::InvalidEmailOrPassword)?;
+
+    let user = User::fetch_by_email(db_pool, &email)
         .await?
         .ok_or(Error::InvalidEmailOrPassword)?;
 
@@ -53,14 +67,7 @@ pub async fn service(
         .verify_password(password.as_ref().as_bytes(), &parsed_hash)
         .map_err(|_| Error::InvalidEmailOrPassword)?;
 
-    let location = request.back.unwrap_or("/".to_owned());
-
-    let response = HttpResponse::SeeOther()
-        .set_session(&state.domain, &user.session, remember_me)
-        .append_header((header::LOCATION, location))
-        .finish();
-
-    Ok(response)
+    Ok(user)
 }
 
 #[derive(Debug)]
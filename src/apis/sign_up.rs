@@ -1,24 +1,32 @@
+use crate::db::insert_user_role;
+use crate::rate_limit::{self, Action as RateLimitAction, Requester};
+use crate::types::challenge::Challenge;
 use crate::types::user::{
     code::Code, email::Email, hashed_password::HashedPassword, password::Password,
-    password_pair::PasswordPair, role::Role as UserRole, session::Session, username::Username,
+    password_pair::PasswordPair, role::UNVERIFIED, session::Session, username::Username,
 };
 use crate::State;
 use actix_web::{
     http::{header, StatusCode},
     post,
     web::{Data, Form},
-    HttpResponse, ResponseError,
+    HttpRequest, HttpResponse, ResponseError,
 };
 use lettre::message::header::ContentType;
 use lettre::message::Mailbox;
 use lettre::transport::smtp::Error as SmtpError;
 use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
 use serde::Deserialize;
-use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use sqlx::mysql::{MySql, MySqlPool, MySqlQueryResult};
+use sqlx::Transaction;
 use thiserror::Error;
 
 #[derive(Debug, Error, Copy, Clone)]
 pub enum Error {
+    #[error("Too many sign-ups from your network, try again later")]
+    RateLimited,
+    #[error("Wrong answer to the question, please try again")]
+    WrongChallengeAnswer,
     #[error("Invalid username")]
     InvalidUsername,
     #[error("Invalid email")]
@@ -45,13 +53,40 @@ pub struct Request {
     pub email: String,
     pub password: String,
     pub password_repeat: String,
+    pub challenge_id: Option<String>,
+    pub challenge_answer: Option<String>,
 }
 
 #[post("/api/sign-up")]
 pub async fn service(
     state: Data<State>,
+    req: HttpRequest,
     Form(request): Form<Request>,
 ) -> Result<HttpResponse, Error> {
+    // Every attempt counts, so the limit also slows down probing for taken usernames
+    let requester = Requester {
+        user_id: None,
+        account_age_days: 0,
+        ip: rate_limit::client_ip(&req, state.config.rate_limit.is_behind_proxy),
+    };
+
+    if state
+        .rate_limiter
+        .check(RateLimitAction::SignUp, &requester)
+        .is_err()
+    {
+        return Err(Error::RateLimited);
+    }
+
+    if state.config.challenge.on_sign_up {
+        verify_challenge(
+            &state,
+            request.challenge_id.as_deref(),
+            request.challenge_answer.as_deref(),
+        )
+        .await?;
+    }
+
     let username = Username::parse(request.username).map_err(|_| Error::InvalidUsername)?;
     let email = Email::parse(request.email).map_err(|_| Error::InvalidEmail)?;
 
@@ -79,7 +114,7 @@ pub async fn service(
 
     // TODO: Check for bounce
     send_email(
-        &state.domain,
+        &state.config.domain,
         state.email_from.clone(),
         &state.mailer,
         &email,
@@ -87,15 +122,22 @@ pub async fn service(
     )
     .await?;
 
-    insert_user(
-        &state.db_pool,
+    let mut tx = state.db_pool.begin().await?;
+
+    let user_id = insert_user(
+        &mut tx,
         &username,
         &email,
         &hashed_password,
         &session,
         &code,
     )
-    .await?;
+    .await?
+    .last_insert_id();
+
+    insert_user_role(&mut tx, user_id, UNVERIFIED).await?;
+
+    tx.commit().await?;
 
     let response = HttpResponse::SeeOther()
         .append_header((header::LOCATION, "/email-verification"))
@@ -104,8 +146,31 @@ pub async fn service(
     Ok(response)
 }
 
+async fn verify_challenge(
+    state: &State,
+    id: Option<&str>,
+    answer: Option<&str>,
+) -> Result<(), Error> {
+    let (Some(id), Some(answer)) = (id, answer) else {
+        return Err(Error::WrongChallengeAnswer);
+    };
+
+    if !Challenge::verify(
+        &state.db_pool,
+        id,
+        answer,
+        state.config.challenge.expire_minutes,
+    )
+    .await?
+    {
+        return Err(Error::WrongChallengeAnswer);
+    }
+
+    Ok(())
+}
+
 async fn insert_user(
-    db_pool: &MySqlPool,
+    tx: &mut Transaction<'_, MySql>,
     username: &Username,
     email: &Email,
     hashed_password: &HashedPassword,
@@ -119,11 +184,8 @@ async fn insert_user(
             username,
             email,
             password,
-            role,
             session,
             code,
-            mini_pfp_file_id,
-            pfp_file_id,
             name,
             description,
             joined_at
@@ -135,9 +197,6 @@ async fn insert_user(
             ?,
             ?,
             ?,
-            ?,
-            NULL,
-            NULL,
             '',
             '',
             NOW()
@@ -146,11 +205,10 @@ async fn insert_user(
         username.as_ref(),
         email.as_ref(),
         hashed_password.as_ref(),
-        UserRole::default().as_str(),
         session.as_ref(),
         code.as_ref(),
     )
-    .execute(db_pool)
+    .execute(&mut *tx)
     .await
 }
 
@@ -0,0 +1,188 @@
+use crate::apis::is_checked;
+use crate::db::insert_mod_action;
+use crate::policy::{self, Action, Resource};
+use crate::session::auth;
+use crate::session::User;
+use crate::types::id::Id;
+use crate::types::mod_action::{reason::Reason, Kind, NewModAction, TargetType};
+use crate::State;
+use actix_web::{
+    http::{header, StatusCode},
+    post,
+    web::{Data, Form},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::Deserialize;
+use sqlx::mysql::{MySql, MySqlPool, MySqlQueryResult};
+use sqlx::Transaction;
+use thiserror::Error;
+
+#[derive(Debug, Clone, Error)]
+#[error("{kind}")]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub tag: String,
+}
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum ErrorKind {
+    #[error("You are not allowed to admin tags")]
+    UserCantAdmin,
+    #[error("Tag not found")]
+    TagNotFound,
+    #[error("Invalid reason")]
+    InvalidReason,
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub tag: String,
+    pub requires_approval: Option<String>,
+    pub reason: Option<String>,
+}
+
+#[post("/api/tag/admin")]
+pub async fn service(
+    state: Data<State>,
+    req: HttpRequest,
+    Form(request): Form<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(user) = auth(&state.db_pool, &req).await else {
+        let location = format!("/sign-in?back=/tag/{}", urlencoding::encode(&request.tag));
+
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, location))
+            .finish();
+
+        return Ok(response);
+    };
+
+    let tag = request.tag.clone();
+
+    service_inner(state, request, user)
+        .await
+        .map_err(|kind| Error { kind, tag })
+}
+
+async fn service_inner(
+    state: Data<State>,
+    request: Request,
+    user: User,
+) -> Result<HttpResponse, ErrorKind> {
+    if !policy::can(&user, Action::Admin, Resource::Site, &state.config) {
+        return Err(ErrorKind::UserCantAdmin);
+    }
+
+    let tag = fetch_tag(&state.db_pool, &request.tag)
+        .await?
+        .ok_or(ErrorKind::TagNotFound)?;
+
+    let reason =
+        Reason::parse(request.reason.unwrap_or_default()).map_err(|_| ErrorKind::InvalidReason)?;
+
+    let requires_approval = is_checked(request.requires_approval);
+
+    let mut tx = state.db_pool.begin().await?;
+
+    // Nothing to do or log if the flag is already set that way
+    if requires_approval != tag.requires_approval {
+        set_tag_requires_approval(&mut tx, tag.id, requires_approval).await?;
+
+        insert_mod_action(
+            &mut tx,
+            &NewModAction {
+                actor_user_id: user.id,
+                kind: if requires_approval {
+                    Kind::RequireApproval
+                } else {
+                    Kind::WaiveApproval
+                },
+                target_type: TargetType::Tag,
+                target_id: tag.id,
+                target_label: &request.tag,
+                reason: &reason,
+                details: "",
+            },
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    let location = format!("/tag/{}", urlencoding::encode(&request.tag));
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, location))
+        .finish();
+
+    Ok(response)
+}
+
+#[derive(Debug)]
+struct Tag {
+    id: Id,
+    requires_approval: bool,
+}
+
+async fn fetch_tag(db_pool: &MySqlPool, tag: &str) -> sqlx::Result<Option<Tag>> {
+    sqlx::query_as!(
+        Tag,
+        "
+        SELECT
+            id,
+            requires_approval AS `requires_approval: bool`
+        FROM tags
+        WHERE
+            name = ?
+        ",
+        tag
+    )
+    .fetch_optional(db_pool)
+    .await
+}
+
+async fn set_tag_requires_approval(
+    tx: &mut Transaction<'_, MySql>,
+    tag_id: Id,
+    requires_approval: bool,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        UPDATE tags
+        SET
+            requires_approval = ?
+        WHERE
+            id = ?
+        ",
+        requires_approval,
+        tag_id
+    )
+    .execute(&mut *tx)
+    .await
+}
+
+impl From<sqlx::Error> for ErrorKind {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl Error {
+    pub fn as_location(&self) -> String {
+        format!("/tag/{}?error={self}", urlencoding::encode(&self.tag))
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SEE_OTHER
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        HttpResponse::build(self.status_code())
+            .append_header((header::LOCATION, self.as_location()))
+            .finish()
+    }
+}
@@ -0,0 +1,254 @@
+use crate::apis::is_checked;
+use crate::flash::Flash;
+use crate::session::auth;
+use crate::session::User;
+use crate::types::api_token::{
+    self,
+    name::Name,
+    scope::{Scope, Scopes},
+};
+use crate::types::id::Id;
+use crate::types::lifetime::Lifetime;
+use crate::State;
+use crate::LIQUID_PARSER;
+use actix_web::{
+    http::{header, StatusCode},
+    post,
+    web::{Data, Form},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use lazy_static::lazy_static;
+use liquid::Template;
+use serde::Deserialize;
+use sqlx::mysql::MySqlPool;
+use thiserror::Error;
+
+pub const MAX_TOKENS_PER_USER: i64 = 20;
+
+#[derive(Debug, Clone, Error)]
+#[error("{kind}")]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub username: String,
+}
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum ErrorKind {
+    #[error("Invalid token name")]
+    InvalidName,
+    #[error("Pick at least one scope")]
+    NoScopes,
+    #[error("Invalid expiry")]
+    InvalidExpiry,
+    #[error("You have too many API tokens, revoke some first")]
+    TooManyTokens,
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub name: String,
+    pub scope_read: Option<String>,
+    pub scope_post: Option<String>,
+    pub scope_comment: Option<String>,
+    pub scope_moderate: Option<String>,
+    pub expiry: String,
+}
+
+// Tokens are always created for the signed in user.
+// The token is only ever shown on the page this responds with
+#[post("/api/token/create")]
+pub async fn service(
+    state: Data<State>,
+    req: HttpRequest,
+    Form(request): Form<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    let username = user.username.clone();
+    // Named apart from the profile's own name, both forms are on the same page
+    let flash = Flash::new().field("token_name", &request.name);
+
+    match service_inner(state.clone(), request, user).await {
+        Ok(response) => Ok(response),
+        Err(kind) => {
+            let err = Error::new(kind, username);
+            let location = err.as_location();
+
+            Ok(state
+                .flash
+                .redirect(&location, flash.error(kind.field(), err)))
+        }
+    }
+}
+
+async fn service_inner(
+    state: Data<State>,
+    request: Request,
+    user: User,
+) -> Result<HttpResponse, ErrorKind> {
+    let name = Name::parse(request.name).map_err(|_| ErrorKind::InvalidName)?;
+
+    let checked = [
+        (Scope::Read, request.scope_read),
+        (Scope::Post, request.scope_post),
+        (Scope::Comment, request.scope_comment),
+        (Scope::Moderate, request.scope_moderate),
+    ];
+
+    let scopes = Scopes::parse(
+        checked
+            .into_iter()
+            .filter_map(|(scope, checkbox)| is_checked(checkbox).then_some(scope)),
+    )
+    .map_err(|_| ErrorKind::NoScopes)?;
+
+    let expiry = Lifetime::parse(&request.expiry).map_err(|_| ErrorKind::InvalidExpiry)?;
+
+    if fetch_token_count(&state.db_pool, user.id).await? >= MAX_TOKENS_PER_USER {
+        return Err(ErrorKind::TooManyTokens);
+    }
+
+    let (token, token_hash) = api_token::generate();
+
+    insert_api_token(&state.db_pool, user.id, &name, &token_hash, &scopes, expiry).await?;
+
+    lazy_static! {
+        static ref TEMPLATE: Template = {
+            let template = include_str!("../../../templates/user/username/token.html");
+
+            LIQUID_PARSER.parse(template).unwrap()
+        };
+    }
+
+    let current_url = urlencoding::encode(&format!("/user/{}/edit", user.username)).into_owned();
+
+    let globals = liquid::object!({
+        "current_url": current_url,
+        "current_user": user,
+        "name": name.as_ref(),
+        "scopes": scopes.labels(),
+        "token": token,
+    });
+
+    let s = TEMPLATE.render(&globals).unwrap();
+
+    // Keeps the token out of caches and the back button
+    let response = HttpResponse::Ok()
+        .append_header((header::CACHE_CONTROL, "no-store"))
+        .body(s);
+
+    Ok(response)
+}
+
+async fn fetch_token_count(db_pool: &MySqlPool, user_id: Id) -> sqlx::Result<i64> {
+    #[derive(Debug)]
+    struct Tokens {
+        count: i64,
+    }
+
+    sqlx::query_as!(
+        Tokens,
+        "
+        SELECT
+            COUNT(*) AS `count!: i64`
+        FROM api_tokens
+        WHERE
+            user_id = ?
+        ",
+        user_id
+    )
+    .fetch_one(db_pool)
+    .await
+    .map(|tokens| tokens.count)
+}
+
+async fn insert_api_token(
+    db_pool: &MySqlPool,
+    user_id: Id,
+    name: &Name,
+    token_hash: &str,
+    scopes: &Scopes,
+    expiry: Lifetime,
+) -> sqlx::Result<()> {
+    sqlx::query!(
+        "
+        INSERT INTO api_tokens
+        (
+            user_id,
+            name,
+            token_hash,
+            scopes,
+            created_at,
+            expires_at,
+            last_used_at
+        )
+        VALUES
+        (
+            ?,
+            ?,
+            ?,
+            ?,
+            NOW(),
+            NOW() + INTERVAL ? DAY,
+            NULL
+        )
+        ",
+        user_id,
+        name.as_ref(),
+        token_hash,
+        scopes.as_string(),
+        expiry.days()
+    )
+    .execute(db_pool)
+    .await?;
+
+    Ok(())
+}
+
+impl From<sqlx::Error> for ErrorKind {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl ErrorKind {
+    // The form field to highlight
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            Self::InvalidName => Some("token_name"),
+            Self::NoScopes => Some("scopes"),
+            Self::InvalidExpiry => Some("expiry"),
+            _ => None,
+        }
+    }
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, username: String) -> Self {
+        Self { kind, username }
+    }
+
+    pub fn as_location(&self) -> String {
+        format!("/user/{}/edit?error={self}", self.username)
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SEE_OTHER
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        HttpResponse::build(self.status_code())
+            .append_header((header::LOCATION, self.as_location()))
+            .finish()
+    }
+}
@@ -0,0 +1,105 @@
+use crate::session::auth;
+use crate::types::id::Id;
+use crate::State;
+use actix_web::{
+    http::{header, StatusCode},
+    post,
+    web::{Data, Form},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::Deserialize;
+use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Error)]
+#[error("{kind}")]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub username: String,
+}
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum ErrorKind {
+    #[error("API token not found")]
+    TokenNotFound,
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub id: Id,
+}
+
+#[post("/api/token/revoke")]
+pub async fn service(
+    state: Data<State>,
+    req: HttpRequest,
+    Form(request): Form<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    // Scoped to the owner, someone else's id just won't match
+    let result = delete_api_token(&state.db_pool, request.id, user.id)
+        .await
+        .map_err(|_| Error::new(ErrorKind::Server, user.username.clone()))?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::new(ErrorKind::TokenNotFound, user.username));
+    }
+
+    let location = format!("/user/{}/edit", user.username);
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, location))
+        .finish();
+
+    Ok(response)
+}
+
+async fn delete_api_token(
+    db_pool: &MySqlPool,
+    id: Id,
+    user_id: Id,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        DELETE FROM api_tokens
+        WHERE
+            id = ?
+            AND user_id = ?
+        ",
+        id,
+        user_id
+    )
+    .execute(db_pool)
+    .await
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, username: String) -> Self {
+        Self { kind, username }
+    }
+
+    pub fn as_location(&self) -> String {
+        format!("/user/{}/edit?error={self}", self.username)
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SEE_OTHER
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        HttpResponse::build(self.status_code())
+            .append_header((header::LOCATION, self.as_location()))
+            .finish()
+    }
+}
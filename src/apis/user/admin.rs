@@ -1,7 +1,17 @@
 use crate::apis::is_checked;
+use crate::db::{
+    ban_user, fetch_account_by_username, fetch_roles, fetch_user_restrictions, fetch_user_roles,
+    insert_mod_action, lift_ban,
+};
+use crate::policy::{self, Action, Resource};
 use crate::session::auth;
 use crate::session::User;
-use crate::types::user::role::Role as UserRole;
+use crate::types::ban;
+use crate::types::id::Id;
+use crate::types::lifetime::Lifetime;
+use crate::types::mod_action::{reason::Reason, Kind, NewModAction, TargetType};
+use crate::types::user::role::{BANNED, UNVERIFIED};
+use crate::webhook;
 use crate::State;
 use actix_web::{
     http::{header, StatusCode},
@@ -10,7 +20,9 @@ use actix_web::{
     HttpRequest, HttpResponse, ResponseError,
 };
 use serde::Deserialize;
-use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use sqlx::mysql::{MySql, MySqlQueryResult};
+use sqlx::Transaction;
+use std::collections::HashMap;
 use thiserror::Error;
 
 #[derive(Debug, Clone, Error)]
@@ -26,10 +38,28 @@ pub enum ErrorKind {
     UserCantAdmin,
     #[error("Invalid role")]
     InvalidRole,
+    #[error("Invalid reason")]
+    InvalidReason,
+    #[error("Invalid ban duration")]
+    InvalidBanDuration,
     #[error("Server error")]
     Server,
 }
 
+// Each one is logged as its own moderation action
+#[derive(Debug)]
+enum Change {
+    Ban(Lifetime),
+    LiftBan,
+    ShadowBan(bool),
+    RequireApproval(bool),
+    // The sorted names of the new roles
+    Roles(Vec<String>),
+    ResetName,
+    ResetDescription,
+    ResetPfp,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Request {
     pub username: String,
@@ -38,7 +68,18 @@ pub struct Request {
     pub reset_description: Option<String>,
     pub reset_pfp: Option<String>,
 
-    pub role: UserRole,
+    // Empty to leave the user unbanned, see `Lifetime::parse`
+    pub ban_duration: Option<String>,
+    pub lift_ban: Option<String>,
+
+    pub is_shadow_banned: Option<String>,
+    pub requires_approval: Option<String>,
+
+    pub reason: Option<String>,
+
+    // `role-{id}` checkboxes
+    #[serde(flatten)]
+    pub roles: HashMap<String, String>,
 }
 
 #[post("/api/user/admin")]
@@ -69,28 +110,161 @@ async fn service_inner(
     request: Request,
     user: User,
 ) -> Result<HttpResponse, ErrorKind> {
-    if !user.role.can_admin() {
+    let Some(account) = fetch_account_by_username(&state.db_pool, &request.username).await? else {
         return Err(ErrorKind::UserCantAdmin);
+    };
+
+    if !policy::can(&user, Action::Admin, Resource::User(account), &state.config) {
+        return Err(ErrorKind::UserCantAdmin);
+    }
+
+    let role_ids = checked_role_ids(&request.roles)?;
+    let roles = fetch_roles(&state.db_pool).await?;
+
+    let mut assigned_roles = Vec::with_capacity(role_ids.len());
+
+    for role_id in role_ids {
+        let Some(role) = roles.iter().find(|role| role.id == role_id) else {
+            return Err(ErrorKind::InvalidRole);
+        };
+
+        // Admins can't make other admins, bans go through `ban_duration`
+        if role.permissions.can_admin || role.name == UNVERIFIED || role.name == BANNED {
+            return Err(ErrorKind::InvalidRole);
+        }
+
+        assigned_roles.push(role);
     }
 
-    if let UserRole::Admin | UserRole::Unverified = request.role {
-        return Err(ErrorKind::InvalidRole);
+    let ban_duration = request
+        .ban_duration
+        .as_deref()
+        .filter(|ban_duration| !ban_duration.is_empty())
+        .map(Lifetime::parse)
+        .transpose()
+        .map_err(|_| ErrorKind::InvalidBanDuration)?;
+
+    let reason =
+        Reason::parse(request.reason.unwrap_or_default()).map_err(|_| ErrorKind::InvalidReason)?;
+
+    let mut current_role_names = fetch_user_roles(&state.db_pool, account.id)
+        .await?
+        .into_iter()
+        .map(|role| role.name)
+        .collect::<Vec<_>>();
+    current_role_names.sort();
+
+    let mut role_names = assigned_roles
+        .iter()
+        .map(|role| role.name.clone())
+        .collect::<Vec<_>>();
+    role_names.sort();
+
+    let role_ids = assigned_roles
+        .into_iter()
+        .map(|role| role.id)
+        .collect::<Vec<_>>();
+
+    let is_banned = current_role_names.iter().any(|role| role == BANNED);
+
+    let mut changes = Vec::new();
+
+    // Roles of a banned user are given back once the ban is lifted,
+    // so they can't be changed in the meantime
+    if let Some(ban_duration) = ban_duration {
+        changes.push(Change::Ban(ban_duration));
+    } else if is_banned {
+        if is_checked(request.lift_ban) {
+            changes.push(Change::LiftBan);
+        }
+    } else if role_names != current_role_names {
+        changes.push(Change::Roles(role_names));
     }
 
-    update_user_role(&state.db_pool, request.role, &request.username).await?;
+    let restrictions = fetch_user_restrictions(&state.db_pool, account.id).await?;
+
+    let is_shadow_banned = is_checked(request.is_shadow_banned);
+
+    if is_shadow_banned != restrictions.is_shadow_banned {
+        changes.push(Change::ShadowBan(is_shadow_banned));
+    }
+
+    let requires_approval = is_checked(request.requires_approval);
+
+    if requires_approval != restrictions.requires_approval {
+        changes.push(Change::RequireApproval(requires_approval));
+    }
 
     if is_checked(request.reset_name) {
-        reset_user_name(&state.db_pool, &request.username).await?;
+        changes.push(Change::ResetName);
     }
 
     if is_checked(request.reset_description) {
-        reset_user_description(&state.db_pool, &request.username).await?;
+        changes.push(Change::ResetDescription);
     }
 
     if is_checked(request.reset_pfp) {
-        reset_user_pfp(&state.db_pool, &request.username).await?;
+        changes.push(Change::ResetPfp);
+    }
+
+    let mut tx = state.db_pool.begin().await?;
+
+    for change in &changes {
+        match *change {
+            Change::Ban(ban_duration) => {
+                ban_user(&mut tx, account.id, user.id, &reason, ban_duration).await?;
+
+                webhook::user_banned(
+                    &mut tx,
+                    &state.config.domain,
+                    account.id,
+                    &request.username,
+                    &user,
+                    &reason,
+                    ban_duration,
+                )
+                .await?;
+            }
+            Change::LiftBan => {
+                lift_ban(&mut tx, account.id, Some(user.id)).await?;
+            }
+            Change::ShadowBan(is_shadow_banned) => {
+                set_user_shadow_ban(&mut tx, account.id, is_shadow_banned).await?;
+            }
+            Change::RequireApproval(requires_approval) => {
+                set_user_requires_approval(&mut tx, account.id, requires_approval).await?;
+            }
+            Change::Roles(_) => {
+                replace_user_roles(&mut tx, account.id, &role_ids).await?;
+            }
+            Change::ResetName => {
+                reset_user_name(&mut tx, &request.username).await?;
+            }
+            Change::ResetDescription => {
+                reset_user_description(&mut tx, &request.username).await?;
+            }
+            Change::ResetPfp => {
+                reset_user_pfp(&mut tx, &request.username).await?;
+            }
+        }
+
+        insert_mod_action(
+            &mut tx,
+            &NewModAction {
+                actor_user_id: user.id,
+                kind: change.kind(),
+                target_type: TargetType::User,
+                target_id: account.id,
+                target_label: &request.username,
+                reason: &reason,
+                details: &change.details(),
+            },
+        )
+        .await?;
     }
 
+    tx.commit().await?;
+
     let location = format!("/user/{}", request.username);
 
     let response = HttpResponse::SeeOther()
@@ -100,7 +274,10 @@ async fn service_inner(
     Ok(response)
 }
 
-async fn reset_user_name(db_pool: &MySqlPool, username: &str) -> sqlx::Result<MySqlQueryResult> {
+async fn reset_user_name(
+    tx: &mut Transaction<'_, MySql>,
+    username: &str,
+) -> sqlx::Result<MySqlQueryResult> {
     sqlx::query!(
         "
         UPDATE users
@@ -111,12 +288,12 @@ async fn reset_user_name(db_pool: &MySqlPool, username: &str) -> sqlx::Result<My
         ",
         username
     )
-    .execute(db_pool)
+    .execute(&mut *tx)
     .await
 }
 
 async fn reset_user_description(
-    db_pool: &MySqlPool,
+    tx: &mut Transaction<'_, MySql>,
     username: &str,
 ) -> sqlx::Result<MySqlQueryResult> {
     sqlx::query!(
@@ -129,47 +306,174 @@ async fn reset_user_description(
         ",
         username
     )
-    .execute(db_pool)
+    .execute(&mut *tx)
     .await
 }
 
-async fn reset_user_pfp(db_pool: &MySqlPool, username: &str) -> sqlx::Result<MySqlQueryResult> {
+async fn reset_user_pfp(
+    tx: &mut Transaction<'_, MySql>,
+    username: &str,
+) -> sqlx::Result<MySqlQueryResult> {
     // TODO: Delete old pfps
     sqlx::query!(
         "
-        UPDATE users
-        SET
-            pfp_file_id = NULL,
-            mini_pfp_file_id = NULL
+        DELETE user_pfp_variants
+        FROM user_pfp_variants
+            JOIN users ON user_pfp_variants.user_id = users.id
         WHERE
-            username = ?
+            users.username = ?
         ",
         username
     )
-    .execute(db_pool)
+    .execute(&mut *tx)
     .await
 }
 
-async fn update_user_role(
-    db_pool: &MySqlPool,
-    role: UserRole,
-    username: &str,
+// Also hides or shows everything they have already posted
+async fn set_user_shadow_ban(
+    tx: &mut Transaction<'_, MySql>,
+    user_id: Id,
+    is_shadow_banned: bool,
+) -> sqlx::Result<()> {
+    sqlx::query!(
+        "
+        UPDATE users
+        SET
+            is_shadow_banned = ?
+        WHERE
+            id = ?
+        ",
+        is_shadow_banned,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "
+        UPDATE posts
+        SET
+            is_shadowed = ?
+        WHERE
+            posted_by_user_id = ?
+        ",
+        is_shadow_banned,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "
+        UPDATE comments
+        SET
+            is_shadowed = ?
+        WHERE
+            posted_by_user_id = ?
+        ",
+        is_shadow_banned,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(())
+}
+
+async fn set_user_requires_approval(
+    tx: &mut Transaction<'_, MySql>,
+    user_id: Id,
+    requires_approval: bool,
 ) -> sqlx::Result<MySqlQueryResult> {
     sqlx::query!(
         "
         UPDATE users
         SET
-            role = ?
+            requires_approval = ?
         WHERE
-            username = ?
+            id = ?
         ",
-        role.as_str(),
-        username
+        requires_approval,
+        user_id
     )
-    .execute(db_pool)
+    .execute(&mut *tx)
     .await
 }
 
+fn checked_role_ids(fields: &HashMap<String, String>) -> Result<Vec<Id>, ErrorKind> {
+    fields
+        .iter()
+        .filter(|(_, value)| value.as_str() == "on")
+        .filter_map(|(key, _)| key.strip_prefix("role-"))
+        .map(|id| id.parse::<Id>().map_err(|_| ErrorKind::InvalidRole))
+        .collect()
+}
+
+async fn replace_user_roles(
+    tx: &mut Transaction<'_, MySql>,
+    user_id: Id,
+    role_ids: &[Id],
+) -> sqlx::Result<()> {
+    sqlx::query!(
+        "
+        DELETE FROM user_roles
+        WHERE
+            user_id = ?
+        ",
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    for role_id in role_ids {
+        sqlx::query!(
+            "
+            INSERT INTO user_roles
+            (
+                user_id,
+                role_id
+            )
+            VALUES
+            (
+                ?,
+                ?
+            )
+            ",
+            user_id,
+            role_id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(())
+}
+
+impl Change {
+    fn kind(&self) -> Kind {
+        match self {
+            Self::Ban(_) => Kind::BanUser,
+            Self::LiftBan => Kind::LiftBan,
+            Self::ShadowBan(true) => Kind::ShadowBanUser,
+            Self::ShadowBan(false) => Kind::LiftShadowBan,
+            Self::RequireApproval(true) => Kind::RequireApproval,
+            Self::RequireApproval(false) => Kind::WaiveApproval,
+            Self::Roles(_) => Kind::ChangeRoles,
+            Self::ResetName => Kind::ResetName,
+            Self::ResetDescription => Kind::ResetDescription,
+            Self::ResetPfp => Kind::ResetPfp,
+        }
+    }
+
+    fn details(&self) -> String {
+        match self {
+            Self::Ban(ban_duration) => ban::duration_label(*ban_duration),
+            Self::Roles(role_names) => role_names.join(", "),
+            _ => String::new(),
+        }
+    }
+}
+
 impl From<sqlx::Error> for ErrorKind {
     fn from(_: sqlx::Error) -> Self {
         Self::Server
@@ -0,0 +1,366 @@
+use crate::db::{
+    ban_user, delete_user_role, fetch_roles, fetch_user_roles, insert_mod_action, insert_user_role,
+    lift_ban,
+};
+use crate::policy::{self, Account, Action, Resource};
+use crate::session::auth;
+use crate::session::User;
+use crate::types::ban;
+use crate::types::id::Id;
+use crate::types::lifetime::Lifetime;
+use crate::types::mod_action::{reason::Reason, Kind, NewModAction, TargetType};
+use crate::types::user::role::{Permissions, Role, BANNED, UNVERIFIED};
+use crate::webhook;
+use crate::State;
+use actix_web::{
+    http::{header, StatusCode},
+    post,
+    web::{Data, Form},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::Deserialize;
+use sqlx::mysql::MySqlPool;
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("You are not allowed to manage these users")]
+    UserCantAdmin,
+    #[error("No users selected")]
+    NoUsersSelected,
+    #[error("Too many users selected")]
+    TooManyUsers,
+    #[error("Invalid operation")]
+    InvalidOperation,
+    #[error("Invalid role")]
+    InvalidRole,
+    #[error("Invalid ban duration")]
+    InvalidBanDuration,
+    #[error("Invalid reason")]
+    InvalidReason,
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Operation {
+    AddRole(Id),
+    RemoveRole(Id),
+    Ban(Lifetime),
+    LiftBan,
+}
+
+// `Operation` with its role looked up
+#[derive(Debug)]
+enum Change {
+    AddRole(String),
+    RemoveRole(String),
+    Ban(Lifetime),
+    LiftBan,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    // `/admin/users` with the filters the admin came from
+    pub back: String,
+
+    pub operation: String,
+    pub role_id: Option<String>,
+    pub ban_duration: Option<String>,
+    pub reason: Option<String>,
+
+    // `user-{id}` checkboxes
+    #[serde(flatten)]
+    pub users: HashMap<String, String>,
+}
+
+#[derive(Debug)]
+struct Target {
+    account: Account,
+    username: String,
+    role_names: Vec<String>,
+}
+
+#[post("/api/user/bulk")]
+pub async fn service(
+    state: Data<State>,
+    req: HttpRequest,
+    Form(request): Form<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/admin/users"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    service_inner(state, request, user).await
+}
+
+async fn service_inner(
+    state: Data<State>,
+    request: Request,
+    user: User,
+) -> Result<HttpResponse, Error> {
+    if !policy::can(&user, Action::Admin, Resource::Site, &state.config) {
+        return Err(Error::UserCantAdmin);
+    }
+
+    let user_ids = checked_user_ids(&request.users)?;
+
+    if user_ids.is_empty() {
+        return Err(Error::NoUsersSelected);
+    }
+
+    if user_ids.len() as u64 > state.config.page.max_limit {
+        return Err(Error::TooManyUsers);
+    }
+
+    let operation = parse_operation(&request)?;
+
+    let reason =
+        Reason::parse(request.reason.unwrap_or_default()).map_err(|_| Error::InvalidReason)?;
+
+    let roles = fetch_roles(&state.db_pool).await?;
+
+    let change = match operation {
+        Operation::AddRole(role_id) => Change::AddRole(assignable_role_name(&roles, role_id)?),
+        Operation::RemoveRole(role_id) => {
+            Change::RemoveRole(assignable_role_name(&roles, role_id)?)
+        }
+        Operation::Ban(duration) => Change::Ban(duration),
+        Operation::LiftBan => Change::LiftBan,
+    };
+
+    // Checked up front so either every selected user is changed or none
+    let mut targets = Vec::with_capacity(user_ids.len());
+
+    for user_id in user_ids {
+        let Some(target) = fetch_target(&state.db_pool, user_id).await? else {
+            return Err(Error::UserCantAdmin);
+        };
+
+        if !policy::can(
+            &user,
+            Action::Admin,
+            Resource::User(target.account),
+            &state.config,
+        ) {
+            return Err(Error::UserCantAdmin);
+        }
+
+        targets.push(target);
+    }
+
+    let mut tx = state.db_pool.begin().await?;
+
+    for target in &targets {
+        let is_banned = target.role_names.iter().any(|role| role == BANNED);
+
+        // Roles of a banned user are given back once the ban is lifted,
+        // so they can't be changed in the meantime
+        let (kind, details) = match &change {
+            Change::AddRole(role_name) => {
+                if is_banned || target.role_names.iter().any(|role| role == role_name) {
+                    continue;
+                }
+
+                insert_user_role(&mut tx, target.account.id, role_name).await?;
+
+                let mut role_names = target.role_names.clone();
+                role_names.push(role_name.clone());
+                role_names.sort();
+
+                (Kind::ChangeRoles, role_names.join(", "))
+            }
+            Change::RemoveRole(role_name) => {
+                if is_banned || !target.role_names.iter().any(|role| role == role_name) {
+                    continue;
+                }
+
+                delete_user_role(&mut tx, target.account.id, role_name).await?;
+
+                let role_names = target
+                    .role_names
+                    .iter()
+                    .filter(|role| *role != role_name)
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                (Kind::ChangeRoles, role_names.join(", "))
+            }
+            &Change::Ban(duration) => {
+                ban_user(&mut tx, target.account.id, user.id, &reason, duration).await?;
+
+                webhook::user_banned(
+                    &mut tx,
+                    &state.config.domain,
+                    target.account.id,
+                    &target.username,
+                    &user,
+                    &reason,
+                    duration,
+                )
+                .await?;
+
+                (Kind::BanUser, ban::duration_label(duration))
+            }
+            Change::LiftBan => {
+                if !is_banned {
+                    continue;
+                }
+
+                lift_ban(&mut tx, target.account.id, Some(user.id)).await?;
+
+                (Kind::LiftBan, String::new())
+            }
+        };
+
+        insert_mod_action(
+            &mut tx,
+            &NewModAction {
+                actor_user_id: user.id,
+                kind,
+                target_type: TargetType::User,
+                target_id: target.account.id,
+                target_label: &target.username,
+                reason: &reason,
+                details: &details,
+            },
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    // Only ever back to the directory
+    let location = if request.back.starts_with("/admin/users") {
+        request.back
+    } else {
+        "/admin/users".to_owned()
+    };
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, location))
+        .finish();
+
+    Ok(response)
+}
+
+fn parse_operation(request: &Request) -> Result<Operation, Error> {
+    let role_id = || {
+        request
+            .role_id
+            .as_deref()
+            .and_then(|role_id| role_id.parse::<Id>().ok())
+            .ok_or(Error::InvalidRole)
+    };
+
+    match request.operation.as_str() {
+        "add_role" => Ok(Operation::AddRole(role_id()?)),
+        "remove_role" => Ok(Operation::RemoveRole(role_id()?)),
+        "ban" => {
+            let duration = request
+                .ban_duration
+                .as_deref()
+                .ok_or(())
+                .and_then(Lifetime::parse)
+                .map_err(|_| Error::InvalidBanDuration)?;
+
+            Ok(Operation::Ban(duration))
+        }
+        "lift_ban" => Ok(Operation::LiftBan),
+        _ => Err(Error::InvalidOperation),
+    }
+}
+
+// Same rules as `/api/user/admin`
+fn assignable_role_name(roles: &[Role], role_id: Id) -> Result<String, Error> {
+    let Some(role) = roles.iter().find(|role| role.id == role_id) else {
+        return Err(Error::InvalidRole);
+    };
+
+    if role.permissions.can_admin || role.name == UNVERIFIED || role.name == BANNED {
+        return Err(Error::InvalidRole);
+    }
+
+    Ok(role.name.clone())
+}
+
+fn checked_user_ids(fields: &HashMap<String, String>) -> Result<Vec<Id>, Error> {
+    fields
+        .iter()
+        .filter(|(_, value)| value.as_str() == "on")
+        .filter_map(|(key, _)| key.strip_prefix("user-"))
+        .map(|id| id.parse::<Id>().map_err(|_| Error::NoUsersSelected))
+        .collect()
+}
+
+async fn fetch_target(db_pool: &MySqlPool, user_id: Id) -> sqlx::Result<Option<Target>> {
+    #[derive(Debug)]
+    struct User {
+        id: Id,
+        username: String,
+    }
+
+    let Some(user) = sqlx::query_as!(
+        User,
+        "
+        SELECT
+            id,
+            username
+        FROM users
+        WHERE
+            id = ?
+        ",
+        user_id
+    )
+    .fetch_optional(db_pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let roles = fetch_user_roles(db_pool, user.id).await?;
+
+    let mut role_names = roles
+        .iter()
+        .map(|role| role.name.clone())
+        .collect::<Vec<_>>();
+    role_names.sort();
+
+    Ok(Some(Target {
+        account: Account {
+            id: user.id,
+            permissions: Permissions::of(&roles),
+        },
+        username: user.username,
+        role_names,
+    }))
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl Error {
+    pub fn as_location(&self) -> String {
+        format!("/admin/users?error={self}")
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SEE_OTHER
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        HttpResponse::build(self.status_code())
+            .append_header((header::LOCATION, self.as_location()))
+            .finish()
+    }
+}
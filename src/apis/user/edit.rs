@@ -1,3 +1,6 @@
+use crate::db::fetch_account_by_username;
+use crate::flash::Flash;
+use crate::policy::{self, Action, Resource};
 use crate::session::auth;
 use crate::session::User;
 use crate::types::id::Id;
@@ -62,10 +65,22 @@ pub async fn service(
     };
 
     let username = request.username.clone();
-
-    service_inner(state, request, user)
-        .await
-        .map_err(|err| Error::new(err, username))
+    // The profile picture isn't kept, it has to be picked again
+    let flash = Flash::new()
+        .field("name", &request.name)
+        .field("description", &request.description);
+
+    match service_inner(state.clone(), request, user).await {
+        Ok(response) => Ok(response),
+        Err(kind) => {
+            let err = Error::new(kind, username);
+            let location = err.as_location();
+
+            Ok(state
+                .flash
+                .redirect(&location, flash.error(kind.field(), err)))
+        }
+    }
 }
 
 async fn service_inner(
@@ -77,11 +92,11 @@ async fn service_inner(
     let Text(name) = request.name;
     let Text(description) = request.description;
 
-    if !user.role.can_edit_self() {
+    let Some(account) = fetch_account_by_username(&state.db_pool, &username).await? else {
         return Err(ErrorKind::UserCantEditUser);
-    }
+    };
 
-    if user.username != username {
+    if !policy::can(&user, Action::Edit, Resource::User(account), &state.config) {
         return Err(ErrorKind::UserCantEditUser);
     }
 
@@ -89,22 +104,28 @@ async fn service_inner(
     let description = Description::parse(description).map_err(|_| ErrorKind::InvalidDescription)?;
 
     if request.pfp.size != 0 {
-        // TODO: replace hardcoded dimensions
-        let mini_pfp_file_id = resize_and_persist_image(&state.db_pool, 32, &request.pfp, &user)
-            .await
-            .map_err(|_| ErrorKind::InvalidPfp)?;
-
-        let pfp_file_id = resize_and_persist_image(&state.db_pool, 128, &request.pfp, &user)
-            .await
-            .map_err(|_| ErrorKind::InvalidPfp)?;
-
-        update_user_pfps(
-            &state.db_pool,
-            Some(mini_pfp_file_id),
-            Some(pfp_file_id),
-            &username,
-        )
-        .await?;
+        let mut variants = Vec::new();
+
+        for size in &state.config.pfp.sizes {
+            for &density in &state.config.pfp.densities {
+                let file_id = resize_and_persist_image(
+                    &state.db_pool,
+                    size.width * density,
+                    &request.pfp,
+                    &user,
+                )
+                .await
+                .map_err(|_| ErrorKind::InvalidPfp)?;
+
+                variants.push(Variant {
+                    size: &size.name,
+                    density,
+                    file_id,
+                });
+            }
+        }
+
+        replace_user_pfp_variants(&state.db_pool, &variants, &user).await?;
     }
 
     update_user(&state.db_pool, &name, &description, &username).await?;
@@ -120,7 +141,7 @@ async fn service_inner(
 
 async fn resize_and_persist_image(
     db_pool: &MySqlPool,
-    width: u64,
+    width: u32,
     file: &TempFile,
     user: &User,
 ) -> Result<Id, ()> {
@@ -210,27 +231,59 @@ async fn update_user(
     .await
 }
 
-async fn update_user_pfps(
+#[derive(Debug)]
+struct Variant<'a> {
+    size: &'a str,
+    density: u32,
+    file_id: Id,
+}
+
+async fn replace_user_pfp_variants(
     db_pool: &MySqlPool,
-    mini_pfp_file_id: Option<Id>,
-    pfp_file_id: Option<Id>,
-    username: &str,
-) -> sqlx::Result<MySqlQueryResult> {
+    variants: &[Variant<'_>],
+    user: &User,
+) -> sqlx::Result<()> {
+    let mut tx = db_pool.begin().await?;
+
     sqlx::query!(
         "
-        UPDATE users
-        SET
-            mini_pfp_file_id = ?,
-            pfp_file_id = ?
+        DELETE FROM user_pfp_variants
         WHERE
-            username = ?
+            user_id = ?
         ",
-        mini_pfp_file_id,
-        pfp_file_id,
-        username
+        user.id
     )
-    .execute(db_pool)
-    .await
+    .execute(&mut tx)
+    .await?;
+
+    for variant in variants {
+        sqlx::query!(
+            "
+            INSERT INTO user_pfp_variants
+            (
+                user_id,
+                size,
+                density,
+                file_id
+            )
+            VALUES
+            (
+                ?,
+                ?,
+                ?,
+                ?
+            )
+            ",
+            user.id,
+            variant.size,
+            variant.density,
+            variant.file_id
+        )
+        .execute(&mut tx)
+        .await?;
+    }
+
+    tx.commit().await
 }
 
 impl From<sqlx::Error> for ErrorKind {
@@ -239,6 +292,18 @@ impl From<sqlx::Error> for ErrorKind {
     }
 }
 
+impl ErrorKind {
+    // The form field to highlight
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            Self::InvalidName => Some("name"),
+            Self::InvalidDescription => Some("description"),
+            Self::InvalidPfp => Some("pfp"),
+            _ => None,
+        }
+    }
+}
+
 impl Error {
     pub fn new(kind: ErrorKind, username: String) -> Self {
         Self { kind, username }
@@ -0,0 +1,65 @@
+use super::{require_auth, Error};
+use crate::apis::sign_in::{verify, Error as SignInError};
+use crate::session::{RemoveSessionCookie, SetSessionCookie};
+use crate::types::api_token::scope::Scope;
+use crate::types::id::Id;
+use crate::State;
+use actix_web::{
+    get,
+    http::StatusCode,
+    post,
+    web::{Data, Json},
+    HttpRequest, HttpResponse,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct SignInRequest {
+    pub email: String,
+    pub password: String,
+    #[serde(default)]
+    pub remember_me: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct SignInResponse {
+    id: Id,
+}
+
+// Sets the same session cookie as `/api/sign-in`
+#[post("/auth/sign-in")]
+pub async fn sign_in(
+    state: Data<State>,
+    Json(request): Json<SignInRequest>,
+) -> Result<HttpResponse, Error> {
+    let user = verify(&state.db_pool, request.email, request.password).await?;
+
+    let response = HttpResponse::Ok()
+        .set_session(&state.config.domain, &user.session, request.remember_me)
+        .json(SignInResponse { id: user.id });
+
+    Ok(response)
+}
+
+#[post("/auth/sign-out")]
+pub async fn sign_out() -> HttpResponse {
+    HttpResponse::NoContent().remove_session().finish()
+}
+
+#[get("/auth/me")]
+pub async fn me(state: Data<State>, req: HttpRequest) -> Result<HttpResponse, Error> {
+    let user = require_auth(&state.db_pool, &req, Scope::Read).await?;
+
+    Ok(HttpResponse::Ok().json(user))
+}
+
+impl From<SignInError> for Error {
+    fn from(err: SignInError) -> Self {
+        match err {
+            SignInError::InvalidEmailOrPassword => {
+                Self::new(StatusCode::UNAUTHORIZED, "invalid_email_or_password", err)
+            }
+            SignInError::Server => Self::server(),
+        }
+    }
+}
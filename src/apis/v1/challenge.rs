@@ -0,0 +1,9 @@
+use crate::types::challenge::Challenge;
+use actix_web::{get, HttpResponse};
+
+// Needed for the first post when `challenge.on_first_post` is set,
+// send `id` back as `challenge_id` along with `challenge_answer`
+#[get("/challenge")]
+pub async fn service() -> HttpResponse {
+    HttpResponse::Ok().json(Challenge::generate())
+}
@@ -0,0 +1,114 @@
+use super::{require_auth, Error};
+use crate::apis::comment::delete::{self, ErrorKind as DeleteError};
+use crate::apis::comment::edit::{self, ErrorKind as EditError};
+use crate::db::fetch_comment_by_id;
+use crate::types::api_token::scope::Scope;
+use crate::types::id::Id;
+use crate::State;
+use actix_web::{
+    delete,
+    http::StatusCode,
+    put,
+    web::{Data, Json, Path, Query},
+    HttpRequest, HttpResponse,
+};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct EditRequest {
+    pub content: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteRequest {
+    pub reason: Option<String>,
+}
+
+#[put("/comments/{id}")]
+pub async fn edit_comment(
+    state: Data<State>,
+    req: HttpRequest,
+    path: Path<Id>,
+    Json(request): Json<EditRequest>,
+) -> Result<HttpResponse, Error> {
+    let user = require_auth(&state.db_pool, &req, Scope::Comment).await?;
+
+    let comment_id = path.into_inner();
+
+    let request = edit::Request {
+        id: comment_id,
+        content: request.content,
+    };
+
+    edit::service_inner(state.clone(), request, user.clone()).await?;
+
+    let comment = fetch_comment_by_id(&state.db_pool, comment_id, &user, &state.config)
+        .await?
+        .ok_or_else(Error::server)?;
+
+    Ok(HttpResponse::Ok().json(comment))
+}
+
+#[delete("/comments/{id}")]
+pub async fn delete_comment(
+    state: Data<State>,
+    req: HttpRequest,
+    path: Path<Id>,
+    Query(query): Query<DeleteRequest>,
+) -> Result<HttpResponse, Error> {
+    let user = require_auth(&state.db_pool, &req, Scope::Moderate).await?;
+
+    let comment_id = path.into_inner();
+
+    let Some(comment) =
+        fetch_comment_by_id(&state.db_pool, comment_id, &user, &state.config).await?
+    else {
+        return Err(Error::not_found());
+    };
+
+    // The commenter's username only has to be typed out to guard the form against misclicks
+    let confirm = comment
+        .posted_by
+        .map(|posted_by| posted_by.username)
+        .unwrap_or("Anonymous".to_owned());
+
+    let request = delete::Request {
+        comment_id,
+        confirm,
+        reason: query.reason,
+    };
+
+    delete::service_inner(state, user, request).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+impl From<EditError> for Error {
+    fn from(err: EditError) -> Self {
+        let (status, code) = match err {
+            EditError::UserCantEditComments => (StatusCode::FORBIDDEN, "user_cant_edit_comment"),
+            EditError::InvalidContent => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_content"),
+            EditError::Spam(_) => (StatusCode::UNPROCESSABLE_ENTITY, "spam"),
+            EditError::Server => return Self::server(),
+        };
+
+        Self::new(status, code, err)
+    }
+}
+
+impl From<DeleteError> for Error {
+    fn from(err: DeleteError) -> Self {
+        let (status, code) = match err {
+            DeleteError::UserCantDeleteComment => {
+                (StatusCode::FORBIDDEN, "user_cant_delete_comment")
+            }
+            DeleteError::InvalidConfirmation => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "invalid_confirmation")
+            }
+            DeleteError::InvalidReason => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_reason"),
+            DeleteError::Server => return Self::server(),
+        };
+
+        Self::new(status, code, err)
+    }
+}
@@ -0,0 +1,163 @@
+use crate::session::{self, ApiAuthError, User};
+use crate::types::api_token::scope::Scope;
+use crate::types::id::Id;
+use actix_web::{
+    error::InternalError,
+    http::StatusCode,
+    web::{JsonConfig, PathConfig, QueryConfig},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::mysql::MySqlPool;
+use thiserror::Error;
+
+pub mod auth;
+pub mod challenge;
+pub mod comments;
+pub mod openapi;
+pub mod posts;
+pub mod tags;
+pub mod users;
+
+// JSON API, mounted under `/api/v1` and described by `openapi.yaml`.
+// Handlers share the validation and the rules of the form handlers
+// by calling their `service_inner`, only the responses differ.
+// Besides the session cookie, personal API tokens are accepted, see `session::auth_api`
+
+// Sent as `{"error": {"code": "...", "message": "..."}}`,
+// `code` is stable and meant for programs, `message` is meant for people
+#[derive(Debug, Clone, Error, Serialize)]
+#[error("{message}")]
+pub struct Error {
+    #[serde(skip)]
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+}
+
+// Posts and comments are paged by id, pass `next_start_id` back as `start_id`
+// for the next page, it's `null` once there is nothing left
+#[derive(Debug, Deserialize)]
+pub struct Paging {
+    pub start_id: Option<Id>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct List<T> {
+    pub items: Vec<T>,
+    pub next_start_id: Option<Id>,
+}
+
+impl Error {
+    pub fn new(status: StatusCode, code: &'static str, message: impl ToString) -> Self {
+        Self {
+            status,
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn unauthorized() -> Self {
+        Self::new(
+            StatusCode::UNAUTHORIZED,
+            "unauthorized",
+            "You need to sign in first",
+        )
+    }
+
+    pub fn not_found() -> Self {
+        Self::new(StatusCode::NOT_FOUND, "not_found", "Not found")
+    }
+
+    pub fn server() -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "server", "Server error")
+    }
+}
+
+impl<T> List<T> {
+    // A full page means there might be more, `next_id` gives the id to continue from
+    pub fn new(items: Vec<T>, limit: u64, next_id: impl Fn(&T) -> Id) -> Self {
+        let next_start_id = if items.len() as u64 == limit {
+            items.last().map(next_id)
+        } else {
+            None
+        };
+
+        Self {
+            items,
+            next_start_id,
+        }
+    }
+}
+
+// Signed in with the session cookie or an API token that has `scope`
+pub async fn require_auth(
+    db_pool: &MySqlPool,
+    req: &HttpRequest,
+    scope: Scope,
+) -> Result<User, Error> {
+    session::auth_api(db_pool, req, scope)
+        .await
+        .map_err(|err| match err {
+            ApiAuthError::Unauthorized => Error::unauthorized(),
+            ApiAuthError::MissingScope(scope) => Error::new(
+                StatusCode::FORBIDDEN,
+                "missing_scope",
+                format!("The API token needs the `{}` scope", scope.as_str()),
+            ),
+        })
+}
+
+// Reading works without signing in, but a signed in user may see more
+pub async fn optional_auth(db_pool: &MySqlPool, req: &HttpRequest) -> Option<User> {
+    session::auth_api(db_pool, req, Scope::Read).await.ok()
+}
+
+// Malformed bodies, queries and paths get the same JSON errors as everything else
+pub fn json_config() -> JsonConfig {
+    JsonConfig::default().error_handler(|err, _| {
+        let error = Error::new(StatusCode::BAD_REQUEST, "invalid_body", &err);
+
+        InternalError::from_response(err, error.error_response()).into()
+    })
+}
+
+pub fn query_config() -> QueryConfig {
+    QueryConfig::default().error_handler(|err, _| {
+        let error = Error::new(StatusCode::BAD_REQUEST, "invalid_query", &err);
+
+        InternalError::from_response(err, error.error_response()).into()
+    })
+}
+
+pub fn path_config() -> PathConfig {
+    PathConfig::default().error_handler(|err, _| {
+        InternalError::from_response(err, Error::not_found().error_response()).into()
+    })
+}
+
+pub async fn not_found() -> Result<HttpResponse, Error> {
+    Err(Error::not_found())
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::server()
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        #[derive(Debug, Serialize)]
+        struct Body<'a> {
+            error: &'a Error,
+        }
+
+        HttpResponse::build(self.status_code()).json(Body { error: self })
+    }
+}
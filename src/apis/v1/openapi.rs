@@ -0,0 +1,10 @@
+use actix_web::{get, http::header, HttpResponse};
+
+#[get("/openapi.yaml")]
+pub async fn service() -> HttpResponse {
+    let document = include_str!("../../../openapi.yaml");
+
+    HttpResponse::Ok()
+        .append_header((header::CONTENT_TYPE, "application/yaml"))
+        .body(document)
+}
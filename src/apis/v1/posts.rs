@@ -0,0 +1,315 @@
+use super::{optional_auth, require_auth, Error, List, Paging};
+use crate::apis::comment::create::{self as comment_create, ErrorKind as CommentCreateError};
+use crate::apis::post::create::{self, Error as CreateError};
+use crate::apis::post::delete::{self, ErrorKind as DeleteError};
+use crate::apis::post::edit::{self, ErrorKind as EditError};
+use crate::db::fetch_comment_by_id;
+use crate::pages::index::{fetch_posts_from_raw, fetch_raw_posts_forwards};
+use crate::pages::post::id::{fetch_comments_forwards, fetch_post_by_id};
+use crate::policy::Viewer;
+use crate::rate_limit;
+use crate::session::User;
+use crate::types::api_token::scope::Scope;
+use crate::types::id::Id;
+use crate::types::page::clamp_limit;
+use crate::types::post::Post;
+use crate::State;
+use actix_web::{
+    delete, get,
+    http::{header, StatusCode},
+    post, put,
+    web::{Data, Json, Path, Query},
+    HttpRequest, HttpResponse,
+};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct ListRequest {
+    pub start_id: Option<Id>,
+    pub limit: Option<u64>,
+    // Part of the title
+    pub query: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EditRequest {
+    pub title: String,
+    pub description: String,
+    pub tags: String,
+    pub content: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteRequest {
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommentRequest {
+    pub reply_to_comment_id: Option<Id>,
+    pub content: String,
+}
+
+// Newest first, same as `/`
+#[get("/posts")]
+pub async fn list_posts(
+    state: Data<State>,
+    req: HttpRequest,
+    Query(query): Query<ListRequest>,
+) -> Result<HttpResponse, Error> {
+    let current_user = optional_auth(&state.db_pool, &req).await;
+
+    let start_id = query.start_id.unwrap_or(i64::MAX as Id);
+    let limit = clamp_limit(query.limit, &state.config.page);
+    let q = format!("%{}%", query.query.unwrap_or_default());
+
+    let viewer = Viewer::of(current_user.as_ref(), &state.config);
+
+    let raw_post_previews =
+        fetch_raw_posts_forwards(&state.db_pool, start_id, limit, &q, viewer).await?;
+    let posts = fetch_posts_from_raw(&state.db_pool, raw_post_previews).await?;
+
+    let list = List::new(posts, limit, |post| post.id.saturating_sub(1));
+
+    Ok(HttpResponse::Ok().json(list))
+}
+
+#[post("/posts")]
+pub async fn create_post(
+    state: Data<State>,
+    req: HttpRequest,
+    Json(request): Json<create::Request>,
+) -> Result<HttpResponse, Error> {
+    let user = require_auth(&state.db_pool, &req, Scope::Post).await?;
+
+    let ip = rate_limit::client_ip(&req, state.config.rate_limit.is_behind_proxy);
+
+    let post_id = create::service_inner(state.clone(), request, user.clone(), ip).await?;
+
+    let post = fetch_visible_post(&state, post_id, Some(&user)).await?;
+
+    let response = HttpResponse::Created()
+        .append_header((header::LOCATION, format!("/api/v1/posts/{post_id}")))
+        .json(post);
+
+    Ok(response)
+}
+
+#[get("/posts/{id}")]
+pub async fn get_post(
+    state: Data<State>,
+    req: HttpRequest,
+    path: Path<Id>,
+) -> Result<HttpResponse, Error> {
+    let current_user = optional_auth(&state.db_pool, &req).await;
+
+    let post = fetch_visible_post(&state, path.into_inner(), current_user.as_ref()).await?;
+
+    Ok(HttpResponse::Ok().json(post))
+}
+
+#[put("/posts/{id}")]
+pub async fn edit_post(
+    state: Data<State>,
+    req: HttpRequest,
+    path: Path<Id>,
+    Json(request): Json<EditRequest>,
+) -> Result<HttpResponse, Error> {
+    let user = require_auth(&state.db_pool, &req, Scope::Post).await?;
+
+    let post_id = path.into_inner();
+
+    let request = edit::Request {
+        id: post_id,
+        title: request.title,
+        description: request.description,
+        tags: request.tags,
+        content: request.content,
+    };
+
+    edit::service_inner(state.clone(), user.clone(), request).await?;
+
+    let post = fetch_visible_post(&state, post_id, Some(&user)).await?;
+
+    Ok(HttpResponse::Ok().json(post))
+}
+
+#[delete("/posts/{id}")]
+pub async fn delete_post(
+    state: Data<State>,
+    req: HttpRequest,
+    path: Path<Id>,
+    Query(query): Query<DeleteRequest>,
+) -> Result<HttpResponse, Error> {
+    let user = require_auth(&state.db_pool, &req, Scope::Moderate).await?;
+
+    let post_id = path.into_inner();
+
+    // The title only has to be typed out to guard the form against misclicks
+    let post = fetch_visible_post(&state, post_id, Some(&user)).await?;
+
+    let request = delete::Request {
+        post_id,
+        confirm: post.title,
+        reason: query.reason,
+    };
+
+    delete::service_inner(state, user, request).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Oldest first, same as `/post/{id}`
+#[get("/posts/{id}/comments")]
+pub async fn list_comments(
+    state: Data<State>,
+    req: HttpRequest,
+    path: Path<Id>,
+    Query(query): Query<Paging>,
+) -> Result<HttpResponse, Error> {
+    let current_user = optional_auth(&state.db_pool, &req).await;
+
+    let post_id = path.into_inner();
+
+    let start_id = query.start_id.unwrap_or(0);
+    let limit = clamp_limit(query.limit, &state.config.page);
+
+    fetch_visible_post(&state, post_id, current_user.as_ref()).await?;
+
+    let comments = fetch_comments_forwards(
+        &state.db_pool,
+        post_id,
+        start_id,
+        limit,
+        current_user.as_ref(),
+        &state.config,
+    )
+    .await?;
+
+    let list = List::new(comments, limit, |comment| comment.id.saturating_add(1));
+
+    Ok(HttpResponse::Ok().json(list))
+}
+
+#[post("/posts/{id}/comments")]
+pub async fn create_comment(
+    state: Data<State>,
+    req: HttpRequest,
+    path: Path<Id>,
+    Json(request): Json<CommentRequest>,
+) -> Result<HttpResponse, Error> {
+    let user = require_auth(&state.db_pool, &req, Scope::Comment).await?;
+
+    let post_id = path.into_inner();
+
+    let request = comment_create::Request {
+        post_id,
+        reply_to_comment_id: request.reply_to_comment_id,
+        content: request.content,
+    };
+
+    let ip = rate_limit::client_ip(&req, state.config.rate_limit.is_behind_proxy);
+
+    let comment_id =
+        comment_create::service_inner(state.clone(), request, user.clone(), ip).await?;
+
+    let comment = fetch_comment_by_id(&state.db_pool, comment_id, &user, &state.config)
+        .await?
+        .ok_or_else(Error::server)?;
+
+    let response = HttpResponse::Created()
+        .append_header((
+            header::LOCATION,
+            format!("/api/v1/posts/{post_id}/comments?start_id={comment_id}"),
+        ))
+        .json(comment);
+
+    Ok(response)
+}
+
+// Held posts are only found by their author and moderators, like on `/post/{id}`
+pub async fn fetch_visible_post(
+    state: &State,
+    post_id: Id,
+    user: Option<&User>,
+) -> Result<Post, Error> {
+    match fetch_post_by_id(&state.db_pool, post_id, user, &state.config).await? {
+        Some(post) if post.is_visible => Ok(post),
+        _ => Err(Error::not_found()),
+    }
+}
+
+impl From<CreateError> for Error {
+    fn from(err: CreateError) -> Self {
+        let (status, code) = match err {
+            CreateError::UserIsBanned => (StatusCode::FORBIDDEN, "user_is_banned"),
+            CreateError::UserCantPost => (StatusCode::FORBIDDEN, "user_cant_post"),
+            CreateError::RateLimited => (StatusCode::TOO_MANY_REQUESTS, "rate_limited"),
+            CreateError::WrongChallengeAnswer => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "wrong_challenge_answer")
+            }
+            CreateError::InvalidTitle => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_title"),
+            CreateError::InvalidDescription => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "invalid_description")
+            }
+            CreateError::InvalidTags => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_tags"),
+            CreateError::InvalidContent => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_content"),
+            CreateError::Spam(_) => (StatusCode::UNPROCESSABLE_ENTITY, "spam"),
+            CreateError::Server => return Self::server(),
+        };
+
+        Self::new(status, code, err)
+    }
+}
+
+impl From<EditError> for Error {
+    fn from(err: EditError) -> Self {
+        let (status, code) = match err {
+            EditError::UserCantEditPost => (StatusCode::FORBIDDEN, "user_cant_edit_post"),
+            EditError::InvalidTitle => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_title"),
+            EditError::InvalidDescription => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "invalid_description")
+            }
+            EditError::InvalidTags => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_tags"),
+            EditError::InvalidContent => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_content"),
+            EditError::Spam(_) => (StatusCode::UNPROCESSABLE_ENTITY, "spam"),
+            EditError::Server => return Self::server(),
+        };
+
+        Self::new(status, code, err)
+    }
+}
+
+impl From<DeleteError> for Error {
+    fn from(err: DeleteError) -> Self {
+        let (status, code) = match err {
+            DeleteError::UserCantDeletePost => (StatusCode::FORBIDDEN, "user_cant_delete_post"),
+            DeleteError::InvalidConfirmation => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "invalid_confirmation")
+            }
+            DeleteError::InvalidReason => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_reason"),
+            DeleteError::Server => return Self::server(),
+        };
+
+        Self::new(status, code, err)
+    }
+}
+
+impl From<CommentCreateError> for Error {
+    fn from(err: CommentCreateError) -> Self {
+        let (status, code) = match err {
+            CommentCreateError::UserIsBanned => (StatusCode::FORBIDDEN, "user_is_banned"),
+            CommentCreateError::UserCantComment => (StatusCode::FORBIDDEN, "user_cant_comment"),
+            CommentCreateError::RateLimited => (StatusCode::TOO_MANY_REQUESTS, "rate_limited"),
+            CommentCreateError::InvalidReply => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_reply"),
+            CommentCreateError::PostNotFound => (StatusCode::NOT_FOUND, "post_not_found"),
+            CommentCreateError::InvalidContent => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "invalid_content")
+            }
+            CommentCreateError::Spam(_) => (StatusCode::UNPROCESSABLE_ENTITY, "spam"),
+            CommentCreateError::Server => return Self::server(),
+        };
+
+        Self::new(status, code, err)
+    }
+}
@@ -0,0 +1,38 @@
+use super::{optional_auth, Error, List, Paging};
+use crate::pages::index::fetch_posts_from_raw;
+use crate::pages::tag::tag::fetch_raw_posts_forwards;
+use crate::policy::Viewer;
+use crate::types::id::Id;
+use crate::types::page::clamp_limit;
+use crate::State;
+use actix_web::{
+    get,
+    web::{Data, Path, Query},
+    HttpRequest, HttpResponse,
+};
+
+// Newest first, same as `/tag/{tag}`
+#[get("/tags/{tag}/posts")]
+pub async fn list_posts(
+    state: Data<State>,
+    req: HttpRequest,
+    path: Path<String>,
+    Query(query): Query<Paging>,
+) -> Result<HttpResponse, Error> {
+    let current_user = optional_auth(&state.db_pool, &req).await;
+
+    let tag = path.into_inner();
+
+    let start_id = query.start_id.unwrap_or(i64::MAX as Id);
+    let limit = clamp_limit(query.limit, &state.config.page);
+
+    let viewer = Viewer::of(current_user.as_ref(), &state.config);
+
+    let raw_post_previews =
+        fetch_raw_posts_forwards(&state.db_pool, &tag, start_id, limit, viewer).await?;
+    let posts = fetch_posts_from_raw(&state.db_pool, raw_post_previews).await?;
+
+    let list = List::new(posts, limit, |post| post.id.saturating_sub(1));
+
+    Ok(HttpResponse::Ok().json(list))
+}
@@ -0,0 +1,100 @@
+use super::{optional_auth, Error, List, Paging};
+use crate::db::fetch_user_by_username;
+use crate::pages::user::username::{fetch_comments, fetch_posts};
+use crate::policy::Viewer;
+use crate::session::User as AuthUser;
+use crate::types::id::Id;
+use crate::types::page::{clamp_limit, Direction};
+use crate::types::user::User;
+use crate::State;
+use actix_web::{
+    get,
+    web::{Data, Path, Query},
+    HttpRequest, HttpResponse,
+};
+
+#[get("/users/{username}")]
+pub async fn get_user(
+    state: Data<State>,
+    req: HttpRequest,
+    path: Path<String>,
+) -> Result<HttpResponse, Error> {
+    let current_user = optional_auth(&state.db_pool, &req).await;
+
+    let user = fetch_user(&state, &path.into_inner(), current_user.as_ref()).await?;
+
+    Ok(HttpResponse::Ok().json(user))
+}
+
+// Newest first, same as `/user/{username}`
+#[get("/users/{username}/posts")]
+pub async fn list_posts(
+    state: Data<State>,
+    req: HttpRequest,
+    path: Path<String>,
+    Query(query): Query<Paging>,
+) -> Result<HttpResponse, Error> {
+    let current_user = optional_auth(&state.db_pool, &req).await;
+
+    let user = fetch_user(&state, &path.into_inner(), current_user.as_ref()).await?;
+
+    let start_id = query.start_id.unwrap_or(i64::MAX as Id);
+    let limit = clamp_limit(query.limit, &state.config.page);
+
+    let viewer = Viewer::of(current_user.as_ref(), &state.config);
+
+    let posts = fetch_posts(
+        &state.db_pool,
+        Direction::Forwards,
+        start_id,
+        limit,
+        &user,
+        viewer,
+    )
+    .await?;
+
+    let list = List::new(posts, limit, |post| post.id.saturating_sub(1));
+
+    Ok(HttpResponse::Ok().json(list))
+}
+
+// Newest first, same as `/user/{username}?display=comments`
+#[get("/users/{username}/comments")]
+pub async fn list_comments(
+    state: Data<State>,
+    req: HttpRequest,
+    path: Path<String>,
+    Query(query): Query<Paging>,
+) -> Result<HttpResponse, Error> {
+    let current_user = optional_auth(&state.db_pool, &req).await;
+
+    let user = fetch_user(&state, &path.into_inner(), current_user.as_ref()).await?;
+
+    let start_id = query.start_id.unwrap_or(i64::MAX as Id);
+    let limit = clamp_limit(query.limit, &state.config.page);
+
+    let comments = fetch_comments(
+        &state.db_pool,
+        Direction::Forwards,
+        start_id,
+        limit,
+        &user,
+        current_user.as_ref(),
+        &state.config,
+    )
+    .await?;
+
+    let list = List::new(comments, limit, |comment| comment.id.saturating_sub(1));
+
+    Ok(HttpResponse::Ok().json(list))
+}
+
+async fn fetch_user(
+    state: &State,
+    username: &str,
+    current_user: Option<&AuthUser>,
+) -> Result<User, Error> {
+    fetch_user_by_username(&state.db_pool, username, current_user, &state.config)
+        .await?
+        .ok_or_else(Error::not_found)
+}
@@ -0,0 +1,171 @@
+use crate::apis::is_checked;
+use crate::policy::{self, Action, Resource};
+use crate::session::auth;
+use crate::types::id::Id;
+use crate::types::post::tags::Tags;
+use crate::types::webhook::{
+    self,
+    event::{Event, Events},
+    url::Url,
+};
+use crate::State;
+use actix_web::{
+    http::{header, StatusCode},
+    post,
+    web::{Data, Form},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::Deserialize;
+use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use thiserror::Error;
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("You are not allowed to manage webhooks")]
+    UserCantManageWebhooks,
+    #[error("Invalid URL, it must be an http or https address")]
+    InvalidUrl,
+    #[error("Pick at least one event")]
+    NoEvents,
+    #[error("Invalid tag")]
+    InvalidTag,
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub url: String,
+    // Empty sends events about all posts
+    pub tag: String,
+
+    pub event_post_created: Option<String>,
+    pub event_comment_created: Option<String>,
+    pub event_post_deleted: Option<String>,
+    pub event_user_banned: Option<String>,
+}
+
+// The secret is generated here and shown on `/admin/webhooks`
+#[post("/api/webhook/create")]
+pub async fn service(
+    state: Data<State>,
+    req: HttpRequest,
+    Form(request): Form<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/admin/webhooks"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    if !policy::can(&user, Action::Admin, Resource::Site, &state.config) {
+        return Err(Error::UserCantManageWebhooks);
+    }
+
+    let url = Url::parse(request.url).map_err(|_| Error::InvalidUrl)?;
+
+    let checked = [
+        (Event::PostCreated, request.event_post_created),
+        (Event::CommentCreated, request.event_comment_created),
+        (Event::PostDeleted, request.event_post_deleted),
+        (Event::UserBanned, request.event_user_banned),
+    ];
+
+    let events = Events::parse(
+        checked
+            .into_iter()
+            .filter_map(|(event, checkbox)| is_checked(checkbox).then_some(event)),
+    )
+    .map_err(|_| Error::NoEvents)?;
+
+    let tag = if request.tag.trim().is_empty() {
+        None
+    } else {
+        let tags = Tags::parse(request.tag, 1).map_err(|_| Error::InvalidTag)?;
+
+        tags.into_iter().next()
+    };
+
+    insert_webhook(
+        &state.db_pool,
+        &url,
+        &webhook::generate_secret(),
+        &events,
+        tag.as_deref(),
+        user.id,
+    )
+    .await?;
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, "/admin/webhooks"))
+        .finish();
+
+    Ok(response)
+}
+
+async fn insert_webhook(
+    db_pool: &MySqlPool,
+    url: &Url,
+    secret: &str,
+    events: &Events,
+    tag: Option<&str>,
+    created_by_user_id: Id,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        INSERT INTO webhooks
+        (
+            url,
+            secret,
+            events,
+            tag,
+            is_active,
+            created_by_user_id,
+            created_at
+        )
+        VALUES
+        (
+            ?,
+            ?,
+            ?,
+            ?,
+            1,
+            ?,
+            NOW()
+        )
+        ",
+        url.as_ref(),
+        secret,
+        events.as_string(),
+        tag,
+        created_by_user_id
+    )
+    .execute(db_pool)
+    .await
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl Error {
+    pub fn as_location(&self) -> String {
+        format!("/admin/webhooks?error={self}")
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SEE_OTHER
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        HttpResponse::build(self.status_code())
+            .append_header((header::LOCATION, self.as_location()))
+            .finish()
+    }
+}
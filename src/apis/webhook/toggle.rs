@@ -0,0 +1,108 @@
+use crate::apis::is_checked;
+use crate::db::fetch_webhook_exists;
+use crate::policy::{self, Action, Resource};
+use crate::session::auth;
+use crate::types::id::Id;
+use crate::State;
+use actix_web::{
+    http::{header, StatusCode},
+    post,
+    web::{Data, Form},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use serde::Deserialize;
+use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use thiserror::Error;
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("You are not allowed to manage webhooks")]
+    UserCantManageWebhooks,
+    #[error("Webhook not found")]
+    WebhookNotFound,
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub id: Id,
+    pub is_active: Option<String>,
+}
+
+// A paused webhook isn't sent anything, events that happen meanwhile
+// are not queued for it and already queued deliveries wait until it's resumed
+#[post("/api/webhook/toggle")]
+pub async fn service(
+    state: Data<State>,
+    req: HttpRequest,
+    Form(request): Form<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/admin/webhooks"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    if !policy::can(&user, Action::Admin, Resource::Site, &state.config) {
+        return Err(Error::UserCantManageWebhooks);
+    }
+
+    if !fetch_webhook_exists(&state.db_pool, request.id).await? {
+        return Err(Error::WebhookNotFound);
+    }
+
+    set_webhook_active(&state.db_pool, request.id, is_checked(request.is_active)).await?;
+
+    let response = HttpResponse::SeeOther()
+        .append_header((header::LOCATION, "/admin/webhooks"))
+        .finish();
+
+    Ok(response)
+}
+
+async fn set_webhook_active(
+    db_pool: &MySqlPool,
+    id: Id,
+    is_active: bool,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        UPDATE webhooks
+        SET
+            is_active = ?
+        WHERE
+            id = ?
+        ",
+        is_active,
+        id
+    )
+    .execute(db_pool)
+    .await
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl Error {
+    pub fn as_location(&self) -> String {
+        format!("/admin/webhooks?error={self}")
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::SEE_OTHER
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        HttpResponse::build(self.status_code())
+            .append_header((header::LOCATION, self.as_location()))
+            .finish()
+    }
+}
@@ -0,0 +1,673 @@
+use crate::rate_limit::Limit;
+use crate::spam::{self, Action as SpamAction};
+use crate::types::page::{DEFAULT_LIMIT, DEFAULT_MAX_LIMIT, MIN_LIMIT};
+use crate::types::post::tags::{DEFAULT_TAG_MAX_COUNT, TAG_MIN_COUNT};
+use crate::types::user::pfp::{self, Size as PfpSize};
+use crate::types::user::role::{ADMIN, MEMBER, MOD};
+use lettre::message::Mailbox;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use thiserror::Error;
+
+pub const CONFIG_PATH_VARIABLE: &str = "NEOR_CONFIG";
+pub const DEFAULT_CONFIG_PATH: &str = "neor.toml";
+
+pub const DEFAULT_EDIT_WINDOW_MINUTES: u32 = 120;
+pub const DEFAULT_PURGE_AFTER_DAYS: u32 = 30;
+pub const DEFAULT_NEW_ACCOUNT_DAYS: u32 = 3;
+pub const DEFAULT_NEW_ACCOUNT_MAX_LINKS: usize = 2;
+pub const DEFAULT_DUPLICATE_WINDOW_HOURS: u32 = 24;
+pub const DEFAULT_RATE_LIMIT_NEW_ACCOUNT_DAYS: u32 = 3;
+pub const DEFAULT_CHALLENGE_EXPIRE_MINUTES: u32 = 30;
+pub const DEFAULT_WEBHOOK_MAX_ATTEMPTS: u32 = 8;
+pub const DEFAULT_WEBHOOK_RETRY_BASE_SECONDS: u64 = 30;
+pub const DEFAULT_WEBHOOK_TIMEOUT_SECONDS: u64 = 10;
+
+// Edit window of every role, roles that are not listed get `DEFAULT_EDIT_WINDOW_MINUTES`
+pub type EditWindows = HashMap<String, EditWindow>;
+
+// Minutes after posting during which the author can still edit, or `"unlimited"`.
+// Ordered from the least to the most generous
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(try_from = "RawEditWindow")]
+pub enum EditWindow {
+    Minutes(u32),
+    Unlimited,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawEditWindow {
+    Minutes(u32),
+    Word(String),
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Failed to read config file `{path}`: {source}")]
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("Failed to parse config file `{path}`: {source}")]
+    Parse {
+        path: String,
+        source: toml::de::Error,
+    },
+    #[error("Environment variable ${0} has an invalid value")]
+    InvalidVariable(&'static str),
+    #[error("`{key}` {reason}")]
+    Invalid {
+        key: &'static str,
+        reason: &'static str,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub port: u16,
+    pub domain: String,
+    pub database_url: String,
+
+    pub email: EmailConfig,
+    pub page: PageConfig,
+    pub post: PostConfig,
+    pub comment: CommentConfig,
+    pub tags: TagsConfig,
+    pub pfp: PfpConfig,
+    pub modlog: ModLogConfig,
+    pub deletion: DeletionConfig,
+    pub spam: SpamConfig,
+    pub rate_limit: RateLimitConfig,
+    pub challenge: ChallengeConfig,
+    pub webhooks: WebhooksConfig,
+    pub markdown: MarkdownConfig,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailConfig {
+    pub from: String,
+    pub password: String,
+    pub relay: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PageConfig {
+    pub default_limit: u64,
+    pub max_limit: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PostConfig {
+    pub edit_window_minutes: EditWindows,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommentConfig {
+    pub edit_window_minutes: EditWindows,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TagsConfig {
+    pub max_count: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PfpConfig {
+    pub sizes: Vec<PfpSize>,
+    pub densities: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModLogConfig {
+    // Everyone can read the log, otherwise only moderators and admins
+    pub is_public: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DeletionConfig {
+    // Deleted posts and comments can be restored until they are purged,
+    // `0` keeps them forever
+    pub purge_after_days: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpamConfig {
+    pub action: SpamAction,
+    // Matched as whole words, ignoring case
+    pub banned_words: Vec<String>,
+    // Regular expressions matched anywhere in the text
+    pub banned_patterns: Vec<String>,
+    // Accounts younger than this can't post more than `new_account_max_links` links
+    pub new_account_days: u32,
+    pub new_account_max_links: usize,
+    // Posts and comments repeating one posted this many hours ago or later
+    // are treated as spam, `0` turns the check off
+    pub duplicate_window_hours: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    // Accounts younger than this get the `new_user` limits
+    pub new_account_days: u32,
+    // Take the client address from `Forwarded`/`X-Forwarded-For`,
+    // only turn on when a reverse proxy sets them
+    pub is_behind_proxy: bool,
+    pub post: ActionLimits,
+    pub comment: ActionLimits,
+    pub sign_up: ActionLimits,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ActionLimits {
+    pub user: Limit,
+    pub new_user: Limit,
+    pub ip: Limit,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChallengeConfig {
+    pub on_sign_up: bool,
+    // Ask accounts that haven't posted before, moderators are never asked
+    pub on_first_post: bool,
+    // Unanswered challenges stop working after this long
+    pub expire_minutes: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhooksConfig {
+    // Deliveries that still fail after this many tries are given up on
+    pub max_attempts: u32,
+    // Waited before the first retry, doubled for each one after it
+    pub retry_base_seconds: u64,
+    // How long a webhook gets to respond
+    pub timeout_seconds: u64,
+}
+
+// GitHub flavoured extensions on top of CommonMark. Changing these only affects
+// posts and comments written or edited after, until they're re-rendered from `/admin`
+// or with `neor rerender`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarkdownConfig {
+    pub tables: bool,
+    pub strikethrough: bool,
+    // Bare URLs and email addresses become links
+    pub autolinks: bool,
+    pub footnotes: bool,
+}
+
+impl Config {
+    // Reads the TOML file at $NEOR_CONFIG (or `neor.toml` if it exists),
+    // then applies environment variable overrides and validates the result
+    pub fn load() -> Result<Self, Error> {
+        let path = std::env::var(CONFIG_PATH_VARIABLE).ok();
+
+        let mut config = match path {
+            Some(path) => Self::from_file(&path)?,
+            None if std::path::Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(DEFAULT_CONFIG_PATH)?
+            }
+            None => Self::default(),
+        };
+
+        config.apply_env()?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    fn from_file(path: &str) -> Result<Self, Error> {
+        let s = std::fs::read_to_string(path).map_err(|source| Error::Read {
+            path: path.to_owned(),
+            source,
+        })?;
+
+        toml::from_str(&s).map_err(|source| Error::Parse {
+            path: path.to_owned(),
+            source,
+        })
+    }
+
+    fn apply_env(&mut self) -> Result<(), Error> {
+        override_from_env("PORT", &mut self.port)?;
+        override_from_env("DOMAIN", &mut self.domain)?;
+        override_from_env("DATABASE_URL", &mut self.database_url)?;
+
+        override_from_env("EMAIL_FROM", &mut self.email.from)?;
+        override_from_env("EMAIL_PASSWORD", &mut self.email.password)?;
+        override_from_env("EMAIL_RELAY", &mut self.email.relay)?;
+
+        override_from_env("PAGE_DEFAULT_LIMIT", &mut self.page.default_limit)?;
+        override_from_env("PAGE_MAX_LIMIT", &mut self.page.max_limit)?;
+
+        if let Ok(windows) = std::env::var("POST_EDIT_WINDOW_MINUTES") {
+            self.post.edit_window_minutes = parse_edit_windows(&windows)
+                .map_err(|_| Error::InvalidVariable("POST_EDIT_WINDOW_MINUTES"))?;
+        }
+
+        if let Ok(windows) = std::env::var("COMMENT_EDIT_WINDOW_MINUTES") {
+            self.comment.edit_window_minutes = parse_edit_windows(&windows)
+                .map_err(|_| Error::InvalidVariable("COMMENT_EDIT_WINDOW_MINUTES"))?;
+        }
+
+        override_from_env("TAGS_MAX_COUNT", &mut self.tags.max_count)?;
+
+        if let Ok(sizes) = std::env::var("PFP_SIZES") {
+            self.pfp.sizes =
+                pfp::parse_sizes(&sizes).map_err(|_| Error::InvalidVariable("PFP_SIZES"))?;
+        }
+
+        if let Ok(densities) = std::env::var("PFP_DENSITIES") {
+            self.pfp.densities = pfp::parse_densities(&densities)
+                .map_err(|_| Error::InvalidVariable("PFP_DENSITIES"))?;
+        }
+
+        override_from_env("MODLOG_PUBLIC", &mut self.modlog.is_public)?;
+
+        override_from_env(
+            "DELETION_PURGE_AFTER_DAYS",
+            &mut self.deletion.purge_after_days,
+        )?;
+
+        override_from_env("SPAM_ACTION", &mut self.spam.action)?;
+
+        if let Ok(words) = std::env::var("SPAM_BANNED_WORDS") {
+            self.spam.banned_words = words
+                .split(',')
+                .map(str::trim)
+                .filter(|word| !word.is_empty())
+                .map(str::to_owned)
+                .collect();
+        }
+
+        override_from_env("SPAM_NEW_ACCOUNT_DAYS", &mut self.spam.new_account_days)?;
+        override_from_env(
+            "SPAM_NEW_ACCOUNT_MAX_LINKS",
+            &mut self.spam.new_account_max_links,
+        )?;
+        override_from_env(
+            "SPAM_DUPLICATE_WINDOW_HOURS",
+            &mut self.spam.duplicate_window_hours,
+        )?;
+
+        override_from_env(
+            "RATE_LIMIT_NEW_ACCOUNT_DAYS",
+            &mut self.rate_limit.new_account_days,
+        )?;
+        override_from_env(
+            "RATE_LIMIT_BEHIND_PROXY",
+            &mut self.rate_limit.is_behind_proxy,
+        )?;
+
+        override_from_env("RATE_LIMIT_POST_USER", &mut self.rate_limit.post.user)?;
+        override_from_env(
+            "RATE_LIMIT_POST_NEW_USER",
+            &mut self.rate_limit.post.new_user,
+        )?;
+        override_from_env("RATE_LIMIT_POST_IP", &mut self.rate_limit.post.ip)?;
+
+        override_from_env("RATE_LIMIT_COMMENT_USER", &mut self.rate_limit.comment.user)?;
+        override_from_env(
+            "RATE_LIMIT_COMMENT_NEW_USER",
+            &mut self.rate_limit.comment.new_user,
+        )?;
+        override_from_env("RATE_LIMIT_COMMENT_IP", &mut self.rate_limit.comment.ip)?;
+
+        override_from_env("RATE_LIMIT_SIGN_UP_IP", &mut self.rate_limit.sign_up.ip)?;
+
+        override_from_env("CHALLENGE_ON_SIGN_UP", &mut self.challenge.on_sign_up)?;
+        override_from_env("CHALLENGE_ON_FIRST_POST", &mut self.challenge.on_first_post)?;
+        override_from_env(
+            "CHALLENGE_EXPIRE_MINUTES",
+            &mut self.challenge.expire_minutes,
+        )?;
+
+        override_from_env("WEBHOOKS_MAX_ATTEMPTS", &mut self.webhooks.max_attempts)?;
+        override_from_env(
+            "WEBHOOKS_RETRY_BASE_SECONDS",
+            &mut self.webhooks.retry_base_seconds,
+        )?;
+        override_from_env(
+            "WEBHOOKS_TIMEOUT_SECONDS",
+            &mut self.webhooks.timeout_seconds,
+        )?;
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.domain.is_empty() {
+            return Err(invalid("domain", "is not set"));
+        }
+
+        if self.database_url.is_empty() {
+            return Err(invalid("database_url", "is not set"));
+        }
+
+        if self.email.from.parse::<Mailbox>().is_err() {
+            return Err(invalid("email.from", "must be a valid email address"));
+        }
+
+        if self.email.password.is_empty() {
+            return Err(invalid("email.password", "is not set"));
+        }
+
+        if self.email.relay.is_empty() {
+            return Err(invalid("email.relay", "is not set"));
+        }
+
+        if self.page.max_limit < MIN_LIMIT {
+            return Err(invalid("page.max_limit", "must be at least 1"));
+        }
+
+        if !(MIN_LIMIT..=self.page.max_limit).contains(&self.page.default_limit) {
+            return Err(invalid(
+                "page.default_limit",
+                "must be between 1 and `page.max_limit`",
+            ));
+        }
+
+        if self.tags.max_count < TAG_MIN_COUNT {
+            return Err(invalid("tags.max_count", "must be at least 1"));
+        }
+
+        pfp::validate_sizes(&self.pfp.sizes).map_err(|_| {
+            invalid(
+                "pfp.sizes",
+                "must have unique non-empty names and non-zero widths, including `mini` and `normal`",
+            )
+        })?;
+
+        pfp::validate_densities(&self.pfp.densities)
+            .map_err(|_| invalid("pfp.densities", "must be non-zero, unique and include `1`"))?;
+
+        spam::Filter::from_config(&self.spam)
+            .map_err(|_| invalid("spam.banned_patterns", "must be valid regular expressions"))?;
+
+        let limits = [
+            ("rate_limit.post", &self.rate_limit.post),
+            ("rate_limit.comment", &self.rate_limit.comment),
+            ("rate_limit.sign_up", &self.rate_limit.sign_up),
+        ];
+
+        for (key, limits) in limits {
+            let is_valid = [limits.user, limits.new_user, limits.ip]
+                .iter()
+                .all(|limit| limit.is_off() || limit.per_hour > 0);
+
+            if !is_valid {
+                return Err(invalid(
+                    key,
+                    "limits with a `burst` must have a non-zero `per_hour`",
+                ));
+            }
+        }
+
+        if self.challenge.expire_minutes == 0 {
+            return Err(invalid("challenge.expire_minutes", "must be at least 1"));
+        }
+
+        if self.webhooks.max_attempts == 0 {
+            return Err(invalid("webhooks.max_attempts", "must be at least 1"));
+        }
+
+        if self.webhooks.retry_base_seconds == 0 {
+            return Err(invalid("webhooks.retry_base_seconds", "must be at least 1"));
+        }
+
+        if self.webhooks.timeout_seconds == 0 {
+            return Err(invalid("webhooks.timeout_seconds", "must be at least 1"));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            port: 80,
+            domain: String::new(),
+            database_url: String::new(),
+
+            email: EmailConfig::default(),
+            page: PageConfig::default(),
+            post: PostConfig::default(),
+            comment: CommentConfig::default(),
+            tags: TagsConfig::default(),
+            pfp: PfpConfig::default(),
+            modlog: ModLogConfig::default(),
+            deletion: DeletionConfig::default(),
+            spam: SpamConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            challenge: ChallengeConfig::default(),
+            webhooks: WebhooksConfig::default(),
+            markdown: MarkdownConfig::default(),
+        }
+    }
+}
+
+impl Default for PageConfig {
+    fn default() -> Self {
+        Self {
+            default_limit: DEFAULT_LIMIT,
+            max_limit: DEFAULT_MAX_LIMIT,
+        }
+    }
+}
+
+impl Default for PostConfig {
+    fn default() -> Self {
+        Self {
+            edit_window_minutes: default_edit_windows(),
+        }
+    }
+}
+
+impl Default for CommentConfig {
+    fn default() -> Self {
+        Self {
+            edit_window_minutes: default_edit_windows(),
+        }
+    }
+}
+
+impl Default for TagsConfig {
+    fn default() -> Self {
+        Self {
+            max_count: DEFAULT_TAG_MAX_COUNT,
+        }
+    }
+}
+
+impl Default for PfpConfig {
+    fn default() -> Self {
+        Self {
+            sizes: pfp::default_sizes(),
+            densities: pfp::default_densities(),
+        }
+    }
+}
+
+impl Default for DeletionConfig {
+    fn default() -> Self {
+        Self {
+            purge_after_days: DEFAULT_PURGE_AFTER_DAYS,
+        }
+    }
+}
+
+impl Default for SpamConfig {
+    fn default() -> Self {
+        Self {
+            action: SpamAction::default(),
+            banned_words: Vec::new(),
+            banned_patterns: Vec::new(),
+            new_account_days: DEFAULT_NEW_ACCOUNT_DAYS,
+            new_account_max_links: DEFAULT_NEW_ACCOUNT_MAX_LINKS,
+            duplicate_window_hours: DEFAULT_DUPLICATE_WINDOW_HOURS,
+        }
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            new_account_days: DEFAULT_RATE_LIMIT_NEW_ACCOUNT_DAYS,
+            is_behind_proxy: false,
+            post: ActionLimits {
+                user: Limit {
+                    burst: 5,
+                    per_hour: 10,
+                },
+                new_user: Limit {
+                    burst: 2,
+                    per_hour: 3,
+                },
+                ip: Limit {
+                    burst: 10,
+                    per_hour: 30,
+                },
+            },
+            comment: ActionLimits {
+                user: Limit {
+                    burst: 10,
+                    per_hour: 60,
+                },
+                new_user: Limit {
+                    burst: 3,
+                    per_hour: 10,
+                },
+                ip: Limit {
+                    burst: 20,
+                    per_hour: 120,
+                },
+            },
+            // Nobody is signed in yet
+            sign_up: ActionLimits {
+                user: Limit::OFF,
+                new_user: Limit::OFF,
+                ip: Limit {
+                    burst: 3,
+                    per_hour: 5,
+                },
+            },
+        }
+    }
+}
+
+impl Default for ChallengeConfig {
+    fn default() -> Self {
+        Self {
+            on_sign_up: true,
+            on_first_post: false,
+            expire_minutes: DEFAULT_CHALLENGE_EXPIRE_MINUTES,
+        }
+    }
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_WEBHOOK_MAX_ATTEMPTS,
+            retry_base_seconds: DEFAULT_WEBHOOK_RETRY_BASE_SECONDS,
+            timeout_seconds: DEFAULT_WEBHOOK_TIMEOUT_SECONDS,
+        }
+    }
+}
+
+impl Default for MarkdownConfig {
+    fn default() -> Self {
+        Self {
+            tables: true,
+            strikethrough: true,
+            autolinks: true,
+            footnotes: true,
+        }
+    }
+}
+
+fn override_from_env<T: FromStr>(variable: &'static str, value: &mut T) -> Result<(), Error> {
+    let Ok(s) = std::env::var(variable) else {
+        return Ok(());
+    };
+
+    *value = s.parse().map_err(|_| Error::InvalidVariable(variable))?;
+
+    Ok(())
+}
+
+impl FromStr for EditWindow {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unlimited" => Ok(Self::Unlimited),
+            minutes => Ok(Self::Minutes(minutes.parse().map_err(|_| ())?)),
+        }
+    }
+}
+
+impl TryFrom<RawEditWindow> for EditWindow {
+    type Error = String;
+
+    fn try_from(raw: RawEditWindow) -> Result<Self, Self::Error> {
+        match raw {
+            RawEditWindow::Minutes(minutes) => Ok(Self::Minutes(minutes)),
+            RawEditWindow::Word(word) => word
+                .parse()
+                .map_err(|_| format!("expected minutes or \"unlimited\", found `{word}`")),
+        }
+    }
+}
+
+fn default_edit_windows() -> EditWindows {
+    HashMap::from([
+        (
+            MEMBER.to_owned(),
+            EditWindow::Minutes(DEFAULT_EDIT_WINDOW_MINUTES),
+        ),
+        (MOD.to_owned(), EditWindow::Unlimited),
+        (ADMIN.to_owned(), EditWindow::Unlimited),
+    ])
+}
+
+// Parses `role:window` pairs, e.g. `Member:120,Mod:unlimited`,
+// an empty string gives every role the default window
+fn parse_edit_windows(s: &str) -> Result<EditWindows, ()> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|window| !window.is_empty())
+        .map(|window| {
+            let (role, minutes) = window.split_once(':').ok_or(())?;
+            let role = role.trim();
+
+            if role.is_empty() {
+                return Err(());
+            }
+            let window = minutes.trim().parse::<EditWindow>()?;
+
+            Ok((role.to_owned(), window))
+        })
+        .collect()
+}
+
+fn invalid(key: &'static str, reason: &'static str) -> Error {
+    Error::Invalid { key, reason }
+}
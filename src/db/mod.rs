@@ -1,44 +1,574 @@
+use crate::config::Config;
+use crate::policy::Account;
 use crate::session::User as AuthUser;
+use crate::spam::{self, Filter as SpamFilter, Rule as SpamRule, Submission};
+use crate::types::ban::{Ban, RawActiveBan, RawBan};
 use crate::types::comment::{Comment, RawComment};
-use crate::types::id::Id;
+use crate::types::id::{Id, IdSet};
+use crate::types::lifetime::Lifetime;
+use crate::types::mod_action::{reason::Reason, NewModAction, TargetType};
 use crate::types::post::tags::Tags;
 use crate::types::post::{Post, Preview as PostPreview, RawPost, RawPreview as RawPostPreview};
-use crate::types::user::{RawUser, User};
-use sqlx::mysql::MySqlPool;
-use sqlx::mysql::MySqlQueryResult;
+use crate::types::report::{Category, Resolution};
+use crate::types::user::role::{Permissions, RawRole, Role, BANNED};
+use crate::types::user::{RawUser, Restrictions, User};
+use sqlx::mysql::{MySql, MySqlPool, MySqlQueryResult};
+use sqlx::Transaction;
+use std::collections::HashMap;
 
 pub async fn fetch_user_by_username(
     db_pool: &MySqlPool,
     username: &str,
     user: impl Into<Option<&AuthUser>>,
+    config: &Config,
 ) -> sqlx::Result<Option<User>> {
-    sqlx::query_as!(
+    let raw_user = sqlx::query_as!(
         RawUser,
         "
         SELECT
             users.id,
             users.username,
-            users.role,
-            CONCAT(files.id, \".\", files.extension) AS pfp,
+            pfps.variants AS pfp,
             users.name,
             users.description,
             users.joined_at
         FROM users
-            LEFT JOIN files ON users.pfp_file_id = files.id
+            LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'normal'
         WHERE
             users.username = ?
         ",
         username
     )
     .fetch_optional(db_pool)
+    .await?;
+
+    let Some(raw_user) = raw_user else {
+        return Ok(None);
+    };
+
+    let roles = fetch_user_roles(db_pool, raw_user.id).await?;
+
+    Ok(Some(User::from_raw(raw_user, roles, user, config)))
+}
+
+#[derive(Debug)]
+struct RawAccount {
+    pub id: Id,
+}
+
+pub async fn fetch_account_by_username(
+    db_pool: &MySqlPool,
+    username: &str,
+) -> sqlx::Result<Option<Account>> {
+    let Some(raw_account) = sqlx::query_as!(
+        RawAccount,
+        "
+        SELECT
+            id
+        FROM users
+        WHERE
+            username = ?
+        ",
+        username
+    )
+    .fetch_optional(db_pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let roles = fetch_user_roles(db_pool, raw_account.id).await?;
+
+    let account = Account {
+        id: raw_account.id,
+        permissions: Permissions::of(&roles),
+    };
+
+    Ok(Some(account))
+}
+
+pub async fn fetch_user_restrictions(
+    db_pool: &MySqlPool,
+    user_id: Id,
+) -> sqlx::Result<Restrictions> {
+    sqlx::query_as!(
+        Restrictions,
+        "
+        SELECT
+            is_shadow_banned AS `is_shadow_banned: bool`,
+            requires_approval AS `requires_approval: bool`
+        FROM users
+        WHERE
+            id = ?
+        ",
+        user_id
+    )
+    .fetch_one(db_pool)
+    .await
+}
+
+pub async fn fetch_roles(db_pool: &MySqlPool) -> sqlx::Result<Vec<Role>> {
+    sqlx::query_as!(
+        RawRole,
+        "
+        SELECT
+            id,
+            name,
+            can_post AS `can_post: bool`,
+            can_comment AS `can_comment: bool`,
+            can_reply AS `can_reply: bool`,
+            can_edit_posts AS `can_edit_posts: bool`,
+            can_edit_comments AS `can_edit_comments: bool`,
+            can_edit_self AS `can_edit_self: bool`,
+            can_anonymise_posts AS `can_anonymise_posts: bool`,
+            can_anonymise_comments AS `can_anonymise_comments: bool`,
+            can_delete_posts AS `can_delete_posts: bool`,
+            can_delete_comments AS `can_delete_comments: bool`,
+            can_admin AS `can_admin: bool`
+        FROM roles
+        ORDER BY id ASC
+        "
+    )
+    .fetch_all(db_pool)
+    .await
+    .map(|result| result.into_iter().map(Role::from_raw).collect())
+}
+
+pub async fn fetch_user_roles(db_pool: &MySqlPool, user_id: Id) -> sqlx::Result<Vec<Role>> {
+    sqlx::query_as!(
+        RawRole,
+        "
+        SELECT
+            roles.id,
+            roles.name,
+            roles.can_post AS `can_post: bool`,
+            roles.can_comment AS `can_comment: bool`,
+            roles.can_reply AS `can_reply: bool`,
+            roles.can_edit_posts AS `can_edit_posts: bool`,
+            roles.can_edit_comments AS `can_edit_comments: bool`,
+            roles.can_edit_self AS `can_edit_self: bool`,
+            roles.can_anonymise_posts AS `can_anonymise_posts: bool`,
+            roles.can_anonymise_comments AS `can_anonymise_comments: bool`,
+            roles.can_delete_posts AS `can_delete_posts: bool`,
+            roles.can_delete_comments AS `can_delete_comments: bool`,
+            roles.can_admin AS `can_admin: bool`
+        FROM user_roles
+            JOIN roles ON user_roles.role_id = roles.id
+        WHERE
+            user_roles.user_id = ?
+        ORDER BY roles.id ASC
+        ",
+        user_id
+    )
+    .fetch_all(db_pool)
+    .await
+    .map(|result| result.into_iter().map(Role::from_raw).collect())
+}
+
+pub async fn insert_user_role(
+    tx: &mut Transaction<'_, MySql>,
+    user_id: Id,
+    role: &str,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        INSERT IGNORE INTO user_roles
+        (
+            user_id,
+            role_id
+        )
+        SELECT
+            ?,
+            id
+        FROM roles
+        WHERE
+            name = ?
+        ",
+        user_id,
+        role
+    )
+    .execute(&mut *tx)
+    .await
+}
+
+pub async fn delete_user_role(
+    tx: &mut Transaction<'_, MySql>,
+    user_id: Id,
+    role: &str,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        DELETE user_roles FROM user_roles
+            JOIN roles ON user_roles.role_id = roles.id
+        WHERE
+            user_roles.user_id = ?
+            AND roles.name = ?
+        ",
+        user_id,
+        role
+    )
+    .execute(&mut *tx)
+    .await
+}
+
+// Banned replaces all other roles, they are given back when the ban is lifted.
+// Banning an already banned user replaces the ban but keeps the roles to give back
+pub async fn ban_user(
+    tx: &mut Transaction<'_, MySql>,
+    user_id: Id,
+    banned_by_user_id: Id,
+    reason: &Reason,
+    duration: Lifetime,
+) -> sqlx::Result<()> {
+    let previous_roles = match close_active_ban(tx, user_id, Some(banned_by_user_id)).await? {
+        Some(previous_roles) => previous_roles,
+        None => fetch_user_role_names(tx, user_id).await?.join(","),
+    };
+
+    sqlx::query!(
+        "
+        DELETE FROM user_roles
+        WHERE
+            user_id = ?
+        ",
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    insert_user_role(tx, user_id, BANNED).await?;
+
+    sqlx::query!(
+        "
+        INSERT INTO bans
+        (
+            user_id,
+            reason,
+            previous_roles,
+            banned_by_user_id,
+            banned_at,
+            expires_at
+        )
+        VALUES
+        (
+            ?,
+            ?,
+            ?,
+            ?,
+            NOW(),
+            DATE_ADD(NOW(), INTERVAL ? DAY)
+        )
+        ",
+        user_id,
+        reason.as_ref(),
+        previous_roles,
+        banned_by_user_id,
+        duration.days()
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(())
+}
+
+// `lifted_by_user_id` is `None` when the ban ran out on its own.
+// Returns whether there was a ban to lift
+pub async fn lift_ban(
+    tx: &mut Transaction<'_, MySql>,
+    user_id: Id,
+    lifted_by_user_id: Option<Id>,
+) -> sqlx::Result<bool> {
+    let previous_roles = close_active_ban(tx, user_id, lifted_by_user_id).await?;
+
+    let is_banned = fetch_user_role_names(tx, user_id)
+        .await?
+        .iter()
+        .any(|role| role == BANNED);
+
+    if previous_roles.is_none() && !is_banned {
+        return Ok(false);
+    }
+
+    delete_user_role(tx, user_id, BANNED).await?;
+
+    let previous_roles = previous_roles.unwrap_or_default();
+
+    // Roles deleted during the ban are silently skipped
+    for role in previous_roles.split(',').filter(|role| !role.is_empty()) {
+        insert_user_role(tx, user_id, role).await?;
+    }
+
+    Ok(true)
+}
+
+// Marks the current ban as lifted without touching roles,
+// returns the roles it was going to give back
+async fn close_active_ban(
+    tx: &mut Transaction<'_, MySql>,
+    user_id: Id,
+    lifted_by_user_id: Option<Id>,
+) -> sqlx::Result<Option<String>> {
+    #[derive(Debug)]
+    struct RawBan {
+        previous_roles: String,
+    }
+
+    let raw_ban = sqlx::query_as!(
+        RawBan,
+        "
+        SELECT
+            previous_roles
+        FROM bans
+        WHERE
+            user_id = ?
+            AND lifted_at IS NULL
+        ORDER BY id DESC
+        LIMIT 1
+        FOR UPDATE
+        ",
+        user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(raw_ban) = raw_ban else {
+        return Ok(None);
+    };
+
+    sqlx::query!(
+        "
+        UPDATE bans
+        SET
+            lifted_by_user_id = ?,
+            lifted_at = NOW()
+        WHERE
+            user_id = ?
+            AND lifted_at IS NULL
+        ",
+        lifted_by_user_id,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(Some(raw_ban.previous_roles))
+}
+
+async fn fetch_user_role_names(
+    tx: &mut Transaction<'_, MySql>,
+    user_id: Id,
+) -> sqlx::Result<Vec<String>> {
+    #[derive(Debug)]
+    struct RawRoleName {
+        name: String,
+    }
+
+    sqlx::query_as!(
+        RawRoleName,
+        "
+        SELECT
+            roles.name
+        FROM user_roles
+            JOIN roles ON user_roles.role_id = roles.id
+        WHERE
+            user_roles.user_id = ?
+        ORDER BY roles.id ASC
+        ",
+        user_id
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map(|result| {
+        result
+            .into_iter()
+            .map(|role| role.name)
+            .filter(|name| name != BANNED)
+            .collect()
+    })
+}
+
+pub async fn fetch_active_ban(
+    db_pool: &MySqlPool,
+    user_id: Id,
+) -> sqlx::Result<Option<RawActiveBan>> {
+    sqlx::query_as!(
+        RawActiveBan,
+        "
+        SELECT
+            reason,
+            expires_at,
+            (expires_at IS NOT NULL AND expires_at <= NOW()) AS `is_expired!: bool`
+        FROM bans
+        WHERE
+            user_id = ?
+            AND lifted_at IS NULL
+        ORDER BY id DESC
+        LIMIT 1
+        ",
+        user_id
+    )
+    .fetch_optional(db_pool)
+    .await
+}
+
+pub async fn fetch_user_bans(db_pool: &MySqlPool, user_id: Id) -> sqlx::Result<Vec<Ban>> {
+    sqlx::query_as!(
+        RawBan,
+        "
+        SELECT
+            bans.id,
+            bans.reason,
+            banned_by.id AS banned_by_user_id,
+            banned_by.username AS banned_by_username,
+            pfps.variants AS banned_by_mini_pfp,
+            bans.banned_at,
+            bans.expires_at,
+            lifted_by.username AS lifted_by_username,
+            bans.lifted_at
+        FROM bans
+            LEFT JOIN users AS banned_by ON bans.banned_by_user_id = banned_by.id
+            LEFT JOIN user_pfps AS pfps ON banned_by.id = pfps.user_id AND pfps.size = 'mini'
+            LEFT JOIN users AS lifted_by ON bans.lifted_by_user_id = lifted_by.id
+        WHERE
+            bans.user_id = ?
+        ORDER BY bans.id DESC
+        ",
+        user_id
+    )
+    .fetch_all(db_pool)
+    .await
+    .map(|result| result.into_iter().map(Ban::from_raw).collect())
+}
+
+pub async fn insert_mod_action(
+    tx: &mut Transaction<'_, MySql>,
+    action: &NewModAction<'_>,
+) -> sqlx::Result<MySqlQueryResult> {
+    // Labels are informational, cut them to fit the column
+    let target_label = action.target_label.chars().take(256).collect::<String>();
+    let details = action.details.chars().take(512).collect::<String>();
+
+    sqlx::query!(
+        "
+        INSERT INTO mod_actions
+        (
+            actor_user_id,
+            action,
+            target_type,
+            target_id,
+            target_label,
+            reason,
+            details,
+            created_at
+        )
+        VALUES
+        (
+            ?,
+            ?,
+            ?,
+            ?,
+            ?,
+            ?,
+            ?,
+            NOW()
+        )
+        ",
+        action.actor_user_id,
+        action.kind.as_str(),
+        action.target_type.as_str(),
+        action.target_id,
+        target_label,
+        action.reason.as_ref(),
+        details
+    )
+    .execute(&mut *tx)
+    .await
+}
+
+// Soft deletion, the row is purged for good after `deletion.purge_after_days`
+pub async fn soft_delete_post(
+    tx: &mut Transaction<'_, MySql>,
+    id: Id,
+    deleted_by_user_id: Id,
+    reason: &Reason,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        UPDATE posts
+        SET
+            deleted_at = NOW(),
+            deleted_by_user_id = ?,
+            deleted_reason = ?
+        WHERE
+            id = ?
+            AND deleted_at IS NULL
+        ",
+        deleted_by_user_id,
+        reason.as_ref(),
+        id
+    )
+    .execute(&mut *tx)
+    .await
+}
+
+pub async fn soft_delete_comment(
+    tx: &mut Transaction<'_, MySql>,
+    id: Id,
+    deleted_by_user_id: Id,
+    reason: &Reason,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        UPDATE comments
+        SET
+            deleted_at = NOW(),
+            deleted_by_user_id = ?,
+            deleted_reason = ?
+        WHERE
+            id = ?
+            AND deleted_at IS NULL
+        ",
+        deleted_by_user_id,
+        reason.as_ref(),
+        id
+    )
+    .execute(&mut *tx)
+    .await
+}
+
+pub async fn resolve_reports(
+    tx: &mut Transaction<'_, MySql>,
+    target_type: TargetType,
+    target_id: Id,
+    resolution: Resolution,
+    resolved_by_user_id: Id,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        UPDATE reports
+        SET
+            resolution = ?,
+            resolved_by_user_id = ?,
+            resolved_at = NOW()
+        WHERE
+            target_type = ?
+            AND target_id = ?
+            AND resolved_at IS NULL
+        ",
+        resolution.as_str(),
+        resolved_by_user_id,
+        target_type.as_str(),
+        target_id
+    )
+    .execute(&mut *tx)
     .await
-    .map(|result| result.map(|raw| User::from_raw(raw, user)))
 }
 
 pub async fn fetch_comment_by_id(
     db_pool: &MySqlPool,
     id: Id,
-    user: &AuthUser,
+    user: impl Into<Option<&AuthUser>>,
+    config: &Config,
 ) -> sqlx::Result<Option<Comment>> {
     sqlx::query_as!(
         RawComment,
@@ -49,36 +579,46 @@ pub async fn fetch_comment_by_id(
             comments.reply_to_comment_id,
             users_reply_to.id AS reply_to_user_id,
             users_reply_to.username AS reply_to_username,
-            CONCAT(files_reply_to.id, \".\", files_reply_to.extension) AS reply_to_mini_pfp,
+            pfps_reply_to.variants AS reply_to_mini_pfp,
             comments.markdown_content AS content,
             users_posted_by.id AS posted_by_user_id,
             users_posted_by.username AS posted_by_username,
-            CONCAT(files_posted_by.id, \".\", files_posted_by.extension) AS posted_by_mini_pfp,
+            pfps_posted_by.variants AS posted_by_mini_pfp,
             comments.posted_at,
-            comments.modified_at
+            comments.modified_at,
+            comments.deleted_at,
+            (
+                SELECT COUNT(*)
+                FROM reports
+                WHERE
+                    reports.target_type = 'Comment'
+                    AND reports.target_id = comments.id
+                    AND reports.resolved_at IS NULL
+            ) AS `open_report_count!`
         FROM comments
             LEFT JOIN comments AS comments_reply_to ON comments.reply_to_comment_id = comments_reply_to.id
             LEFT JOIN users AS users_reply_to ON comments_reply_to.posted_by_user_id = users_reply_to.id
-            LEFT JOIN files AS files_reply_to ON users_reply_to.mini_pfp_file_id = files_reply_to.id
+            LEFT JOIN user_pfps AS pfps_reply_to ON users_reply_to.id = pfps_reply_to.user_id AND pfps_reply_to.size = 'mini'
             LEFT JOIN users AS users_posted_by ON comments.posted_by_user_id = users_posted_by.id
-            LEFT JOIN files AS files_posted_by ON users_posted_by.mini_pfp_file_id = files_posted_by.id
+            LEFT JOIN user_pfps AS pfps_posted_by ON users_posted_by.id = pfps_posted_by.user_id AND pfps_posted_by.size = 'mini'
         WHERE
             comments.id = ?
         ",
         id
-    ).fetch_optional(db_pool).await.map(|result| result.map(|raw_comment| Comment::from_raw(raw_comment, user)))
+    ).fetch_optional(db_pool).await.map(|result| result.map(|raw_comment| Comment::from_raw(raw_comment, user.into(), config)))
 }
 
 pub async fn fetch_contentless_post_by_id(
     db_pool: &MySqlPool,
     post_id: Id,
     user: &AuthUser,
+    config: &Config,
 ) -> sqlx::Result<Option<Post>> {
     let Some(raw_post) = fetch_raw_contentless_post_by_id(db_pool, post_id).await? else {
         return Ok(None);
     };
 
-    let post = fetch_post_from_raw(db_pool, raw_post, user).await?;
+    let post = fetch_post_from_raw(db_pool, raw_post, user, config).await?;
 
     Ok(Some(post))
 }
@@ -97,12 +637,23 @@ pub async fn fetch_raw_contentless_post_by_id(
             '' AS content,
             users.id AS posted_by_user_id,
             users.username AS posted_by_username,
-            CONCAT(files.id, \".\", files.extension) AS posted_by_mini_pfp,
+            pfps.variants AS posted_by_mini_pfp,
             posts.posted_at,
-            posts.modified_at
+            posts.modified_at,
+            posts.deleted_at,
+            posts.is_pending AS `is_pending: bool`,
+            posts.is_shadowed AS `is_shadowed: bool`,
+            (
+                SELECT COUNT(*)
+                FROM reports
+                WHERE
+                    reports.target_type = 'Post'
+                    AND reports.target_id = posts.id
+                    AND reports.resolved_at IS NULL
+            ) AS `open_report_count!`
         FROM posts
             LEFT JOIN users ON posts.posted_by_user_id = users.id
-            LEFT JOIN files ON users.mini_pfp_file_id = files.id
+            LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
         WHERE
             posts.id = ?
         ",
@@ -112,23 +663,242 @@ pub async fn fetch_raw_contentless_post_by_id(
     .await
 }
 
-pub async fn insert_post_tags(
+// New posts are held for approval when their author or any of their tags requires it
+pub async fn fetch_requires_approval(
     db_pool: &MySqlPool,
+    user_id: Id,
+    tags: &Tags,
+) -> sqlx::Result<bool> {
+    if fetch_user_restrictions(db_pool, user_id)
+        .await?
+        .requires_approval
+    {
+        return Ok(true);
+    }
+
+    #[derive(Debug)]
+    struct Tag {
+        requires_approval: bool,
+    }
+
+    for tag in tags {
+        let tag = sqlx::query_as!(
+            Tag,
+            "
+            SELECT
+                requires_approval AS `requires_approval: bool`
+            FROM tags
+            WHERE
+                name = ?
+            ",
+            tag
+        )
+        .fetch_optional(db_pool)
+        .await?;
+
+        if tag.map(|tag| tag.requires_approval).unwrap_or(false) {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+// `post_id` is left out of the duplicate check, so edits don't match themselves
+pub async fn check_post_spam(
+    db_pool: &MySqlPool,
+    filter: &SpamFilter,
+    config: &Config,
+    user_id: Id,
+    text: &str,
+    post_id: Option<Id>,
+) -> sqlx::Result<Option<SpamRule>> {
+    #[derive(Debug)]
+    struct Post {
+        text: String,
+    }
+
+    let recent = if config.spam.duplicate_window_hours > 0 {
+        sqlx::query_as!(
+            Post,
+            "
+            SELECT
+                CONCAT_WS('\n', title, description, content) AS `text!`
+            FROM posts
+            WHERE
+                posted_at > DATE_SUB(NOW(), INTERVAL ? HOUR)
+                AND deleted_at IS NULL
+                AND NOT id <=> ?
+            ORDER BY id DESC
+            LIMIT ?
+            ",
+            config.spam.duplicate_window_hours,
+            post_id,
+            spam::DUPLICATE_SCAN_LIMIT
+        )
+        .fetch_all(db_pool)
+        .await?
+        .into_iter()
+        .map(|post| post.text)
+        .collect()
+    } else {
+        Vec::new()
+    };
+
+    let submission = Submission {
+        text,
+        account_age_days: fetch_account_age_days(db_pool, user_id).await?,
+        recent: &recent,
+    };
+
+    Ok(filter.check(&submission))
+}
+
+pub async fn check_comment_spam(
+    db_pool: &MySqlPool,
+    filter: &SpamFilter,
+    config: &Config,
+    user_id: Id,
+    content: &str,
+    comment_id: Option<Id>,
+) -> sqlx::Result<Option<SpamRule>> {
+    #[derive(Debug)]
+    struct Comment {
+        content: String,
+    }
+
+    let recent = if config.spam.duplicate_window_hours > 0 {
+        sqlx::query_as!(
+            Comment,
+            "
+            SELECT
+                content
+            FROM comments
+            WHERE
+                posted_at > DATE_SUB(NOW(), INTERVAL ? HOUR)
+                AND deleted_at IS NULL
+                AND NOT id <=> ?
+            ORDER BY id DESC
+            LIMIT ?
+            ",
+            config.spam.duplicate_window_hours,
+            comment_id,
+            spam::DUPLICATE_SCAN_LIMIT
+        )
+        .fetch_all(db_pool)
+        .await?
+        .into_iter()
+        .map(|comment| comment.content)
+        .collect()
+    } else {
+        Vec::new()
+    };
+
+    let submission = Submission {
+        text: content,
+        account_age_days: fetch_account_age_days(db_pool, user_id).await?,
+        recent: &recent,
+    };
+
+    Ok(filter.check(&submission))
+}
+
+// Deleted posts count too
+pub async fn fetch_has_posted(db_pool: &MySqlPool, user_id: Id) -> sqlx::Result<bool> {
+    sqlx::query!(
+        "
+        SELECT
+            id
+        FROM posts
+        WHERE
+            posted_by_user_id = ?
+        LIMIT 1
+        ",
+        user_id
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map(|result| result.is_some())
+}
+
+pub async fn fetch_account_age_days(db_pool: &MySqlPool, user_id: Id) -> sqlx::Result<i64> {
+    #[derive(Debug)]
+    struct User {
+        age_days: i64,
+    }
+
+    sqlx::query_as!(
+        User,
+        "
+        SELECT
+            DATEDIFF(NOW(), joined_at) AS `age_days!: i64`
+        FROM users
+        WHERE
+            id = ?
+        ",
+        user_id
+    )
+    .fetch_one(db_pool)
+    .await
+    .map(|user| user.age_days)
+}
+
+// Held by the spam filter, shows up in `/mod/queue` without a reporter
+pub async fn insert_automatic_report(
+    tx: &mut Transaction<'_, MySql>,
+    target_type: TargetType,
+    target_id: Id,
+    rule: SpamRule,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        INSERT INTO reports
+        (
+            target_type,
+            target_id,
+            category,
+            details,
+            reported_by_user_id,
+            reported_at,
+            is_automatic
+        )
+        VALUES
+        (
+            ?,
+            ?,
+            ?,
+            ?,
+            NULL,
+            NOW(),
+            1
+        )
+        ",
+        target_type.as_str(),
+        target_id,
+        Category::Spam.as_str(),
+        rule.to_string()
+    )
+    .execute(&mut *tx)
+    .await
+}
+
+pub async fn insert_post_tags(
+    tx: &mut Transaction<'_, MySql>,
     tags: &Tags,
     post_id: Id,
     user_id: Id,
 ) -> sqlx::Result<()> {
     for tag in tags {
-        let tag_id = upsert_tag(db_pool, tag, user_id).await?;
+        let tag_id = upsert_tag(tx, tag, user_id).await?;
 
-        insert_post_tag(db_pool, post_id, tag_id).await?;
+        insert_post_tag(tx, post_id, tag_id).await?;
     }
 
     Ok(())
 }
 
 async fn insert_post_tag(
-    db_pool: &MySqlPool,
+    tx: &mut Transaction<'_, MySql>,
     post_id: Id,
     tag_id: Id,
 ) -> sqlx::Result<MySqlQueryResult> {
@@ -148,11 +918,15 @@ async fn insert_post_tag(
         post_id,
         tag_id
     )
-    .execute(db_pool)
+    .execute(&mut *tx)
     .await
 }
 
-async fn upsert_tag(db_pool: &MySqlPool, tag: &str, created_by_user_id: Id) -> sqlx::Result<Id> {
+async fn upsert_tag(
+    tx: &mut Transaction<'_, MySql>,
+    tag: &str,
+    created_by_user_id: Id,
+) -> sqlx::Result<Id> {
     // TODO: Batch this up
     #[derive(Debug)]
     struct Tag {
@@ -170,7 +944,7 @@ async fn upsert_tag(db_pool: &MySqlPool, tag: &str, created_by_user_id: Id) -> s
         ",
         tag
     )
-    .fetch_optional(db_pool)
+    .fetch_optional(&mut *tx)
     .await?
     {
         return Ok(tag.id);
@@ -194,7 +968,7 @@ async fn upsert_tag(db_pool: &MySqlPool, tag: &str, created_by_user_id: Id) -> s
         tag,
         created_by_user_id
     )
-    .execute(db_pool)
+    .execute(&mut *tx)
     .await?;
 
     Ok(tag_insert_result.last_insert_id())
@@ -204,10 +978,11 @@ pub async fn fetch_post_from_raw<'a>(
     db_pool: &MySqlPool,
     raw_post: RawPost,
     user: impl Into<Option<&'a AuthUser>>,
+    config: &Config,
 ) -> sqlx::Result<Post> {
     let tags = fetch_post_tags(db_pool, raw_post.id).await?;
 
-    let post = Post::from_raw(raw_post, tags, user);
+    let post = Post::from_raw(raw_post, tags, user, config);
 
     Ok(post)
 }
@@ -223,6 +998,25 @@ pub async fn fetch_post_preview_from_raw(
     Ok(post)
 }
 
+// For listings, the tags of all posts are fetched at once
+pub async fn fetch_post_previews_from_raw(
+    db_pool: &MySqlPool,
+    raw_post_previews: Vec<RawPostPreview>,
+) -> sqlx::Result<Vec<PostPreview>> {
+    let mut tags = fetch_posts_tags(db_pool, raw_post_previews.iter().map(|raw| raw.id)).await?;
+
+    let post_previews = raw_post_previews
+        .into_iter()
+        .map(|raw_post_preview| {
+            let tags = tags.remove(&raw_post_preview.id).unwrap_or_default();
+
+            PostPreview::from_raw(raw_post_preview, tags)
+        })
+        .collect();
+
+    Ok(post_previews)
+}
+
 async fn fetch_post_tags(db_pool: &MySqlPool, post_id: Id) -> sqlx::Result<Vec<String>> {
     #[derive(Debug)]
     struct Tag {
@@ -245,3 +1039,212 @@ async fn fetch_post_tags(db_pool: &MySqlPool, post_id: Id) -> sqlx::Result<Vec<S
     .await
     .map(|result| result.into_iter().map(|tag| tag.name).collect())
 }
+
+async fn fetch_posts_tags(
+    db_pool: &MySqlPool,
+    post_ids: impl IntoIterator<Item = Id>,
+) -> sqlx::Result<HashMap<Id, Vec<String>>> {
+    #[derive(Debug)]
+    struct PostTag {
+        pub post_id: Id,
+        pub name: String,
+    }
+
+    let Some(ids) = IdSet::new(post_ids) else {
+        return Ok(HashMap::new());
+    };
+
+    let post_tags = sqlx::query_as!(
+        PostTag,
+        "
+        SELECT
+            post_tags.post_id,
+            tags.name
+        FROM post_tags
+            JOIN tags on post_tags.tag_id = tags.id
+        WHERE
+            post_tags.post_id BETWEEN ? AND ?
+            AND FIND_IN_SET(post_tags.post_id, ?)
+        ",
+        ids.min,
+        ids.max,
+        ids.list
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    let mut tags: HashMap<Id, Vec<String>> = HashMap::new();
+
+    for post_tag in post_tags {
+        tags.entry(post_tag.post_id)
+            .or_default()
+            .push(post_tag.name);
+    }
+
+    Ok(tags)
+}
+
+pub async fn fetch_webhook_exists(db_pool: &MySqlPool, id: Id) -> sqlx::Result<bool> {
+    #[derive(Debug)]
+    struct Webhook {
+        id: Id,
+    }
+
+    sqlx::query_as!(
+        Webhook,
+        "
+        SELECT
+            id
+        FROM webhooks
+        WHERE
+            id = ?
+        ",
+        id
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map(|result| result.map(|webhook| webhook.id).is_some())
+}
+
+// Edits keep every version in `post_revisions`. The version an edit replaces only has to be
+// saved for the first edit, after that it's the newest revision already. Its editor is only
+// known when it's the original post
+pub async fn insert_original_post_revision(
+    tx: &mut Transaction<'_, MySql>,
+    post_id: Id,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        INSERT INTO post_revisions
+        (
+            post_id,
+            title,
+            description,
+            content,
+            edited_by_user_id,
+            created_at
+        )
+        SELECT
+            id,
+            title,
+            description,
+            content,
+            IF(modified_at IS NULL, posted_by_user_id, NULL),
+            COALESCE(modified_at, posted_at)
+        FROM posts
+        WHERE
+            id = ?
+            AND NOT EXISTS (
+                SELECT 1
+                FROM post_revisions
+                WHERE
+                    post_id = ?
+            )
+        ",
+        post_id,
+        post_id
+    )
+    .execute(&mut *tx)
+    .await
+}
+
+// Called after the post is updated, copies the new version
+pub async fn insert_post_revision(
+    tx: &mut Transaction<'_, MySql>,
+    post_id: Id,
+    edited_by_user_id: Id,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        INSERT INTO post_revisions
+        (
+            post_id,
+            title,
+            description,
+            content,
+            edited_by_user_id,
+            created_at
+        )
+        SELECT
+            id,
+            title,
+            description,
+            content,
+            ?,
+            NOW()
+        FROM posts
+        WHERE
+            id = ?
+        ",
+        edited_by_user_id,
+        post_id
+    )
+    .execute(&mut *tx)
+    .await
+}
+
+// Same as `insert_original_post_revision`
+pub async fn insert_original_comment_revision(
+    tx: &mut Transaction<'_, MySql>,
+    comment_id: Id,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        INSERT INTO comment_revisions
+        (
+            comment_id,
+            content,
+            edited_by_user_id,
+            created_at
+        )
+        SELECT
+            id,
+            content,
+            IF(modified_at IS NULL, posted_by_user_id, NULL),
+            COALESCE(modified_at, posted_at)
+        FROM comments
+        WHERE
+            id = ?
+            AND NOT EXISTS (
+                SELECT 1
+                FROM comment_revisions
+                WHERE
+                    comment_id = ?
+            )
+        ",
+        comment_id,
+        comment_id
+    )
+    .execute(&mut *tx)
+    .await
+}
+
+pub async fn insert_comment_revision(
+    tx: &mut Transaction<'_, MySql>,
+    comment_id: Id,
+    edited_by_user_id: Id,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        INSERT INTO comment_revisions
+        (
+            comment_id,
+            content,
+            edited_by_user_id,
+            created_at
+        )
+        SELECT
+            id,
+            content,
+            ?,
+            NOW()
+        FROM comments
+        WHERE
+            id = ?
+        ",
+        edited_by_user_id,
+        comment_id
+    )
+    .execute(&mut *tx)
+    .await
+}
@@ -0,0 +1,307 @@
+use crate::types::comment::Comment;
+use crate::types::id::{Id, IdSet};
+use crate::types::post::Preview as PostPreview;
+use crate::LIQUID_PARSER;
+use actix_web::http::header::{
+    self, ETag, EntityTag, Header, HttpDate, IfModifiedSince, IfNoneMatch, LastModified,
+};
+use actix_web::{HttpRequest, HttpResponse};
+use chrono::{NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use lazy_static::lazy_static;
+use liquid::Template;
+use sha2::{Digest, Sha256};
+use sqlx::mysql::MySqlPool;
+use std::collections::HashMap;
+use std::time::{Duration, UNIX_EPOCH};
+
+// Feeds are always built for an anonymous viewer, so readers never get to see
+// pending, shadowed or deleted posts and comments
+
+#[derive(Debug, Copy, Clone)]
+pub enum Format {
+    Atom,
+    Rss,
+}
+
+impl Format {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Atom => "application/atom+xml; charset=utf-8",
+            Self::Rss => "application/rss+xml; charset=utf-8",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Feed {
+    pub title: String,
+    // The page the feed follows
+    pub url: String,
+    pub self_url: String,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug)]
+pub struct Entry {
+    pub title: String,
+    pub url: String,
+    pub author: Option<String>,
+    pub summary: String,
+    // Rendered markdown
+    pub content: String,
+    pub categories: Vec<String>,
+    pub published: NaiveDateTime,
+    pub updated: NaiveDateTime,
+}
+
+// What the listing queries of the pages leave out
+#[derive(Debug)]
+struct RawBody {
+    id: Id,
+    markdown_content: String,
+    posted_at: NaiveDateTime,
+    modified_at: Option<NaiveDateTime>,
+}
+
+impl Feed {
+    // Newest change to anything in the feed, `None` when it's empty
+    fn updated(&self) -> Option<NaiveDateTime> {
+        self.entries.iter().map(|entry| entry.updated).max()
+    }
+}
+
+pub async fn post_entries(
+    db_pool: &MySqlPool,
+    domain: &str,
+    posts: Vec<PostPreview>,
+) -> sqlx::Result<Vec<Entry>> {
+    let mut bodies = fetch_post_bodies(db_pool, posts.iter().map(|post| post.id)).await?;
+
+    let mut entries = Vec::new();
+
+    for post in posts {
+        let Some(body) = bodies.remove(&post.id) else {
+            continue;
+        };
+
+        entries.push(Entry {
+            title: post.title,
+            url: format!("https://{domain}/post/{}", post.id),
+            author: post.posted_by.map(|user| user.username),
+            summary: post.description,
+            content: body.markdown_content,
+            categories: post.tags,
+            published: body.posted_at,
+            updated: body.modified_at.unwrap_or(body.posted_at),
+        });
+    }
+
+    Ok(entries)
+}
+
+pub async fn comment_entries(
+    db_pool: &MySqlPool,
+    domain: &str,
+    post_title: &str,
+    comments: Vec<Comment>,
+) -> sqlx::Result<Vec<Entry>> {
+    let comments: Vec<Comment> = comments
+        .into_iter()
+        .filter(|comment| !comment.is_deleted)
+        .collect();
+
+    let mut bodies =
+        fetch_comment_bodies(db_pool, comments.iter().map(|comment| comment.id)).await?;
+
+    let mut entries = Vec::new();
+
+    for comment in comments {
+        let Some(body) = bodies.remove(&comment.id) else {
+            continue;
+        };
+
+        let author = comment.posted_by.map(|user| user.username);
+
+        entries.push(Entry {
+            title: match &author {
+                Some(username) => format!("{username} on {post_title}"),
+                None => format!("Comment on {post_title}"),
+            },
+            url: format!(
+                "https://{domain}/post/{}?start_id={}#{}",
+                comment.post_id, comment.id, comment.id
+            ),
+            author,
+            summary: String::new(),
+            content: body.markdown_content,
+            categories: Vec::new(),
+            published: body.posted_at,
+            updated: body.modified_at.unwrap_or(body.posted_at),
+        });
+    }
+
+    Ok(entries)
+}
+
+// Renders the feed, answering with `304 Not Modified` when the reader already has it
+pub fn respond(req: &HttpRequest, feed: Feed, format: Format) -> HttpResponse {
+    lazy_static! {
+        static ref ATOM_TEMPLATE: Template = {
+            let template = include_str!("../templates/feed/atom.xml");
+
+            LIQUID_PARSER.parse(template).unwrap()
+        };
+        static ref RSS_TEMPLATE: Template = {
+            let template = include_str!("../templates/feed/rss.xml");
+
+            LIQUID_PARSER.parse(template).unwrap()
+        };
+    }
+
+    let updated = feed.updated();
+
+    let (template, format_date): (&Template, fn(NaiveDateTime) -> String) = match format {
+        Format::Atom => (&ATOM_TEMPLATE, format_rfc3339),
+        Format::Rss => (&RSS_TEMPLATE, format_rfc2822),
+    };
+
+    let entries: Vec<_> = feed
+        .entries
+        .into_iter()
+        .map(|entry| {
+            liquid::object!({
+                "title": entry.title,
+                "url": entry.url,
+                "author": entry.author,
+                "summary": entry.summary,
+                "content": entry.content,
+                "categories": entry.categories,
+                "published": format_date(entry.published),
+                "updated": format_date(entry.updated),
+            })
+        })
+        .collect();
+
+    let globals = liquid::object!({
+        "title": feed.title,
+        "url": feed.url,
+        "self_url": feed.self_url,
+        "updated": format_date(updated.unwrap_or_else(|| Utc::now().naive_utc())),
+        "entries": entries,
+    });
+
+    let body = template.render(&globals).unwrap();
+
+    let etag = EntityTag::new_strong(hex::encode(&Sha256::digest(body.as_bytes())[..16]));
+    let last_modified = updated.map(to_http_date);
+
+    // If-Modified-Since only counts when there is no If-None-Match
+    let is_fresh = if req.headers().contains_key(header::IF_NONE_MATCH) {
+        match IfNoneMatch::parse(req) {
+            Ok(IfNoneMatch::Any) => true,
+            Ok(IfNoneMatch::Items(etags)) => etags.iter().any(|other| other.weak_eq(&etag)),
+            Err(_) => false,
+        }
+    } else {
+        match (IfModifiedSince::parse(req), last_modified) {
+            (Ok(IfModifiedSince(since)), Some(last_modified)) => last_modified <= since,
+            _ => false,
+        }
+    };
+
+    let mut response = if is_fresh {
+        HttpResponse::NotModified()
+    } else {
+        HttpResponse::Ok()
+    };
+
+    response.insert_header(ETag(etag));
+
+    if let Some(last_modified) = last_modified {
+        response.insert_header(LastModified(last_modified));
+    }
+
+    if is_fresh {
+        response.finish()
+    } else {
+        response.content_type(format.content_type()).body(body)
+    }
+}
+
+fn format_rfc3339(date: NaiveDateTime) -> String {
+    Utc.from_utc_datetime(&date)
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn format_rfc2822(date: NaiveDateTime) -> String {
+    Utc.from_utc_datetime(&date).to_rfc2822()
+}
+
+fn to_http_date(date: NaiveDateTime) -> HttpDate {
+    let seconds = Utc.from_utc_datetime(&date).timestamp().max(0) as u64;
+
+    HttpDate::from(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+// One query for the whole feed
+async fn fetch_post_bodies(
+    db_pool: &MySqlPool,
+    post_ids: impl IntoIterator<Item = Id>,
+) -> sqlx::Result<HashMap<Id, RawBody>> {
+    let Some(ids) = IdSet::new(post_ids) else {
+        return Ok(HashMap::new());
+    };
+
+    let bodies = sqlx::query_as!(
+        RawBody,
+        "
+        SELECT
+            id,
+            markdown_content,
+            posted_at,
+            modified_at
+        FROM posts
+        WHERE
+            id BETWEEN ? AND ?
+            AND FIND_IN_SET(id, ?)
+        ",
+        ids.min,
+        ids.max,
+        ids.list
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    Ok(bodies.into_iter().map(|body| (body.id, body)).collect())
+}
+
+async fn fetch_comment_bodies(
+    db_pool: &MySqlPool,
+    comment_ids: impl IntoIterator<Item = Id>,
+) -> sqlx::Result<HashMap<Id, RawBody>> {
+    let Some(ids) = IdSet::new(comment_ids) else {
+        return Ok(HashMap::new());
+    };
+
+    let bodies = sqlx::query_as!(
+        RawBody,
+        "
+        SELECT
+            id,
+            markdown_content,
+            posted_at,
+            modified_at
+        FROM comments
+        WHERE
+            id BETWEEN ? AND ?
+            AND FIND_IN_SET(id, ?)
+        ",
+        ids.min,
+        ids.max,
+        ids.list
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    Ok(bodies.into_iter().map(|body| (body.id, body)).collect())
+}
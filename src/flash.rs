@@ -0,0 +1,127 @@
+use actix_web::{
+    cookie::{time::Duration as CookieDuration, Cookie},
+    http::header,
+    HttpRequest, HttpResponse,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+// What was submitted with a form that failed, kept in memory for the page the user is sent
+// back to. Only its id goes into a cookie, posts are too long for one
+
+pub const FLASH_COOKIE_NAME: &str = "flash";
+
+// Flashes that weren't picked up by then are dropped
+const TTL: Duration = Duration::from_secs(5 * 60);
+// Expired flashes are forgotten once there are this many, none are kept over it
+const PRUNE_THRESHOLD: usize = 10_000;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Flash {
+    pub fields: HashMap<String, String>,
+    // By field name, errors that aren't about a field are only shown through `?error=`
+    pub errors: HashMap<String, String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Store {
+    flashes: Mutex<HashMap<Uuid, (Instant, Flash)>>,
+}
+
+impl Flash {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn field(mut self, name: &str, value: &str) -> Self {
+        self.fields.insert(name.to_owned(), value.to_owned());
+
+        self
+    }
+
+    pub fn error(mut self, field: Option<&str>, error: impl Display) -> Self {
+        if let Some(field) = field {
+            self.errors.insert(field.to_owned(), error.to_string());
+        }
+
+        self
+    }
+
+    // What the user submitted if there is a flash, otherwise what the form starts with
+    pub fn value(&self, name: &str, default: impl Into<String>) -> String {
+        match self.fields.get(name) {
+            Some(value) => value.clone(),
+            None => default.into(),
+        }
+    }
+}
+
+impl Store {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Redirects to `location` with the flash waiting there. The cookie is limited to the page's
+    // path, so no other page can take it on the way
+    pub fn redirect(&self, location: &str, flash: Flash) -> HttpResponse {
+        let mut response = HttpResponse::SeeOther();
+
+        response.append_header((header::LOCATION, location));
+
+        if let Some(id) = self.put(flash) {
+            let path = location.split(['?', '#']).next().unwrap_or("/");
+
+            let cookie = Cookie::build(FLASH_COOKIE_NAME, id.to_string())
+                // .secure(true)
+                .path(path)
+                .http_only(true)
+                .max_age(CookieDuration::seconds(TTL.as_secs() as i64))
+                .finish();
+
+            response.cookie(cookie);
+        }
+
+        response.finish()
+    }
+
+    // A flash is only shown once, without one the form is filled the usual way
+    pub fn take(&self, req: &HttpRequest) -> Flash {
+        let Some(id) = req
+            .cookie(FLASH_COOKIE_NAME)
+            .and_then(|cookie| Uuid::parse_str(cookie.value()).ok())
+        else {
+            return Flash::new();
+        };
+
+        let mut flashes = self.flashes.lock().unwrap_or_else(PoisonError::into_inner);
+
+        flashes
+            .remove(&id)
+            .filter(|(created_at, _)| created_at.elapsed() < TTL)
+            .map(|(_, flash)| flash)
+            .unwrap_or_default()
+    }
+
+    fn put(&self, flash: Flash) -> Option<Uuid> {
+        let now = Instant::now();
+        let mut flashes = self.flashes.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if flashes.len() >= PRUNE_THRESHOLD {
+            flashes.retain(|_, (created_at, _)| now.duration_since(*created_at) < TTL);
+        }
+
+        if flashes.len() >= PRUNE_THRESHOLD {
+            return None;
+        }
+
+        let id = Uuid::new_v4();
+
+        flashes.insert(id, (now, flash));
+
+        Some(id)
+    }
+}
@@ -0,0 +1,33 @@
+use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use std::time::Duration;
+
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+// Forgets answered challenges once they'd have expired anyway, every ten minutes
+pub async fn run(db_pool: MySqlPool, expire_minutes: u32) {
+    let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = delete_expired(&db_pool, expire_minutes).await {
+            eprintln!("Failed to delete used challenges: {e}");
+        }
+    }
+}
+
+async fn delete_expired(
+    db_pool: &MySqlPool,
+    expire_minutes: u32,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        DELETE FROM used_challenges
+        WHERE
+            used_at < NOW() - INTERVAL ? MINUTE
+        ",
+        expire_minutes
+    )
+    .execute(db_pool)
+    .await
+}
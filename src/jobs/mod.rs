@@ -0,0 +1,7 @@
+// Background tasks spawned on startup, next to the HTTP server
+
+pub mod challenges;
+pub mod purge;
+pub mod rerender;
+pub mod scheduled_posts;
+pub mod webhooks;
@@ -0,0 +1,75 @@
+use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use std::time::Duration;
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Hard deletes posts and comments that were soft deleted
+// more than `purge_after_days` days ago, once an hour
+pub async fn run(db_pool: MySqlPool, purge_after_days: u32) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = purge(&db_pool, purge_after_days).await {
+            eprintln!("Failed to purge deleted posts and comments: {e}");
+        }
+    }
+}
+
+async fn purge(db_pool: &MySqlPool, purge_after_days: u32) -> sqlx::Result<()> {
+    purge_comments(db_pool, purge_after_days).await?;
+
+    // Comments and tags of a purged post go with it
+    purge_posts(db_pool, purge_after_days).await?;
+
+    // Reports point at posts and comments without a foreign key
+    purge_orphaned_reports(db_pool).await?;
+
+    Ok(())
+}
+
+async fn purge_comments(
+    db_pool: &MySqlPool,
+    purge_after_days: u32,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        DELETE FROM comments
+        WHERE
+            deleted_at < NOW() - INTERVAL ? DAY
+        ",
+        purge_after_days
+    )
+    .execute(db_pool)
+    .await
+}
+
+async fn purge_posts(db_pool: &MySqlPool, purge_after_days: u32) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        DELETE FROM posts
+        WHERE
+            deleted_at < NOW() - INTERVAL ? DAY
+        ",
+        purge_after_days
+    )
+    .execute(db_pool)
+    .await
+}
+
+async fn purge_orphaned_reports(db_pool: &MySqlPool) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        DELETE reports
+        FROM reports
+            LEFT JOIN posts ON reports.target_type = 'Post' AND reports.target_id = posts.id
+            LEFT JOIN comments ON reports.target_type = 'Comment' AND reports.target_id = comments.id
+        WHERE
+            posts.id IS NULL
+            AND comments.id IS NULL
+        "
+    )
+    .execute(db_pool)
+    .await
+}
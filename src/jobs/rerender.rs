@@ -0,0 +1,299 @@
+use crate::config::MarkdownConfig;
+use crate::render;
+use crate::types::id::Id;
+use crate::types::post::format_posted_at;
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+// Rows rendered again per query
+const BATCH_SIZE: i64 = 100;
+
+#[derive(Debug, Copy, Clone)]
+enum Table {
+    Posts,
+    Comments,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Progress {
+    pub is_running: bool,
+    pub done: i64,
+    pub total: i64,
+    pub percent: i64,
+    pub error: Option<String>,
+    pub finished_at: Option<String>,
+}
+
+// How the current or last run went, started from `/admin` or `neor rerender`
+#[derive(Debug, Default)]
+pub struct Status {
+    progress: Mutex<Progress>,
+}
+
+#[derive(Debug)]
+struct Outdated {
+    id: Id,
+    content: String,
+}
+
+impl Status {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn progress(&self) -> Progress {
+        self.lock().clone()
+    }
+
+    // Only one run at a time, returns `false` if one is going already
+    pub fn try_start(&self) -> bool {
+        let mut progress = self.lock();
+
+        if progress.is_running {
+            return false;
+        }
+
+        *progress = Progress {
+            is_running: true,
+            ..Progress::default()
+        };
+
+        true
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Progress> {
+        self.progress.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// Renders posts and comments with an outdated `render_version` again from their `content`,
+// calling `on_batch` with the progress after every batch. `Status::try_start` has to be
+// called first, the outcome ends up in `Status::progress`
+pub async fn run(
+    db_pool: &MySqlPool,
+    config: &MarkdownConfig,
+    status: &Status,
+    on_batch: impl Fn(&Progress),
+) {
+    let result = rerender_all(db_pool, config, status, &on_batch).await;
+
+    let mut progress = status.lock();
+
+    progress.is_running = false;
+    progress.finished_at = Some(format_posted_at(Utc::now().naive_utc()));
+
+    if let Err(e) = result {
+        progress.error = Some(e.to_string());
+    }
+}
+
+pub async fn fetch_outdated_count(
+    db_pool: &MySqlPool,
+    config: &MarkdownConfig,
+) -> sqlx::Result<i64> {
+    let version = render::version(config);
+
+    Ok(
+        fetch_table_outdated_count(db_pool, Table::Posts, version).await?
+            + fetch_table_outdated_count(db_pool, Table::Comments, version).await?,
+    )
+}
+
+async fn rerender_all(
+    db_pool: &MySqlPool,
+    config: &MarkdownConfig,
+    status: &Status,
+    on_batch: &impl Fn(&Progress),
+) -> sqlx::Result<()> {
+    let version = render::version(config);
+
+    let total = fetch_outdated_count(db_pool, config).await?;
+
+    status.lock().total = total;
+
+    for table in [Table::Posts, Table::Comments] {
+        // Walked by id, a row that stays outdated can't be fetched over and over
+        let mut after_id = 0;
+
+        loop {
+            let rows = fetch_outdated(db_pool, table, version, after_id).await?;
+
+            let Some(last) = rows.last() else {
+                break;
+            };
+
+            after_id = last.id;
+
+            for row in &rows {
+                let html = render::markdown(&row.content, config);
+
+                update_rendered(db_pool, table, row.id, &html, version).await?;
+            }
+
+            {
+                let mut progress = status.lock();
+
+                progress.done += rows.len() as i64;
+                progress.percent = match progress.total {
+                    0 => 100,
+                    total => (progress.done * 100 / total).min(100),
+                };
+            }
+
+            on_batch(&status.progress());
+        }
+    }
+
+    Ok(())
+}
+
+async fn fetch_table_outdated_count(
+    db_pool: &MySqlPool,
+    table: Table,
+    version: u32,
+) -> sqlx::Result<i64> {
+    #[derive(Debug)]
+    struct Count {
+        count: i64,
+    }
+
+    let count = match table {
+        Table::Posts => {
+            sqlx::query_as!(
+                Count,
+                "
+                SELECT
+                    COUNT(*) AS `count!: i64`
+                FROM posts
+                WHERE
+                    render_version <> ?
+                ",
+                version
+            )
+            .fetch_one(db_pool)
+            .await?
+        }
+        Table::Comments => {
+            sqlx::query_as!(
+                Count,
+                "
+                SELECT
+                    COUNT(*) AS `count!: i64`
+                FROM comments
+                WHERE
+                    render_version <> ?
+                ",
+                version
+            )
+            .fetch_one(db_pool)
+            .await?
+        }
+    };
+
+    Ok(count.count)
+}
+
+async fn fetch_outdated(
+    db_pool: &MySqlPool,
+    table: Table,
+    version: u32,
+    after_id: Id,
+) -> sqlx::Result<Vec<Outdated>> {
+    match table {
+        Table::Posts => {
+            sqlx::query_as!(
+                Outdated,
+                "
+                SELECT
+                    id,
+                    content
+                FROM posts
+                WHERE
+                    render_version <> ?
+                    AND id > ?
+                ORDER BY id
+                LIMIT ?
+                ",
+                version,
+                after_id,
+                BATCH_SIZE
+            )
+            .fetch_all(db_pool)
+            .await
+        }
+        Table::Comments => {
+            sqlx::query_as!(
+                Outdated,
+                "
+                SELECT
+                    id,
+                    content
+                FROM comments
+                WHERE
+                    render_version <> ?
+                    AND id > ?
+                ORDER BY id
+                LIMIT ?
+                ",
+                version,
+                after_id,
+                BATCH_SIZE
+            )
+            .fetch_all(db_pool)
+            .await
+        }
+    }
+}
+
+// Rows edited since they were fetched are already rendered with `version` and left alone.
+// `modified_at` isn't touched, rendering again isn't an edit
+async fn update_rendered(
+    db_pool: &MySqlPool,
+    table: Table,
+    id: Id,
+    html: &str,
+    version: u32,
+) -> sqlx::Result<MySqlQueryResult> {
+    match table {
+        Table::Posts => {
+            sqlx::query!(
+                "
+                UPDATE posts
+                SET
+                    markdown_content = ?,
+                    render_version = ?
+                WHERE
+                    id = ?
+                    AND render_version <> ?
+                ",
+                html,
+                version,
+                id,
+                version
+            )
+            .execute(db_pool)
+            .await
+        }
+        Table::Comments => {
+            sqlx::query!(
+                "
+                UPDATE comments
+                SET
+                    markdown_content = ?,
+                    render_version = ?
+                WHERE
+                    id = ?
+                    AND render_version <> ?
+                ",
+                html,
+                version,
+                id,
+                version
+            )
+            .execute(db_pool)
+            .await
+        }
+    }
+}
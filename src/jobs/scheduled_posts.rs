@@ -0,0 +1,197 @@
+use crate::apis::post::create::{self, Error as PostCreateError, NewPost};
+use crate::config::Config;
+use crate::session::user_by_id;
+use crate::spam::Filter as SpamFilter;
+use crate::types::id::Id;
+use chrono::NaiveDateTime;
+use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+// Due drafts published per poll
+const BATCH_SIZE: i64 = 20;
+
+#[derive(Debug)]
+struct DueDraft {
+    id: Id,
+    user_id: Id,
+    title: String,
+    description: String,
+    tags: String,
+    content: String,
+    publish_at: NaiveDateTime,
+}
+
+// Publishes drafts whose time has come every thirty seconds. Drafts that can't be published
+// keep an error for their owner and stay unscheduled until they're scheduled again
+pub async fn run(db_pool: MySqlPool, spam_filter: SpamFilter, config: Config) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = publish_due(&db_pool, &spam_filter, &config).await {
+            eprintln!("Failed to publish scheduled posts: {e}");
+        }
+    }
+}
+
+async fn publish_due(
+    db_pool: &MySqlPool,
+    spam_filter: &SpamFilter,
+    config: &Config,
+) -> sqlx::Result<()> {
+    for draft in fetch_due_drafts(db_pool).await? {
+        // Taken off the schedule first so a draft is never published twice
+        if claim_draft(db_pool, draft.id, draft.publish_at)
+            .await?
+            .rows_affected()
+            == 0
+        {
+            continue;
+        }
+
+        match publish(db_pool, spam_filter, config, &draft).await {
+            // Already off the schedule, a draft left behind isn't published again
+            Ok(post_id) => {
+                if let Err(e) = delete_draft(db_pool, draft.id).await {
+                    eprintln!("Failed to delete draft {} of post {post_id}: {e}", draft.id);
+                }
+            }
+            // Nothing was saved, `publish` is one transaction. Tried again on the next poll
+            Err(PostCreateError::Server) => {
+                restore_publish_at(db_pool, draft.id, draft.publish_at).await?;
+            }
+            Err(e) => {
+                set_last_error(db_pool, draft.id, &e.to_string()).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Checked again, the user could have been banned or the post edited into something invalid
+// since it was scheduled
+async fn publish(
+    db_pool: &MySqlPool,
+    spam_filter: &SpamFilter,
+    config: &Config,
+    draft: &DueDraft,
+) -> Result<Id, PostCreateError> {
+    let user = user_by_id(db_pool, draft.user_id)
+        .await
+        .map_err(|_| PostCreateError::Server)?;
+
+    create::check_can_post(&user, config)?;
+
+    let post = NewPost::parse(
+        draft.title.clone(),
+        draft.description.clone(),
+        draft.tags.clone(),
+        draft.content.clone(),
+        config,
+    )?;
+
+    create::publish(db_pool, spam_filter, config, &user, &post).await
+}
+
+async fn fetch_due_drafts(db_pool: &MySqlPool) -> sqlx::Result<Vec<DueDraft>> {
+    sqlx::query_as!(
+        DueDraft,
+        "
+        SELECT
+            id,
+            user_id,
+            title,
+            description,
+            tags,
+            content,
+            publish_at AS `publish_at!`
+        FROM drafts
+        WHERE
+            publish_at <= NOW()
+        ORDER BY publish_at
+        LIMIT ?
+        ",
+        BATCH_SIZE
+    )
+    .fetch_all(db_pool)
+    .await
+}
+
+async fn claim_draft(
+    db_pool: &MySqlPool,
+    id: Id,
+    publish_at: NaiveDateTime,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        UPDATE drafts
+        SET
+            publish_at = NULL
+        WHERE
+            id = ?
+            AND publish_at = ?
+        ",
+        id,
+        publish_at
+    )
+    .execute(db_pool)
+    .await
+}
+
+async fn restore_publish_at(
+    db_pool: &MySqlPool,
+    id: Id,
+    publish_at: NaiveDateTime,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        UPDATE drafts
+        SET
+            publish_at = ?
+        WHERE
+            id = ?
+            AND publish_at IS NULL
+        ",
+        publish_at,
+        id
+    )
+    .execute(db_pool)
+    .await
+}
+
+async fn set_last_error(
+    db_pool: &MySqlPool,
+    id: Id,
+    error: &str,
+) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        UPDATE drafts
+        SET
+            last_error = ?
+        WHERE
+            id = ?
+        ",
+        error,
+        id
+    )
+    .execute(db_pool)
+    .await
+}
+
+async fn delete_draft(db_pool: &MySqlPool, id: Id) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        DELETE FROM drafts
+        WHERE
+            id = ?
+        ",
+        id
+    )
+    .execute(db_pool)
+    .await
+}
@@ -0,0 +1,357 @@
+use crate::config::WebhooksConfig;
+use crate::types::id::Id;
+use crate::types::webhook::Status;
+use crate::webhook::{self, DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER};
+use futures::future::join_all;
+use reqwest::{header, Client};
+use sqlx::mysql::{MySqlPool, MySqlQueryResult};
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Due deliveries sent at once per poll
+const BATCH_SIZE: i64 = 20;
+// Retries never wait longer than this, however many there were before
+const MAX_RETRY_DELAY_SECONDS: u64 = 6 * 60 * 60;
+// Finished deliveries are kept in the log this long
+const LOG_RETENTION_DAYS: u32 = 30;
+const ERROR_MAX_CHAR_COUNT: usize = 512;
+
+const USER_AGENT: &str = concat!("neor-webhooks/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Debug)]
+struct DueDelivery {
+    id: Id,
+    event: String,
+    payload: String,
+    attempt_count: u32,
+    url: String,
+    secret: String,
+}
+
+// What came of one attempt, the status code is kept whenever there was a response
+struct Attempt {
+    is_delivered: bool,
+    response_status: Option<u16>,
+    error: Option<String>,
+}
+
+// Sends pending deliveries every five seconds, retrying failed ones with exponential backoff,
+// and clears out old finished deliveries once an hour
+pub async fn run(db_pool: MySqlPool, config: WebhooksConfig) {
+    let client = Client::builder()
+        .timeout(Duration::from_secs(config.timeout_seconds))
+        .user_agent(USER_AGENT)
+        .build()
+        .expect("Failed to create webhook HTTP client");
+
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+    let mut cleanup = tokio::time::interval(CLEANUP_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = poll.tick() => {
+                if let Err(e) = deliver_due(&db_pool, &client, &config).await {
+                    eprintln!("Failed to deliver webhooks: {e}");
+                }
+            }
+            _ = cleanup.tick() => {
+                if let Err(e) = delete_old_deliveries(&db_pool).await {
+                    eprintln!("Failed to delete old webhook deliveries: {e}");
+                }
+            }
+        }
+    }
+}
+
+async fn deliver_due(
+    db_pool: &MySqlPool,
+    client: &Client,
+    config: &WebhooksConfig,
+) -> sqlx::Result<()> {
+    let deliveries = fetch_due_deliveries(db_pool).await?;
+
+    let attempts = join_all(
+        deliveries
+            .iter()
+            .map(|delivery| attempt_delivery(client, delivery)),
+    )
+    .await;
+
+    for (delivery, attempt) in deliveries.iter().zip(attempts) {
+        record_attempt(db_pool, config, delivery, attempt).await?;
+    }
+
+    Ok(())
+}
+
+async fn attempt_delivery(client: &Client, delivery: &DueDelivery) -> Attempt {
+    let response = client
+        .post(&delivery.url)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, &delivery.event)
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .header(
+            SIGNATURE_HEADER,
+            webhook::sign(&delivery.secret, &delivery.payload),
+        )
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status().is_success() => Attempt {
+            is_delivered: true,
+            response_status: Some(response.status().as_u16()),
+            error: None,
+        },
+        Ok(response) => Attempt {
+            is_delivered: false,
+            response_status: Some(response.status().as_u16()),
+            error: Some(format!("Responded with {}", response.status())),
+        },
+        Err(e) => Attempt {
+            is_delivered: false,
+            response_status: None,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+async fn record_attempt(
+    db_pool: &MySqlPool,
+    config: &WebhooksConfig,
+    delivery: &DueDelivery,
+    attempt: Attempt,
+) -> sqlx::Result<MySqlQueryResult> {
+    let attempt_count = delivery.attempt_count + 1;
+
+    let status = if attempt.is_delivered {
+        Status::Succeeded
+    } else if attempt_count >= config.max_attempts {
+        Status::Failed
+    } else {
+        Status::Pending
+    };
+
+    let error = attempt
+        .error
+        .map(|error| error.chars().take(ERROR_MAX_CHAR_COUNT).collect::<String>());
+
+    sqlx::query!(
+        "
+        UPDATE webhook_deliveries
+        SET
+            status = ?,
+            attempt_count = ?,
+            response_status = ?,
+            last_error = ?,
+            next_attempt_at = NOW() + INTERVAL ? SECOND,
+            finished_at = IF(? = 'pending', NULL, NOW())
+        WHERE
+            id = ?
+        ",
+        status.as_str(),
+        attempt_count,
+        attempt.response_status,
+        error,
+        retry_delay_seconds(config.retry_base_seconds, attempt_count),
+        status.as_str(),
+        delivery.id
+    )
+    .execute(db_pool)
+    .await
+}
+
+// `retry_base_seconds` after the first attempt, doubling after each one that follows
+fn retry_delay_seconds(retry_base_seconds: u64, attempt_count: u32) -> u64 {
+    2u64.checked_pow(attempt_count.saturating_sub(1))
+        .and_then(|factor| retry_base_seconds.checked_mul(factor))
+        .unwrap_or(MAX_RETRY_DELAY_SECONDS)
+        .min(MAX_RETRY_DELAY_SECONDS)
+}
+
+// Paused webhooks keep their deliveries pending until they are resumed
+async fn fetch_due_deliveries(db_pool: &MySqlPool) -> sqlx::Result<Vec<DueDelivery>> {
+    sqlx::query_as!(
+        DueDelivery,
+        "
+        SELECT
+            webhook_deliveries.id,
+            webhook_deliveries.event,
+            webhook_deliveries.payload,
+            webhook_deliveries.attempt_count,
+            webhooks.url,
+            webhooks.secret
+        FROM webhook_deliveries
+            JOIN webhooks ON webhook_deliveries.webhook_id = webhooks.id
+        WHERE
+            webhook_deliveries.status = 'pending'
+            AND webhook_deliveries.next_attempt_at <= NOW()
+            AND webhooks.is_active = 1
+        ORDER BY webhook_deliveries.id
+        LIMIT ?
+        ",
+        BATCH_SIZE
+    )
+    .fetch_all(db_pool)
+    .await
+}
+
+async fn delete_old_deliveries(db_pool: &MySqlPool) -> sqlx::Result<MySqlQueryResult> {
+    sqlx::query!(
+        "
+        DELETE FROM webhook_deliveries
+        WHERE
+            finished_at < NOW() - INTERVAL ? DAY
+        ",
+        LOG_RETENTION_DAYS
+    )
+    .execute(db_pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    const PAYLOAD: &str = r#"{"event":"ping","data":{}}"#;
+    const SECRET: &str = "secret";
+
+    #[test]
+    fn retry_delay_doubles() {
+        assert_eq!(retry_delay_seconds(60, 1), 60);
+        assert_eq!(retry_delay_seconds(60, 2), 120);
+        assert_eq!(retry_delay_seconds(60, 3), 240);
+        assert_eq!(retry_delay_seconds(60, 4), 480);
+        // Nothing was attempted yet
+        assert_eq!(retry_delay_seconds(60, 0), 60);
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        assert_eq!(retry_delay_seconds(60, 20), MAX_RETRY_DELAY_SECONDS);
+        // Past what fits in a `u64`
+        assert_eq!(retry_delay_seconds(60, 100), MAX_RETRY_DELAY_SECONDS);
+        assert_eq!(retry_delay_seconds(u64::MAX, 2), MAX_RETRY_DELAY_SECONDS);
+    }
+
+    // A receiver on a local port that answers one request with `status`,
+    // the handle gives back the request's lowercased head and its body
+    async fn stand_in(status: u16) -> (String, JoinHandle<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+
+            let head_len = loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+
+                if let Some(i) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+                    break i + 4;
+                }
+            };
+
+            let head = String::from_utf8_lossy(&request[..head_len]).to_lowercase();
+
+            let content_length = head
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .map(|length| length.trim().parse::<usize>().unwrap())
+                .unwrap_or(0);
+
+            while request.len() < head_len + content_length {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+
+            let body = String::from_utf8_lossy(&request[head_len..]).into_owned();
+
+            let response = format!(
+                "HTTP/1.1 {status} Status\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+
+            (head, body)
+        });
+
+        (url, handle)
+    }
+
+    fn delivery(url: String) -> DueDelivery {
+        DueDelivery {
+            id: 7,
+            event: "ping".to_owned(),
+            payload: PAYLOAD.to_owned(),
+            attempt_count: 0,
+            url,
+            secret: SECRET.to_owned(),
+        }
+    }
+
+    fn client() -> Client {
+        Client::builder()
+            .timeout(Duration::from_secs(5))
+            .user_agent(USER_AGENT)
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn delivers_signed_payload() {
+        let (url, handle) = stand_in(204).await;
+
+        let attempt = attempt_delivery(&client(), &delivery(url)).await;
+        let (head, body) = handle.await.unwrap();
+
+        assert!(attempt.is_delivered);
+        assert_eq!(attempt.response_status, Some(204));
+        assert_eq!(attempt.error, None);
+
+        assert!(head.starts_with("post /hook "));
+        assert!(head.contains("content-type: application/json\r\n"));
+        assert!(head.contains(&format!("{}: ping\r\n", EVENT_HEADER.to_lowercase())));
+        assert!(head.contains(&format!("{}: 7\r\n", DELIVERY_HEADER.to_lowercase())));
+        assert!(head.contains(&format!(
+            "{}: {}\r\n",
+            SIGNATURE_HEADER.to_lowercase(),
+            webhook::sign(SECRET, PAYLOAD)
+        )));
+        assert_eq!(body, PAYLOAD);
+    }
+
+    #[tokio::test]
+    async fn error_status_is_retried() {
+        let (url, handle) = stand_in(500).await;
+
+        let attempt = attempt_delivery(&client(), &delivery(url)).await;
+        handle.await.unwrap();
+
+        assert!(!attempt.is_delivered);
+        assert_eq!(attempt.response_status, Some(500));
+        assert!(attempt.error.unwrap().contains("500"));
+    }
+
+    #[tokio::test]
+    async fn unreachable_receiver_is_retried() {
+        // Bound and dropped, so nothing listens there
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        drop(listener);
+
+        let attempt = attempt_delivery(&client(), &delivery(url)).await;
+
+        assert!(!attempt.is_delivered);
+        assert_eq!(attempt.response_status, None);
+        assert!(attempt.error.is_some());
+    }
+}
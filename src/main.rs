@@ -1,7 +1,8 @@
 use actix_web::{
-    web::{route, Data},
+    web::{route, scope, Data},
     App, HttpServer,
 };
+use config::Config;
 use dotenvy::dotenv;
 use lazy_static::lazy_static;
 use lettre::message::Mailbox;
@@ -9,13 +10,23 @@ use lettre::transport::smtp::authentication::Credentials;
 use lettre::AsyncSmtpTransport;
 use lettre::Tokio1Executor;
 use sqlx::mysql::MySqlPool;
+use std::sync::Arc;
 
 mod apis;
+mod config;
 mod db;
+mod feed;
+mod flash;
+mod jobs;
 mod pages;
+mod policy;
+mod rate_limit;
+mod render;
 mod session;
+mod spam;
 mod theme;
 mod types;
+mod webhook;
 
 lazy_static! {
     static ref LIQUID_PARSER: liquid::Parser = {
@@ -27,6 +38,10 @@ lazy_static! {
             include_str!("../partials/post_preview.html"),
         );
         sources.add("comment", include_str!("../partials/comment.html"));
+        sources.add("challenge", include_str!("../partials/challenge.html"));
+        sources.add("meta", include_str!("../partials/meta.html"));
+        sources.add("revision", include_str!("../partials/revision.html"));
+        sources.add("preview", include_str!("../partials/preview.html"));
 
         let partials = liquid::partials::EagerCompiler::new(sources);
 
@@ -39,7 +54,15 @@ lazy_static! {
 
 #[derive(Debug)]
 pub struct State {
-    domain: String,
+    config: Config,
+    spam_filter: spam::Filter,
+    // Shared by all workers
+    rate_limiter: Arc<rate_limit::Limiter>,
+    // Shared by all workers too, a form can be sent back to any of them
+    flash: Arc<flash::Store>,
+    // Shared by all workers too, only one re-render runs at a time
+    rerender: Arc<jobs::rerender::Status>,
+
     email_from: Mailbox,
     mailer: AsyncSmtpTransport<Tokio1Executor>,
 
@@ -48,50 +71,143 @@ pub struct State {
 
 #[tokio::main]
 async fn main() {
-    dotenv().expect("Failed to load `.env` file");
+    // `.env` is optional, everything can be set in the config file instead
+    dotenv().ok();
+
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid configuration: {e}");
+
+            std::process::exit(1);
+        }
+    };
+
+    let spam_filter = spam::Filter::from_config(&config.spam)
+        .expect("`spam.banned_patterns` were validated with the rest of the config");
 
-    let port = std::env::var("PORT")
-        .expect("Evnironment variable $PORT is not set")
-        .parse::<u16>()
-        .expect("Environment variable $PORT must be a `u16`");
+    let rate_limiter = Arc::new(rate_limit::Limiter::new(config.rate_limit.clone()));
+    let flash = Arc::new(flash::Store::new());
+    let rerender = Arc::new(jobs::rerender::Status::new());
 
-    let domain = std::env::var("DOMAIN").expect("Evnironment variable $DOMAIN is not set");
-    let email_from =
-        std::env::var("EMAIL_FROM").expect("Evnironment variable $EMAIL_FROM is not set");
-    let from = email_from
+    let from = config
+        .email
+        .from
         .parse::<Mailbox>()
-        .expect("$EMAIL_FROM contains an invalid email address");
+        .expect("`email.from` contains an invalid email address");
 
     // TODO: Check that email works
-    let email_password =
-        std::env::var("EMAIL_PASSWORD").expect("Evnironment variable $EMAIL_PASSWORD is not set");
-    let email_relay =
-        std::env::var("EMAIL_RELAY").expect("Evnironment variable $EMAIL_RELAY is not set");
+    let email_credentials =
+        Credentials::new(config.email.from.clone(), config.email.password.clone());
 
-    let email_credentials = Credentials::new(email_from, email_password);
-
-    let mailer = AsyncSmtpTransport::<Tokio1Executor>::relay(&email_relay)
+    let mailer = AsyncSmtpTransport::<Tokio1Executor>::relay(&config.email.relay)
         .expect("Failed to create email transport")
         .credentials(email_credentials)
         .build::<Tokio1Executor>();
 
-    let db_connection_string =
-        std::env::var("DATABASE_URL").expect("Environment variable $DATABASE_URL is not set");
-
-    let db_pool = MySqlPool::connect(&db_connection_string)
+    let db_pool = MySqlPool::connect(&config.database_url)
         .await
         .expect("Failed to connect to DB");
 
+    // `neor rerender` brings stored HTML up to date with the current markdown settings and exits
+    if std::env::args().nth(1).as_deref() == Some("rerender") {
+        rerender.try_start();
+
+        jobs::rerender::run(&db_pool, &config.markdown, &rerender, |progress| {
+            println!(
+                "Re-rendered {}/{} posts and comments ({}%)",
+                progress.done, progress.total, progress.percent
+            );
+        })
+        .await;
+
+        let progress = rerender.progress();
+
+        if let Some(e) = progress.error {
+            eprintln!("Failed to re-render: {e}");
+
+            std::process::exit(1);
+        }
+
+        println!("{} posts and comments are up to date", progress.done);
+
+        return;
+    }
+
+    let port = config.port;
+
+    if config.deletion.purge_after_days > 0 {
+        tokio::spawn(jobs::purge::run(
+            db_pool.clone(),
+            config.deletion.purge_after_days,
+        ));
+    }
+
+    if config.challenge.on_sign_up || config.challenge.on_first_post {
+        tokio::spawn(jobs::challenges::run(
+            db_pool.clone(),
+            config.challenge.expire_minutes,
+        ));
+    }
+
+    tokio::spawn(jobs::webhooks::run(
+        db_pool.clone(),
+        config.webhooks.clone(),
+    ));
+
+    tokio::spawn(jobs::scheduled_posts::run(
+        db_pool.clone(),
+        spam_filter.clone(),
+        config.clone(),
+    ));
+
     HttpServer::new(move || {
         App::new()
             .app_data(Data::new(State {
-                domain: domain.clone(),
+                config: config.clone(),
+                spam_filter: spam_filter.clone(),
+                rate_limiter: rate_limiter.clone(),
+                flash: flash.clone(),
+                rerender: rerender.clone(),
+
                 email_from: from.clone(),
                 mailer: mailer.clone(),
 
                 db_pool: db_pool.clone(),
             }))
+            .service(
+                scope("/api/v1")
+                    .app_data(apis::v1::json_config())
+                    .app_data(apis::v1::query_config())
+                    .app_data(apis::v1::path_config())
+                    .service(apis::v1::openapi::service)
+                    .service(apis::v1::auth::sign_in)
+                    .service(apis::v1::auth::sign_out)
+                    .service(apis::v1::auth::me)
+                    .service(apis::v1::challenge::service)
+                    .service(apis::v1::posts::list_posts)
+                    .service(apis::v1::posts::create_post)
+                    .service(apis::v1::posts::get_post)
+                    .service(apis::v1::posts::edit_post)
+                    .service(apis::v1::posts::delete_post)
+                    .service(apis::v1::posts::list_comments)
+                    .service(apis::v1::posts::create_comment)
+                    .service(apis::v1::comments::edit_comment)
+                    .service(apis::v1::comments::delete_comment)
+                    .service(apis::v1::tags::list_posts)
+                    .service(apis::v1::users::get_user)
+                    .service(apis::v1::users::list_posts)
+                    .service(apis::v1::users::list_comments)
+                    .default_service(route().to(apis::v1::not_found)),
+            )
             .service(apis::user::admin::service)
+            .service(apis::user::bulk::service)
+            .service(apis::role::save::service)
+            .service(apis::role::delete::service)
+            .service(apis::webhook::create::service)
+            .service(apis::webhook::delete::service)
+            .service(apis::webhook::toggle::service)
+            .service(apis::webhook::ping::service)
             .service(apis::password_reset::service)
             .service(apis::password_change::service)
             .service(apis::sign_up::service)
@@ -100,25 +216,60 @@ async fn main() {
             .service(apis::comment::anonymise::service)
             .service(apis::post::delete::service)
             .service(apis::comment::delete::service)
+            .service(apis::post::restore::service)
+            .service(apis::comment::restore::service)
+            .service(apis::post::approve::service)
+            .service(apis::tag::admin::service)
+            .service(apis::rerender::service)
+            .service(apis::report::service)
+            .service(apis::moderation::resolve::service)
             .service(apis::sign_in::service)
             .service(apis::post::create::service)
+            .service(apis::draft::save::service)
+            .service(apis::draft::schedule::service)
+            .service(apis::draft::unschedule::service)
+            .service(apis::draft::delete::service)
             .service(apis::comment::create::service)
+            .service(apis::markdown::preview::service)
             .service(apis::post::edit::service)
             .service(apis::user::edit::service)
+            .service(apis::token::create::service)
+            .service(apis::token::revoke::service)
             .service(apis::comment::edit::service)
             .service(pages::files::service)
             .service(pages::style::service)
             .service(pages::switch_theme::service)
             .service(pages::index::service)
+            .service(pages::feed::atom)
+            .service(pages::feed::rss)
+            .service(pages::sitemap::index)
+            .service(pages::sitemap::service)
+            .service(pages::robots::service)
             .service(pages::post::create::service)
             .service(pages::tag::tag::service)
+            .service(pages::tag::feed::service)
             .service(pages::comment::id::edit::service)
+            .service(pages::comment::id::history::service)
             .service(pages::comment::id::delete::service)
+            .service(pages::comment::id::restore::service)
+            .service(pages::comment::id::report::service)
             .service(pages::post::id::anonymise::service)
             .service(pages::post::id::delete::service)
+            .service(pages::post::id::restore::service)
+            .service(pages::post::id::report::service)
             .service(pages::post::id::edit::service)
+            .service(pages::post::id::feed::service)
+            .service(pages::post::id::history::service)
             .service(pages::user::username::edit::service)
             .service(pages::user::username::admin::service)
+            .service(pages::user::username::feed::service)
+            .service(pages::admin::dashboard::service)
+            .service(pages::admin::roles::service)
+            .service(pages::admin::users::service)
+            .service(pages::admin::webhooks::service)
+            .service(pages::modlog::service)
+            .service(pages::moderation::queue::service)
+            .service(pages::moderation::approvals::service)
             .service(pages::post::id::service)
             .service(pages::user::username::service)
             .service(pages::comment::create::service)
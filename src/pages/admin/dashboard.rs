@@ -0,0 +1,481 @@
+use crate::config::MarkdownConfig;
+use crate::jobs::rerender::{self, Progress as RerenderProgress};
+use crate::policy::{self, Action, Resource};
+use crate::session::auth;
+use crate::types::default_mini_pfp;
+use crate::types::id::Id;
+use crate::types::post::format_posted_at;
+use crate::types::user::{pfp::Pfp, Preview as UserPreview};
+use crate::State;
+use crate::LIQUID_PARSER;
+use actix_web::{
+    get,
+    http::header,
+    web::{Data, Query},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use lazy_static::lazy_static;
+use liquid::Template;
+use serde::{Deserialize, Serialize};
+use sqlx::mysql::MySqlPool;
+use std::collections::HashMap;
+use thiserror::Error;
+
+// Days of activity shown in the chart, today included
+const ACTIVITY_DAYS: i64 = 30;
+const TOP_TAGS_LIMIT: u64 = 10;
+const RECENT_SIGN_UPS_LIMIT: u64 = 10;
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Serialize)]
+struct Stats {
+    user_count: i64,
+    roles: Vec<Bar>,
+    activity: Vec<Day>,
+    post_count: i64,
+    comment_count: i64,
+    top_tags: Vec<Bar>,
+    open_report_count: i64,
+    pending_post_count: i64,
+    recent_sign_ups: Vec<SignUp>,
+    file_count: i64,
+    storage_used: String,
+    // Posts and comments rendered with other markdown settings
+    outdated_count: i64,
+    rerender: RerenderProgress,
+}
+
+// A row of a horizontal bar chart, `percent` is relative to the longest bar
+#[derive(Debug, Serialize)]
+struct Bar {
+    label: String,
+    count: i64,
+    percent: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct Day {
+    date: String,
+    posts: i64,
+    comments: i64,
+    posts_percent: i64,
+    comments_percent: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct SignUp {
+    user: UserPreview,
+    joined_at: String,
+}
+
+#[derive(Debug)]
+struct Count {
+    label: String,
+    count: i64,
+}
+
+#[derive(Debug)]
+struct DayCount {
+    day: NaiveDate,
+    count: i64,
+}
+
+#[get("/admin")]
+pub async fn service(
+    req: HttpRequest,
+    state: Data<State>,
+    Query(query): Query<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(current_user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/admin"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    let is_allowed = policy::can(&current_user, Action::Admin, Resource::Site, &state.config);
+
+    if !is_allowed && query.error.is_none() {
+        let response = HttpResponse::SeeOther()
+            .append_header((
+                header::LOCATION,
+                "/admin?error=You are not allowed to view the dashboard",
+            ))
+            .finish();
+
+        return Ok(response);
+    }
+
+    let stats = if is_allowed {
+        Some(
+            fetch_stats(
+                &state.db_pool,
+                &state.config.markdown,
+                state.rerender.progress(),
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+
+    lazy_static! {
+        static ref TEMPLATE: Template = {
+            let template = include_str!("../../../templates/admin/dashboard.html");
+
+            LIQUID_PARSER.parse(template).unwrap()
+        };
+    }
+
+    let current_url = urlencoding::encode(&req.uri().to_string()).into_owned();
+
+    let globals = liquid::object!({
+        "current_url": current_url,
+        "current_user": current_user,
+        "stats": stats,
+        "activity_days": ACTIVITY_DAYS,
+        "error": query.error,
+    });
+
+    let s = TEMPLATE.render(&globals).unwrap();
+
+    Ok(HttpResponse::Ok().body(s))
+}
+
+async fn fetch_stats(
+    db_pool: &MySqlPool,
+    markdown_config: &MarkdownConfig,
+    rerender: RerenderProgress,
+) -> sqlx::Result<Stats> {
+    let today = chrono::Utc::now().date_naive();
+    let since = today - Duration::days(ACTIVITY_DAYS - 1);
+
+    let posts_per_day = fetch_posts_per_day(db_pool, since).await?;
+    let comments_per_day = fetch_comments_per_day(db_pool, since).await?;
+
+    let (file_count, storage_used) = fetch_storage(db_pool).await?;
+
+    Ok(Stats {
+        user_count: fetch_user_count(db_pool).await?,
+        roles: bars(fetch_role_counts(db_pool).await?),
+        post_count: posts_per_day.iter().map(|day| day.count).sum(),
+        comment_count: comments_per_day.iter().map(|day| day.count).sum(),
+        activity: activity(since, today, posts_per_day, comments_per_day),
+        top_tags: bars(fetch_top_tags(db_pool).await?),
+        open_report_count: fetch_open_report_count(db_pool).await?,
+        pending_post_count: fetch_pending_post_count(db_pool).await?,
+        recent_sign_ups: fetch_recent_sign_ups(db_pool).await?,
+        file_count,
+        storage_used: format_size(storage_used),
+        outdated_count: rerender::fetch_outdated_count(db_pool, markdown_config).await?,
+        rerender,
+    })
+}
+
+fn bars(counts: Vec<Count>) -> Vec<Bar> {
+    let max = counts.iter().map(|count| count.count).max().unwrap_or(0);
+
+    counts
+        .into_iter()
+        .map(|count| Bar {
+            percent: percent(count.count, max),
+            label: count.label,
+            count: count.count,
+        })
+        .collect()
+}
+
+// Fills in the days nothing was posted on
+fn activity(
+    since: NaiveDate,
+    today: NaiveDate,
+    posts_per_day: Vec<DayCount>,
+    comments_per_day: Vec<DayCount>,
+) -> Vec<Day> {
+    let posts: HashMap<NaiveDate, i64> = posts_per_day
+        .into_iter()
+        .map(|day| (day.day, day.count))
+        .collect();
+    let comments: HashMap<NaiveDate, i64> = comments_per_day
+        .into_iter()
+        .map(|day| (day.day, day.count))
+        .collect();
+
+    // Both series share a scale so the bars can be compared
+    let max = posts
+        .values()
+        .chain(comments.values())
+        .copied()
+        .max()
+        .unwrap_or(0);
+
+    since
+        .iter_days()
+        .take_while(|day| *day <= today)
+        .map(|day| {
+            let post_count = posts.get(&day).copied().unwrap_or(0);
+            let comment_count = comments.get(&day).copied().unwrap_or(0);
+
+            Day {
+                date: day.format("%B %d").to_string(),
+                posts: post_count,
+                comments: comment_count,
+                posts_percent: percent(post_count, max),
+                comments_percent: percent(comment_count, max),
+            }
+        })
+        .collect()
+}
+
+fn percent(count: i64, max: i64) -> i64 {
+    if max == 0 {
+        return 0;
+    }
+
+    count * 100 / max
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        return format!("{bytes} B");
+    }
+
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+async fn fetch_user_count(db_pool: &MySqlPool) -> sqlx::Result<i64> {
+    #[derive(Debug)]
+    struct Users {
+        count: i64,
+    }
+
+    sqlx::query_as!(
+        Users,
+        "
+        SELECT
+            COUNT(*) AS `count!: i64`
+        FROM users
+        "
+    )
+    .fetch_one(db_pool)
+    .await
+    .map(|users| users.count)
+}
+
+async fn fetch_role_counts(db_pool: &MySqlPool) -> sqlx::Result<Vec<Count>> {
+    sqlx::query_as!(
+        Count,
+        "
+        SELECT
+            roles.name AS label,
+            COUNT(user_roles.user_id) AS `count!: i64`
+        FROM roles
+            LEFT JOIN user_roles ON roles.id = user_roles.role_id
+        GROUP BY roles.id, roles.name
+        ORDER BY COUNT(user_roles.user_id) DESC, roles.name ASC
+        "
+    )
+    .fetch_all(db_pool)
+    .await
+}
+
+async fn fetch_posts_per_day(db_pool: &MySqlPool, since: NaiveDate) -> sqlx::Result<Vec<DayCount>> {
+    sqlx::query_as!(
+        DayCount,
+        "
+        SELECT
+            DATE(posted_at) AS `day!: NaiveDate`,
+            COUNT(*) AS `count!: i64`
+        FROM posts
+        WHERE
+            posted_at >= ?
+            AND deleted_at IS NULL
+        GROUP BY DATE(posted_at)
+        ",
+        since
+    )
+    .fetch_all(db_pool)
+    .await
+}
+
+async fn fetch_comments_per_day(
+    db_pool: &MySqlPool,
+    since: NaiveDate,
+) -> sqlx::Result<Vec<DayCount>> {
+    sqlx::query_as!(
+        DayCount,
+        "
+        SELECT
+            DATE(posted_at) AS `day!: NaiveDate`,
+            COUNT(*) AS `count!: i64`
+        FROM comments
+        WHERE
+            posted_at >= ?
+            AND deleted_at IS NULL
+        GROUP BY DATE(posted_at)
+        ",
+        since
+    )
+    .fetch_all(db_pool)
+    .await
+}
+
+async fn fetch_top_tags(db_pool: &MySqlPool) -> sqlx::Result<Vec<Count>> {
+    sqlx::query_as!(
+        Count,
+        "
+        SELECT
+            tags.name AS label,
+            COUNT(*) AS `count!: i64`
+        FROM post_tags
+            JOIN tags ON post_tags.tag_id = tags.id
+            JOIN posts ON post_tags.post_id = posts.id
+        WHERE
+            posts.deleted_at IS NULL
+        GROUP BY tags.id, tags.name
+        ORDER BY COUNT(*) DESC, tags.name ASC
+        LIMIT ?
+        ",
+        TOP_TAGS_LIMIT
+    )
+    .fetch_all(db_pool)
+    .await
+}
+
+// Posts and comments with at least one open report, as counted by `/mod/queue`
+async fn fetch_open_report_count(db_pool: &MySqlPool) -> sqlx::Result<i64> {
+    #[derive(Debug)]
+    struct Reports {
+        count: i64,
+    }
+
+    sqlx::query_as!(
+        Reports,
+        "
+        SELECT
+            COUNT(DISTINCT target_type, target_id) AS `count!: i64`
+        FROM reports
+        WHERE
+            resolved_at IS NULL
+        "
+    )
+    .fetch_one(db_pool)
+    .await
+    .map(|reports| reports.count)
+}
+
+async fn fetch_pending_post_count(db_pool: &MySqlPool) -> sqlx::Result<i64> {
+    #[derive(Debug)]
+    struct Posts {
+        count: i64,
+    }
+
+    sqlx::query_as!(
+        Posts,
+        "
+        SELECT
+            COUNT(*) AS `count!: i64`
+        FROM posts
+        WHERE
+            is_pending = 1
+            AND deleted_at IS NULL
+        "
+    )
+    .fetch_one(db_pool)
+    .await
+    .map(|posts| posts.count)
+}
+
+async fn fetch_recent_sign_ups(db_pool: &MySqlPool) -> sqlx::Result<Vec<SignUp>> {
+    #[derive(Debug)]
+    struct User {
+        id: Id,
+        username: String,
+        mini_pfp: Option<String>,
+        joined_at: NaiveDateTime,
+    }
+
+    let users = sqlx::query_as!(
+        User,
+        "
+        SELECT
+            users.id,
+            users.username,
+            pfps.variants AS mini_pfp,
+            users.joined_at
+        FROM users
+            LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
+        ORDER BY users.id DESC
+        LIMIT ?
+        ",
+        RECENT_SIGN_UPS_LIMIT
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    let sign_ups = users
+        .into_iter()
+        .map(|user| SignUp {
+            user: UserPreview {
+                id: user.id,
+                username: user.username,
+                mini_pfp: Pfp::from_variants(user.mini_pfp, default_mini_pfp()),
+            },
+            joined_at: format_posted_at(user.joined_at),
+        })
+        .collect();
+
+    Ok(sign_ups)
+}
+
+// Number of uploaded files and their total size in bytes
+async fn fetch_storage(db_pool: &MySqlPool) -> sqlx::Result<(i64, u64)> {
+    #[derive(Debug)]
+    struct Files {
+        count: i64,
+        size: u64,
+    }
+
+    sqlx::query_as!(
+        Files,
+        "
+        SELECT
+            COUNT(*) AS `count!: i64`,
+            CAST(COALESCE(SUM(size), 0) AS UNSIGNED) AS `size!: u64`
+        FROM files
+        "
+    )
+    .fetch_one(db_pool)
+    .await
+    .map(|files| (files.count, files.size))
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl ResponseError for Error {}
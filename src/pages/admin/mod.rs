@@ -0,0 +1,4 @@
+pub mod dashboard;
+pub mod roles;
+pub mod users;
+pub mod webhooks;
@@ -0,0 +1,172 @@
+use crate::db::fetch_roles;
+use crate::policy::{self, Action, Resource};
+use crate::session::auth;
+use crate::types::id::Id;
+use crate::types::user::role::{name::NAME_MAX_CHAR_COUNT, Permissions, Role};
+use crate::State;
+use crate::LIQUID_PARSER;
+use actix_web::{
+    get,
+    http::header,
+    web::{Data, Query},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use lazy_static::lazy_static;
+use liquid::Template;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Serialize)]
+struct RoleView {
+    id: Id,
+    name: String,
+    is_builtin: bool,
+    has_fixed_permissions: bool,
+    permissions: Vec<PermissionView>,
+}
+
+#[derive(Debug, Serialize)]
+struct PermissionView {
+    key: &'static str,
+    label: &'static str,
+    is_set: bool,
+}
+
+#[get("/admin/roles")]
+pub async fn service(
+    req: HttpRequest,
+    state: Data<State>,
+    Query(query): Query<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(current_user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/admin/roles"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    let is_allowed = policy::can(&current_user, Action::Admin, Resource::Site, &state.config);
+
+    if !is_allowed && query.error.is_none() {
+        let response = HttpResponse::SeeOther()
+            .append_header((
+                header::LOCATION,
+                "/admin/roles?error=You are not allowed to manage roles",
+            ))
+            .finish();
+
+        return Ok(response);
+    }
+
+    let roles = if is_allowed {
+        fetch_roles(&state.db_pool)
+            .await?
+            .into_iter()
+            .map(RoleView::from_role)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    lazy_static! {
+        static ref TEMPLATE: Template = {
+            let template = include_str!("../../../templates/admin/roles.html");
+
+            LIQUID_PARSER.parse(template).unwrap()
+        };
+    }
+
+    let current_url = urlencoding::encode(&req.uri().to_string()).into_owned();
+
+    let globals = liquid::object!({
+        "current_url": current_url,
+        "current_user": current_user,
+        "roles": roles,
+        "new_role_permissions": permission_views(Permissions::default()),
+        "name_max_char_count": NAME_MAX_CHAR_COUNT,
+        "error": query.error,
+    });
+
+    let s = TEMPLATE.render(&globals).unwrap();
+
+    Ok(HttpResponse::Ok().body(s))
+}
+
+impl RoleView {
+    fn from_role(role: Role) -> Self {
+        Self {
+            id: role.id,
+            name: role.name,
+            is_builtin: role.is_builtin,
+            has_fixed_permissions: role.has_fixed_permissions,
+            permissions: permission_views(role.permissions),
+        }
+    }
+}
+
+// Keys match the checkbox names `/api/role/save` expects
+fn permission_views(permissions: Permissions) -> Vec<PermissionView> {
+    let view = |key, label, is_set| PermissionView { key, label, is_set };
+
+    vec![
+        view("can_post", "Post", permissions.can_post),
+        view("can_comment", "Comment", permissions.can_comment),
+        view("can_reply", "Reply", permissions.can_reply),
+        view(
+            "can_edit_posts",
+            "Edit own posts",
+            permissions.can_edit_posts,
+        ),
+        view(
+            "can_edit_comments",
+            "Edit own comments",
+            permissions.can_edit_comments,
+        ),
+        view(
+            "can_edit_self",
+            "Edit own profile",
+            permissions.can_edit_self,
+        ),
+        view(
+            "can_anonymise_posts",
+            "Anonymise own posts",
+            permissions.can_anonymise_posts,
+        ),
+        view(
+            "can_anonymise_comments",
+            "Anonymise own comments",
+            permissions.can_anonymise_comments,
+        ),
+        view(
+            "can_delete_posts",
+            "Delete any post",
+            permissions.can_delete_posts,
+        ),
+        view(
+            "can_delete_comments",
+            "Delete any comment",
+            permissions.can_delete_comments,
+        ),
+        view("can_admin", "Admin", permissions.can_admin),
+    ]
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl ResponseError for Error {}
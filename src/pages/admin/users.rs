@@ -0,0 +1,395 @@
+use crate::db::fetch_roles;
+use crate::policy::{self, Action, Resource};
+use crate::session::auth;
+use crate::types::ban;
+use crate::types::default_mini_pfp;
+use crate::types::id::Id;
+use crate::types::mod_action::reason::REASON_MAX_CHAR_COUNT;
+use crate::types::page::clamp_limit;
+use crate::types::post::format_posted_at;
+use crate::types::user::pfp::Pfp;
+use crate::types::user::role::{BANNED, UNVERIFIED};
+use crate::State;
+use crate::LIQUID_PARSER;
+use actix_web::{
+    get,
+    http::header,
+    web::{Data, Query},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use chrono::{NaiveDate, NaiveDateTime};
+use lazy_static::lazy_static;
+use liquid::Template;
+use serde::{Deserialize, Serialize};
+use sqlx::mysql::MySqlPool;
+use thiserror::Error;
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    // Part of a username, email or name
+    pub q: Option<String>,
+    pub role_id: Option<String>,
+    pub joined_after: Option<String>,
+    pub joined_before: Option<String>,
+    pub sort: Option<String>,
+    pub page: Option<u64>,
+    pub limit: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Sort {
+    #[default]
+    Newest,
+    Oldest,
+    Username,
+    UsernameDesc,
+}
+
+#[derive(Debug, Serialize)]
+struct FilterOption {
+    value: String,
+    label: String,
+    is_selected: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct UserRow {
+    id: Id,
+    username: String,
+    email: String,
+    name: String,
+    mini_pfp: Pfp,
+    roles: String,
+    is_banned: bool,
+    joined_at: String,
+}
+
+#[derive(Debug)]
+struct RawUserRow {
+    id: Id,
+    username: String,
+    email: String,
+    name: String,
+    mini_pfp: Option<String>,
+    roles: String,
+    joined_at: NaiveDateTime,
+}
+
+#[derive(Debug)]
+struct Filters {
+    pattern: Option<String>,
+    role_id: Option<Id>,
+    joined_after: Option<NaiveDate>,
+    joined_before: Option<NaiveDate>,
+    sort: Sort,
+    offset: u64,
+    limit: u64,
+}
+
+impl Sort {
+    pub const ALL: [Self; 4] = [
+        Self::Newest,
+        Self::Oldest,
+        Self::Username,
+        Self::UsernameDesc,
+    ];
+
+    pub fn from_str(s: &str) -> Result<Self, ()> {
+        Self::ALL
+            .into_iter()
+            .find(|sort| sort.as_str() == s)
+            .ok_or(())
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Newest => "newest",
+            Self::Oldest => "oldest",
+            Self::Username => "username",
+            Self::UsernameDesc => "username_desc",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Newest => "Newest first",
+            Self::Oldest => "Oldest first",
+            Self::Username => "Username A-Z",
+            Self::UsernameDesc => "Username Z-A",
+        }
+    }
+}
+
+#[get("/admin/users")]
+pub async fn service(
+    req: HttpRequest,
+    state: Data<State>,
+    Query(query): Query<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(current_user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/admin/users"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    let is_allowed = policy::can(&current_user, Action::Admin, Resource::Site, &state.config);
+
+    if !is_allowed && query.error.is_none() {
+        let response = HttpResponse::SeeOther()
+            .append_header((
+                header::LOCATION,
+                "/admin/users?error=You are not allowed to manage users",
+            ))
+            .finish();
+
+        return Ok(response);
+    }
+
+    // Unknown or empty filters are ignored
+    let q = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty());
+    let joined_after = query.joined_after.as_deref().and_then(parse_date);
+    let joined_before = query.joined_before.as_deref().and_then(parse_date);
+    let sort = query
+        .sort
+        .as_deref()
+        .and_then(|sort| Sort::from_str(sort).ok())
+        .unwrap_or_default();
+
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = clamp_limit(query.limit, &state.config.page);
+
+    let roles = if is_allowed {
+        fetch_roles(&state.db_pool).await?
+    } else {
+        Vec::new()
+    };
+
+    let role_id = query
+        .role_id
+        .as_deref()
+        .and_then(|role_id| role_id.parse::<Id>().ok())
+        .filter(|role_id| roles.iter().any(|role| role.id == *role_id));
+
+    let users = if is_allowed {
+        let filters = Filters {
+            pattern: q.map(like_pattern),
+            role_id,
+            joined_after,
+            joined_before,
+            sort,
+            offset: (page - 1) * limit,
+            limit,
+        };
+
+        fetch_users(&state.db_pool, &filters)
+            .await?
+            .into_iter()
+            .map(UserRow::from_raw)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let has_next_page = users.len() as u64 == limit;
+
+    let role_options = roles
+        .iter()
+        .map(|role| FilterOption {
+            value: role.id.to_string(),
+            label: role.name.clone(),
+            is_selected: Some(role.id) == role_id,
+        })
+        .collect::<Vec<_>>();
+
+    // Same rules as `/api/user/admin`, bans have their own operation
+    let assignable_roles = roles
+        .iter()
+        .filter(|role| {
+            !role.permissions.can_admin && role.name != UNVERIFIED && role.name != BANNED
+        })
+        .map(|role| FilterOption {
+            value: role.id.to_string(),
+            label: role.name.clone(),
+            is_selected: false,
+        })
+        .collect::<Vec<_>>();
+
+    let sort_options = Sort::ALL
+        .into_iter()
+        .map(|option| FilterOption {
+            value: option.as_str().to_owned(),
+            label: option.label().to_owned(),
+            is_selected: option == sort,
+        })
+        .collect::<Vec<_>>();
+
+    let joined_after = joined_after.map(|date| date.to_string());
+    let joined_before = joined_before.map(|date| date.to_string());
+
+    // Carried over to the page links and back from `/api/user/bulk`
+    let filter_query = format!(
+        "q={}&role_id={}&joined_after={}&joined_before={}&sort={}&limit={limit}",
+        urlencoding::encode(q.unwrap_or_default()),
+        role_id
+            .map(|role_id| role_id.to_string())
+            .unwrap_or_default(),
+        joined_after.as_deref().unwrap_or_default(),
+        joined_before.as_deref().unwrap_or_default(),
+        sort.as_str(),
+    );
+
+    lazy_static! {
+        static ref TEMPLATE: Template = {
+            let template = include_str!("../../../templates/admin/users.html");
+
+            LIQUID_PARSER.parse(template).unwrap()
+        };
+    }
+
+    let current_url = urlencoding::encode(&req.uri().to_string()).into_owned();
+
+    let globals = liquid::object!({
+        "current_url": current_url,
+        "current_user": current_user,
+        "is_allowed": is_allowed,
+        "users": users,
+        "q": q,
+        "joined_after": joined_after,
+        "joined_before": joined_before,
+        "role_options": role_options,
+        "assignable_roles": assignable_roles,
+        "sort_options": sort_options,
+        "ban_duration_options": ban::duration_options(),
+        "reason_max_char_count": REASON_MAX_CHAR_COUNT,
+        "filter_query": filter_query,
+        "page": page,
+        "previous_page": (page > 1).then(|| page - 1),
+        "next_page": has_next_page.then(|| page + 1),
+        "error": query.error,
+    });
+
+    let s = TEMPLATE.render(&globals).unwrap();
+
+    Ok(HttpResponse::Ok().body(s))
+}
+
+impl UserRow {
+    fn from_raw(raw: RawUserRow) -> Self {
+        Self {
+            id: raw.id,
+            is_banned: raw.roles.split(", ").any(|role| role == BANNED),
+            username: raw.username,
+            email: raw.email,
+            name: raw.name,
+            mini_pfp: Pfp::from_variants(raw.mini_pfp, default_mini_pfp()),
+            roles: raw.roles,
+            joined_at: format_posted_at(raw.joined_at),
+        }
+    }
+}
+
+// `<input type="date">` sends `YYYY-MM-DD`
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+}
+
+// Matches `q` anywhere, `%` and `_` in it are taken literally
+fn like_pattern(q: &str) -> String {
+    let escaped = q
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+
+    format!("%{escaped}%")
+}
+
+async fn fetch_users(db_pool: &MySqlPool, filters: &Filters) -> sqlx::Result<Vec<RawUserRow>> {
+    let sort = filters.sort.as_str();
+
+    sqlx::query_as!(
+        RawUserRow,
+        "
+        SELECT
+            users.id,
+            users.username,
+            users.email,
+            users.name,
+            pfps.variants AS mini_pfp,
+            COALESCE(
+                (
+                    SELECT
+                        GROUP_CONCAT(roles.name ORDER BY roles.name SEPARATOR ', ')
+                    FROM user_roles
+                        JOIN roles ON user_roles.role_id = roles.id
+                    WHERE
+                        user_roles.user_id = users.id
+                ),
+                ''
+            ) AS `roles!: String`,
+            users.joined_at
+        FROM users
+            LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
+        WHERE
+            (
+                ? IS NULL
+                OR users.username LIKE ?
+                OR users.email LIKE ?
+                OR users.name LIKE ?
+            )
+            AND (
+                ? IS NULL
+                OR EXISTS (
+                    SELECT
+                        1
+                    FROM user_roles
+                    WHERE
+                        user_roles.user_id = users.id
+                        AND user_roles.role_id = ?
+                )
+            )
+            AND (? IS NULL OR users.joined_at >= ?)
+            AND (? IS NULL OR users.joined_at < ? + INTERVAL 1 DAY)
+        ORDER BY
+            CASE WHEN ? = 'username' THEN users.username END ASC,
+            CASE WHEN ? = 'username_desc' THEN users.username END DESC,
+            CASE WHEN ? = 'oldest' THEN users.id END ASC,
+            users.id DESC
+        LIMIT ?
+        OFFSET ?
+        ",
+        filters.pattern,
+        filters.pattern,
+        filters.pattern,
+        filters.pattern,
+        filters.role_id,
+        filters.role_id,
+        filters.joined_after,
+        filters.joined_after,
+        filters.joined_before,
+        filters.joined_before,
+        sort,
+        sort,
+        sort,
+        filters.limit,
+        filters.offset
+    )
+    .fetch_all(db_pool)
+    .await
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl ResponseError for Error {}
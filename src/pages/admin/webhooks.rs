@@ -0,0 +1,175 @@
+use crate::policy::{self, Action, Resource};
+use crate::session::auth;
+use crate::types::id::Id;
+use crate::types::post::tags::TAG_MAX_CHAR_COUNT;
+use crate::types::webhook::{
+    event::Event, url::URL_MAX_CHAR_COUNT, Delivery, RawDelivery, RawWebhook, Webhook,
+};
+use crate::webhook::{DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER};
+use crate::State;
+use crate::LIQUID_PARSER;
+use actix_web::{
+    get,
+    http::header,
+    web::{Data, Query},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use lazy_static::lazy_static;
+use liquid::Template;
+use serde::Deserialize;
+use sqlx::mysql::MySqlPool;
+use thiserror::Error;
+
+// Newest deliveries shown in the log
+const DELIVERY_LOG_LIMIT: i64 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    // Only show the deliveries of one webhook
+    pub webhook_id: Option<Id>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("Server error")]
+    Server,
+}
+
+#[get("/admin/webhooks")]
+pub async fn service(
+    req: HttpRequest,
+    state: Data<State>,
+    Query(query): Query<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(current_user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/admin/webhooks"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    let is_allowed = policy::can(&current_user, Action::Admin, Resource::Site, &state.config);
+
+    if !is_allowed && query.error.is_none() {
+        let response = HttpResponse::SeeOther()
+            .append_header((
+                header::LOCATION,
+                "/admin/webhooks?error=You are not allowed to manage webhooks",
+            ))
+            .finish();
+
+        return Ok(response);
+    }
+
+    let (webhooks, deliveries) = if is_allowed {
+        (
+            fetch_webhooks(&state.db_pool).await?,
+            fetch_deliveries(&state.db_pool, query.webhook_id).await?,
+        )
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    lazy_static! {
+        static ref TEMPLATE: Template = {
+            let template = include_str!("../../../templates/admin/webhooks.html");
+
+            LIQUID_PARSER.parse(template).unwrap()
+        };
+    }
+
+    let current_url = urlencoding::encode(&req.uri().to_string()).into_owned();
+
+    let globals = liquid::object!({
+        "current_url": current_url,
+        "current_user": current_user,
+        "is_allowed": is_allowed,
+        "webhooks": webhooks,
+        "deliveries": deliveries,
+        "webhook_id": query.webhook_id,
+        "event_options": Event::options(),
+        "url_max_char_count": URL_MAX_CHAR_COUNT,
+        "tag_max_char_count": TAG_MAX_CHAR_COUNT,
+        "signature_header": SIGNATURE_HEADER,
+        "event_header": EVENT_HEADER,
+        "delivery_header": DELIVERY_HEADER,
+        "error": query.error,
+    });
+
+    let s = TEMPLATE.render(&globals).unwrap();
+
+    Ok(HttpResponse::Ok().body(s))
+}
+
+async fn fetch_webhooks(db_pool: &MySqlPool) -> sqlx::Result<Vec<Webhook>> {
+    let raw_webhooks = sqlx::query_as!(
+        RawWebhook,
+        "
+        SELECT
+            id,
+            url,
+            secret,
+            events,
+            tag,
+            is_active AS `is_active: bool`,
+            created_at
+        FROM webhooks
+        ORDER BY id
+        "
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    let webhooks = raw_webhooks.into_iter().map(Webhook::from_raw).collect();
+
+    Ok(webhooks)
+}
+
+async fn fetch_deliveries(
+    db_pool: &MySqlPool,
+    webhook_id: Option<Id>,
+) -> sqlx::Result<Vec<Delivery>> {
+    let raw_deliveries = sqlx::query_as!(
+        RawDelivery,
+        "
+        SELECT
+            webhook_deliveries.id,
+            webhook_deliveries.webhook_id,
+            webhooks.url,
+            webhook_deliveries.event,
+            webhook_deliveries.payload,
+            webhook_deliveries.status,
+            webhook_deliveries.attempt_count,
+            webhook_deliveries.response_status,
+            webhook_deliveries.last_error,
+            webhook_deliveries.created_at,
+            webhook_deliveries.next_attempt_at,
+            webhook_deliveries.finished_at
+        FROM webhook_deliveries
+            JOIN webhooks ON webhook_deliveries.webhook_id = webhooks.id
+        WHERE
+            (? IS NULL OR webhook_deliveries.webhook_id = ?)
+        ORDER BY webhook_deliveries.id DESC
+        LIMIT ?
+        ",
+        webhook_id,
+        webhook_id,
+        DELIVERY_LOG_LIMIT
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    let deliveries = raw_deliveries.into_iter().map(Delivery::from_raw).collect();
+
+    Ok(deliveries)
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl ResponseError for Error {}
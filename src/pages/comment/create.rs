@@ -69,11 +69,18 @@ pub async fn service(
     };
     let comment = match query.reply_to_comment_id {
         Some(reply_to_comment_id) => {
-            Some(fetch_comment_by_id(&state.db_pool, reply_to_comment_id, &current_user).await?)
+            Some(fetch_comment_by_id(&state.db_pool, reply_to_comment_id, &current_user, &state.config).await?)
         }
         None => None,
     };
 
+    // Filled in with what was sent when the form comes back with an error
+    let flash = state.flash.take(&req);
+
+    let form = liquid::object!({
+        "content": flash.value("content", ""),
+    });
+
     let current_url = urlencoding::encode(&req.uri().to_string()).into_owned();
 
     let globals = liquid::object!({
@@ -82,6 +89,8 @@ pub async fn service(
         "post": post,
         "comment": comment,
         "content_max_char_count": CONTENT_MAX_CHAR_COUNT,
+        "form": form,
+        "errors": flash.errors,
         "error": query.error,
     });
 
@@ -100,13 +109,16 @@ async fn fetch_post(db_pool: &MySqlPool, post_id: Id) -> sqlx::Result<Option<Pos
             posts.description,
             users.id AS posted_by_user_id,
             users.username AS posted_by_username,
-            CONCAT(files.id, \".\", files.extension) AS posted_by_mini_pfp,
-            posts.posted_at
+            pfps.variants AS posted_by_mini_pfp,
+            posts.posted_at,
+            posts.is_pending AS `is_pending: bool`
         FROM posts
             LEFT JOIN users ON posts.posted_by_user_id = users.id
-            LEFT JOIN files ON users.mini_pfp_file_id = files.id
+            LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
         WHERE
             posts.id = ?
+            AND posts.deleted_at IS NULL
+            AND posts.is_pending = 0
         ",
         post_id
     )
@@ -45,7 +45,7 @@ pub async fn service(
         return Ok(response);
     };
 
-    let Some(comment) = fetch_comment_by_id(&state.db_pool, comment_id, &current_user).await? else {
+    let Some(comment) = fetch_comment_by_id(&state.db_pool, comment_id, &current_user, &state.config).await? else {
         return Ok(crate::pages::not_found::service().await);
     };
 
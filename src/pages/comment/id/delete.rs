@@ -1,6 +1,7 @@
 use crate::db::fetch_comment_by_id;
 use crate::session::auth;
 use crate::types::id::Id;
+use crate::types::mod_action::reason::REASON_MAX_CHAR_COUNT;
 use crate::types::user::username::USERNAME_MAX_CHAR_COUNT;
 use crate::State;
 use crate::LIQUID_PARSER;
@@ -45,7 +46,7 @@ pub async fn service(
         return Ok(response);
     };
 
-    let Some(comment) = fetch_comment_by_id(&state.db_pool, comment_id, &current_user).await? else {
+    let Some(comment) = fetch_comment_by_id(&state.db_pool, comment_id, &current_user, &state.config).await? else {
         return Ok(crate::pages::not_found::service().await);
     };
 
@@ -76,6 +77,7 @@ pub async fn service(
         "current_user": current_user,
         "comment": comment,
         "username_max_char_count": USERNAME_MAX_CHAR_COUNT,
+        "reason_max_char_count": REASON_MAX_CHAR_COUNT,
         "error": query.error,
     });
 
@@ -1,3 +1,4 @@
+use crate::config::Config;
 use crate::session::{auth, User};
 use crate::types::comment::content::CONTENT_MAX_CHAR_COUNT;
 use crate::types::comment::{Comment, RawComment};
@@ -48,7 +49,7 @@ pub async fn service(
         return Ok(response);
     };
 
-    let Some(comment) = fetch_comment_by_id(&state.db_pool, comment_id, &current_user).await? else {
+    let Some(comment) = fetch_comment_by_id(&state.db_pool, comment_id, &current_user, &state.config).await? else {
         return Ok(crate::pages::not_found::service().await);
     };
 
@@ -71,6 +72,13 @@ pub async fn service(
         };
     }
 
+    // Filled in with what was sent when the form comes back with an error
+    let flash = state.flash.take(&req);
+
+    let form = liquid::object!({
+        "content": flash.value("content", comment.content.clone()),
+    });
+
     let current_url = urlencoding::encode(&req.uri().to_string()).into_owned();
 
     let globals = liquid::object!({
@@ -78,6 +86,8 @@ pub async fn service(
         "current_user": current_user,
         "content_max_char_count": CONTENT_MAX_CHAR_COUNT,
         "comment": comment,
+        "form": form,
+        "errors": flash.errors,
         "error": query.error,
     });
 
@@ -90,6 +100,7 @@ async fn fetch_comment_by_id(
     db_pool: &MySqlPool,
     comment_id: Id,
     user: &User,
+    config: &Config,
 ) -> sqlx::Result<Option<Comment>> {
     sqlx::query_as!(
         RawComment,
@@ -100,19 +111,28 @@ async fn fetch_comment_by_id(
             comments.reply_to_comment_id,
             users_reply_to.id AS reply_to_user_id,
             users_reply_to.username AS reply_to_username,
-            CONCAT(files_reply_to.id, \".\", files_reply_to.extension) AS reply_to_mini_pfp,
+            pfps_reply_to.variants AS reply_to_mini_pfp,
             comments.content AS content,
             users_posted_by.id AS posted_by_user_id,
             users_posted_by.username AS posted_by_username,
-            CONCAT(files_posted_by.id, \".\", files_posted_by.extension) AS posted_by_mini_pfp,
+            pfps_posted_by.variants AS posted_by_mini_pfp,
             comments.posted_at,
-            comments.modified_at
+            comments.modified_at,
+            comments.deleted_at,
+            (
+                SELECT COUNT(*)
+                FROM reports
+                WHERE
+                    reports.target_type = 'Comment'
+                    AND reports.target_id = comments.id
+                    AND reports.resolved_at IS NULL
+            ) AS `open_report_count!`
         FROM comments
             LEFT JOIN comments AS comments_reply_to ON comments.reply_to_comment_id = comments_reply_to.id
             LEFT JOIN users AS users_reply_to ON comments_reply_to.posted_by_user_id = users_reply_to.id
-            LEFT JOIN files AS files_reply_to ON users_reply_to.mini_pfp_file_id = files_reply_to.id
+            LEFT JOIN user_pfps AS pfps_reply_to ON users_reply_to.id = pfps_reply_to.user_id AND pfps_reply_to.size = 'mini'
             LEFT JOIN users AS users_posted_by ON comments.posted_by_user_id = users_posted_by.id
-            LEFT JOIN files AS files_posted_by ON users_posted_by.mini_pfp_file_id = files_posted_by.id
+            LEFT JOIN user_pfps AS pfps_posted_by ON users_posted_by.id = pfps_posted_by.user_id AND pfps_posted_by.size = 'mini'
         WHERE
             comments.id = ?
         ",
@@ -120,7 +140,7 @@ async fn fetch_comment_by_id(
     )
     .fetch_optional(db_pool)
     .await
-    .map(|result| result.map(|raw_comment| Comment::from_raw(raw_comment, user)))
+    .map(|result| result.map(|raw_comment| Comment::from_raw(raw_comment, user, config)))
 }
 
 impl From<sqlx::Error> for Error {
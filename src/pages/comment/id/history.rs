@@ -0,0 +1,160 @@
+use crate::db::fetch_comment_by_id;
+use crate::pages::post::id::fetch_post_by_id;
+use crate::policy::Viewer;
+use crate::session::auth;
+use crate::types::id::Id;
+use crate::types::revision::{RawRevision, Revision};
+use crate::State;
+use crate::LIQUID_PARSER;
+use actix_web::{
+    get,
+    web::{Data, Path},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use lazy_static::lazy_static;
+use liquid::Template;
+use sqlx::mysql::MySqlPool;
+use thiserror::Error;
+
+#[derive(Debug, Copy, Clone, Error)]
+pub enum Error {
+    #[error("Server error")]
+    Server,
+}
+
+#[get("/comment/{id}/history")]
+pub async fn service(
+    req: HttpRequest,
+    state: Data<State>,
+    path: Path<Id>,
+) -> Result<HttpResponse, Error> {
+    let current_user = auth(&state.db_pool, &req).await.ok();
+
+    let comment_id = path.into_inner();
+
+    let Some(comment) = fetch_comment_by_id(
+        &state.db_pool,
+        comment_id,
+        current_user.as_ref(),
+        &state.config,
+    )
+    .await?
+    else {
+        return Ok(crate::pages::not_found::service().await);
+    };
+
+    let Some(post) = fetch_post_by_id(
+        &state.db_pool,
+        comment.post_id,
+        current_user.as_ref(),
+        &state.config,
+    )
+    .await?
+    else {
+        return Ok(crate::pages::not_found::service().await);
+    };
+
+    let viewer = Viewer::of(current_user.as_ref(), &state.config);
+
+    if !post.is_visible || !fetch_is_visible(&state.db_pool, comment.id, viewer).await? {
+        return Ok(crate::pages::not_found::service().await);
+    }
+
+    // Only those who can restore a deleted comment get to see what it was
+    if comment.is_deleted && !comment.is_restorable {
+        return Ok(crate::pages::not_found::service().await);
+    }
+
+    // Mods always see who edited, everyone else only while the comment isn't anonymised
+    let are_editors_visible = comment.posted_by.is_some() || comment.is_resolvable;
+
+    let raw_revisions = fetch_raw_revisions(&state.db_pool, comment.id).await?;
+    let revisions = Revision::history(raw_revisions, are_editors_visible);
+
+    lazy_static! {
+        static ref TEMPLATE: Template = {
+            let template = include_str!("../../../../templates/comment/id/history.html");
+
+            LIQUID_PARSER.parse(template).unwrap()
+        };
+    }
+
+    let current_url = urlencoding::encode(&req.uri().to_string()).into_owned();
+
+    let globals = liquid::object!({
+        "current_url": current_url,
+        "current_user": current_user,
+        "comment": comment,
+        "post": post,
+        "revisions": revisions,
+    });
+
+    let s = TEMPLATE.render(&globals).unwrap();
+
+    Ok(HttpResponse::Ok().body(s))
+}
+
+// Shadowed comments are only visible to their author and moderators
+async fn fetch_is_visible(
+    db_pool: &MySqlPool,
+    comment_id: Id,
+    viewer: Viewer,
+) -> sqlx::Result<bool> {
+    #[derive(Debug)]
+    struct Count {
+        count: i64,
+    }
+
+    sqlx::query_as!(
+        Count,
+        "
+        SELECT
+            COUNT(*) AS `count!`
+        FROM comments
+        WHERE
+            id = ?
+            AND (is_shadowed = 0 OR posted_by_user_id = ? OR ?)
+        ",
+        comment_id,
+        viewer.user_id,
+        viewer.can_view_held
+    )
+    .fetch_one(db_pool)
+    .await
+    .map(|result| result.count > 0)
+}
+
+async fn fetch_raw_revisions(
+    db_pool: &MySqlPool,
+    comment_id: Id,
+) -> sqlx::Result<Vec<RawRevision>> {
+    sqlx::query_as!(
+        RawRevision,
+        "
+        SELECT
+            comment_revisions.id,
+            comment_revisions.content AS text,
+            users.id AS edited_by_user_id,
+            users.username AS edited_by_username,
+            pfps.variants AS edited_by_mini_pfp,
+            comment_revisions.created_at
+        FROM comment_revisions
+            LEFT JOIN users ON comment_revisions.edited_by_user_id = users.id
+            LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
+        WHERE
+            comment_revisions.comment_id = ?
+        ORDER BY comment_revisions.id ASC
+        ",
+        comment_id
+    )
+    .fetch_all(db_pool)
+    .await
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl ResponseError for Error {}
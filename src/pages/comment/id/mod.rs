@@ -1,3 +1,6 @@
 pub mod anonymise;
 pub mod delete;
 pub mod edit;
+pub mod history;
+pub mod report;
+pub mod restore;
@@ -0,0 +1,53 @@
+use crate::feed::{self, Feed, Format};
+use crate::pages::index::{fetch_posts_from_raw, fetch_raw_posts_forwards};
+use crate::policy::Viewer;
+use crate::types::id::Id;
+use crate::types::page::clamp_limit;
+use crate::State;
+use actix_web::{get, web::Data, HttpRequest, HttpResponse, ResponseError};
+use thiserror::Error;
+
+#[derive(Debug, Copy, Clone, Error)]
+pub enum Error {
+    #[error("Server error")]
+    Server,
+}
+
+#[get("/feed.atom")]
+pub async fn atom(state: Data<State>, req: HttpRequest) -> Result<HttpResponse, Error> {
+    let feed = fetch_feed(&state, "/feed.atom").await?;
+
+    Ok(feed::respond(&req, feed, Format::Atom))
+}
+
+#[get("/feed.rss")]
+pub async fn rss(state: Data<State>, req: HttpRequest) -> Result<HttpResponse, Error> {
+    let feed = fetch_feed(&state, "/feed.rss").await?;
+
+    Ok(feed::respond(&req, feed, Format::Rss))
+}
+
+async fn fetch_feed(state: &State, path: &str) -> sqlx::Result<Feed> {
+    let domain = &state.config.domain;
+    let limit = clamp_limit(None, &state.config.page);
+    let viewer = Viewer::of(None, &state.config);
+
+    let raw_post_previews =
+        fetch_raw_posts_forwards(&state.db_pool, i64::MAX as Id, limit, "%%", viewer).await?;
+    let posts = fetch_posts_from_raw(&state.db_pool, raw_post_previews).await?;
+
+    Ok(Feed {
+        title: "neor".to_owned(),
+        url: format!("https://{domain}/"),
+        self_url: format!("https://{domain}{path}"),
+        entries: feed::post_entries(&state.db_pool, domain, posts).await?,
+    })
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl ResponseError for Error {}
@@ -1,4 +1,5 @@
-use crate::db::fetch_post_preview_from_raw;
+use crate::db::fetch_post_previews_from_raw;
+use crate::policy::Viewer;
 use crate::session::auth;
 use crate::types::id::Id;
 use crate::types::page::{clamp_limit, Direction};
@@ -40,7 +41,7 @@ pub async fn service(
 
     let direction = query.direction.unwrap_or_default();
     let start_id = query.start_id.unwrap_or(i64::MAX as Id);
-    let limit = clamp_limit(query.limit);
+    let limit = clamp_limit(query.limit, &state.config.page);
 
     lazy_static! {
         static ref TEMPLATE: Template = {
@@ -52,19 +53,21 @@ pub async fn service(
 
     let q = format!("%{}%", query.query.clone().unwrap_or_default());
 
-    let (min_id, max_id) = fetch_min_max_post_id(&state.db_pool, &q).await?;
+    let viewer = Viewer::of(current_user.as_ref(), &state.config);
+
+    let (min_id, max_id) = fetch_min_max_post_id(&state.db_pool, &q, viewer).await?;
 
     let raw_post_previews = match direction {
         Direction::Backwards => {
             let mut raw_post_previews =
-                fetch_raw_posts_backwards(&state.db_pool, start_id, limit, &q).await?;
+                fetch_raw_posts_backwards(&state.db_pool, start_id, limit, &q, viewer).await?;
 
             raw_post_previews.reverse();
 
             raw_post_previews
         }
         Direction::Forwards => {
-            fetch_raw_posts_forwards(&state.db_pool, start_id, limit, &q).await?
+            fetch_raw_posts_forwards(&state.db_pool, start_id, limit, &q, viewer).await?
         }
     };
 
@@ -107,26 +110,19 @@ pub async fn service(
     Ok(HttpResponse::Ok().body(s))
 }
 
-async fn fetch_posts_from_raw(
+pub async fn fetch_posts_from_raw(
     db_pool: &MySqlPool,
     raw_post_previews: Vec<RawPostPreview>,
 ) -> sqlx::Result<Vec<PostPreview>> {
-    let mut post_previews = Vec::new();
-
-    for raw_post_preview in raw_post_previews {
-        let post_preview = fetch_post_preview_from_raw(db_pool, raw_post_preview).await?;
-
-        post_previews.push(post_preview);
-    }
-
-    Ok(post_previews)
+    fetch_post_previews_from_raw(db_pool, raw_post_previews).await
 }
 
-async fn fetch_raw_posts_forwards(
+pub async fn fetch_raw_posts_forwards(
     db_pool: &MySqlPool,
     start_id: Id,
     limit: u64,
     query: &str,
+    viewer: Viewer,
 ) -> sqlx::Result<Vec<RawPostPreview>> {
     sqlx::query_as!(
         RawPostPreview,
@@ -137,19 +133,26 @@ async fn fetch_raw_posts_forwards(
             posts.description,
             users.id AS posted_by_user_id,
             users.username AS posted_by_username,
-            CONCAT(files.id, \".\", files.extension) AS posted_by_mini_pfp,
-            posts.posted_at
+            pfps.variants AS posted_by_mini_pfp,
+            posts.posted_at,
+            posts.is_pending AS `is_pending: bool`
         FROM posts
             LEFT JOIN users ON posts.posted_by_user_id = users.id
-            LEFT JOIN files ON users.mini_pfp_file_id = files.id
+            LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
         WHERE
             posts.id <= ?
             AND posts.title LIKE ?
+            AND posts.deleted_at IS NULL
+            AND (posts.is_pending = 0 OR posts.posted_by_user_id = ?)
+            AND (posts.is_shadowed = 0 OR posts.posted_by_user_id = ? OR ?)
         ORDER BY posts.id DESC
         LIMIT ?
         ",
         start_id,
         query,
+        viewer.user_id,
+        viewer.user_id,
+        viewer.can_view_held,
         limit
     )
     .fetch_all(db_pool)
@@ -161,6 +164,7 @@ async fn fetch_raw_posts_backwards(
     start_id: Id,
     limit: u64,
     query: &str,
+    viewer: Viewer,
 ) -> sqlx::Result<Vec<RawPostPreview>> {
     sqlx::query_as!(
         RawPostPreview,
@@ -171,19 +175,26 @@ async fn fetch_raw_posts_backwards(
             posts.description,
             users.id AS posted_by_user_id,
             users.username AS posted_by_username,
-            CONCAT(files.id, \".\", files.extension) AS posted_by_mini_pfp,
-            posts.posted_at
+            pfps.variants AS posted_by_mini_pfp,
+            posts.posted_at,
+            posts.is_pending AS `is_pending: bool`
         FROM posts
             LEFT JOIN users ON posts.posted_by_user_id = users.id
-            LEFT JOIN files ON users.mini_pfp_file_id = files.id
+            LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
         WHERE
             posts.id >= ?
             AND posts.title LIKE ?
+            AND posts.deleted_at IS NULL
+            AND (posts.is_pending = 0 OR posts.posted_by_user_id = ?)
+            AND (posts.is_shadowed = 0 OR posts.posted_by_user_id = ? OR ?)
         ORDER BY posts.id ASC
         LIMIT ?
         ",
         start_id,
         query,
+        viewer.user_id,
+        viewer.user_id,
+        viewer.can_view_held,
         limit
     )
     .fetch_all(db_pool)
@@ -193,6 +204,7 @@ async fn fetch_raw_posts_backwards(
 async fn fetch_min_max_post_id(
     db_pool: &MySqlPool,
     query: &str,
+    viewer: Viewer,
 ) -> sqlx::Result<(Option<Id>, Option<Id>)> {
     #[derive(Debug)]
     struct MinMax {
@@ -209,8 +221,14 @@ async fn fetch_min_max_post_id(
         FROM posts
         WHERE
             posts.title LIKE ?
+            AND posts.deleted_at IS NULL
+            AND (posts.is_pending = 0 OR posts.posted_by_user_id = ?)
+            AND (posts.is_shadowed = 0 OR posts.posted_by_user_id = ? OR ?)
         ",
-        query
+        query,
+        viewer.user_id,
+        viewer.user_id,
+        viewer.can_view_held
     )
     .fetch_one(db_pool)
     .await
@@ -1,14 +1,20 @@
+pub mod admin;
 pub mod comment;
 pub mod email_verification;
+pub mod feed;
 pub mod files;
 pub mod index;
+pub mod moderation;
+pub mod modlog;
 pub mod not_found;
 pub mod password_change;
 pub mod password_reset;
 pub mod post;
+pub mod robots;
 pub mod sign_in;
 pub mod sign_out;
 pub mod sign_up;
+pub mod sitemap;
 pub mod style;
 pub mod switch_theme;
 pub mod tag;
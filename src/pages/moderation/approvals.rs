@@ -0,0 +1,133 @@
+use crate::db::fetch_contentless_post_by_id;
+use crate::policy::{self, Action, Resource};
+use crate::session::auth;
+use crate::types::id::Id;
+use crate::types::mod_action::reason::REASON_MAX_CHAR_COUNT;
+use crate::types::page::clamp_limit;
+use crate::State;
+use crate::LIQUID_PARSER;
+use actix_web::{
+    get,
+    http::header,
+    web::{Data, Query},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use lazy_static::lazy_static;
+use liquid::Template;
+use serde::Deserialize;
+use sqlx::mysql::MySqlPool;
+use thiserror::Error;
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub limit: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("Server error")]
+    Server,
+}
+
+#[get("/mod/approvals")]
+pub async fn service(
+    req: HttpRequest,
+    state: Data<State>,
+    Query(query): Query<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(current_user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/mod/approvals"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    let is_allowed = policy::can(&current_user, Action::View, Resource::Held, &state.config);
+
+    if !is_allowed && query.error.is_none() {
+        let response = HttpResponse::SeeOther()
+            .append_header((
+                header::LOCATION,
+                "/mod/approvals?error=You are not allowed to view pending posts",
+            ))
+            .finish();
+
+        return Ok(response);
+    }
+
+    let limit = clamp_limit(query.limit, &state.config.page);
+
+    let mut posts = Vec::new();
+
+    if is_allowed {
+        for post_id in fetch_pending_post_ids(&state.db_pool, limit).await? {
+            let Some(post) =
+                fetch_contentless_post_by_id(&state.db_pool, post_id, &current_user, &state.config)
+                    .await?
+            else {
+                continue;
+            };
+
+            posts.push(post);
+        }
+    }
+
+    lazy_static! {
+        static ref TEMPLATE: Template = {
+            let template = include_str!("../../../templates/moderation/approvals.html");
+
+            LIQUID_PARSER.parse(template).unwrap()
+        };
+    }
+
+    let current_url = urlencoding::encode(&req.uri().to_string()).into_owned();
+
+    let globals = liquid::object!({
+        "current_url": current_url,
+        "current_user": current_user,
+        "is_allowed": is_allowed,
+        "posts": posts,
+        "reason_max_char_count": REASON_MAX_CHAR_COUNT,
+        "error": query.error,
+    });
+
+    let s = TEMPLATE.render(&globals).unwrap();
+
+    Ok(HttpResponse::Ok().body(s))
+}
+
+// Oldest first, they have been waiting the longest
+async fn fetch_pending_post_ids(db_pool: &MySqlPool, limit: u64) -> sqlx::Result<Vec<Id>> {
+    #[derive(Debug)]
+    struct Post {
+        id: Id,
+    }
+
+    sqlx::query_as!(
+        Post,
+        "
+        SELECT
+            id
+        FROM posts
+        WHERE
+            is_pending = 1
+            AND deleted_at IS NULL
+        ORDER BY id ASC
+        LIMIT ?
+        ",
+        limit
+    )
+    .fetch_all(db_pool)
+    .await
+    .map(|result| result.into_iter().map(|post| post.id).collect())
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl ResponseError for Error {}
@@ -0,0 +1,281 @@
+use crate::config::Config;
+use crate::db::{fetch_account_by_username, fetch_comment_by_id, fetch_contentless_post_by_id};
+use crate::policy::{self, Action, Resource};
+use crate::session::{auth, User};
+use crate::types::ban;
+use crate::types::id::Id;
+use crate::types::mod_action::{reason::REASON_MAX_CHAR_COUNT, TargetType};
+use crate::types::page::clamp_limit;
+use crate::types::report::{RawReport, Report};
+use crate::types::user::Preview as UserPreview;
+use crate::State;
+use crate::LIQUID_PARSER;
+use actix_web::{
+    get,
+    http::header,
+    web::{Data, Query},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use lazy_static::lazy_static;
+use liquid::Template;
+use serde::{Deserialize, Serialize};
+use sqlx::mysql::MySqlPool;
+use thiserror::Error;
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub limit: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("Server error")]
+    Server,
+}
+
+// A reported post or comment with all of its open reports
+#[derive(Debug, Serialize)]
+struct QueueItem {
+    target_type: &'static str,
+    target_id: Id,
+    url: String,
+    title: String,
+    content: String,
+    posted_by: Option<UserPreview>,
+    report_count: i64,
+    reports: Vec<Report>,
+
+    is_deleted: bool,
+    is_resolvable: bool,
+    is_deletable: bool,
+    is_bannable: bool,
+}
+
+#[derive(Debug)]
+struct RawQueueItem {
+    target_type: String,
+    target_id: Id,
+    report_count: i64,
+}
+
+#[get("/mod/queue")]
+pub async fn service(
+    req: HttpRequest,
+    state: Data<State>,
+    Query(query): Query<Request>,
+) -> Result<HttpResponse, Error> {
+    let Ok(current_user) = auth(&state.db_pool, &req).await else {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/mod/queue"))
+            .finish();
+
+        return Ok(response);
+    };
+
+    let is_allowed = policy::can(
+        &current_user,
+        Action::View,
+        Resource::ModQueue,
+        &state.config,
+    );
+
+    if !is_allowed && query.error.is_none() {
+        let response = HttpResponse::SeeOther()
+            .append_header((
+                header::LOCATION,
+                "/mod/queue?error=You are not allowed to view the moderation queue",
+            ))
+            .finish();
+
+        return Ok(response);
+    }
+
+    let limit = clamp_limit(query.limit, &state.config.page);
+
+    let items = if is_allowed {
+        fetch_queue_items(&state.db_pool, limit, &current_user, &state.config).await?
+    } else {
+        Vec::new()
+    };
+
+    lazy_static! {
+        static ref TEMPLATE: Template = {
+            let template = include_str!("../../../templates/moderation/queue.html");
+
+            LIQUID_PARSER.parse(template).unwrap()
+        };
+    }
+
+    let current_url = urlencoding::encode(&req.uri().to_string()).into_owned();
+
+    let globals = liquid::object!({
+        "current_url": current_url,
+        "current_user": current_user,
+        "is_allowed": is_allowed,
+        "items": items,
+        "reason_max_char_count": REASON_MAX_CHAR_COUNT,
+        "ban_duration_options": ban::duration_options(),
+        "error": query.error,
+    });
+
+    let s = TEMPLATE.render(&globals).unwrap();
+
+    Ok(HttpResponse::Ok().body(s))
+}
+
+// Most reported first, then the ones waiting the longest
+async fn fetch_queue_items(
+    db_pool: &MySqlPool,
+    limit: u64,
+    user: &User,
+    config: &Config,
+) -> sqlx::Result<Vec<QueueItem>> {
+    let raw_items = sqlx::query_as!(
+        RawQueueItem,
+        "
+        SELECT
+            target_type,
+            target_id,
+            COUNT(*) AS report_count
+        FROM reports
+        WHERE
+            resolved_at IS NULL
+        GROUP BY target_type, target_id
+        ORDER BY report_count DESC, MIN(reported_at) ASC
+        LIMIT ?
+        ",
+        limit
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    let mut items = Vec::with_capacity(raw_items.len());
+
+    for raw_item in raw_items {
+        let Some(item) = fetch_queue_item(db_pool, raw_item, user, config).await? else {
+            continue;
+        };
+
+        items.push(item);
+    }
+
+    Ok(items)
+}
+
+async fn fetch_queue_item(
+    db_pool: &MySqlPool,
+    raw_item: RawQueueItem,
+    user: &User,
+    config: &Config,
+) -> sqlx::Result<Option<QueueItem>> {
+    let target_type = TargetType::from_str(&raw_item.target_type).ok();
+
+    let mut item = match target_type {
+        Some(TargetType::Post) => {
+            let Some(post) =
+                fetch_contentless_post_by_id(db_pool, raw_item.target_id, user, config).await?
+            else {
+                return Ok(None);
+            };
+
+            QueueItem {
+                target_type: TargetType::Post.as_str(),
+                target_id: post.id,
+                url: format!("/post/{}", post.id),
+                title: post.title,
+                content: post.description,
+                posted_by: post.posted_by,
+                report_count: raw_item.report_count,
+                reports: Vec::new(),
+
+                is_deleted: post.is_deleted,
+                is_resolvable: post.is_resolvable,
+                is_deletable: post.is_deletable,
+                is_bannable: false,
+            }
+        }
+        Some(TargetType::Comment) => {
+            let Some(comment) =
+                fetch_comment_by_id(db_pool, raw_item.target_id, user, config).await?
+            else {
+                return Ok(None);
+            };
+
+            QueueItem {
+                target_type: TargetType::Comment.as_str(),
+                target_id: comment.id,
+                url: format!(
+                    "/post/{}?start_id={}#{}",
+                    comment.post_id, comment.id, comment.id
+                ),
+                title: format!("Comment on post {}", comment.post_id),
+                content: comment.content,
+                posted_by: comment.posted_by,
+                report_count: raw_item.report_count,
+                reports: Vec::new(),
+
+                is_deleted: comment.is_deleted,
+                is_resolvable: comment.is_resolvable,
+                is_deletable: comment.is_deletable,
+                is_bannable: false,
+            }
+        }
+        Some(TargetType::User | TargetType::Tag | TargetType::Role) | None => return Ok(None),
+    };
+
+    if let Some(posted_by) = &item.posted_by {
+        if let Some(account) = fetch_account_by_username(db_pool, &posted_by.username).await? {
+            item.is_bannable = policy::can(user, Action::Admin, Resource::User(account), config);
+        }
+    }
+
+    item.reports = fetch_open_reports(db_pool, &raw_item.target_type, raw_item.target_id)
+        .await?
+        .into_iter()
+        .map(Report::from_raw)
+        .collect();
+
+    Ok(Some(item))
+}
+
+async fn fetch_open_reports(
+    db_pool: &MySqlPool,
+    target_type: &str,
+    target_id: Id,
+) -> sqlx::Result<Vec<RawReport>> {
+    sqlx::query_as!(
+        RawReport,
+        "
+        SELECT
+            reports.id,
+            reports.category,
+            reports.details,
+            users.id AS reported_by_user_id,
+            users.username AS reported_by_username,
+            pfps.variants AS reported_by_mini_pfp,
+            reports.reported_at,
+            reports.is_automatic AS `is_automatic: bool`
+        FROM reports
+            LEFT JOIN users ON reports.reported_by_user_id = users.id
+            LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
+        WHERE
+            reports.target_type = ?
+            AND reports.target_id = ?
+            AND reports.resolved_at IS NULL
+        ORDER BY reports.id ASC
+        ",
+        target_type,
+        target_id
+    )
+    .fetch_all(db_pool)
+    .await
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl ResponseError for Error {}
@@ -0,0 +1,252 @@
+use crate::policy::{self, Action, Resource};
+use crate::session::auth;
+use crate::types::id::Id;
+use crate::types::mod_action::{Kind, ModAction, RawModAction, TargetType};
+use crate::types::page::clamp_limit;
+use crate::State;
+use crate::LIQUID_PARSER;
+use actix_web::{
+    get,
+    http::header,
+    web::{Data, Query},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use lazy_static::lazy_static;
+use liquid::Template;
+use serde::{Deserialize, Serialize};
+use sqlx::mysql::MySqlPool;
+use thiserror::Error;
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub actor: Option<String>,
+    pub start_id: Option<Id>,
+    pub limit: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Serialize)]
+struct FilterOption {
+    value: &'static str,
+    label: &'static str,
+    is_selected: bool,
+}
+
+#[get("/modlog")]
+pub async fn service(
+    req: HttpRequest,
+    state: Data<State>,
+    Query(query): Query<Request>,
+) -> Result<HttpResponse, Error> {
+    let current_user = auth(&state.db_pool, &req).await.ok();
+
+    let is_allowed = match &current_user {
+        Some(current_user) => {
+            policy::can(current_user, Action::View, Resource::ModLog, &state.config)
+        }
+        None => state.config.modlog.is_public,
+    };
+
+    if !is_allowed && current_user.is_none() {
+        let response = HttpResponse::SeeOther()
+            .append_header((header::LOCATION, "/sign-in?back=/modlog"))
+            .finish();
+
+        return Ok(response);
+    }
+
+    if !is_allowed && query.error.is_none() {
+        let response = HttpResponse::SeeOther()
+            .append_header((
+                header::LOCATION,
+                "/modlog?error=You are not allowed to view the moderation log",
+            ))
+            .finish();
+
+        return Ok(response);
+    }
+
+    let can_view_held = current_user
+        .as_ref()
+        .map(|current_user| policy::can(current_user, Action::View, Resource::Held, &state.config))
+        .unwrap_or(false);
+
+    // Unknown or empty filters are ignored, so are those the viewer can't see
+    let kind = query
+        .action
+        .as_deref()
+        .and_then(|action| Kind::from_str(action).ok())
+        .filter(|kind| can_view_held || !kind.is_held());
+    let target_type = query
+        .target_type
+        .as_deref()
+        .and_then(|target_type| TargetType::from_str(target_type).ok());
+    let actor = query
+        .actor
+        .as_deref()
+        .map(str::trim)
+        .filter(|actor| !actor.is_empty());
+
+    let start_id = query.start_id.unwrap_or(i64::MAX as Id);
+    let limit = clamp_limit(query.limit, &state.config.page);
+
+    let actions = if is_allowed {
+        fetch_mod_actions(
+            &state.db_pool,
+            kind.map(|kind| kind.as_str()),
+            target_type.map(|target_type| target_type.as_str()),
+            actor,
+            can_view_held,
+            start_id,
+            limit,
+        )
+        .await?
+        .into_iter()
+        .map(ModAction::from_raw)
+        .collect()
+    } else {
+        Vec::new()
+    };
+
+    let next_start_id = if actions.len() as u64 == limit {
+        actions
+            .last()
+            .map(|action: &ModAction| action.id.saturating_sub(1))
+    } else {
+        None
+    };
+
+    let action_options = Kind::ALL
+        .into_iter()
+        .filter(|option| can_view_held || !option.is_held())
+        .map(|option| FilterOption {
+            value: option.as_str(),
+            label: option.label(),
+            is_selected: Some(option) == kind,
+        })
+        .collect::<Vec<_>>();
+
+    let target_type_options = TargetType::ALL
+        .into_iter()
+        .map(|option| FilterOption {
+            value: option.as_str(),
+            label: option.as_str(),
+            is_selected: Some(option) == target_type,
+        })
+        .collect::<Vec<_>>();
+
+    // Carried over to the next page link
+    let filter_query = format!(
+        "action={}&target_type={}&actor={}",
+        kind.map(|kind| kind.as_str()).unwrap_or_default(),
+        target_type
+            .map(|target_type| target_type.as_str())
+            .unwrap_or_default(),
+        urlencoding::encode(actor.unwrap_or_default()),
+    );
+
+    lazy_static! {
+        static ref TEMPLATE: Template = {
+            let template = include_str!("../../templates/modlog.html");
+
+            LIQUID_PARSER.parse(template).unwrap()
+        };
+    }
+
+    let current_url = urlencoding::encode(&req.uri().to_string()).into_owned();
+
+    let globals = liquid::object!({
+        "current_url": current_url,
+        "current_user": current_user,
+        "is_allowed": is_allowed,
+        "actions": actions,
+        "action_options": action_options,
+        "target_type_options": target_type_options,
+        "actor": actor,
+        "filter_query": filter_query,
+        "next_start_id": next_start_id,
+        "limit": limit,
+        "error": query.error,
+    });
+
+    let s = TEMPLATE.render(&globals).unwrap();
+
+    Ok(HttpResponse::Ok().body(s))
+}
+
+async fn fetch_mod_actions(
+    db_pool: &MySqlPool,
+    action: Option<&str>,
+    target_type: Option<&str>,
+    actor: Option<&str>,
+    can_view_held: bool,
+    start_id: Id,
+    limit: u64,
+) -> sqlx::Result<Vec<RawModAction>> {
+    let held = Kind::ALL
+        .into_iter()
+        .filter(Kind::is_held)
+        .map(|kind| kind.as_str())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    sqlx::query_as!(
+        RawModAction,
+        "
+        SELECT
+            mod_actions.id,
+            users.id AS actor_user_id,
+            users.username AS actor_username,
+            pfps.variants AS actor_mini_pfp,
+            mod_actions.action,
+            mod_actions.target_type,
+            mod_actions.target_id,
+            mod_actions.target_label,
+            comments.post_id AS target_post_id,
+            mod_actions.reason,
+            mod_actions.details,
+            mod_actions.created_at
+        FROM mod_actions
+            LEFT JOIN users ON mod_actions.actor_user_id = users.id
+            LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
+            LEFT JOIN comments ON mod_actions.target_type = 'Comment'
+                AND mod_actions.target_id = comments.id
+        WHERE
+            mod_actions.id <= ?
+            AND (? IS NULL OR mod_actions.action = ?)
+            AND (? IS NULL OR mod_actions.target_type = ?)
+            AND (? IS NULL OR users.username = ?)
+            AND (? OR NOT FIND_IN_SET(mod_actions.action, ?))
+        ORDER BY mod_actions.id DESC
+        LIMIT ?
+        ",
+        start_id,
+        action,
+        action,
+        target_type,
+        target_type,
+        actor,
+        actor,
+        can_view_held,
+        held,
+        limit
+    )
+    .fetch_all(db_pool)
+    .await
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl ResponseError for Error {}
@@ -1,7 +1,10 @@
 use crate::session::auth;
+use crate::types::challenge::{Challenge, ANSWER_MAX_CHAR_COUNT};
+use crate::types::draft::{Draft, RawDraft};
+use crate::types::id::Id;
 use crate::types::post::{
     content::CONTENT_MAX_CHAR_COUNT, description::DESCRIPTION_MAX_CHAR_COUNT,
-    tags::TAGS_MAX_CHAR_COUNT, title::TITLE_MAX_CHAR_COUNT,
+    tags::tags_max_char_count, title::TITLE_MAX_CHAR_COUNT,
 };
 use crate::State;
 use crate::LIQUID_PARSER;
@@ -9,15 +12,24 @@ use actix_web::{
     get,
     http::header,
     web::{Data, Query},
-    HttpRequest, HttpResponse,
+    HttpRequest, HttpResponse, ResponseError,
 };
 use lazy_static::lazy_static;
 use liquid::Template;
 use serde::Deserialize;
+use sqlx::mysql::MySqlPool;
+use thiserror::Error;
 
 #[derive(Debug, Deserialize)]
 pub struct Request {
     pub error: Option<String>,
+    pub draft_id: Option<Id>,
+}
+
+#[derive(Debug, Error, Copy, Clone)]
+pub enum Error {
+    #[error("Server error")]
+    Server,
 }
 
 #[get("/post/create")]
@@ -25,15 +37,44 @@ pub async fn service(
     req: HttpRequest,
     state: Data<State>,
     Query(query): Query<Request>,
-) -> HttpResponse {
+) -> Result<HttpResponse, Error> {
     let Ok(current_user) = auth(&state.db_pool, &req).await else {
         let response = HttpResponse::SeeOther()
             .append_header((header::LOCATION, "/sign-in?back=/post/create"))
             .finish();
 
-        return response;
+        return Ok(response);
+    };
+
+    // Only the owner can open a draft
+    let draft = match query.draft_id {
+        Some(draft_id) => match fetch_draft(&state.db_pool, draft_id, current_user.id).await? {
+            Some(raw_draft) => Some(Draft::from_raw(raw_draft)),
+            None => return Ok(crate::pages::not_found::service().await),
+        },
+        None => None,
     };
 
+    // Filled in with what was sent when the form comes back with an error
+    let flash = state.flash.take(&req);
+
+    let defaults = draft.clone().unwrap_or_default();
+
+    let form = liquid::object!({
+        "title": flash.value("title", defaults.title),
+        "description": flash.value("description", defaults.description),
+        "tags": flash.value("tags", defaults.tags),
+        "content": flash.value("content", defaults.content),
+        "publish_at": flash.value("publish_at", defaults.publish_at_input.unwrap_or_default()),
+    });
+
+    let challenge =
+        if Challenge::is_required_for_post(&state.db_pool, &current_user, &state.config).await? {
+            Some(Challenge::generate())
+        } else {
+            None
+        };
+
     lazy_static! {
         static ref TEMPLATE: Template = {
             let template = include_str!("../../../templates/post/create.html");
@@ -50,11 +91,49 @@ pub async fn service(
         "title_max_char_count": TITLE_MAX_CHAR_COUNT,
         "description_max_char_count": DESCRIPTION_MAX_CHAR_COUNT,
         "content_max_char_count": CONTENT_MAX_CHAR_COUNT,
-        "tags_max_char_count": TAGS_MAX_CHAR_COUNT,
+        "tags_max_char_count": tags_max_char_count(state.config.tags.max_count),
         "error": query.error,
+        "challenge": challenge,
+        "challenge_answer_max_char_count": ANSWER_MAX_CHAR_COUNT,
+        "draft": draft,
+        "form": form,
+        "errors": flash.errors,
     });
 
     let s = TEMPLATE.render(&globals).unwrap();
 
-    HttpResponse::Ok().body(s)
+    Ok(HttpResponse::Ok().body(s))
+}
+
+async fn fetch_draft(db_pool: &MySqlPool, id: Id, user_id: Id) -> sqlx::Result<Option<RawDraft>> {
+    sqlx::query_as!(
+        RawDraft,
+        "
+        SELECT
+            id,
+            title,
+            description,
+            tags,
+            content,
+            publish_at,
+            last_error,
+            updated_at
+        FROM drafts
+        WHERE
+            id = ?
+            AND user_id = ?
+        ",
+        id,
+        user_id
+    )
+    .fetch_optional(db_pool)
+    .await
 }
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl ResponseError for Error {}
@@ -45,7 +45,7 @@ pub async fn service(
         return Ok(response);
     };
 
-    let Some(post) = fetch_contentless_post_by_id(&state.db_pool, post_id, &current_user).await? else {
+    let Some(post) = fetch_contentless_post_by_id(&state.db_pool, post_id, &current_user, &state.config).await? else {
         return Ok(crate::pages::not_found::service().await);
     };
 
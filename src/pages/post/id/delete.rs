@@ -1,6 +1,7 @@
 use crate::db::fetch_contentless_post_by_id;
 use crate::session::auth;
 use crate::types::id::Id;
+use crate::types::mod_action::reason::REASON_MAX_CHAR_COUNT;
 use crate::types::post::title::TITLE_MAX_CHAR_COUNT;
 use crate::State;
 use crate::LIQUID_PARSER;
@@ -45,7 +46,7 @@ pub async fn service(
         return Ok(response);
     };
 
-    let Some(post) = fetch_contentless_post_by_id(&state.db_pool, post_id, &current_user).await? else {
+    let Some(post) = fetch_contentless_post_by_id(&state.db_pool, post_id, &current_user, &state.config).await? else {
         return Ok(crate::pages::not_found::service().await);
     };
 
@@ -75,6 +76,7 @@ pub async fn service(
         "current_user": current_user,
         "post": post,
         "title_max_char_count": TITLE_MAX_CHAR_COUNT,
+        "reason_max_char_count": REASON_MAX_CHAR_COUNT,
         "error": query.error,
     });
 
@@ -1,9 +1,10 @@
+use crate::config::Config;
 use crate::db::fetch_post_from_raw;
 use crate::session::{auth, User};
 use crate::types::id::Id;
 use crate::types::post::{
     content::CONTENT_MAX_CHAR_COUNT, description::DESCRIPTION_MAX_CHAR_COUNT,
-    tags::TAGS_MAX_CHAR_COUNT, title::TITLE_MAX_CHAR_COUNT,
+    tags::tags_max_char_count, title::TITLE_MAX_CHAR_COUNT,
 };
 use crate::types::post::{Post, RawPost};
 use crate::State;
@@ -50,7 +51,9 @@ pub async fn service(
         return Ok(response);
     };
 
-    let Some(post) = fetch_post_by_id(&state.db_pool, post_id, &current_user).await? else {
+    let Some(post) =
+        fetch_post_by_id(&state.db_pool, post_id, &current_user, &state.config).await?
+    else {
         return Ok(crate::pages::not_found::service().await);
     };
 
@@ -72,16 +75,28 @@ pub async fn service(
         };
     }
 
+    // Filled in with what was sent when the form comes back with an error
+    let flash = state.flash.take(&req);
+
+    let form = liquid::object!({
+        "title": flash.value("title", post.title.clone()),
+        "description": flash.value("description", post.description.clone()),
+        "tags": flash.value("tags", post.tags.join(" ")),
+        "content": flash.value("content", post.content.clone()),
+    });
+
     let current_url = urlencoding::encode(&req.uri().to_string()).into_owned();
 
     let globals = liquid::object!({
         "current_url": current_url,
         "current_user": current_user,
         "post": post,
+        "form": form,
+        "errors": flash.errors,
         "title_max_char_count": TITLE_MAX_CHAR_COUNT,
         "description_max_char_count": DESCRIPTION_MAX_CHAR_COUNT,
         "content_max_char_count": CONTENT_MAX_CHAR_COUNT,
-        "tags_max_char_count": TAGS_MAX_CHAR_COUNT,
+        "tags_max_char_count": tags_max_char_count(state.config.tags.max_count),
         "error": query.error,
     });
 
@@ -94,12 +109,13 @@ async fn fetch_post_by_id(
     db_pool: &MySqlPool,
     post_id: Id,
     user: &User,
+    config: &Config,
 ) -> sqlx::Result<Option<Post>> {
     let Some(raw_post) = fetch_raw_post_by_id(db_pool, post_id).await? else {
         return Ok(None);
     };
 
-    let post = fetch_post_from_raw(db_pool, raw_post, user).await?;
+    let post = fetch_post_from_raw(db_pool, raw_post, user, config).await?;
 
     Ok(Some(post))
 }
@@ -115,12 +131,23 @@ async fn fetch_raw_post_by_id(db_pool: &MySqlPool, post_id: Id) -> sqlx::Result<
             posts.content,
             users.id AS posted_by_user_id,
             users.username AS posted_by_username,
-            CONCAT(files.id, \".\", files.extension) AS posted_by_mini_pfp,
+            pfps.variants AS posted_by_mini_pfp,
             posts.posted_at,
-            posts.modified_at
+            posts.modified_at,
+            posts.deleted_at,
+            posts.is_pending AS `is_pending: bool`,
+            posts.is_shadowed AS `is_shadowed: bool`,
+            (
+                SELECT COUNT(*)
+                FROM reports
+                WHERE
+                    reports.target_type = 'Post'
+                    AND reports.target_id = posts.id
+                    AND reports.resolved_at IS NULL
+            ) AS `open_report_count!`
         FROM posts
             LEFT JOIN users ON posts.posted_by_user_id = users.id
-            LEFT JOIN files ON users.mini_pfp_file_id = files.id
+            LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
         WHERE
             posts.id = ?
         ",
@@ -0,0 +1,67 @@
+use crate::feed::{self, Feed, Format};
+use crate::pages::post::id::{fetch_comments_backwards, fetch_post_by_id};
+use crate::types::id::Id;
+use crate::types::page::clamp_limit;
+use crate::State;
+use actix_web::{
+    get,
+    web::{Data, Path},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use thiserror::Error;
+
+#[derive(Debug, Copy, Clone, Error)]
+pub enum Error {
+    #[error("Server error")]
+    Server,
+}
+
+#[get("/post/{id}/feed.atom")]
+pub async fn service(
+    req: HttpRequest,
+    state: Data<State>,
+    path: Path<Id>,
+) -> Result<HttpResponse, Error> {
+    let post_id = path.into_inner();
+
+    let Some(post) = fetch_post_by_id(&state.db_pool, post_id, None, &state.config).await? else {
+        return Ok(crate::pages::not_found::service().await);
+    };
+
+    if !post.is_visible || post.is_deleted {
+        return Ok(crate::pages::not_found::service().await);
+    }
+
+    let domain = &state.config.domain;
+    let limit = clamp_limit(None, &state.config.page);
+
+    // The newest comments, newest first
+    let mut comments = fetch_comments_backwards(
+        &state.db_pool,
+        post.id,
+        i64::MAX as Id,
+        limit,
+        None,
+        &state.config,
+    )
+    .await?;
+
+    comments.reverse();
+
+    let feed = Feed {
+        title: format!("Comments on {}", post.title),
+        url: format!("https://{domain}/post/{}", post.id),
+        self_url: format!("https://{domain}/post/{}/feed.atom", post.id),
+        entries: feed::comment_entries(&state.db_pool, domain, &post.title, comments).await?,
+    };
+
+    Ok(feed::respond(&req, feed, Format::Atom))
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl ResponseError for Error {}
@@ -0,0 +1,111 @@
+use crate::pages::post::id::fetch_post_by_id;
+use crate::session::auth;
+use crate::types::id::Id;
+use crate::types::revision::{RawRevision, Revision};
+use crate::State;
+use crate::LIQUID_PARSER;
+use actix_web::{
+    get,
+    web::{Data, Path},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use lazy_static::lazy_static;
+use liquid::Template;
+use sqlx::mysql::MySqlPool;
+use thiserror::Error;
+
+#[derive(Debug, Copy, Clone, Error)]
+pub enum Error {
+    #[error("Server error")]
+    Server,
+}
+
+#[get("/post/{id}/history")]
+pub async fn service(
+    req: HttpRequest,
+    state: Data<State>,
+    path: Path<Id>,
+) -> Result<HttpResponse, Error> {
+    let current_user = auth(&state.db_pool, &req).await.ok();
+
+    let post_id = path.into_inner();
+
+    let Some(post) = fetch_post_by_id(
+        &state.db_pool,
+        post_id,
+        current_user.as_ref(),
+        &state.config,
+    )
+    .await?
+    else {
+        return Ok(crate::pages::not_found::service().await);
+    };
+
+    // Only those who can restore a deleted post get to see what it was
+    if !post.is_visible || (post.is_deleted && !post.is_restorable) {
+        return Ok(crate::pages::not_found::service().await);
+    }
+
+    // Mods always see who edited, everyone else only while the post isn't anonymised
+    let are_editors_visible = post.posted_by.is_some() || post.is_resolvable;
+
+    let raw_revisions = fetch_raw_revisions(&state.db_pool, post.id).await?;
+    let revisions = Revision::history(raw_revisions, are_editors_visible);
+
+    lazy_static! {
+        static ref TEMPLATE: Template = {
+            let template = include_str!("../../../../templates/post/id/history.html");
+
+            LIQUID_PARSER.parse(template).unwrap()
+        };
+    }
+
+    let current_url = urlencoding::encode(&req.uri().to_string()).into_owned();
+
+    let globals = liquid::object!({
+        "current_url": current_url,
+        "current_user": current_user,
+        "post": post,
+        "revisions": revisions,
+    });
+
+    let s = TEMPLATE.render(&globals).unwrap();
+
+    Ok(HttpResponse::Ok().body(s))
+}
+
+async fn fetch_raw_revisions(db_pool: &MySqlPool, post_id: Id) -> sqlx::Result<Vec<RawRevision>> {
+    sqlx::query_as!(
+        RawRevision,
+        "
+        SELECT
+            post_revisions.id,
+            CONCAT(
+                post_revisions.title, '\\n\\n',
+                post_revisions.description, '\\n\\n',
+                post_revisions.content
+            ) AS `text!`,
+            users.id AS edited_by_user_id,
+            users.username AS edited_by_username,
+            pfps.variants AS edited_by_mini_pfp,
+            post_revisions.created_at
+        FROM post_revisions
+            LEFT JOIN users ON post_revisions.edited_by_user_id = users.id
+            LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
+        WHERE
+            post_revisions.post_id = ?
+        ORDER BY post_revisions.id ASC
+        ",
+        post_id
+    )
+    .fetch_all(db_pool)
+    .await
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl ResponseError for Error {}
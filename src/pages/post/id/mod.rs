@@ -1,8 +1,11 @@
-use crate::db::fetch_post_from_raw;
+use crate::config::Config;
+use crate::db::{fetch_post_from_raw, fetch_user_by_username};
+use crate::policy::Viewer;
 use crate::session::auth;
 use crate::session::User;
 use crate::types::comment::{Comment, RawComment};
 use crate::types::id::Id;
+use crate::types::meta::Meta;
 use crate::types::page::{clamp_limit, Direction};
 use crate::types::post::{Post, RawPost};
 use crate::State;
@@ -21,6 +24,10 @@ use thiserror::Error;
 pub mod anonymise;
 pub mod delete;
 pub mod edit;
+pub mod feed;
+pub mod history;
+pub mod report;
+pub mod restore;
 
 #[derive(Debug, Deserialize)]
 pub struct Request {
@@ -48,7 +55,7 @@ pub async fn service(
 
     let direction = query.direction.unwrap_or_default();
     let start_id = query.start_id.unwrap_or(0);
-    let limit = clamp_limit(query.limit);
+    let limit = clamp_limit(query.limit, &state.config.page);
 
     lazy_static! {
         static ref TEMPLATE: Template = {
@@ -58,13 +65,41 @@ pub async fn service(
         };
     }
 
+    let viewer = Viewer::of(current_user.as_ref(), &state.config);
+
     // TODO: Make all requests concurrent
-    let (min_id, max_id) = fetch_min_max_comment_id(&state.db_pool, post_id).await?;
+    let (min_id, max_id) = fetch_min_max_comment_id(&state.db_pool, post_id, viewer).await?;
 
-    let Some(post) = fetch_post_by_id(&state.db_pool, post_id, current_user.as_ref()).await? else {
+    let Some(post) = fetch_post_by_id(
+        &state.db_pool,
+        post_id,
+        current_user.as_ref(),
+        &state.config,
+    )
+    .await?
+    else {
         return Ok(crate::pages::not_found::service().await);
     };
 
+    if !post.is_visible {
+        return Ok(crate::pages::not_found::service().await);
+    }
+
+    let author = match &post.posted_by {
+        Some(posted_by) => {
+            fetch_user_by_username(
+                &state.db_pool,
+                &posted_by.username,
+                current_user.as_ref(),
+                &state.config,
+            )
+            .await?
+        }
+        None => None,
+    };
+
+    let meta = Meta::post(&state.config.domain, &post, author.as_ref());
+
     let comments = match direction {
         Direction::Backwards => {
             fetch_comments_backwards(
@@ -73,6 +108,7 @@ pub async fn service(
                 start_id,
                 limit,
                 current_user.as_ref(),
+                &state.config,
             )
             .await?
         }
@@ -83,6 +119,7 @@ pub async fn service(
                 start_id,
                 limit,
                 current_user.as_ref(),
+                &state.config,
             )
             .await?
         }
@@ -113,6 +150,7 @@ pub async fn service(
         "current_url": current_url,
         "current_user": current_user,
         "post": post,
+        "meta": meta,
         "comments": comments,
         "min_id": min_id,
         "prev_start_id": prev_start_id,
@@ -126,16 +164,17 @@ pub async fn service(
     Ok(HttpResponse::Ok().body(s))
 }
 
-async fn fetch_post_by_id(
+pub async fn fetch_post_by_id(
     db_pool: &MySqlPool,
     post_id: Id,
     user: Option<&User>,
+    config: &Config,
 ) -> sqlx::Result<Option<Post>> {
     let Some(raw_post) = fetch_raw_post_by_id(db_pool, post_id).await? else {
         return Ok(None);
     };
 
-    let post = fetch_post_from_raw(db_pool, raw_post, user).await?;
+    let post = fetch_post_from_raw(db_pool, raw_post, user, config).await?;
 
     Ok(Some(post))
 }
@@ -151,12 +190,23 @@ async fn fetch_raw_post_by_id(db_pool: &MySqlPool, id: Id) -> sqlx::Result<Optio
             posts.markdown_content AS content,
             users.id AS posted_by_user_id,
             users.username AS posted_by_username,
-            CONCAT(files.id, \".\", files.extension) AS posted_by_mini_pfp,
+            pfps.variants AS posted_by_mini_pfp,
             posts.posted_at,
-            posts.modified_at
+            posts.modified_at,
+            posts.deleted_at,
+            posts.is_pending AS `is_pending: bool`,
+            posts.is_shadowed AS `is_shadowed: bool`,
+            (
+                SELECT COUNT(*)
+                FROM reports
+                WHERE
+                    reports.target_type = 'Post'
+                    AND reports.target_id = posts.id
+                    AND reports.resolved_at IS NULL
+            ) AS `open_report_count!`
         FROM posts
             LEFT JOIN users ON posts.posted_by_user_id = users.id
-            LEFT JOIN files ON users.mini_pfp_file_id = files.id
+            LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
         WHERE
             posts.id = ?
         ",
@@ -166,13 +216,16 @@ async fn fetch_raw_post_by_id(db_pool: &MySqlPool, id: Id) -> sqlx::Result<Optio
     .await
 }
 
-async fn fetch_comments_forwards(
+pub async fn fetch_comments_forwards(
     db_pool: &MySqlPool,
     post_id: Id,
     start_id: Id,
     limit: u64,
     user: Option<&User>,
+    config: &Config,
 ) -> sqlx::Result<Vec<Comment>> {
+    let viewer = Viewer::of(user, config);
+
     sqlx::query_as!(
         RawComment,
         "
@@ -182,39 +235,54 @@ async fn fetch_comments_forwards(
             comments.reply_to_comment_id,
             users_reply_to.id AS reply_to_user_id,
             users_reply_to.username AS reply_to_username,
-            CONCAT(files_reply_to.id, \".\", files_reply_to.extension) AS reply_to_mini_pfp,
+            pfps_reply_to.variants AS reply_to_mini_pfp,
             comments.markdown_content AS content,
             users_posted_by.id AS posted_by_user_id,
             users_posted_by.username AS posted_by_username,
-            CONCAT(files_posted_by.id, \".\", files_posted_by.extension) AS posted_by_mini_pfp,
+            pfps_posted_by.variants AS posted_by_mini_pfp,
             comments.posted_at,
-            comments.modified_at
+            comments.modified_at,
+            comments.deleted_at,
+            (
+                SELECT COUNT(*)
+                FROM reports
+                WHERE
+                    reports.target_type = 'Comment'
+                    AND reports.target_id = comments.id
+                    AND reports.resolved_at IS NULL
+            ) AS `open_report_count!`
         FROM comments
             LEFT JOIN comments AS comments_reply_to ON comments.reply_to_comment_id = comments_reply_to.id
             LEFT JOIN users AS users_reply_to ON comments_reply_to.posted_by_user_id = users_reply_to.id
-            LEFT JOIN files AS files_reply_to ON users_reply_to.mini_pfp_file_id = files_reply_to.id
+            LEFT JOIN user_pfps AS pfps_reply_to ON users_reply_to.id = pfps_reply_to.user_id AND pfps_reply_to.size = 'mini'
             LEFT JOIN users AS users_posted_by ON comments.posted_by_user_id = users_posted_by.id
-            LEFT JOIN files AS files_posted_by ON users_posted_by.mini_pfp_file_id = files_posted_by.id
+            LEFT JOIN user_pfps AS pfps_posted_by ON users_posted_by.id = pfps_posted_by.user_id AND pfps_posted_by.size = 'mini'
         WHERE
             comments.post_id = ?
             AND comments.id >= ?
+            AND (comments.is_shadowed = 0 OR comments.posted_by_user_id = ? OR ?)
         ORDER BY comments.posted_at ASC
         LIMIT ?
         ",
         post_id,
         start_id,
+        viewer.user_id,
+        viewer.can_view_held,
         limit
     )
-    .fetch_all(db_pool).await.map(|result| result.into_iter().map(|raw_comment| Comment::from_raw(raw_comment, user)).collect())
+    .fetch_all(db_pool).await.map(|result| result.into_iter().map(|raw_comment| Comment::from_raw(raw_comment, user, config)).collect())
 }
 
-async fn fetch_comments_backwards(
+pub async fn fetch_comments_backwards(
     db_pool: &MySqlPool,
     post_id: Id,
     start_id: Id,
     limit: u64,
     user: Option<&User>,
+    config: &Config,
 ) -> sqlx::Result<Vec<Comment>> {
+    let viewer = Viewer::of(user, config);
+
     sqlx::query_as!(
         RawComment,
         "
@@ -224,35 +292,48 @@ async fn fetch_comments_backwards(
             comments.reply_to_comment_id,
             users_reply_to.id AS reply_to_user_id,
             users_reply_to.username AS reply_to_username,
-            CONCAT(files_reply_to.id, \".\", files_reply_to.extension) AS reply_to_mini_pfp,
+            pfps_reply_to.variants AS reply_to_mini_pfp,
             comments.markdown_content AS content,
             users_posted_by.id AS posted_by_user_id,
             users_posted_by.username AS posted_by_username,
-            CONCAT(files_posted_by.id, \".\", files_posted_by.extension) AS posted_by_mini_pfp,
+            pfps_posted_by.variants AS posted_by_mini_pfp,
             comments.posted_at,
-            comments.modified_at
+            comments.modified_at,
+            comments.deleted_at,
+            (
+                SELECT COUNT(*)
+                FROM reports
+                WHERE
+                    reports.target_type = 'Comment'
+                    AND reports.target_id = comments.id
+                    AND reports.resolved_at IS NULL
+            ) AS `open_report_count!`
         FROM comments
             LEFT JOIN comments AS comments_reply_to ON comments.reply_to_comment_id = comments_reply_to.id
             LEFT JOIN users AS users_reply_to ON comments_reply_to.posted_by_user_id = users_reply_to.id
-            LEFT JOIN files AS files_reply_to ON users_reply_to.mini_pfp_file_id = files_reply_to.id
+            LEFT JOIN user_pfps AS pfps_reply_to ON users_reply_to.id = pfps_reply_to.user_id AND pfps_reply_to.size = 'mini'
             LEFT JOIN users AS users_posted_by ON comments.posted_by_user_id = users_posted_by.id
-            LEFT JOIN files AS files_posted_by ON users_posted_by.mini_pfp_file_id = files_posted_by.id
+            LEFT JOIN user_pfps AS pfps_posted_by ON users_posted_by.id = pfps_posted_by.user_id AND pfps_posted_by.size = 'mini'
         WHERE
             comments.post_id = ?
             AND comments.id <= ?
+            AND (comments.is_shadowed = 0 OR comments.posted_by_user_id = ? OR ?)
         ORDER BY comments.posted_at DESC
         LIMIT ?
         ",
         post_id,
         start_id,
+        viewer.user_id,
+        viewer.can_view_held,
         limit
     )
-    .fetch_all(db_pool).await.map(|result| result.into_iter().rev().map(|raw_comment| Comment::from_raw(raw_comment, user)).collect())
+    .fetch_all(db_pool).await.map(|result| result.into_iter().rev().map(|raw_comment| Comment::from_raw(raw_comment, user, config)).collect())
 }
 
 async fn fetch_min_max_comment_id(
     db_pool: &MySqlPool,
     post_id: Id,
+    viewer: Viewer,
 ) -> sqlx::Result<(Option<Id>, Option<Id>)> {
     #[derive(Debug)]
     struct MinMax {
@@ -269,8 +350,11 @@ async fn fetch_min_max_comment_id(
         FROM comments
         WHERE
             post_id = ?
+            AND (is_shadowed = 0 OR posted_by_user_id = ? OR ?)
         ",
-        post_id
+        post_id,
+        viewer.user_id,
+        viewer.can_view_held
     )
     .fetch_one(db_pool)
     .await
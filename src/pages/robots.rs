@@ -0,0 +1,36 @@
+use crate::State;
+use actix_web::{get, web::Data, HttpResponse};
+
+// Everything that's only a form, a moderation tool or an account page is kept out
+const DISALLOWED: &[&str] = &[
+    "/api/",
+    "/admin",
+    "/mod/",
+    "/comment/",
+    "/post/create",
+    "/sign-in",
+    "/sign-up",
+    "/sign-out",
+    "/switch-theme",
+    "/password-reset",
+    "/password-change",
+    "/email-verification",
+];
+
+#[get("/robots.txt")]
+pub async fn service(state: Data<State>) -> HttpResponse {
+    let mut s = String::from("User-agent: *\n");
+
+    for path in DISALLOWED {
+        s.push_str(&format!("Disallow: {path}\n"));
+    }
+
+    s.push_str(&format!(
+        "\nSitemap: https://{}/sitemap.xml\n",
+        state.config.domain
+    ));
+
+    HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .body(s)
+}
@@ -1,4 +1,5 @@
 use crate::session::auth;
+use crate::types::challenge::{Challenge, ANSWER_MAX_CHAR_COUNT};
 use crate::types::user::{
     email::EMAIL_MAX_CHAR_COUNT, password::PASSWORD_MAX_CHAR_COUNT,
     username::USERNAME_MAX_CHAR_COUNT,
@@ -27,6 +28,12 @@ pub async fn service(
 ) -> HttpResponse {
     let current_user = auth(&state.db_pool, &req).await.ok();
 
+    let challenge = if state.config.challenge.on_sign_up {
+        Some(Challenge::generate())
+    } else {
+        None
+    };
+
     lazy_static! {
         static ref TEMPLATE: Template = {
             let template = include_str!("../../templates/sign-up.html");
@@ -44,6 +51,8 @@ pub async fn service(
         "username_max_char_count": USERNAME_MAX_CHAR_COUNT,
         "email_max_char_count": EMAIL_MAX_CHAR_COUNT,
         "password_max_char_count": PASSWORD_MAX_CHAR_COUNT,
+        "challenge": challenge,
+        "challenge_answer_max_char_count": ANSWER_MAX_CHAR_COUNT,
     });
 
     let s = TEMPLATE.render(&globals).unwrap();
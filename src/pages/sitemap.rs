@@ -0,0 +1,296 @@
+use crate::State;
+use crate::LIQUID_PARSER;
+use actix_web::{
+    get,
+    web::{Data, Path},
+    HttpResponse, ResponseError,
+};
+use chrono::NaiveDateTime;
+use lazy_static::lazy_static;
+use liquid::Template;
+use serde::{Deserialize, Serialize};
+use sqlx::mysql::MySqlPool;
+use thiserror::Error;
+
+// URLs per sitemap, the protocol allows up to 50 000
+const URLS_PER_SITEMAP: u64 = 10_000;
+
+const CONTENT_TYPE: &str = "application/xml; charset=utf-8";
+
+#[derive(Debug, Copy, Clone, Error)]
+pub enum Error {
+    #[error("Server error")]
+    Server,
+}
+
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Section {
+    Posts,
+    Tags,
+    Users,
+}
+
+impl Section {
+    pub const ALL: [Self; 3] = [Self::Posts, Self::Tags, Self::Users];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Posts => "posts",
+            Self::Tags => "tags",
+            Self::Users => "users",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Url {
+    loc: String,
+    lastmod: Option<String>,
+}
+
+#[derive(Debug)]
+struct RawUrl {
+    path: String,
+    lastmod: Option<NaiveDateTime>,
+}
+
+// Only what anyone can see is listed: no deleted, pending or shadowed posts,
+// tags without such posts or shadow-banned users
+#[get("/sitemap.xml")]
+pub async fn index(state: Data<State>) -> Result<HttpResponse, Error> {
+    lazy_static! {
+        static ref TEMPLATE: Template = {
+            let template = include_str!("../../templates/sitemap/index.xml");
+
+            LIQUID_PARSER.parse(template).unwrap()
+        };
+    }
+
+    let domain = &state.config.domain;
+
+    let mut sitemaps = Vec::new();
+
+    for section in Section::ALL {
+        let count = fetch_count(&state.db_pool, section).await?;
+        let page_count = (count as u64).div_ceil(URLS_PER_SITEMAP);
+
+        for page in 1..=page_count {
+            sitemaps.push(format!(
+                "https://{domain}/sitemap/{}/{page}.xml",
+                section.as_str()
+            ));
+        }
+    }
+
+    let globals = liquid::object!({
+        "sitemaps": sitemaps,
+    });
+
+    let s = TEMPLATE.render(&globals).unwrap();
+
+    Ok(HttpResponse::Ok().content_type(CONTENT_TYPE).body(s))
+}
+
+#[get("/sitemap/{section}/{page}.xml")]
+pub async fn service(
+    state: Data<State>,
+    path: Path<(Section, u64)>,
+) -> Result<HttpResponse, Error> {
+    let (section, page) = path.into_inner();
+
+    lazy_static! {
+        static ref TEMPLATE: Template = {
+            let template = include_str!("../../templates/sitemap/urlset.xml");
+
+            LIQUID_PARSER.parse(template).unwrap()
+        };
+    }
+
+    if page == 0 {
+        return Ok(crate::pages::not_found::service().await);
+    }
+
+    let offset = (page - 1) * URLS_PER_SITEMAP;
+
+    let raw_urls = match section {
+        Section::Posts => fetch_post_urls(&state.db_pool, offset).await?,
+        Section::Tags => fetch_tag_urls(&state.db_pool, offset).await?,
+        Section::Users => fetch_user_urls(&state.db_pool, offset).await?,
+    };
+
+    if raw_urls.is_empty() {
+        return Ok(crate::pages::not_found::service().await);
+    }
+
+    let domain = &state.config.domain;
+
+    let urls: Vec<_> = raw_urls
+        .into_iter()
+        .map(|raw_url| Url {
+            loc: format!("https://{domain}{}", raw_url.path),
+            lastmod: raw_url
+                .lastmod
+                .map(|lastmod| lastmod.format("%Y-%m-%dT%H:%M:%S+00:00").to_string()),
+        })
+        .collect();
+
+    let globals = liquid::object!({
+        "urls": urls,
+    });
+
+    let s = TEMPLATE.render(&globals).unwrap();
+
+    Ok(HttpResponse::Ok().content_type(CONTENT_TYPE).body(s))
+}
+
+async fn fetch_count(db_pool: &MySqlPool, section: Section) -> sqlx::Result<i64> {
+    #[derive(Debug)]
+    struct Count {
+        count: i64,
+    }
+
+    let count = match section {
+        Section::Posts => {
+            sqlx::query_as!(
+                Count,
+                "
+                SELECT
+                    COUNT(*) AS `count!`
+                FROM posts
+                WHERE
+                    deleted_at IS NULL
+                    AND is_pending = 0
+                    AND is_shadowed = 0
+                "
+            )
+            .fetch_one(db_pool)
+            .await?
+        }
+        Section::Tags => {
+            sqlx::query_as!(
+                Count,
+                "
+                SELECT
+                    COUNT(DISTINCT post_tags.tag_id) AS `count!`
+                FROM post_tags
+                    JOIN posts ON post_tags.post_id = posts.id
+                WHERE
+                    posts.deleted_at IS NULL
+                    AND posts.is_pending = 0
+                    AND posts.is_shadowed = 0
+                "
+            )
+            .fetch_one(db_pool)
+            .await?
+        }
+        Section::Users => {
+            sqlx::query_as!(
+                Count,
+                "
+                SELECT
+                    COUNT(*) AS `count!`
+                FROM users
+                WHERE
+                    is_shadow_banned = 0
+                "
+            )
+            .fetch_one(db_pool)
+            .await?
+        }
+    };
+
+    Ok(count.count)
+}
+
+async fn fetch_post_urls(db_pool: &MySqlPool, offset: u64) -> sqlx::Result<Vec<RawUrl>> {
+    sqlx::query_as!(
+        RawUrl,
+        "
+        SELECT
+            CONCAT('/post/', id) AS `path!`,
+            COALESCE(modified_at, posted_at) AS lastmod
+        FROM posts
+        WHERE
+            deleted_at IS NULL
+            AND is_pending = 0
+            AND is_shadowed = 0
+        ORDER BY id
+        LIMIT ?
+        OFFSET ?
+        ",
+        URLS_PER_SITEMAP,
+        offset
+    )
+    .fetch_all(db_pool)
+    .await
+}
+
+// A tag last changed when its newest post was made
+async fn fetch_tag_urls(db_pool: &MySqlPool, offset: u64) -> sqlx::Result<Vec<RawUrl>> {
+    sqlx::query_as!(
+        RawUrl,
+        "
+        SELECT
+            CONCAT('/tag/', tags.name) AS `path!`,
+            MAX(posts.posted_at) AS lastmod
+        FROM tags
+            JOIN post_tags ON tags.id = post_tags.tag_id
+            JOIN posts ON post_tags.post_id = posts.id
+        WHERE
+            posts.deleted_at IS NULL
+            AND posts.is_pending = 0
+            AND posts.is_shadowed = 0
+        GROUP BY tags.id
+        ORDER BY tags.id
+        LIMIT ?
+        OFFSET ?
+        ",
+        URLS_PER_SITEMAP,
+        offset
+    )
+    .fetch_all(db_pool)
+    .await
+}
+
+async fn fetch_user_urls(db_pool: &MySqlPool, offset: u64) -> sqlx::Result<Vec<RawUrl>> {
+    #[derive(Debug)]
+    struct Username {
+        username: String,
+    }
+
+    sqlx::query_as!(
+        Username,
+        "
+        SELECT
+            username
+        FROM users
+        WHERE
+            is_shadow_banned = 0
+        ORDER BY id
+        LIMIT ?
+        OFFSET ?
+        ",
+        URLS_PER_SITEMAP,
+        offset
+    )
+    .fetch_all(db_pool)
+    .await
+    .map(|result| {
+        result
+            .into_iter()
+            .map(|user| RawUrl {
+                path: format!("/user/{}", user.username),
+                lastmod: None,
+            })
+            .collect()
+    })
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl ResponseError for Error {}
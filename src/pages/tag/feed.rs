@@ -0,0 +1,53 @@
+use crate::feed::{self, Feed, Format};
+use crate::pages::index::fetch_posts_from_raw;
+use crate::pages::tag::tag::fetch_raw_posts_forwards;
+use crate::policy::Viewer;
+use crate::types::id::Id;
+use crate::types::page::clamp_limit;
+use crate::State;
+use actix_web::{
+    get,
+    web::{Data, Path},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use thiserror::Error;
+
+#[derive(Debug, Copy, Clone, Error)]
+pub enum Error {
+    #[error("Server error")]
+    Server,
+}
+
+#[get("/tag/{tag}/feed.atom")]
+pub async fn service(
+    req: HttpRequest,
+    state: Data<State>,
+    path: Path<String>,
+) -> Result<HttpResponse, Error> {
+    let tag = path.into_inner();
+
+    let domain = &state.config.domain;
+    let limit = clamp_limit(None, &state.config.page);
+    let viewer = Viewer::of(None, &state.config);
+
+    let raw_post_previews =
+        fetch_raw_posts_forwards(&state.db_pool, &tag, i64::MAX as Id, limit, viewer).await?;
+    let posts = fetch_posts_from_raw(&state.db_pool, raw_post_previews).await?;
+
+    let feed = Feed {
+        title: format!("{tag} · neor"),
+        url: format!("https://{domain}/tag/{tag}"),
+        self_url: format!("https://{domain}/tag/{tag}/feed.atom"),
+        entries: feed::post_entries(&state.db_pool, domain, posts).await?,
+    };
+
+    Ok(feed::respond(&req, feed, Format::Atom))
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl ResponseError for Error {}
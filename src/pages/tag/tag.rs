@@ -1,6 +1,9 @@
-use crate::db::fetch_post_preview_from_raw;
+use crate::db::fetch_post_previews_from_raw;
+use crate::policy::{self, Action, Resource, Viewer};
 use crate::session::auth;
 use crate::types::id::Id;
+use crate::types::meta::Meta;
+use crate::types::mod_action::reason::REASON_MAX_CHAR_COUNT;
 use crate::types::page::{clamp_limit, Direction};
 use crate::types::post::{Preview as PostPreview, RawPreview as RawPostPreview};
 use crate::State;
@@ -27,6 +30,7 @@ pub struct Request {
     pub direction: Option<Direction>,
     pub start_id: Option<Id>,
     pub limit: Option<u64>,
+    pub error: Option<String>,
 }
 
 #[get("/tag/{tag}")]
@@ -42,7 +46,7 @@ pub async fn service(
 
     let direction = query.direction.unwrap_or_default();
     let start_id = query.start_id.unwrap_or(i64::MAX as Id);
-    let limit = clamp_limit(query.limit);
+    let limit = clamp_limit(query.limit, &state.config.page);
 
     lazy_static! {
         static ref TEMPLATE: Template = {
@@ -52,24 +56,37 @@ pub async fn service(
         };
     }
 
-    let (min_id, max_id) = fetch_min_max_post_id(&state.db_pool, &tag).await?;
+    let viewer = Viewer::of(current_user.as_ref(), &state.config);
+
+    let (min_id, max_id) = fetch_min_max_post_id(&state.db_pool, &tag, viewer).await?;
 
     let raw_post_previews = match direction {
         Direction::Backwards => {
             let mut raw_post_previews =
-                fetch_raw_posts_backwards(&state.db_pool, &tag, start_id, limit).await?;
+                fetch_raw_posts_backwards(&state.db_pool, &tag, start_id, limit, viewer).await?;
 
             raw_post_previews.reverse();
 
             raw_post_previews
         }
         Direction::Forwards => {
-            fetch_raw_posts_forwards(&state.db_pool, &tag, start_id, limit).await?
+            fetch_raw_posts_forwards(&state.db_pool, &tag, start_id, limit, viewer).await?
         }
     };
 
     let posts = fetch_posts_from_raw(&state.db_pool, raw_post_previews).await?;
 
+    let is_adminable = current_user
+        .as_ref()
+        .map(|user| policy::can(user, Action::Admin, Resource::Site, &state.config))
+        .unwrap_or(false);
+
+    let requires_approval = if is_adminable {
+        fetch_tag_requires_approval(&state.db_pool, &tag).await?
+    } else {
+        false
+    };
+
     let prev_start_id = posts
         .first()
         .map(|post| post.id)
@@ -93,6 +110,7 @@ pub async fn service(
     let globals = liquid::object!({
         "current_url": current_url,
         "current_user": current_user,
+        "meta": Meta::tag(&state.config.domain, &tag),
         "tag": tag,
         "posts": posts,
         "min_id": min_id,
@@ -100,6 +118,10 @@ pub async fn service(
         "prev_start_id": prev_start_id,
         "next_start_id": next_start_id,
         "limit": limit,
+        "is_adminable": is_adminable,
+        "requires_approval": requires_approval,
+        "reason_max_char_count": REASON_MAX_CHAR_COUNT,
+        "error": query.error,
     });
 
     let s = TEMPLATE.render(&globals).unwrap();
@@ -111,22 +133,15 @@ async fn fetch_posts_from_raw(
     db_pool: &MySqlPool,
     raw_post_previews: Vec<RawPostPreview>,
 ) -> sqlx::Result<Vec<PostPreview>> {
-    let mut post_previews = Vec::new();
-
-    for raw_post_preview in raw_post_previews {
-        let post_preview = fetch_post_preview_from_raw(db_pool, raw_post_preview).await?;
-
-        post_previews.push(post_preview);
-    }
-
-    Ok(post_previews)
+    fetch_post_previews_from_raw(db_pool, raw_post_previews).await
 }
 
-async fn fetch_raw_posts_forwards(
+pub async fn fetch_raw_posts_forwards(
     db_pool: &MySqlPool,
     tag: &str,
     start_id: Id,
     limit: u64,
+    viewer: Viewer,
 ) -> sqlx::Result<Vec<RawPostPreview>> {
     sqlx::query_as!(
         RawPostPreview,
@@ -137,21 +152,28 @@ async fn fetch_raw_posts_forwards(
             posts.description,
             users.id AS posted_by_user_id,
             users.username AS posted_by_username,
-            CONCAT(files.id, \".\", files.extension) AS posted_by_mini_pfp,
-            posts.posted_at
+            pfps.variants AS posted_by_mini_pfp,
+            posts.posted_at,
+            posts.is_pending AS `is_pending: bool`
         FROM posts
             JOIN post_tags ON posts.id = post_tags.post_id
             JOIN tags ON post_tags.tag_id = tags.id
             LEFT JOIN users ON posts.posted_by_user_id = users.id
-            LEFT JOIN files ON users.mini_pfp_file_id = files.id
+            LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
         WHERE
             posts.id <= ?
             AND tags.name = ?
+            AND posts.deleted_at IS NULL
+            AND (posts.is_pending = 0 OR posts.posted_by_user_id = ?)
+            AND (posts.is_shadowed = 0 OR posts.posted_by_user_id = ? OR ?)
         ORDER BY posts.id DESC
         LIMIT ?
         ",
         start_id,
         tag,
+        viewer.user_id,
+        viewer.user_id,
+        viewer.can_view_held,
         limit
     )
     .fetch_all(db_pool)
@@ -163,6 +185,7 @@ async fn fetch_raw_posts_backwards(
     tag: &str,
     start_id: Id,
     limit: u64,
+    viewer: Viewer,
 ) -> sqlx::Result<Vec<RawPostPreview>> {
     sqlx::query_as!(
         RawPostPreview,
@@ -173,28 +196,39 @@ async fn fetch_raw_posts_backwards(
             posts.description,
             users.id AS posted_by_user_id,
             users.username AS posted_by_username,
-            CONCAT(files.id, \".\", files.extension) AS posted_by_mini_pfp,
-            posts.posted_at
+            pfps.variants AS posted_by_mini_pfp,
+            posts.posted_at,
+            posts.is_pending AS `is_pending: bool`
         FROM posts
             JOIN post_tags ON posts.id = post_tags.post_id
             JOIN tags ON post_tags.tag_id = tags.id
             LEFT JOIN users ON posts.posted_by_user_id = users.id
-            LEFT JOIN files ON users.mini_pfp_file_id = files.id
+            LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
         WHERE
             posts.id >= ?
             AND tags.name = ?
+            AND posts.deleted_at IS NULL
+            AND (posts.is_pending = 0 OR posts.posted_by_user_id = ?)
+            AND (posts.is_shadowed = 0 OR posts.posted_by_user_id = ? OR ?)
         ORDER BY posts.id ASC
         LIMIT ?
         ",
         start_id,
         tag,
+        viewer.user_id,
+        viewer.user_id,
+        viewer.can_view_held,
         limit
     )
     .fetch_all(db_pool)
     .await
 }
 
-async fn fetch_min_max_post_id(db_pool: &MySqlPool, tag: &str) -> sqlx::Result<(Id, Id)> {
+async fn fetch_min_max_post_id(
+    db_pool: &MySqlPool,
+    tag: &str,
+    viewer: Viewer,
+) -> sqlx::Result<(Id, Id)> {
     #[derive(Debug)]
     struct MinMax {
         min: Option<Id>,
@@ -212,8 +246,14 @@ async fn fetch_min_max_post_id(db_pool: &MySqlPool, tag: &str) -> sqlx::Result<(
             JOIN tags ON post_tags.tag_id = tags.id
         WHERE
             tags.name = ?
+            AND posts.deleted_at IS NULL
+            AND (posts.is_pending = 0 OR posts.posted_by_user_id = ?)
+            AND (posts.is_shadowed = 0 OR posts.posted_by_user_id = ? OR ?)
         ",
-        tag
+        tag,
+        viewer.user_id,
+        viewer.user_id,
+        viewer.can_view_held
     )
     .fetch_one(db_pool)
     .await
@@ -225,6 +265,28 @@ async fn fetch_min_max_post_id(db_pool: &MySqlPool, tag: &str) -> sqlx::Result<(
     })
 }
 
+async fn fetch_tag_requires_approval(db_pool: &MySqlPool, tag: &str) -> sqlx::Result<bool> {
+    #[derive(Debug)]
+    struct Tag {
+        requires_approval: bool,
+    }
+
+    sqlx::query_as!(
+        Tag,
+        "
+        SELECT
+            requires_approval AS `requires_approval: bool`
+        FROM tags
+        WHERE
+            name = ?
+        ",
+        tag
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map(|result| result.map(|tag| tag.requires_approval).unwrap_or(false))
+}
+
 impl From<sqlx::Error> for Error {
     fn from(_: sqlx::Error) -> Self {
         Self::Server
@@ -1,5 +1,9 @@
-use crate::db::fetch_user_by_username;
+use crate::db::{fetch_roles, fetch_user_bans, fetch_user_by_username, fetch_user_restrictions};
 use crate::session::auth;
+use crate::types::ban;
+use crate::types::id::Id;
+use crate::types::mod_action::reason::REASON_MAX_CHAR_COUNT;
+use crate::types::user::role::{BANNED, UNVERIFIED};
 use crate::State;
 use crate::LIQUID_PARSER;
 use actix_web::{
@@ -10,7 +14,7 @@ use actix_web::{
 };
 use lazy_static::lazy_static;
 use liquid::Template;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Debug, Deserialize)]
@@ -18,6 +22,13 @@ pub struct Request {
     pub error: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+struct AssignableRole {
+    id: Id,
+    name: String,
+    is_assigned: bool,
+}
+
 #[derive(Debug, Error, Copy, Clone)]
 pub enum Error {
     #[error("Server error")]
@@ -35,7 +46,7 @@ pub async fn service(
 
     let username = path.into_inner();
 
-    let Some(user) = fetch_user_by_username(&state.db_pool, &username, current_user.as_ref()).await? else {
+    let Some(user) = fetch_user_by_username(&state.db_pool, &username, current_user.as_ref(), &state.config).await? else {
         return Ok(crate::pages::not_found::service().await);
     };
 
@@ -50,6 +61,30 @@ pub async fn service(
         return Ok(response);
     }
 
+    let roles = fetch_roles(&state.db_pool)
+        .await?
+        .into_iter()
+        .filter(|role| {
+            !role.permissions.can_admin && role.name != UNVERIFIED && role.name != BANNED
+        })
+        .map(|role| AssignableRole {
+            id: role.id,
+            is_assigned: user.roles.contains(&role.name),
+            name: role.name,
+        })
+        .collect::<Vec<_>>();
+
+    let is_banned = user.roles.iter().any(|role| role == BANNED);
+
+    let (bans, restrictions) = if user.is_adminable {
+        (
+            fetch_user_bans(&state.db_pool, user.id).await?,
+            Some(fetch_user_restrictions(&state.db_pool, user.id).await?),
+        )
+    } else {
+        (Vec::new(), None)
+    };
+
     lazy_static! {
         static ref TEMPLATE: Template = {
             let template = include_str!("../../../../templates/user/username/admin.html");
@@ -64,6 +99,12 @@ pub async fn service(
         "current_url": current_url,
         "current_user": current_user,
         "user": user,
+        "roles": roles,
+        "is_banned": is_banned,
+        "bans": bans,
+        "restrictions": restrictions,
+        "ban_duration_options": ban::duration_options(),
+        "reason_max_char_count": REASON_MAX_CHAR_COUNT,
         "error": query.error,
     });
 
@@ -1,5 +1,10 @@
 use crate::db::fetch_user_by_username;
 use crate::session::auth;
+use crate::types::api_token::{
+    self, name::NAME_MAX_CHAR_COUNT as TOKEN_NAME_MAX_CHAR_COUNT, scope::Scope, ApiToken,
+    RawApiToken,
+};
+use crate::types::id::Id;
 use crate::types::user::{description::DESCRIPTION_MAX_CHAR_COUNT, name::NAME_MAX_CHAR_COUNT};
 use crate::State;
 use crate::LIQUID_PARSER;
@@ -12,6 +17,7 @@ use actix_web::{
 use lazy_static::lazy_static;
 use liquid::Template;
 use serde::Deserialize;
+use sqlx::mysql::MySqlPool;
 use thiserror::Error;
 
 #[derive(Debug, Copy, Clone, Error)]
@@ -44,7 +50,9 @@ pub async fn service(
         return Ok(response);
     };
 
-    let Some(user) = fetch_user_by_username(&state.db_pool, &username, &current_user).await? else {
+    let Some(user) =
+        fetch_user_by_username(&state.db_pool, &username, &current_user, &state.config).await?
+    else {
         return Ok(crate::pages::not_found::service().await);
     };
 
@@ -59,6 +67,15 @@ pub async fn service(
         return Ok(response);
     }
 
+    let is_own_profile = current_user.id == user.id;
+
+    // Tokens are only ever shown to their owner, even admins can't see someone else's
+    let api_tokens = if is_own_profile {
+        fetch_api_tokens(&state.db_pool, user.id).await?
+    } else {
+        Vec::new()
+    };
+
     lazy_static! {
         static ref TEMPLATE: Template = {
             let template = include_str!("../../../../templates/user/username/edit.html");
@@ -67,6 +84,15 @@ pub async fn service(
         };
     }
 
+    // Filled in with what was sent when either form comes back with an error
+    let flash = state.flash.take(&req);
+
+    let form = liquid::object!({
+        "name": flash.value("name", user.name.clone()),
+        "description": flash.value("description", user.description.clone()),
+        "token_name": flash.value("token_name", ""),
+    });
+
     let current_url = urlencoding::encode(&req.uri().to_string()).into_owned();
 
     let globals = liquid::object!({
@@ -75,6 +101,13 @@ pub async fn service(
         "user": user,
         "name_max_char_count": NAME_MAX_CHAR_COUNT,
         "description_max_char_count": DESCRIPTION_MAX_CHAR_COUNT,
+        "is_own_profile": is_own_profile,
+        "api_tokens": api_tokens,
+        "scope_options": Scope::options(),
+        "expiry_options": api_token::expiry_options(),
+        "token_name_max_char_count": TOKEN_NAME_MAX_CHAR_COUNT,
+        "form": form,
+        "errors": flash.errors,
         "error": query.error,
     });
 
@@ -83,6 +116,33 @@ pub async fn service(
     Ok(HttpResponse::Ok().body(s))
 }
 
+async fn fetch_api_tokens(db_pool: &MySqlPool, user_id: Id) -> sqlx::Result<Vec<ApiToken>> {
+    let raw_api_tokens = sqlx::query_as!(
+        RawApiToken,
+        "
+        SELECT
+            id,
+            name,
+            scopes,
+            created_at,
+            expires_at,
+            last_used_at,
+            COALESCE(expires_at <= NOW(), FALSE) AS `is_expired!: bool`
+        FROM api_tokens
+        WHERE
+            user_id = ?
+        ORDER BY id DESC
+        ",
+        user_id
+    )
+    .fetch_all(db_pool)
+    .await?;
+
+    let api_tokens = raw_api_tokens.into_iter().map(ApiToken::from_raw).collect();
+
+    Ok(api_tokens)
+}
+
 impl From<sqlx::Error> for Error {
     fn from(_: sqlx::Error) -> Self {
         Self::Server
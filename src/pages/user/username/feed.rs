@@ -0,0 +1,66 @@
+use crate::db::fetch_user_by_username;
+use crate::feed::{self, Feed, Format};
+use crate::pages::user::username::fetch_posts;
+use crate::policy::Viewer;
+use crate::session::User as AuthUser;
+use crate::types::id::Id;
+use crate::types::page::{clamp_limit, Direction};
+use crate::State;
+use actix_web::{
+    get,
+    web::{Data, Path},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use thiserror::Error;
+
+#[derive(Debug, Copy, Clone, Error)]
+pub enum Error {
+    #[error("Server error")]
+    Server,
+}
+
+#[get("/user/{username}/feed.atom")]
+pub async fn service(
+    req: HttpRequest,
+    state: Data<State>,
+    path: Path<String>,
+) -> Result<HttpResponse, Error> {
+    let username = path.into_inner();
+
+    let Some(user) =
+        fetch_user_by_username(&state.db_pool, &username, None::<&AuthUser>, &state.config).await?
+    else {
+        return Ok(crate::pages::not_found::service().await);
+    };
+
+    let domain = &state.config.domain;
+    let limit = clamp_limit(None, &state.config.page);
+    let viewer = Viewer::of(None, &state.config);
+
+    let posts = fetch_posts(
+        &state.db_pool,
+        Direction::Forwards,
+        i64::MAX as Id,
+        limit,
+        &user,
+        viewer,
+    )
+    .await?;
+
+    let feed = Feed {
+        title: format!("{} · neor", user.username),
+        url: format!("https://{domain}/user/{}", user.username),
+        self_url: format!("https://{domain}/user/{}/feed.atom", user.username),
+        entries: feed::post_entries(&state.db_pool, domain, posts).await?,
+    };
+
+    Ok(feed::respond(&req, feed, Format::Atom))
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(_: sqlx::Error) -> Self {
+        Self::Server
+    }
+}
+
+impl ResponseError for Error {}
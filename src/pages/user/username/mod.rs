@@ -1,7 +1,11 @@
-use crate::db::{fetch_post_preview_from_raw, fetch_user_by_username};
+use crate::config::Config;
+use crate::db::{fetch_post_previews_from_raw, fetch_user_by_username};
+use crate::policy::Viewer;
 use crate::session::{auth, User as AuthUser};
 use crate::types::comment::{Comment, RawComment};
+use crate::types::draft::{Draft, RawDraft};
 use crate::types::id::Id;
+use crate::types::meta::Meta;
 use crate::types::page::{clamp_limit, Direction};
 use crate::types::post::{Preview as PostPreview, RawPreview as RawPostPreview};
 use crate::types::user::User;
@@ -20,6 +24,7 @@ use thiserror::Error;
 
 pub mod admin;
 pub mod edit;
+pub mod feed;
 
 #[derive(Debug, Copy, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
@@ -61,25 +66,27 @@ pub async fn service(
 
     let direction = query.direction.unwrap_or_default();
     let start_id = query.start_id.unwrap_or(i64::MAX as Id);
-    let limit = clamp_limit(query.limit);
+    let limit = clamp_limit(query.limit, &state.config.page);
 
     let display = query.display.unwrap_or_default();
 
     let mut posts = Vec::new();
     let mut comments = Vec::new();
 
-    let Some(user) = fetch_user_by_username(&state.db_pool, &username, current_user.as_ref()).await? else {
+    let Some(user) = fetch_user_by_username(&state.db_pool, &username, current_user.as_ref(), &state.config).await? else {
         return Ok(crate::pages::not_found::service().await);
     };
 
+    let viewer = Viewer::of(current_user.as_ref(), &state.config);
+
     let (min_id, max_id) = match display {
-        Display::Posts => fetch_min_max_post_id(&state.db_pool, user.id).await?,
-        Display::Comments => fetch_min_max_comment_id(&state.db_pool, user.id).await?,
+        Display::Posts => fetch_min_max_post_id(&state.db_pool, user.id, viewer).await?,
+        Display::Comments => fetch_min_max_comment_id(&state.db_pool, user.id, viewer).await?,
     };
 
     match display {
         Display::Posts => {
-            posts = fetch_posts(&state.db_pool, direction, start_id, limit, &user).await?;
+            posts = fetch_posts(&state.db_pool, direction, start_id, limit, &user, viewer).await?;
         }
         Display::Comments => {
             comments = fetch_comments(
@@ -89,11 +96,20 @@ pub async fn service(
                 limit,
                 &user,
                 current_user.as_ref(),
+                &state.config,
             )
             .await?;
         }
     }
 
+    // Drafts are private, nobody else gets to see them, moderators included
+    let drafts = match &current_user {
+        Some(current_user) if current_user.id == user.id => {
+            fetch_drafts(&state.db_pool, user.id).await?
+        }
+        _ => Vec::new(),
+    };
+
     lazy_static! {
         static ref TEMPLATE: Template = {
             let template = include_str!("../../../../templates/user/username.html");
@@ -139,10 +155,12 @@ pub async fn service(
     let globals = liquid::object!({
         "current_url": current_url,
         "current_user": current_user,
+        "meta": Meta::user(&state.config.domain, &user),
         "user": user,
         "display": display,
         "posts": posts,
         "comments": comments,
+        "drafts": drafts,
         "start_id": start_id,
         "min_id": min_id,
         "prev_start_id": prev_start_id,
@@ -156,24 +174,18 @@ pub async fn service(
     Ok(HttpResponse::Ok().body(s))
 }
 
-async fn fetch_posts(
+pub async fn fetch_posts(
     db_pool: &MySqlPool,
     direction: Direction,
     start_id: Id,
     limit: u64,
     user: &User,
+    viewer: Viewer,
 ) -> sqlx::Result<Vec<PostPreview>> {
-    let raw_post_previews = fetch_raw_posts(db_pool, direction, start_id, limit, user).await?;
-
-    let mut post_previews = Vec::new();
-
-    for raw_post in raw_post_previews {
-        let post = fetch_post_preview_from_raw(db_pool, raw_post).await?;
+    let raw_post_previews =
+        fetch_raw_posts(db_pool, direction, start_id, limit, user, viewer).await?;
 
-        post_previews.push(post);
-    }
-
-    Ok(post_previews)
+    fetch_post_previews_from_raw(db_pool, raw_post_previews).await
 }
 
 async fn fetch_raw_posts(
@@ -182,10 +194,15 @@ async fn fetch_raw_posts(
     start_id: Id,
     limit: u64,
     user: &User,
+    viewer: Viewer,
 ) -> sqlx::Result<Vec<RawPostPreview>> {
     match direction {
-        Direction::Forwards => fetch_raw_posts_forwards(db_pool, start_id, limit, user).await,
-        Direction::Backwards => fetch_raw_posts_backwards(db_pool, start_id, limit, user).await,
+        Direction::Forwards => {
+            fetch_raw_posts_forwards(db_pool, start_id, limit, user, viewer).await
+        }
+        Direction::Backwards => {
+            fetch_raw_posts_backwards(db_pool, start_id, limit, user, viewer).await
+        }
     }
 }
 
@@ -194,6 +211,7 @@ async fn fetch_raw_posts_forwards(
     start_id: Id,
     limit: u64,
     user: &User,
+    viewer: Viewer,
 ) -> sqlx::Result<Vec<RawPostPreview>> {
     sqlx::query_as!(
         RawPostPreview,
@@ -204,19 +222,26 @@ async fn fetch_raw_posts_forwards(
             posts.description,
             users.id AS posted_by_user_id,
             users.username AS posted_by_username,
-            CONCAT(files.id, \".\", files.extension) AS posted_by_mini_pfp,
-            posts.posted_at
+            pfps.variants AS posted_by_mini_pfp,
+            posts.posted_at,
+            posts.is_pending AS `is_pending: bool`
         FROM posts
             LEFT JOIN users ON posts.posted_by_user_id = users.id
-            LEFT JOIN files ON users.mini_pfp_file_id = files.id
+            LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
         WHERE
             posts.id <= ?
             AND posts.posted_by_user_id = ?
+            AND posts.deleted_at IS NULL
+            AND (posts.is_pending = 0 OR posts.posted_by_user_id = ?)
+            AND (posts.is_shadowed = 0 OR posts.posted_by_user_id = ? OR ?)
         ORDER BY posts.id DESC
         LIMIT ?
         ",
         start_id,
         user.id,
+        viewer.user_id,
+        viewer.user_id,
+        viewer.can_view_held,
         limit
     )
     .fetch_all(db_pool)
@@ -228,6 +253,7 @@ async fn fetch_raw_posts_backwards(
     start_id: Id,
     limit: u64,
     user: &User,
+    viewer: Viewer,
 ) -> sqlx::Result<Vec<RawPostPreview>> {
     sqlx::query_as!(
         RawPostPreview,
@@ -238,19 +264,26 @@ async fn fetch_raw_posts_backwards(
             posts.description,
             users.id AS posted_by_user_id,
             users.username AS posted_by_username,
-            CONCAT(files.id, \".\", files.extension) AS posted_by_mini_pfp,
-            posts.posted_at
+            pfps.variants AS posted_by_mini_pfp,
+            posts.posted_at,
+            posts.is_pending AS `is_pending: bool`
         FROM posts
             LEFT JOIN users ON posts.posted_by_user_id = users.id
-            LEFT JOIN files ON users.mini_pfp_file_id = files.id
+            LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
         WHERE
             posts.id >= ?
             AND posts.posted_by_user_id = ?
+            AND posts.deleted_at IS NULL
+            AND (posts.is_pending = 0 OR posts.posted_by_user_id = ?)
+            AND (posts.is_shadowed = 0 OR posts.posted_by_user_id = ? OR ?)
         ORDER BY posts.id ASC
         LIMIT ?
         ",
         start_id,
         user.id,
+        viewer.user_id,
+        viewer.user_id,
+        viewer.can_view_held,
         limit
     )
     .fetch_all(db_pool)
@@ -262,20 +295,21 @@ async fn fetch_raw_posts_backwards(
     })
 }
 
-async fn fetch_comments(
+pub async fn fetch_comments(
     db_pool: &MySqlPool,
     direction: Direction,
     start_id: Id,
     limit: u64,
     user: &User,
     current_user: Option<&AuthUser>,
+    config: &Config,
 ) -> sqlx::Result<Vec<Comment>> {
     match direction {
         Direction::Backwards => {
-            fetch_comments_backwards(db_pool, start_id, limit, user, current_user).await
+            fetch_comments_backwards(db_pool, start_id, limit, user, current_user, config).await
         }
         Direction::Forwards => {
-            fetch_comments_forwards(db_pool, start_id, limit, user, current_user).await
+            fetch_comments_forwards(db_pool, start_id, limit, user, current_user, config).await
         }
     }
 }
@@ -286,7 +320,10 @@ async fn fetch_comments_forwards(
     limit: u64,
     user: &User,
     current_user: Option<&AuthUser>,
+    config: &Config,
 ) -> sqlx::Result<Vec<Comment>> {
+    let viewer = Viewer::of(current_user, config);
+
     sqlx::query_as!(
         RawComment,
         "
@@ -296,30 +333,43 @@ async fn fetch_comments_forwards(
             comments.reply_to_comment_id,
             users_reply_to.id AS reply_to_user_id,
             users_reply_to.username AS reply_to_username,
-            CONCAT(files_reply_to.id, \".\", files_reply_to.extension) AS reply_to_mini_pfp,
+            pfps_reply_to.variants AS reply_to_mini_pfp,
             comments.markdown_content AS content,
             users_posted_by.id AS posted_by_user_id,
             users_posted_by.username AS posted_by_username,
-            CONCAT(files_posted_by.id, \".\", files_posted_by.extension) AS posted_by_mini_pfp,
+            pfps_posted_by.variants AS posted_by_mini_pfp,
             comments.posted_at,
-            comments.modified_at
+            comments.modified_at,
+            comments.deleted_at,
+            (
+                SELECT COUNT(*)
+                FROM reports
+                WHERE
+                    reports.target_type = 'Comment'
+                    AND reports.target_id = comments.id
+                    AND reports.resolved_at IS NULL
+            ) AS `open_report_count!`
         FROM comments
             LEFT JOIN comments AS comments_reply_to ON comments.reply_to_comment_id = comments_reply_to.id
             LEFT JOIN users AS users_reply_to ON comments_reply_to.posted_by_user_id = users_reply_to.id
-            LEFT JOIN files AS files_reply_to ON users_reply_to.mini_pfp_file_id = files_reply_to.id
+            LEFT JOIN user_pfps AS pfps_reply_to ON users_reply_to.id = pfps_reply_to.user_id AND pfps_reply_to.size = 'mini'
             LEFT JOIN users AS users_posted_by ON comments.posted_by_user_id = users_posted_by.id
-            LEFT JOIN files AS files_posted_by ON users_posted_by.mini_pfp_file_id = files_posted_by.id
+            LEFT JOIN user_pfps AS pfps_posted_by ON users_posted_by.id = pfps_posted_by.user_id AND pfps_posted_by.size = 'mini'
         WHERE
             comments.id <= ?
             AND comments.posted_by_user_id = ?
+            AND comments.deleted_at IS NULL
+            AND (comments.is_shadowed = 0 OR comments.posted_by_user_id = ? OR ?)
         ORDER BY comments.posted_at DESC
         LIMIT ?
         ",
         start_id,
         user.id,
+        viewer.user_id,
+        viewer.can_view_held,
         limit
         )
-    .fetch_all(db_pool).await.map(|result| result.into_iter().map(|raw_comment| Comment::from_raw(raw_comment, current_user)).collect())
+    .fetch_all(db_pool).await.map(|result| result.into_iter().map(|raw_comment| Comment::from_raw(raw_comment, current_user, config)).collect())
 }
 
 async fn fetch_comments_backwards(
@@ -328,7 +378,10 @@ async fn fetch_comments_backwards(
     limit: u64,
     user: &User,
     current_user: Option<&AuthUser>,
+    config: &Config,
 ) -> sqlx::Result<Vec<Comment>> {
+    let viewer = Viewer::of(current_user, config);
+
     sqlx::query_as!(
         RawComment,
         "
@@ -338,35 +391,49 @@ async fn fetch_comments_backwards(
             comments.reply_to_comment_id,
             users_reply_to.id AS reply_to_user_id,
             users_reply_to.username AS reply_to_username,
-            CONCAT(files_reply_to.id, \".\", files_reply_to.extension) AS reply_to_mini_pfp,
+            pfps_reply_to.variants AS reply_to_mini_pfp,
             comments.markdown_content AS content,
             users_posted_by.id AS posted_by_user_id,
             users_posted_by.username AS posted_by_username,
-            CONCAT(files_posted_by.id, \".\", files_posted_by.extension) AS posted_by_mini_pfp,
+            pfps_posted_by.variants AS posted_by_mini_pfp,
             comments.posted_at,
-            comments.modified_at
+            comments.modified_at,
+            comments.deleted_at,
+            (
+                SELECT COUNT(*)
+                FROM reports
+                WHERE
+                    reports.target_type = 'Comment'
+                    AND reports.target_id = comments.id
+                    AND reports.resolved_at IS NULL
+            ) AS `open_report_count!`
         FROM comments
             LEFT JOIN comments AS comments_reply_to ON comments.reply_to_comment_id = comments_reply_to.id
             LEFT JOIN users AS users_reply_to ON comments_reply_to.posted_by_user_id = users_reply_to.id
-            LEFT JOIN files AS files_reply_to ON users_reply_to.mini_pfp_file_id = files_reply_to.id
+            LEFT JOIN user_pfps AS pfps_reply_to ON users_reply_to.id = pfps_reply_to.user_id AND pfps_reply_to.size = 'mini'
             LEFT JOIN users AS users_posted_by ON comments.posted_by_user_id = users_posted_by.id
-            LEFT JOIN files AS files_posted_by ON users_posted_by.mini_pfp_file_id = files_posted_by.id
+            LEFT JOIN user_pfps AS pfps_posted_by ON users_posted_by.id = pfps_posted_by.user_id AND pfps_posted_by.size = 'mini'
         WHERE
             comments.id >= ?
             AND comments.posted_by_user_id = ?
+            AND comments.deleted_at IS NULL
+            AND (comments.is_shadowed = 0 OR comments.posted_by_user_id = ? OR ?)
         ORDER BY comments.posted_at ASC
         LIMIT ?
         ",
         start_id,
         user.id,
+        viewer.user_id,
+        viewer.can_view_held,
         limit
         )
-    .fetch_all(db_pool).await.map(|result| result.into_iter().map(|raw_comment| Comment::from_raw(raw_comment, current_user)).rev().collect())
+    .fetch_all(db_pool).await.map(|result| result.into_iter().map(|raw_comment| Comment::from_raw(raw_comment, current_user, config)).rev().collect())
 }
 
 async fn fetch_min_max_post_id(
     db_pool: &MySqlPool,
     user_id: Id,
+    viewer: Viewer,
 ) -> sqlx::Result<(Option<Id>, Option<Id>)> {
     #[derive(Debug)]
     struct MinMax {
@@ -383,8 +450,14 @@ async fn fetch_min_max_post_id(
         FROM posts
         WHERE
             posted_by_user_id = ?
+            AND deleted_at IS NULL
+            AND (is_pending = 0 OR posted_by_user_id = ?)
+            AND (is_shadowed = 0 OR posted_by_user_id = ? OR ?)
         ",
         user_id,
+        viewer.user_id,
+        viewer.user_id,
+        viewer.can_view_held
     )
     .fetch_one(db_pool)
     .await
@@ -394,6 +467,7 @@ async fn fetch_min_max_post_id(
 async fn fetch_min_max_comment_id(
     db_pool: &MySqlPool,
     user_id: Id,
+    viewer: Viewer,
 ) -> sqlx::Result<(Option<Id>, Option<Id>)> {
     #[derive(Debug)]
     struct MinMax {
@@ -410,14 +484,43 @@ async fn fetch_min_max_comment_id(
         FROM comments
         WHERE
             posted_by_user_id = ?
+            AND deleted_at IS NULL
+            AND (is_shadowed = 0 OR posted_by_user_id = ? OR ?)
         ",
         user_id,
+        viewer.user_id,
+        viewer.can_view_held
     )
     .fetch_one(db_pool)
     .await
     .map(|result| (result.min, result.max))
 }
 
+async fn fetch_drafts(db_pool: &MySqlPool, user_id: Id) -> sqlx::Result<Vec<Draft>> {
+    sqlx::query_as!(
+        RawDraft,
+        "
+        SELECT
+            id,
+            title,
+            description,
+            tags,
+            content,
+            publish_at,
+            last_error,
+            updated_at
+        FROM drafts
+        WHERE
+            user_id = ?
+        ORDER BY updated_at DESC
+        ",
+        user_id
+    )
+    .fetch_all(db_pool)
+    .await
+    .map(|result| result.into_iter().map(Draft::from_raw).collect())
+}
+
 impl From<sqlx::Error> for Error {
     fn from(_: sqlx::Error) -> Self {
         Self::Server
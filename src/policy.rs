@@ -0,0 +1,675 @@
+use crate::config::{Config, EditWindow, EditWindows, DEFAULT_EDIT_WINDOW_MINUTES};
+use crate::session::User;
+use crate::types::id::Id;
+use crate::types::user::role::{Permissions, BANNED};
+use chrono::NaiveDateTime;
+
+// Answers "can user U perform action A on resource R".
+// Pages use it to decide what to show and APIs to decide what to allow,
+// so the two can't drift apart
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Action {
+    View,
+    Create,
+    Edit,
+    Anonymise,
+    Delete,
+    Restore,
+    // Close reports on a post or comment
+    Resolve,
+    // Let a pending post through
+    Approve,
+    Admin,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum Resource {
+    // Collections, the only thing you can do with them is `Create`
+    Posts,
+    Comments,
+    Replies,
+    Reports,
+
+    // Site wide settings, e.g. roles
+    Site,
+
+    // Log of moderation actions
+    ModLog,
+    // Open reports grouped by post and comment
+    ModQueue,
+    // Pending posts, and posts and comments of shadow-banned users
+    Held,
+
+    Post(Authored),
+    Comment(Authored),
+    User(Account),
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Authored {
+    pub posted_by_user_id: Option<Id>,
+    pub posted_at: NaiveDateTime,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Account {
+    pub id: Id,
+    pub permissions: Permissions,
+}
+
+// What listings need to know about whoever is looking at them.
+// Pending posts are only listed for their author, posts and comments
+// of shadow-banned users for their author and those who can view `Held`
+#[derive(Debug, Copy, Clone)]
+pub struct Viewer {
+    pub user_id: Option<Id>,
+    pub can_view_held: bool,
+}
+
+impl Viewer {
+    pub fn of(user: Option<&User>, config: &Config) -> Self {
+        Self {
+            user_id: user.map(|user| user.id),
+            can_view_held: user
+                .map(|user| can(user, Action::View, Resource::Held, config))
+                .unwrap_or(false),
+        }
+    }
+}
+
+pub fn can(user: &User, action: Action, resource: Resource, config: &Config) -> bool {
+    // A ban outweighs whatever other roles the user still has
+    let permissions = if user.roles.iter().any(|role| role == BANNED) {
+        Permissions::default()
+    } else {
+        user.permissions
+    };
+
+    match (action, resource) {
+        (Action::View, Resource::ModLog) => {
+            config.modlog.is_public
+                || permissions.can_delete_posts
+                || permissions.can_delete_comments
+                || permissions.can_admin
+        }
+
+        (Action::Create, Resource::Posts) => permissions.can_post,
+        (Action::Create, Resource::Comments) => permissions.can_comment,
+        (Action::Create, Resource::Replies) => permissions.can_reply,
+        // Anyone who takes part in discussions can flag them
+        (Action::Create, Resource::Reports) => permissions.can_comment,
+
+        (Action::View, Resource::ModQueue) => {
+            permissions.can_delete_posts || permissions.can_delete_comments
+        }
+        (Action::View, Resource::Held) => {
+            permissions.can_delete_posts || permissions.can_delete_comments
+        }
+
+        (Action::Edit, Resource::Post(post)) => {
+            permissions.can_edit_posts
+                && is_author(user, post)
+                && is_within_edit_window(user, post, &config.post.edit_window_minutes)
+        }
+        (Action::Edit, Resource::Comment(comment)) => {
+            permissions.can_edit_comments
+                && is_author(user, comment)
+                && is_within_edit_window(user, comment, &config.comment.edit_window_minutes)
+        }
+        (Action::Edit, Resource::User(account)) => {
+            permissions.can_edit_self && user.id == account.id
+        }
+
+        (Action::Anonymise, Resource::Post(post)) => {
+            permissions.can_anonymise_posts && is_author(user, post)
+        }
+        (Action::Anonymise, Resource::Comment(comment)) => {
+            permissions.can_anonymise_comments && is_author(user, comment)
+        }
+
+        (Action::Delete, Resource::Post(_)) => permissions.can_delete_posts,
+        (Action::Delete, Resource::Comment(_)) => permissions.can_delete_comments,
+
+        // Whoever can delete can also take it back
+        (Action::Restore, Resource::Post(_)) => permissions.can_delete_posts,
+        (Action::Restore, Resource::Comment(_)) => permissions.can_delete_comments,
+
+        (Action::Resolve, Resource::Post(_)) => permissions.can_delete_posts,
+        (Action::Resolve, Resource::Comment(_)) => permissions.can_delete_comments,
+
+        (Action::Approve, Resource::Post(_)) => permissions.can_delete_posts,
+
+        (Action::Admin, Resource::Site) => permissions.can_admin,
+        // Admins can't admin each other
+        (Action::Admin, Resource::User(account)) => {
+            permissions.can_admin && !account.permissions.can_admin
+        }
+
+        _ => false,
+    }
+}
+
+fn is_author(user: &User, authored: Authored) -> bool {
+    authored
+        .posted_by_user_id
+        .map(|id| user.id == id)
+        .unwrap_or(false)
+}
+
+// The most generous of the user's roles wins, only roles configured as unlimited lift it
+fn is_within_edit_window(user: &User, authored: Authored, edit_windows: &EditWindows) -> bool {
+    let default = EditWindow::Minutes(DEFAULT_EDIT_WINDOW_MINUTES);
+
+    let window = user
+        .roles
+        .iter()
+        .map(|role| edit_windows.get(role).copied().unwrap_or(default))
+        .max()
+        .unwrap_or(default);
+
+    let EditWindow::Minutes(minutes) = window else {
+        return true;
+    };
+
+    let minutes_since_posted_at =
+        (chrono::Utc::now().naive_utc() - authored.posted_at).num_minutes();
+
+    minutes_since_posted_at < i64::from(minutes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ban::ActiveBan;
+    use crate::types::user::pfp::Pfp;
+    use crate::types::user::role::{ADMIN, BANNED, MEMBER, MOD, UNVERIFIED};
+    use chrono::Duration;
+    use std::collections::HashMap;
+
+    const USER_ID: Id = 1;
+    const OTHER_USER_ID: Id = 2;
+
+    // Same as the builtin roles seeded by `db/scheme.sql`
+    fn permissions(role: &str) -> Permissions {
+        let member = Permissions {
+            can_post: true,
+            can_comment: true,
+            can_reply: true,
+            can_edit_posts: true,
+            can_edit_comments: true,
+            can_edit_self: true,
+            can_anonymise_posts: true,
+            can_anonymise_comments: true,
+            ..Permissions::default()
+        };
+
+        match role {
+            ADMIN => Permissions {
+                can_delete_posts: true,
+                can_delete_comments: true,
+                can_admin: true,
+                ..member
+            },
+            MOD => Permissions {
+                can_delete_posts: true,
+                can_delete_comments: true,
+                ..member
+            },
+            MEMBER => member,
+            _ => Permissions::default(),
+        }
+    }
+
+    fn user(role: &str) -> User {
+        User {
+            id: USER_ID,
+            username: role.to_lowercase(),
+            roles: vec![role.to_owned()],
+            permissions: permissions(role),
+            mini_pfp: Pfp {
+                src: String::new(),
+                srcset: String::new(),
+            },
+            ban: None,
+        }
+    }
+
+    fn authored(posted_by_user_id: Id, minutes_ago: i64) -> Authored {
+        Authored {
+            posted_by_user_id: Some(posted_by_user_id),
+            posted_at: chrono::Utc::now().naive_utc() - Duration::minutes(minutes_ago),
+        }
+    }
+
+    // Every action on a post and a comment posted just now
+    fn content_actions(user: &User, posted_by_user_id: Id, config: &Config) -> [bool; 12] {
+        let authored = authored(posted_by_user_id, 0);
+
+        [
+            can(user, Action::Edit, Resource::Post(authored), config),
+            can(user, Action::Edit, Resource::Comment(authored), config),
+            can(user, Action::Anonymise, Resource::Post(authored), config),
+            can(user, Action::Anonymise, Resource::Comment(authored), config),
+            can(user, Action::Delete, Resource::Post(authored), config),
+            can(user, Action::Delete, Resource::Comment(authored), config),
+            can(user, Action::Restore, Resource::Post(authored), config),
+            can(user, Action::Restore, Resource::Comment(authored), config),
+            can(user, Action::Resolve, Resource::Post(authored), config),
+            can(user, Action::Resolve, Resource::Comment(authored), config),
+            can(user, Action::Approve, Resource::Post(authored), config),
+            can(user, Action::Approve, Resource::Comment(authored), config),
+        ]
+    }
+
+    #[test]
+    fn own_content() {
+        let config = Config::default();
+
+        let cases = [
+            (
+                ADMIN,
+                [
+                    true, true, true, true, true, true, true, true, true, true, true, false,
+                ],
+            ),
+            (
+                MOD,
+                [
+                    true, true, true, true, true, true, true, true, true, true, true, false,
+                ],
+            ),
+            (
+                MEMBER,
+                [
+                    true, true, true, true, false, false, false, false, false, false, false, false,
+                ],
+            ),
+            (BANNED, [false; 12]),
+            (UNVERIFIED, [false; 12]),
+        ];
+
+        for (role, expected) in cases {
+            assert_eq!(
+                content_actions(&user(role), USER_ID, &config),
+                expected,
+                "{role}"
+            );
+        }
+    }
+
+    #[test]
+    fn foreign_content() {
+        let config = Config::default();
+
+        let cases = [
+            (
+                ADMIN,
+                [
+                    false, false, false, false, true, true, true, true, true, true, true, false,
+                ],
+            ),
+            (
+                MOD,
+                [
+                    false, false, false, false, true, true, true, true, true, true, true, false,
+                ],
+            ),
+            (MEMBER, [false; 12]),
+            (BANNED, [false; 12]),
+            (UNVERIFIED, [false; 12]),
+        ];
+
+        for (role, expected) in cases {
+            assert_eq!(
+                content_actions(&user(role), OTHER_USER_ID, &config),
+                expected,
+                "{role}"
+            );
+        }
+    }
+
+    #[test]
+    fn anonymised_content_has_no_author() {
+        let config = Config::default();
+        let anonymised = Authored {
+            posted_by_user_id: None,
+            posted_at: chrono::Utc::now().naive_utc(),
+        };
+
+        for role in [ADMIN, MOD, MEMBER] {
+            let user = user(role);
+
+            assert!(
+                !can(&user, Action::Edit, Resource::Post(anonymised), &config),
+                "{role}"
+            );
+            assert!(
+                !can(&user, Action::Edit, Resource::Comment(anonymised), &config),
+                "{role}"
+            );
+        }
+    }
+
+    #[test]
+    fn collections() {
+        let config = Config::default();
+
+        for (role, expected) in [
+            (ADMIN, true),
+            (MOD, true),
+            (MEMBER, true),
+            (BANNED, false),
+            (UNVERIFIED, false),
+        ] {
+            let user = user(role);
+
+            for resource in [
+                Resource::Posts,
+                Resource::Comments,
+                Resource::Replies,
+                Resource::Reports,
+            ] {
+                assert_eq!(
+                    can(&user, Action::Create, resource, &config),
+                    expected,
+                    "{role} {resource:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn moderation_and_admin() {
+        let config = Config::default();
+
+        for (role, can_moderate, can_admin) in [
+            (ADMIN, true, true),
+            (MOD, true, false),
+            (MEMBER, false, false),
+            (BANNED, false, false),
+            (UNVERIFIED, false, false),
+        ] {
+            let user = user(role);
+
+            assert_eq!(
+                can(&user, Action::View, Resource::ModQueue, &config),
+                can_moderate,
+                "{role}"
+            );
+            assert_eq!(
+                can(&user, Action::View, Resource::Held, &config),
+                can_moderate,
+                "{role}"
+            );
+            assert_eq!(
+                can(&user, Action::View, Resource::ModLog, &config),
+                can_moderate,
+                "{role}"
+            );
+            assert_eq!(
+                can(&user, Action::Admin, Resource::Site, &config),
+                can_admin,
+                "{role}"
+            );
+        }
+    }
+
+    #[test]
+    fn public_mod_log() {
+        let mut config = Config::default();
+        config.modlog.is_public = true;
+
+        assert!(can(
+            &user(UNVERIFIED),
+            Action::View,
+            Resource::ModLog,
+            &config
+        ));
+    }
+
+    #[test]
+    fn users() {
+        let config = Config::default();
+        let account = |id, role| Account {
+            id,
+            permissions: permissions(role),
+        };
+
+        let admin = user(ADMIN);
+
+        assert!(can(
+            &admin,
+            Action::Admin,
+            Resource::User(account(OTHER_USER_ID, MEMBER)),
+            &config
+        ));
+        assert!(can(
+            &admin,
+            Action::Admin,
+            Resource::User(account(OTHER_USER_ID, MOD)),
+            &config
+        ));
+        // Admins can't admin each other
+        assert!(!can(
+            &admin,
+            Action::Admin,
+            Resource::User(account(OTHER_USER_ID, ADMIN)),
+            &config
+        ));
+        assert!(!can(
+            &user(MOD),
+            Action::Admin,
+            Resource::User(account(OTHER_USER_ID, MEMBER)),
+            &config
+        ));
+
+        for (role, expected) in [(ADMIN, true), (MEMBER, true), (BANNED, false)] {
+            let user = user(role);
+
+            assert_eq!(
+                can(
+                    &user,
+                    Action::Edit,
+                    Resource::User(account(USER_ID, role)),
+                    &config
+                ),
+                expected,
+                "{role}"
+            );
+            assert!(
+                !can(
+                    &user,
+                    Action::Edit,
+                    Resource::User(account(OTHER_USER_ID, MEMBER)),
+                    &config
+                ),
+                "{role}"
+            );
+        }
+    }
+
+    #[test]
+    fn edit_window() {
+        let config = Config::default();
+        let window = i64::from(DEFAULT_EDIT_WINDOW_MINUTES);
+
+        let member = user(MEMBER);
+        let fresh = authored(USER_ID, window - 1);
+        let stale = authored(USER_ID, window + 1);
+
+        assert!(can(&member, Action::Edit, Resource::Post(fresh), &config));
+        assert!(can(
+            &member,
+            Action::Edit,
+            Resource::Comment(fresh),
+            &config
+        ));
+        assert!(!can(&member, Action::Edit, Resource::Post(stale), &config));
+        assert!(!can(
+            &member,
+            Action::Edit,
+            Resource::Comment(stale),
+            &config
+        ));
+
+        // Mods have no window by default
+        let moderator = user(MOD);
+        let old = authored(USER_ID, 60 * 24 * 365);
+
+        assert!(can(&moderator, Action::Edit, Resource::Post(old), &config));
+        assert!(can(
+            &moderator,
+            Action::Edit,
+            Resource::Comment(old),
+            &config
+        ));
+
+        // Anonymising isn't limited by the window
+        assert!(can(
+            &member,
+            Action::Anonymise,
+            Resource::Post(stale),
+            &config
+        ));
+    }
+
+    #[test]
+    fn most_generous_edit_window_wins() {
+        let mut config = Config::default();
+        config.post.edit_window_minutes = HashMap::from([
+            (MEMBER.to_owned(), EditWindow::Minutes(10)),
+            (MOD.to_owned(), EditWindow::Minutes(60)),
+        ]);
+
+        let mut user = user(MEMBER);
+        user.roles.push(MOD.to_owned());
+        user.permissions = permissions(MEMBER).union(permissions(MOD));
+
+        assert!(can(
+            &user,
+            Action::Edit,
+            Resource::Post(authored(USER_ID, 30)),
+            &config
+        ));
+        assert!(!can(
+            &user,
+            Action::Edit,
+            Resource::Post(authored(USER_ID, 90)),
+            &config
+        ));
+    }
+
+    #[test]
+    fn unlisted_role_keeps_the_window() {
+        let config = Config::default();
+        let window = i64::from(DEFAULT_EDIT_WINDOW_MINUTES);
+
+        let mut user = user(MEMBER);
+        user.roles.push("Tag curator".to_owned());
+
+        assert!(can(
+            &user,
+            Action::Edit,
+            Resource::Post(authored(USER_ID, window - 1)),
+            &config
+        ));
+        assert!(!can(
+            &user,
+            Action::Edit,
+            Resource::Post(authored(USER_ID, window + 1)),
+            &config
+        ));
+        assert!(!can(
+            &user,
+            Action::Edit,
+            Resource::Comment(authored(USER_ID, window + 1)),
+            &config
+        ));
+    }
+
+    #[test]
+    fn unlimited_role_lifts_the_window() {
+        let mut config = Config::default();
+        config
+            .post
+            .edit_window_minutes
+            .insert("Tag curator".to_owned(), EditWindow::Unlimited);
+
+        let mut user = user(MEMBER);
+        user.roles.push("Tag curator".to_owned());
+
+        let old = authored(USER_ID, 60 * 24 * 365);
+
+        assert!(can(&user, Action::Edit, Resource::Post(old), &config));
+        assert!(!can(&user, Action::Edit, Resource::Comment(old), &config));
+    }
+
+    #[test]
+    fn banned_user() {
+        let config = Config::default();
+
+        // A ban takes away every other role until it's lifted, even one given back since
+        for other_role in [None, Some(MEMBER), Some(MOD), Some(ADMIN)] {
+            let mut user = user(BANNED);
+            user.ban = Some(ActiveBan {
+                reason: "Spam".to_owned(),
+                expires_at: None,
+            });
+
+            if let Some(other_role) = other_role {
+                user.roles.push(other_role.to_owned());
+                user.permissions = user.permissions.union(permissions(other_role));
+            }
+
+            let role = other_role.unwrap_or(BANNED);
+
+            assert_eq!(
+                content_actions(&user, USER_ID, &config),
+                [false; 12],
+                "{role}"
+            );
+            assert_eq!(
+                content_actions(&user, OTHER_USER_ID, &config),
+                [false; 12],
+                "{role}"
+            );
+            assert!(
+                !can(&user, Action::Create, Resource::Posts, &config),
+                "{role}"
+            );
+            assert!(
+                !can(&user, Action::Create, Resource::Comments, &config),
+                "{role}"
+            );
+            assert!(
+                !can(&user, Action::Create, Resource::Replies, &config),
+                "{role}"
+            );
+            assert!(
+                !can(&user, Action::Create, Resource::Reports, &config),
+                "{role}"
+            );
+            assert!(
+                !can(
+                    &user,
+                    Action::Edit,
+                    Resource::User(Account {
+                        id: USER_ID,
+                        permissions: user.permissions,
+                    }),
+                    &config
+                ),
+                "{role}"
+            );
+            assert!(
+                !can(&user, Action::View, Resource::ModQueue, &config),
+                "{role}"
+            );
+            assert!(
+                !can(&user, Action::Admin, Resource::Site, &config),
+                "{role}"
+            );
+        }
+    }
+}
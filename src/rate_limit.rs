@@ -0,0 +1,197 @@
+use crate::config::{ActionLimits, RateLimitConfig};
+use crate::types::id::Id;
+use actix_web::HttpRequest;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{Mutex, PoisonError};
+use std::time::Instant;
+
+// Token buckets kept in memory, one per action and user or IP.
+// Every request takes a token from each bucket that applies to it,
+// tokens trickle back in at `per_hour` up to `burst`
+
+// Full buckets are forgotten once there are this many
+const PRUNE_THRESHOLD: usize = 10_000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Action {
+    Post,
+    Comment,
+    SignUp,
+}
+
+// `burst` requests at once, then `per_hour` more every hour.
+// A `burst` of `0` turns the limit off
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Limit {
+    pub burst: u32,
+    pub per_hour: u32,
+}
+
+// Who is making the request, users are limited by both account and IP
+#[derive(Debug, Copy, Clone)]
+pub struct Requester {
+    pub user_id: Option<Id>,
+    pub account_age_days: i64,
+    pub ip: Option<IpAddr>,
+}
+
+#[derive(Debug)]
+pub struct Limiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<(Action, Key), Bucket>>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    User(Id),
+    Ip(IpAddr),
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Bucket {
+    limit: Limit,
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Limit {
+    pub const OFF: Self = Self {
+        burst: 0,
+        per_hour: 0,
+    };
+
+    pub fn is_off(&self) -> bool {
+        self.burst == 0
+    }
+}
+
+// `burst/per_hour`, e.g. `5/10`
+impl FromStr for Limit {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (burst, per_hour) = s.split_once('/').ok_or(())?;
+
+        Ok(Self {
+            burst: burst.trim().parse().map_err(|_| ())?,
+            per_hour: per_hour.trim().parse().map_err(|_| ())?,
+        })
+    }
+}
+
+impl Limiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Takes a token from every bucket that applies, or none at all if any of them is empty
+    pub fn check(&self, action: Action, requester: &Requester) -> Result<(), ()> {
+        let limits = self.limits(action);
+
+        let mut keys = Vec::with_capacity(2);
+
+        if let Some(user_id) = requester.user_id {
+            let limit = if requester.account_age_days < i64::from(self.config.new_account_days) {
+                limits.new_user
+            } else {
+                limits.user
+            };
+
+            keys.push((Key::User(user_id), limit));
+        }
+
+        if let Some(ip) = requester.ip {
+            keys.push((Key::Ip(ip), limits.ip));
+        }
+
+        keys.retain(|(_, limit)| !limit.is_off());
+
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if buckets.len() > PRUNE_THRESHOLD {
+            buckets.retain(|_, bucket| {
+                bucket.refill(now);
+
+                !bucket.is_full()
+            });
+        }
+
+        for (key, limit) in &keys {
+            let bucket = buckets
+                .entry((action, *key))
+                .or_insert_with(|| Bucket::new(*limit, now));
+
+            // New accounts grow into the regular limit
+            bucket.limit = *limit;
+            bucket.refill(now);
+
+            if bucket.tokens < 1.0 {
+                return Err(());
+            }
+        }
+
+        for (key, _) in &keys {
+            if let Some(bucket) = buckets.get_mut(&(action, *key)) {
+                bucket.tokens -= 1.0;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn limits(&self, action: Action) -> &ActionLimits {
+        match action {
+            Action::Post => &self.config.post,
+            Action::Comment => &self.config.comment,
+            Action::SignUp => &self.config.sign_up,
+        }
+    }
+}
+
+impl Bucket {
+    fn new(limit: Limit, now: Instant) -> Self {
+        Self {
+            limit,
+            tokens: f64::from(limit.burst),
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let hours = now.duration_since(self.updated_at).as_secs_f64() / 3600.0;
+
+        self.tokens =
+            (self.tokens + hours * f64::from(self.limit.per_hour)).min(f64::from(self.limit.burst));
+        self.updated_at = now;
+    }
+
+    fn is_full(&self) -> bool {
+        self.tokens >= f64::from(self.limit.burst)
+    }
+}
+
+// Behind a reverse proxy every request comes from the proxy,
+// so the address it forwards is used instead
+pub fn client_ip(req: &HttpRequest, is_behind_proxy: bool) -> Option<IpAddr> {
+    if is_behind_proxy {
+        return req.connection_info().realip_remote_addr().and_then(|addr| {
+            addr.parse::<IpAddr>()
+                .or_else(|_| addr.parse::<SocketAddr>().map(|addr| addr.ip()))
+                .ok()
+        });
+    }
+
+    req.peer_addr().map(|addr| addr.ip())
+}
@@ -0,0 +1,165 @@
+use crate::config::MarkdownConfig;
+use markdown::{CompileOptions, Constructs, Options, ParseOptions};
+
+// Bump whenever stored HTML should be rendered again, like after updating the markdown crate
+// or changing the options below. Changing the config is picked up by `version` on its own
+const RENDERER_VERSION: u32 = 1;
+
+// Everything posts and comments are rendered with. Raw HTML is escaped and links
+// can't use protocols like `javascript:`, whatever is configured
+pub fn markdown(text: &str, config: &MarkdownConfig) -> String {
+    // Only MDX can fail to parse and it's never turned on
+    markdown::to_html_with_options(text, &options(config))
+        .expect("Failed to render markdown without MDX")
+}
+
+// Stored next to the HTML, rows with another version are out of date
+pub fn version(config: &MarkdownConfig) -> u32 {
+    let extensions = [
+        config.tables,
+        config.strikethrough,
+        config.autolinks,
+        config.footnotes,
+    ];
+
+    let extension_bits = extensions
+        .iter()
+        .enumerate()
+        .fold(0, |bits, (i, &is_on)| bits | u32::from(is_on) << i);
+
+    RENDERER_VERSION << extensions.len() | extension_bits
+}
+
+fn options(config: &MarkdownConfig) -> Options {
+    let constructs = Constructs {
+        gfm_table: config.tables,
+        gfm_strikethrough: config.strikethrough,
+        gfm_autolink_literal: config.autolinks,
+        gfm_footnote_definition: config.footnotes,
+        gfm_label_start_footnote: config.footnotes,
+        ..Constructs::default()
+    };
+
+    Options {
+        parse: ParseOptions {
+            constructs,
+            ..ParseOptions::default()
+        },
+        compile: CompileOptions::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Inputs are `tests/markdown/<name>.md`, the HTML they should render to is next to them
+    macro_rules! assert_renders {
+        ($name:literal, $config:expr) => {
+            assert_renders!($name, $name, $config)
+        };
+        ($input:literal, $expected:literal, $config:expr) => {
+            assert_eq!(
+                markdown(
+                    include_str!(concat!("../tests/markdown/", $input, ".md")),
+                    &$config
+                ),
+                include_str!(concat!("../tests/markdown/", $expected, ".html")),
+            )
+        };
+    }
+
+    fn no_extensions() -> MarkdownConfig {
+        MarkdownConfig {
+            tables: false,
+            strikethrough: false,
+            autolinks: false,
+            footnotes: false,
+        }
+    }
+
+    #[test]
+    fn sanitization() {
+        assert_renders!("sanitization", MarkdownConfig::default());
+    }
+
+    #[test]
+    fn sanitization_is_not_configurable() {
+        assert_renders!("sanitization", no_extensions());
+    }
+
+    #[test]
+    fn links() {
+        assert_renders!("links", MarkdownConfig::default());
+    }
+
+    #[test]
+    fn code_blocks() {
+        assert_renders!("code_blocks", MarkdownConfig::default());
+    }
+
+    #[test]
+    fn extensions_on() {
+        assert_renders!("extensions", MarkdownConfig::default());
+    }
+
+    #[test]
+    fn extensions_off() {
+        assert_renders!("extensions", "extensions_off", no_extensions());
+    }
+
+    #[test]
+    fn single_extensions() {
+        let only_tables = MarkdownConfig {
+            tables: true,
+            ..no_extensions()
+        };
+        let only_strikethrough = MarkdownConfig {
+            strikethrough: true,
+            ..no_extensions()
+        };
+        let only_autolinks = MarkdownConfig {
+            autolinks: true,
+            ..no_extensions()
+        };
+        let only_footnotes = MarkdownConfig {
+            footnotes: true,
+            ..no_extensions()
+        };
+
+        assert_renders!("extensions", "extensions_tables", only_tables);
+        assert_renders!("extensions", "extensions_strikethrough", only_strikethrough);
+        assert_renders!("extensions", "extensions_autolinks", only_autolinks);
+        assert_renders!("extensions", "extensions_footnotes", only_footnotes);
+    }
+
+    #[test]
+    fn version_changes_with_every_option() {
+        let configs = [
+            MarkdownConfig::default(),
+            no_extensions(),
+            MarkdownConfig {
+                tables: false,
+                ..MarkdownConfig::default()
+            },
+            MarkdownConfig {
+                strikethrough: false,
+                ..MarkdownConfig::default()
+            },
+            MarkdownConfig {
+                autolinks: false,
+                ..MarkdownConfig::default()
+            },
+            MarkdownConfig {
+                footnotes: false,
+                ..MarkdownConfig::default()
+            },
+        ];
+
+        let mut versions: Vec<u32> = configs.iter().map(version).collect();
+        versions.sort_unstable();
+        versions.dedup();
+
+        assert_eq!(versions.len(), configs.len());
+    }
+}
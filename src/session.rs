@@ -1,6 +1,13 @@
-use crate::types::user::role::Role as UserRole;
+use crate::db::{fetch_active_ban, fetch_user_roles, lift_ban};
+use crate::types::api_token::{
+    self,
+    scope::{Scope, Scopes},
+};
+use crate::types::ban::ActiveBan;
+use crate::types::user::pfp::Pfp;
+use crate::types::user::role::{Permissions, Role, BANNED};
 use crate::types::{default_mini_pfp, id::Id};
-use actix_web::{cookie::Cookie, HttpRequest, HttpResponseBuilder};
+use actix_web::{cookie::Cookie, http::header, HttpRequest, HttpResponseBuilder};
 use serde::Serialize;
 use sqlx::mysql::MySqlPool;
 
@@ -22,8 +29,19 @@ pub trait RemoveSessionCookie {
 pub struct User {
     pub id: Id,
     pub username: String,
-    pub role: UserRole,
-    pub mini_pfp: String,
+    pub roles: Vec<String>,
+    pub permissions: Permissions,
+    pub mini_pfp: Pfp,
+    // Set while the user is banned, so pages can tell them why
+    pub ban: Option<ActiveBan>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum ApiAuthError {
+    // No credentials, or they are wrong or expired
+    Unauthorized,
+    // The token is valid but wasn't given this scope
+    MissingScope(Scope),
 }
 
 pub async fn auth(db_pool: &MySqlPool, req: &HttpRequest) -> Result<User, ()> {
@@ -34,7 +52,103 @@ pub async fn auth(db_pool: &MySqlPool, req: &HttpRequest) -> Result<User, ()> {
         .map_err(|_| ())?
         .ok_or(())?;
 
-    let user = User::from_raw(raw_user)?;
+    user_from_raw(db_pool, raw_user).await
+}
+
+// `/api/v1` also takes personal API tokens as `Authorization: Bearer <token>`,
+// without one it falls back to the session cookie, which allows every scope
+pub async fn auth_api(
+    db_pool: &MySqlPool,
+    req: &HttpRequest,
+    scope: Scope,
+) -> Result<User, ApiAuthError> {
+    let Some(token) = bearer_token(req) else {
+        return auth(db_pool, req)
+            .await
+            .map_err(|_| ApiAuthError::Unauthorized);
+    };
+
+    let raw_token = RawTokenUser::fetch_by_token_hash(db_pool, &api_token::hash(token))
+        .await
+        .map_err(|_| ApiAuthError::Unauthorized)?
+        .ok_or(ApiAuthError::Unauthorized)?;
+
+    if !Scopes::from_stored(&raw_token.scopes).contains(scope) {
+        return Err(ApiAuthError::MissingScope(scope));
+    }
+
+    touch_api_token(db_pool, raw_token.token_id)
+        .await
+        .map_err(|_| ApiAuthError::Unauthorized)?;
+
+    let raw_user = RawUser {
+        id: raw_token.id,
+        username: raw_token.username,
+        mini_pfp: raw_token.mini_pfp,
+    };
+
+    user_from_raw(db_pool, raw_user)
+        .await
+        .map_err(|_| ApiAuthError::Unauthorized)
+}
+
+// For work done on a user's behalf outside of a request, like publishing a scheduled post
+pub async fn user_by_id(db_pool: &MySqlPool, id: Id) -> Result<User, ()> {
+    let raw_user = RawUser::fetch_by_id(db_pool, id)
+        .await
+        .map_err(|_| ())?
+        .ok_or(())?;
+
+    user_from_raw(db_pool, raw_user).await
+}
+
+fn bearer_token(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+// Roles and bans are looked up the same way however the user signed in
+async fn user_from_raw(db_pool: &MySqlPool, raw_user: RawUser) -> Result<User, ()> {
+    let mut roles = fetch_user_roles(db_pool, raw_user.id)
+        .await
+        .map_err(|_| ())?;
+
+    let mut ban = None;
+
+    if roles.iter().any(|role| role.name == BANNED) {
+        let raw_ban = fetch_active_ban(db_pool, raw_user.id)
+            .await
+            .map_err(|_| ())?;
+
+        match raw_ban {
+            // Expired bans are lifted by the first request that notices
+            Some(raw_ban) if raw_ban.is_expired => {
+                let mut tx = db_pool.begin().await.map_err(|_| ())?;
+
+                lift_ban(&mut tx, raw_user.id, None).await.map_err(|_| ())?;
+
+                tx.commit().await.map_err(|_| ())?;
+
+                roles = fetch_user_roles(db_pool, raw_user.id)
+                    .await
+                    .map_err(|_| ())?;
+            }
+            Some(raw_ban) => ban = Some(ActiveBan::from_raw(raw_ban)),
+            // Banned before bans were recorded, so there is no end date or reason
+            None => {
+                ban = Some(ActiveBan {
+                    reason: String::new(),
+                    expires_at: None,
+                })
+            }
+        }
+    }
+
+    let user = User::from_raw(raw_user, roles, ban);
 
     Ok(user)
 }
@@ -74,17 +188,15 @@ impl RemoveSessionCookie for HttpResponseBuilder {
 }
 
 impl User {
-    fn from_raw(raw: RawUser) -> Result<Self, ()> {
-        let role = UserRole::from_str(&raw.role)?;
-
-        let user = Self {
+    fn from_raw(raw: RawUser, roles: Vec<Role>, ban: Option<ActiveBan>) -> Self {
+        Self {
             id: raw.id,
             username: raw.username,
-            role,
-            mini_pfp: raw.mini_pfp.unwrap_or(default_mini_pfp()),
-        };
-
-        Ok(user)
+            permissions: Permissions::of(&roles),
+            roles: roles.into_iter().map(|role| role.name).collect(),
+            mini_pfp: Pfp::from_variants(raw.mini_pfp, default_mini_pfp()),
+            ban,
+        }
     }
 }
 
@@ -92,10 +204,65 @@ impl User {
 struct RawUser {
     pub id: Id,
     pub username: String,
-    pub role: String,
     pub mini_pfp: Option<String>,
 }
 
+#[derive(Debug)]
+struct RawTokenUser {
+    pub token_id: Id,
+    pub scopes: String,
+    pub id: Id,
+    pub username: String,
+    pub mini_pfp: Option<String>,
+}
+
+impl RawTokenUser {
+    pub async fn fetch_by_token_hash(
+        db_pool: &MySqlPool,
+        token_hash: &str,
+    ) -> sqlx::Result<Option<Self>> {
+        sqlx::query_as!(
+            Self,
+            "
+            SELECT
+                api_tokens.id AS token_id,
+                api_tokens.scopes,
+                users.id,
+                users.username,
+                pfps.variants AS mini_pfp
+            FROM api_tokens
+                JOIN users ON api_tokens.user_id = users.id
+                LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
+            WHERE
+                api_tokens.token_hash = ?
+                AND (api_tokens.expires_at IS NULL OR api_tokens.expires_at > NOW())
+            ",
+            token_hash
+        )
+        .fetch_optional(db_pool)
+        .await
+    }
+}
+
+// Written at most once a minute, a busy bot doesn't need a write per request
+async fn touch_api_token(db_pool: &MySqlPool, token_id: Id) -> sqlx::Result<()> {
+    sqlx::query!(
+        "
+        UPDATE api_tokens
+        SET
+            last_used_at = NOW()
+        WHERE
+            id = ?
+            AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL 1 MINUTE)
+        ",
+        token_id
+    )
+    .execute(db_pool)
+    .await?;
+
+    Ok(())
+}
+
 impl RawUser {
     pub async fn fetch_by_session(
         db_pool: &MySqlPool,
@@ -107,10 +274,9 @@ impl RawUser {
             SELECT
                 users.id,
                 users.username,
-                users.role,
-                CONCAT(files.id, \".\", files.extension) AS mini_pfp
+                pfps.variants AS mini_pfp
             FROM users
-                LEFT JOIN files ON users.mini_pfp_file_id = files.id
+                LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
             WHERE
                 session = ?
             ",
@@ -119,4 +285,23 @@ impl RawUser {
         .fetch_optional(db_pool)
         .await
     }
+
+    pub async fn fetch_by_id(db_pool: &MySqlPool, id: Id) -> sqlx::Result<Option<Self>> {
+        sqlx::query_as!(
+            Self,
+            "
+            SELECT
+                users.id,
+                users.username,
+                pfps.variants AS mini_pfp
+            FROM users
+                LEFT JOIN user_pfps AS pfps ON users.id = pfps.user_id AND pfps.size = 'mini'
+            WHERE
+                users.id = ?
+            ",
+            id
+        )
+        .fetch_optional(db_pool)
+        .await
+    }
 }
@@ -0,0 +1,328 @@
+use crate::config::SpamConfig;
+use lazy_static::lazy_static;
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use std::str::FromStr;
+use thiserror::Error;
+
+// Checks posts and comments against the rules in `[spam]`.
+// Nothing here touches the database, callers fetch the recent content
+// to compare against, so the rules can be tried out on plain strings
+
+// Short texts like "Thanks!" are posted over and over by real people
+pub const DUPLICATE_MIN_CHAR_COUNT: usize = 32;
+// How many of the latest posts or comments are compared against
+pub const DUPLICATE_SCAN_LIMIT: u64 = 200;
+
+lazy_static! {
+    static ref LINK_REGEX: Regex = Regex::new(r"(?i)\b(?:https?://|www\.)\S+").unwrap();
+    static ref WHITESPACE_REGEX: Regex = Regex::new(r"\s+").unwrap();
+}
+
+// What happens to content that breaks a rule
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    // Refuse it with an error
+    Reject,
+    // Let it through but send it to moderators,
+    // posts wait for approval and comments are reported
+    #[default]
+    Hold,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Error)]
+pub enum Rule {
+    #[error("Contains a banned word")]
+    BannedWord,
+    #[error("Matches a banned pattern")]
+    BannedPattern,
+    #[error("Too many links for a new account")]
+    TooManyLinks,
+    #[error("Repeats something posted recently")]
+    Duplicate,
+}
+
+#[derive(Debug, Clone)]
+pub struct Filter {
+    pub action: Action,
+    // All banned words in one case-insensitive whole word regex
+    banned_words: Option<Regex>,
+    banned_patterns: Vec<Regex>,
+    new_account_days: u32,
+    new_account_max_links: usize,
+}
+
+#[derive(Debug)]
+pub struct Submission<'a> {
+    pub text: &'a str,
+    pub account_age_days: i64,
+    // Other content posted within `duplicate_window_hours`
+    pub recent: &'a [String],
+}
+
+impl FromStr for Action {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reject" => Ok(Self::Reject),
+            "hold" => Ok(Self::Hold),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Filter {
+    pub fn from_config(config: &SpamConfig) -> Result<Self, regex::Error> {
+        let words: Vec<String> = config
+            .banned_words
+            .iter()
+            .map(|word| word.trim())
+            .filter(|word| !word.is_empty())
+            .map(regex::escape)
+            .collect();
+
+        let banned_words = if words.is_empty() {
+            None
+        } else {
+            let pattern = format!(r"\b(?:{})\b", words.join("|"));
+
+            Some(RegexBuilder::new(&pattern).case_insensitive(true).build()?)
+        };
+
+        let banned_patterns = config
+            .banned_patterns
+            .iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            action: config.action,
+            banned_words,
+            banned_patterns,
+            new_account_days: config.new_account_days,
+            new_account_max_links: config.new_account_max_links,
+        })
+    }
+
+    // The first rule the submission breaks, if any
+    pub fn check(&self, submission: &Submission) -> Option<Rule> {
+        let text = submission.text;
+
+        if let Some(banned_words) = &self.banned_words {
+            if banned_words.is_match(text) {
+                return Some(Rule::BannedWord);
+            }
+        }
+
+        if self
+            .banned_patterns
+            .iter()
+            .any(|pattern| pattern.is_match(text))
+        {
+            return Some(Rule::BannedPattern);
+        }
+
+        if submission.account_age_days < i64::from(self.new_account_days)
+            && count_links(text) > self.new_account_max_links
+        {
+            return Some(Rule::TooManyLinks);
+        }
+
+        if is_duplicate(text, submission.recent) {
+            return Some(Rule::Duplicate);
+        }
+
+        None
+    }
+}
+
+// Everything of a post that is checked, the recent posts are joined the same way
+pub fn post_text(title: &str, description: &str, content: &str) -> String {
+    format!("{title}\n{description}\n{content}")
+}
+
+pub fn count_links(text: &str) -> usize {
+    LINK_REGEX.find_iter(text).count()
+}
+
+// Same text up to case and whitespace
+pub fn is_duplicate(text: &str, recent: &[String]) -> bool {
+    let text = normalize(text);
+
+    if text.chars().count() < DUPLICATE_MIN_CHAR_COUNT {
+        return false;
+    }
+
+    recent.iter().any(|other| normalize(other) == text)
+}
+
+fn normalize(text: &str) -> String {
+    WHITESPACE_REGEX
+        .replace_all(text.trim(), " ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD_ACCOUNT_DAYS: i64 = 365;
+
+    fn filter(config: SpamConfig) -> Filter {
+        Filter::from_config(&config).unwrap()
+    }
+
+    fn check(filter: &Filter, text: &str, account_age_days: i64) -> Option<Rule> {
+        filter.check(&Submission {
+            text,
+            account_age_days,
+            recent: &[],
+        })
+    }
+
+    #[test]
+    fn clean_text_passes() {
+        let filter = filter(SpamConfig::default());
+
+        assert_eq!(check(&filter, "Just a regular comment", 0), None);
+    }
+
+    #[test]
+    fn counts_links() {
+        assert_eq!(count_links("no links here"), 0);
+        assert_eq!(
+            count_links("see https://example.com and http://example.org"),
+            2
+        );
+        assert_eq!(count_links("WWW.EXAMPLE.COM"), 1);
+        // Needs a scheme or `www.`
+        assert_eq!(count_links("example.com"), 0);
+    }
+
+    #[test]
+    fn link_limit_only_applies_to_new_accounts() {
+        let filter = filter(SpamConfig {
+            new_account_days: 3,
+            new_account_max_links: 2,
+            ..SpamConfig::default()
+        });
+
+        let two_links = "https://a.example https://b.example";
+        let three_links = "https://a.example https://b.example https://c.example";
+
+        assert_eq!(check(&filter, two_links, 0), None);
+        assert_eq!(check(&filter, three_links, 0), Some(Rule::TooManyLinks));
+        assert_eq!(check(&filter, three_links, 2), Some(Rule::TooManyLinks));
+        assert_eq!(check(&filter, three_links, 3), None);
+        assert_eq!(check(&filter, three_links, OLD_ACCOUNT_DAYS), None);
+    }
+
+    #[test]
+    fn banned_words_match_whole_words_ignoring_case() {
+        let filter = filter(SpamConfig {
+            banned_words: vec!["casino".to_owned(), " ".to_owned(), "spam.bot".to_owned()],
+            ..SpamConfig::default()
+        });
+
+        assert_eq!(
+            check(&filter, "Best CASINO in town", OLD_ACCOUNT_DAYS),
+            Some(Rule::BannedWord)
+        );
+        assert_eq!(check(&filter, "casinos", OLD_ACCOUNT_DAYS), None);
+        // Words are escaped, not regular expressions
+        assert_eq!(check(&filter, "spamxbot", OLD_ACCOUNT_DAYS), None);
+        assert_eq!(
+            check(&filter, "sent by spam.bot", OLD_ACCOUNT_DAYS),
+            Some(Rule::BannedWord)
+        );
+        // Blank words are skipped instead of matching every space
+        assert_eq!(check(&filter, "a b c", OLD_ACCOUNT_DAYS), None);
+    }
+
+    #[test]
+    fn banned_patterns_match_anywhere() {
+        let filter = filter(SpamConfig {
+            banned_patterns: vec![r"buy\s+now".to_owned()],
+            ..SpamConfig::default()
+        });
+
+        assert_eq!(
+            check(&filter, "please buy   now!", OLD_ACCOUNT_DAYS),
+            Some(Rule::BannedPattern)
+        );
+        assert_eq!(check(&filter, "buying is fun", OLD_ACCOUNT_DAYS), None);
+    }
+
+    #[test]
+    fn invalid_pattern_is_an_error() {
+        let config = SpamConfig {
+            banned_patterns: vec!["(".to_owned()],
+            ..SpamConfig::default()
+        };
+
+        assert!(Filter::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn duplicates_ignore_case_and_whitespace() {
+        let text = "This exact sentence was posted a moment ago";
+        let recent = vec!["  this EXACT sentence\nwas posted   a moment ago ".to_owned()];
+
+        assert!(is_duplicate(text, &recent));
+        assert!(!is_duplicate(
+            "Something else entirely, long enough",
+            &recent
+        ));
+        assert!(!is_duplicate(text, &[]));
+    }
+
+    #[test]
+    fn short_texts_are_never_duplicates() {
+        let text = "Thanks!";
+
+        assert!(text.chars().count() < DUPLICATE_MIN_CHAR_COUNT);
+        assert!(!is_duplicate(text, &[text.to_owned()]));
+    }
+
+    #[test]
+    fn first_broken_rule_wins() {
+        let filter = filter(SpamConfig {
+            banned_words: vec!["casino".to_owned()],
+            banned_patterns: vec!["jackpot".to_owned()],
+            ..SpamConfig::default()
+        });
+
+        let text = "casino jackpot https://a.example https://b.example https://c.example";
+        let recent = vec![text.to_owned()];
+
+        let submission = Submission {
+            text,
+            account_age_days: 0,
+            recent: &recent,
+        };
+
+        assert_eq!(filter.check(&submission), Some(Rule::BannedWord));
+    }
+
+    #[test]
+    fn repeated_post_is_a_duplicate() {
+        let filter = filter(SpamConfig::default());
+
+        let text = post_text(
+            "Title",
+            "A description",
+            "Some content long enough to count",
+        );
+        let recent = vec![text.clone()];
+
+        let submission = Submission {
+            text: &text,
+            account_age_days: OLD_ACCOUNT_DAYS,
+            recent: &recent,
+        };
+
+        assert_eq!(filter.check(&submission), Some(Rule::Duplicate));
+    }
+}
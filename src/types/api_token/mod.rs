@@ -0,0 +1,95 @@
+use super::id::Id;
+use super::lifetime::{Lifetime, LifetimeOption};
+use chrono::NaiveDateTime;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+pub mod name;
+pub mod scope;
+use scope::Scopes;
+
+// Personal tokens for `/api/v1`, sent as `Authorization: Bearer <token>`.
+// The token itself is shown once when it's created, only its hash is stored,
+// it's random enough that a plain SHA-256 can be looked up directly
+
+pub const TOKEN_PREFIX: &str = "neor_";
+const TOKEN_RANDOM_CHAR_COUNT: usize = 40;
+
+// Offered when creating a token
+pub const EXPIRIES: [Lifetime; 5] = [
+    Lifetime::Days(7),
+    Lifetime::Days(30),
+    Lifetime::Days(90),
+    Lifetime::Days(365),
+    Lifetime::Forever,
+];
+
+#[derive(Debug, Serialize)]
+pub struct ApiToken {
+    pub id: Id,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub created_at: String,
+    pub expires_at: Option<String>,
+    pub last_used_at: Option<String>,
+    pub is_expired: bool,
+}
+
+#[derive(Debug)]
+pub struct RawApiToken {
+    pub id: Id,
+    pub name: String,
+    pub scopes: String,
+    pub created_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub is_expired: bool,
+}
+
+pub fn expiry_label(expiry: Lifetime) -> String {
+    match expiry {
+        Lifetime::Days(days) => format!("{days} days"),
+        Lifetime::Forever => "Never expires".to_owned(),
+    }
+}
+
+pub fn expiry_options() -> Vec<LifetimeOption> {
+    Lifetime::options(&EXPIRIES, expiry_label)
+}
+
+impl ApiToken {
+    pub fn from_raw(raw: RawApiToken) -> Self {
+        Self {
+            id: raw.id,
+            name: raw.name,
+            scopes: Scopes::from_stored(&raw.scopes).labels(),
+            created_at: format_token_date(raw.created_at),
+            expires_at: raw.expires_at.map(format_token_date),
+            last_used_at: raw.last_used_at.map(format_token_date),
+            is_expired: raw.is_expired,
+        }
+    }
+}
+
+// A new token and the hash to store for it
+pub fn generate() -> (String, String) {
+    let random = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_RANDOM_CHAR_COUNT)
+        .map(char::from)
+        .collect::<String>();
+
+    let token = format!("{TOKEN_PREFIX}{random}");
+    let token_hash = hash(&token);
+
+    (token, token_hash)
+}
+
+pub fn hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn format_token_date(date: NaiveDateTime) -> String {
+    format!("{} UTC", date.format("%B %d · %Y %H:%M"))
+}
@@ -0,0 +1,26 @@
+pub const NAME_MAX_CHAR_COUNT: usize = 64;
+
+#[derive(Debug)]
+pub struct Name(String);
+
+impl AsRef<str> for Name {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Name {
+    pub fn parse(name: impl Into<String>) -> Result<Self, ()> {
+        let name = name.into().trim().to_owned();
+
+        if name.is_empty() {
+            return Err(());
+        }
+
+        if name.chars().count() > NAME_MAX_CHAR_COUNT {
+            return Err(());
+        }
+
+        Ok(Self(name))
+    }
+}
@@ -0,0 +1,103 @@
+use serde::Serialize;
+
+// What a token may be used for, a token never allows more than its user could do
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Scope {
+    Read,
+    Post,
+    Comment,
+    Moderate,
+}
+
+// For the scope checkboxes
+#[derive(Debug, Serialize)]
+pub struct ScopeOption {
+    pub value: String,
+    pub label: String,
+}
+
+// Stored as a comma separated list, e.g. `read,post`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scopes(Vec<Scope>);
+
+impl Scope {
+    pub const ALL: [Self; 4] = [Self::Read, Self::Post, Self::Comment, Self::Moderate];
+
+    pub fn from_str(s: &str) -> Result<Self, ()> {
+        Self::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or(())
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Post => "post",
+            Self::Comment => "comment",
+            Self::Moderate => "moderate",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Read => "Read",
+            Self::Post => "Create and edit posts",
+            Self::Comment => "Create and edit comments",
+            Self::Moderate => "Delete posts and comments",
+        }
+    }
+
+    pub fn options() -> Vec<ScopeOption> {
+        Self::ALL
+            .into_iter()
+            .map(|scope| ScopeOption {
+                value: scope.as_str().to_owned(),
+                label: scope.label().to_owned(),
+            })
+            .collect()
+    }
+}
+
+impl Scopes {
+    pub fn parse(scopes: impl IntoIterator<Item = Scope>) -> Result<Self, ()> {
+        let mut scopes = scopes.into_iter().collect::<Vec<_>>();
+
+        scopes.sort_by_key(|scope| Scope::ALL.iter().position(|s| s == scope));
+        scopes.dedup();
+
+        if scopes.is_empty() {
+            return Err(());
+        }
+
+        Ok(Self(scopes))
+    }
+
+    // Unknown scopes in the DB are dropped rather than granted
+    pub fn from_stored(s: &str) -> Self {
+        Self(
+            s.split(',')
+                .filter_map(|scope| Scope::from_str(scope.trim()).ok())
+                .collect(),
+        )
+    }
+
+    pub fn contains(&self, scope: Scope) -> bool {
+        self.0.contains(&scope)
+    }
+
+    pub fn as_string(&self) -> String {
+        self.0
+            .iter()
+            .map(Scope::as_str)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn labels(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .map(|scope| scope.label().to_owned())
+            .collect()
+    }
+}
@@ -0,0 +1,103 @@
+use super::default_mini_pfp;
+use super::id::Id;
+use super::lifetime::{Lifetime, LifetimeOption};
+use super::user::{pfp::Pfp, Preview as UserPreview};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+// Offered when banning someone
+pub const DURATIONS: [Lifetime; 6] = [
+    Lifetime::Days(1),
+    Lifetime::Days(3),
+    Lifetime::Days(7),
+    Lifetime::Days(30),
+    Lifetime::Days(365),
+    Lifetime::Forever,
+];
+
+pub fn duration_label(duration: Lifetime) -> String {
+    match duration {
+        Lifetime::Days(1) => "For 1 day".to_owned(),
+        Lifetime::Days(days) => format!("For {days} days"),
+        Lifetime::Forever => "Permanently".to_owned(),
+    }
+}
+
+pub fn duration_options() -> Vec<LifetimeOption> {
+    Lifetime::options(&DURATIONS, duration_label)
+}
+
+// What a banned user is told about their ban
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveBan {
+    pub reason: String,
+    pub expires_at: Option<String>,
+}
+
+impl ActiveBan {
+    pub fn from_raw(raw: RawActiveBan) -> Self {
+        Self {
+            reason: raw.reason,
+            expires_at: raw.expires_at.map(format_ban_date),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RawActiveBan {
+    pub reason: String,
+    pub expires_at: Option<NaiveDateTime>,
+    pub is_expired: bool,
+}
+
+// An entry of a user's ban history
+#[derive(Debug, Serialize)]
+pub struct Ban {
+    pub id: Id,
+    pub reason: String,
+    pub banned_by: Option<UserPreview>,
+    pub banned_at: String,
+    pub expires_at: Option<String>,
+    pub lifted_by: Option<String>,
+    pub lifted_at: Option<String>,
+    pub is_active: bool,
+}
+
+impl Ban {
+    pub fn from_raw(raw: RawBan) -> Self {
+        Self {
+            id: raw.id,
+            reason: raw.reason,
+            banned_by: raw
+                .banned_by_user_id
+                .zip(raw.banned_by_username)
+                .map(|(id, username)| UserPreview {
+                    id,
+                    username,
+                    mini_pfp: Pfp::from_variants(raw.banned_by_mini_pfp, default_mini_pfp()),
+                }),
+            banned_at: format_ban_date(raw.banned_at),
+            expires_at: raw.expires_at.map(format_ban_date),
+            lifted_by: raw.lifted_by_username,
+            lifted_at: raw.lifted_at.map(format_ban_date),
+            is_active: raw.lifted_at.is_none(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RawBan {
+    pub id: Id,
+    pub reason: String,
+    pub banned_by_user_id: Option<Id>,
+    pub banned_by_username: Option<String>,
+    pub banned_by_mini_pfp: Option<String>,
+    pub banned_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+    pub lifted_by_username: Option<String>,
+    pub lifted_at: Option<NaiveDateTime>,
+}
+
+pub fn format_ban_date(date: NaiveDateTime) -> String {
+    format!("{} UTC", date.format("%B %d · %Y %H:%M"))
+}
@@ -0,0 +1,241 @@
+use crate::config::Config;
+use crate::db::fetch_has_posted;
+use crate::policy::{self, Action, Resource};
+use crate::session::User;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Serialize;
+use sha2::Sha256;
+use sqlx::mysql::MySqlPool;
+use uuid::Uuid;
+
+// A small arithmetic question written out in words, asked before
+// signing up (and optionally before the first post) to keep simple bots out.
+// Nothing is stored when it's asked, the id carries when it was asked and a
+// signature the question is worked out from. Answered ids are kept until
+// they expire so every challenge can be answered once
+
+pub const ANSWER_MAX_CHAR_COUNT: usize = 8;
+
+const NUMBERS: [&str; 21] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+    "twenty",
+];
+
+lazy_static! {
+    // Made anew on every start, which voids the challenges asked before it
+    static ref KEY: [u8; 32] = rand::thread_rng().gen();
+}
+
+#[derive(Debug, Serialize)]
+pub struct Challenge {
+    // Goes into a hidden form field, `<asked at>.<nonce>.<signature>`
+    pub id: String,
+    pub question: String,
+}
+
+impl Challenge {
+    pub fn generate() -> Self {
+        Self::asked_at(Utc::now().timestamp())
+    }
+
+    fn asked_at(asked_at: i64) -> Self {
+        let nonce = Uuid::new_v4().to_string();
+        let signature = mac(asked_at, &nonce).finalize().into_bytes();
+
+        let mut seed = [0; 32];
+        seed.copy_from_slice(&signature);
+
+        let (question, _) = question_and_answer(seed);
+
+        Self {
+            id: format!("{asked_at}.{nonce}.{}", hex::encode(signature)),
+            question,
+        }
+    }
+
+    // Accounts that haven't posted yet when `challenge.on_first_post` is set
+    pub async fn is_required_for_post(
+        db_pool: &MySqlPool,
+        user: &User,
+        config: &Config,
+    ) -> sqlx::Result<bool> {
+        if !config.challenge.on_first_post
+            || policy::can(user, Action::View, Resource::Held, config)
+        {
+            return Ok(false);
+        }
+
+        Ok(!fetch_has_posted(db_pool, user.id).await?)
+    }
+
+    // Uses the challenge up whether the answer is right or not,
+    // so a bot can't keep guessing
+    pub async fn verify(
+        db_pool: &MySqlPool,
+        id: &str,
+        answer: &str,
+        expire_minutes: u32,
+    ) -> sqlx::Result<bool> {
+        let Some((nonce, seed)) = parse_id(id, expire_minutes) else {
+            return Ok(false);
+        };
+
+        let insert_result = sqlx::query!(
+            "
+            INSERT IGNORE INTO used_challenges
+            (
+                id,
+                used_at
+            )
+            VALUES
+            (
+                ?,
+                NOW()
+            )
+            ",
+            nonce
+        )
+        .execute(db_pool)
+        .await?;
+
+        // Answered before
+        if insert_result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        let (_, expected) = question_and_answer(seed);
+
+        Ok(answer.trim().parse::<u32>().ok() == Some(expected))
+    }
+}
+
+fn mac(asked_at: i64, nonce: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(&*KEY).expect("HMAC takes keys of any size");
+
+    mac.update(format!("{asked_at}.{nonce}").as_bytes());
+
+    mac
+}
+
+// The nonce and the seed of the question, for ids this server signed that haven't expired
+fn parse_id(id: &str, expire_minutes: u32) -> Option<(&str, [u8; 32])> {
+    let mut parts = id.splitn(3, '.');
+
+    let asked_at = parts.next()?.parse::<i64>().ok()?;
+    let nonce = parts.next()?;
+    let signature = hex::decode(parts.next()?).ok()?;
+
+    let age_seconds = Utc::now().timestamp() - asked_at;
+
+    if !(0..i64::from(expire_minutes) * 60).contains(&age_seconds) {
+        return None;
+    }
+
+    mac(asked_at, nonce).verify_slice(&signature).ok()?;
+
+    let mut seed = [0; 32];
+    seed.copy_from_slice(&signature);
+
+    Some((nonce, seed))
+}
+
+fn question_and_answer(seed: [u8; 32]) -> (String, u32) {
+    let mut rng = StdRng::from_seed(seed);
+
+    let a = rng.gen_range(2..NUMBERS.len());
+    let b = rng.gen_range(2..NUMBERS.len());
+
+    let (question, answer) = match rng.gen_range(0..3) {
+        0 => (
+            format!("What is {} plus {}?", NUMBERS[a], NUMBERS[b]),
+            a + b,
+        ),
+        1 => {
+            let (a, b) = (a.max(b), a.min(b));
+
+            (
+                format!("What is {} minus {}?", NUMBERS[a], NUMBERS[b]),
+                a - b,
+            )
+        }
+        _ => {
+            let b = b % 10;
+
+            (
+                format!("What is {} times {}?", NUMBERS[a], NUMBERS[b]),
+                a * b,
+            )
+        }
+    };
+
+    (question, answer as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPIRE_MINUTES: u32 = 30;
+
+    #[test]
+    fn asks_what_it_checks() {
+        let challenge = Challenge::generate();
+
+        let (_, seed) = parse_id(&challenge.id, EXPIRE_MINUTES).unwrap();
+
+        assert_eq!(question_and_answer(seed).0, challenge.question);
+    }
+
+    #[test]
+    fn rejects_tampered_ids() {
+        let challenge = Challenge::generate();
+        let other = Challenge::generate();
+
+        let (asked_at, rest) = challenge.id.split_once('.').unwrap();
+        let (unsigned, _) = challenge.id.rsplit_once('.').unwrap();
+        let (_, other_signature) = other.id.rsplit_once('.').unwrap();
+
+        let earlier = format!("{}.{rest}", asked_at.parse::<i64>().unwrap() - 1);
+        let swapped = format!("{unsigned}.{other_signature}");
+
+        assert!(parse_id(&earlier, EXPIRE_MINUTES).is_none());
+        assert!(parse_id(&swapped, EXPIRE_MINUTES).is_none());
+        assert!(parse_id("", EXPIRE_MINUTES).is_none());
+        assert!(parse_id(&challenge.id.replace('.', ""), EXPIRE_MINUTES).is_none());
+    }
+
+    #[test]
+    fn rejects_expired_ids() {
+        let now = Utc::now().timestamp();
+
+        let fresh = Challenge::asked_at(now - 60);
+        let expired = Challenge::asked_at(now - i64::from(EXPIRE_MINUTES) * 60);
+        let ahead = Challenge::asked_at(now + 60);
+
+        assert!(parse_id(&fresh.id, EXPIRE_MINUTES).is_some());
+        assert!(parse_id(&expired.id, EXPIRE_MINUTES).is_none());
+        assert!(parse_id(&ahead.id, EXPIRE_MINUTES).is_none());
+    }
+}
@@ -1,7 +1,9 @@
-use super::default_mini_pfp;
 use super::id::Id;
 use super::post::format_posted_at;
-use super::user::Preview as UserPreview;
+use super::user::{pfp::Pfp, Preview as UserPreview};
+use super::{default_mini_pfp, REMOVED_PLACEHOLDER};
+use crate::config::Config;
+use crate::policy::{self, Action, Authored, Resource};
 use crate::session::User;
 use chrono::NaiveDateTime;
 use serde::Serialize;
@@ -21,16 +23,44 @@ pub struct Comment {
     pub posted_at: String,
     pub modified_at: Option<String>,
 
+    pub is_deleted: bool,
+    // Only counted for those who can resolve the reports
+    pub open_report_count: i64,
+
     pub is_repliable: bool,
     pub is_editable: bool,
     pub is_anonymisable: bool,
     pub is_deletable: bool,
+    pub is_restorable: bool,
+    pub is_reportable: bool,
+    pub is_resolvable: bool,
 }
 
 impl Comment {
-    pub fn from_raw<'a>(raw: RawComment, user: impl Into<Option<&'a User>>) -> Self {
+    pub fn from_raw<'a>(
+        raw: RawComment,
+        user: impl Into<Option<&'a User>>,
+        config: &Config,
+    ) -> Self {
         let user = user.into();
 
+        let authored = Authored {
+            posted_by_user_id: raw.posted_by_user_id,
+            posted_at: raw.posted_at,
+        };
+        let can = |action, resource| {
+            user.map(|user| policy::can(user, action, resource, config))
+                .unwrap_or(false)
+        };
+
+        let is_deleted = raw.deleted_at.is_some();
+        let is_resolvable = can(Action::Resolve, Resource::Comment(authored));
+        let is_restorable = is_deleted && can(Action::Restore, Resource::Comment(authored));
+
+        // Keep the comment in place so replies still make sense,
+        // but only those who can restore it see what was removed
+        let is_hidden = is_deleted && !is_restorable;
+
         Self {
             id: raw.id,
             post_id: raw.post_id,
@@ -42,45 +72,40 @@ impl Comment {
                     .map(|(id, username)| UserPreview {
                         id,
                         username,
-                        mini_pfp: raw.reply_to_mini_pfp.unwrap_or(default_mini_pfp()),
+                        mini_pfp: Pfp::from_variants(raw.reply_to_mini_pfp, default_mini_pfp()),
                     }),
             }),
-            content: raw.content,
+            content: if is_hidden {
+                REMOVED_PLACEHOLDER.to_owned()
+            } else {
+                raw.content
+            },
             posted_by: raw
                 .posted_by_user_id
                 .zip(raw.posted_by_username)
+                .filter(|_| !is_hidden)
                 .map(|(id, username)| UserPreview {
                     id,
                     username,
-                    mini_pfp: raw.posted_by_mini_pfp.unwrap_or(default_mini_pfp()),
+                    mini_pfp: Pfp::from_variants(raw.posted_by_mini_pfp, default_mini_pfp()),
                 }),
             posted_at: format_posted_at(raw.posted_at),
-            modified_at: raw.modified_at.map(format_posted_at),
+            modified_at: raw.modified_at.filter(|_| !is_hidden).map(format_posted_at),
 
-            // TODO: move out the functions and reuse them in API
-            is_repliable: user.map(|user| user.role.can_reply()).unwrap_or(false),
-            is_editable: user
-                .map(|user| {
-                    let hours_since_posted_at =
-                        (chrono::Utc::now().naive_utc() - raw.posted_at).num_hours();
+            is_deleted,
+            open_report_count: if is_resolvable {
+                raw.open_report_count
+            } else {
+                0
+            },
 
-                    // TODO: Make edit time window configurable
-                    raw.posted_by_user_id
-                        .map(|id| user.id == id && user.role.can_edit_comments())
-                        .unwrap_or(false)
-                        && hours_since_posted_at < 2
-                })
-                .unwrap_or(false),
-            is_anonymisable: user
-                .map(|user| {
-                    raw.posted_by_user_id
-                        .map(|id| user.id == id && user.role.can_anonymise_comments())
-                        .unwrap_or(false)
-                })
-                .unwrap_or(false),
-            is_deletable: user
-                .map(|user| user.role.can_delete_comments())
-                .unwrap_or(false),
+            is_repliable: !is_deleted && can(Action::Create, Resource::Replies),
+            is_editable: !is_deleted && can(Action::Edit, Resource::Comment(authored)),
+            is_anonymisable: !is_deleted && can(Action::Anonymise, Resource::Comment(authored)),
+            is_deletable: !is_deleted && can(Action::Delete, Resource::Comment(authored)),
+            is_restorable,
+            is_reportable: !is_deleted && can(Action::Create, Resource::Reports),
+            is_resolvable,
         }
     }
 }
@@ -99,4 +124,6 @@ pub struct RawComment {
     pub posted_by_mini_pfp: Option<String>,
     pub posted_at: NaiveDateTime,
     pub modified_at: Option<NaiveDateTime>,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub open_report_count: i64,
 }
@@ -0,0 +1,63 @@
+use super::id::Id;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+// As sent by `<input type="datetime-local">`, with or without seconds
+const PUBLISH_AT_FORMATS: [&str; 2] = ["%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M:%S"];
+const PUBLISH_AT_INPUT_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Draft {
+    pub id: Id,
+    pub title: String,
+    pub description: String,
+    pub tags: String,
+    pub content: String,
+    pub publish_at: Option<String>,
+    // `publish_at` again, the way the schedule input wants it
+    pub publish_at_input: Option<String>,
+    pub last_error: Option<String>,
+    pub updated_at: String,
+}
+
+#[derive(Debug)]
+pub struct RawDraft {
+    pub id: Id,
+    pub title: String,
+    pub description: String,
+    pub tags: String,
+    pub content: String,
+    pub publish_at: Option<NaiveDateTime>,
+    pub last_error: Option<String>,
+    pub updated_at: NaiveDateTime,
+}
+
+impl Draft {
+    pub fn from_raw(raw: RawDraft) -> Self {
+        Self {
+            id: raw.id,
+            title: raw.title,
+            description: raw.description,
+            tags: raw.tags,
+            content: raw.content,
+            publish_at: raw.publish_at.map(format_date),
+            publish_at_input: raw
+                .publish_at
+                .map(|publish_at| publish_at.format(PUBLISH_AT_INPUT_FORMAT).to_string()),
+            last_error: raw.last_error,
+            updated_at: format_date(raw.updated_at),
+        }
+    }
+}
+
+// Times are UTC, same as everything else in the database
+pub fn parse_publish_at(publish_at: &str) -> Result<NaiveDateTime, ()> {
+    PUBLISH_AT_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(publish_at.trim(), format).ok())
+        .ok_or(())
+}
+
+fn format_date(date: NaiveDateTime) -> String {
+    format!("{}", date.format("%B %d · %Y %H:%M UTC"))
+}
@@ -1 +1,23 @@
 pub type Id = u64;
+
+// Ids to look up at once, bound as `BETWEEN ? AND ? AND FIND_IN_SET(id, ?)`.
+// A list can't be bound, the range keeps the lookup on the index
+#[derive(Debug)]
+pub struct IdSet {
+    pub min: Id,
+    pub max: Id,
+    pub list: String,
+}
+
+impl IdSet {
+    // `None` when there is nothing to look up
+    pub fn new(ids: impl IntoIterator<Item = Id>) -> Option<Self> {
+        let ids: Vec<Id> = ids.into_iter().collect();
+
+        Some(Self {
+            min: *ids.iter().min()?,
+            max: *ids.iter().max()?,
+            list: ids.iter().map(Id::to_string).collect::<Vec<_>>().join(","),
+        })
+    }
+}
@@ -0,0 +1,58 @@
+use serde::Serialize;
+
+pub const MAX_LIFETIME_DAYS: u32 = 3650;
+
+// How long a ban or an API token lasts, picked from presets in a `<select>`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Lifetime {
+    Days(u32),
+    Forever,
+}
+
+// For the lifetime `<select>`
+#[derive(Debug, Serialize)]
+pub struct LifetimeOption {
+    pub value: String,
+    pub label: String,
+}
+
+impl Lifetime {
+    pub fn parse(s: &str) -> Result<Self, ()> {
+        if s == "never" {
+            return Ok(Self::Forever);
+        }
+
+        let days = s.parse::<u32>().map_err(|_| ())?;
+
+        if !(1..=MAX_LIFETIME_DAYS).contains(&days) {
+            return Err(());
+        }
+
+        Ok(Self::Days(days))
+    }
+
+    pub fn as_string(&self) -> String {
+        match self {
+            Self::Days(days) => days.to_string(),
+            Self::Forever => "never".to_owned(),
+        }
+    }
+
+    // `None` never ends
+    pub fn days(&self) -> Option<u32> {
+        match self {
+            Self::Days(days) => Some(*days),
+            Self::Forever => None,
+        }
+    }
+
+    pub fn options(presets: &[Self], label: fn(Self) -> String) -> Vec<LifetimeOption> {
+        presets
+            .iter()
+            .map(|&lifetime| LifetimeOption {
+                value: lifetime.as_string(),
+                label: label(lifetime),
+            })
+            .collect()
+    }
+}
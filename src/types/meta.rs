@@ -0,0 +1,48 @@
+use super::post::Post;
+use super::user::User;
+use serde::Serialize;
+
+// Canonical link, description and OpenGraph/Twitter tags rendered by the `meta` partial,
+// every URL in here is absolute so previews work wherever the link is pasted
+#[derive(Debug, Clone, Serialize)]
+pub struct Meta {
+    // OpenGraph `og:type`
+    pub kind: &'static str,
+    pub title: String,
+    pub description: String,
+    pub url: String,
+    pub image: Option<String>,
+}
+
+impl Meta {
+    // `author` is looked up separately for its normal sized pfp, posts only carry the mini one
+    pub fn post(domain: &str, post: &Post, author: Option<&User>) -> Self {
+        Self {
+            kind: "article",
+            title: post.title.clone(),
+            description: post.description.clone(),
+            url: format!("https://{domain}/post/{}", post.id),
+            image: author.map(|author| format!("https://{domain}{}", author.pfp.src)),
+        }
+    }
+
+    pub fn tag(domain: &str, tag: &str) -> Self {
+        Self {
+            kind: "website",
+            title: tag.to_owned(),
+            description: format!("Posts tagged {tag}"),
+            url: format!("https://{domain}/tag/{tag}"),
+            image: None,
+        }
+    }
+
+    pub fn user(domain: &str, user: &User) -> Self {
+        Self {
+            kind: "profile",
+            title: user.username.clone(),
+            description: user.description.clone(),
+            url: format!("https://{domain}/user/{}", user.username),
+            image: Some(format!("https://{domain}{}", user.pfp.src)),
+        }
+    }
+}
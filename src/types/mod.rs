@@ -1,8 +1,21 @@
+pub mod api_token;
+pub mod ban;
+pub mod challenge;
 pub mod comment;
+pub mod draft;
 pub mod id;
+pub mod lifetime;
+pub mod meta;
+pub mod mod_action;
 pub mod page;
 pub mod post;
+pub mod report;
+pub mod revision;
 pub mod user;
+pub mod webhook;
+
+// Shown in place of soft deleted posts and comments
+pub const REMOVED_PLACEHOLDER: &str = "[removed by moderator]";
 
 pub fn default_mini_pfp() -> String {
     "default.jpg".to_owned()
@@ -0,0 +1,236 @@
+use super::default_mini_pfp;
+use super::id::Id;
+use super::user::{pfp::Pfp, Preview as UserPreview};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+pub mod reason;
+use reason::Reason;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Kind {
+    DeletePost,
+    DeleteComment,
+    RestorePost,
+    RestoreComment,
+    ResolveReports,
+    DismissReports,
+    ApprovePost,
+    BanUser,
+    LiftBan,
+    ShadowBanUser,
+    LiftShadowBan,
+    RequireApproval,
+    WaiveApproval,
+    ChangeRoles,
+    ResetName,
+    ResetDescription,
+    ResetPfp,
+    CreateRole,
+    EditRole,
+    DeleteRole,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TargetType {
+    Post,
+    Comment,
+    User,
+    Tag,
+    Role,
+}
+
+impl Kind {
+    pub const ALL: [Self; 20] = [
+        Self::DeletePost,
+        Self::DeleteComment,
+        Self::RestorePost,
+        Self::RestoreComment,
+        Self::ResolveReports,
+        Self::DismissReports,
+        Self::ApprovePost,
+        Self::BanUser,
+        Self::LiftBan,
+        Self::ShadowBanUser,
+        Self::LiftShadowBan,
+        Self::RequireApproval,
+        Self::WaiveApproval,
+        Self::ChangeRoles,
+        Self::ResetName,
+        Self::ResetDescription,
+        Self::ResetPfp,
+        Self::CreateRole,
+        Self::EditRole,
+        Self::DeleteRole,
+    ];
+
+    pub fn from_str(s: &str) -> Result<Self, ()> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or(())
+    }
+
+    // Only listed for those who can view `Held`, a shadow-ban
+    // the user can read about in the log is no shadow-ban
+    pub fn is_held(&self) -> bool {
+        matches!(self, Self::ShadowBanUser | Self::LiftShadowBan)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DeletePost => "DeletePost",
+            Self::DeleteComment => "DeleteComment",
+            Self::RestorePost => "RestorePost",
+            Self::RestoreComment => "RestoreComment",
+            Self::ResolveReports => "ResolveReports",
+            Self::DismissReports => "DismissReports",
+            Self::ApprovePost => "ApprovePost",
+            Self::BanUser => "BanUser",
+            Self::LiftBan => "LiftBan",
+            Self::ShadowBanUser => "ShadowBanUser",
+            Self::LiftShadowBan => "LiftShadowBan",
+            Self::RequireApproval => "RequireApproval",
+            Self::WaiveApproval => "WaiveApproval",
+            Self::ChangeRoles => "ChangeRoles",
+            Self::ResetName => "ResetName",
+            Self::ResetDescription => "ResetDescription",
+            Self::ResetPfp => "ResetPfp",
+            Self::CreateRole => "CreateRole",
+            Self::EditRole => "EditRole",
+            Self::DeleteRole => "DeleteRole",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::DeletePost => "Deleted post",
+            Self::DeleteComment => "Deleted comment",
+            Self::RestorePost => "Restored post",
+            Self::RestoreComment => "Restored comment",
+            Self::ResolveReports => "Resolved reports",
+            Self::DismissReports => "Dismissed reports",
+            Self::ApprovePost => "Approved post",
+            Self::BanUser => "Banned user",
+            Self::LiftBan => "Lifted ban",
+            Self::ShadowBanUser => "Shadow-banned user",
+            Self::LiftShadowBan => "Lifted shadow-ban",
+            Self::RequireApproval => "Required approval",
+            Self::WaiveApproval => "Stopped requiring approval",
+            Self::ChangeRoles => "Changed roles",
+            Self::ResetName => "Reset name",
+            Self::ResetDescription => "Reset description",
+            Self::ResetPfp => "Reset profile picture",
+            Self::CreateRole => "Created role",
+            Self::EditRole => "Edited role",
+            Self::DeleteRole => "Deleted role",
+        }
+    }
+}
+
+impl TargetType {
+    pub const ALL: [Self; 5] = [Self::Post, Self::Comment, Self::User, Self::Tag, Self::Role];
+
+    pub fn from_str(s: &str) -> Result<Self, ()> {
+        Self::ALL
+            .into_iter()
+            .find(|target_type| target_type.as_str() == s)
+            .ok_or(())
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Post => "Post",
+            Self::Comment => "Comment",
+            Self::User => "User",
+            Self::Tag => "Tag",
+            Self::Role => "Role",
+        }
+    }
+}
+
+// What a handler records, `target_label` keeps the log readable
+// after the target itself is gone
+#[derive(Debug)]
+pub struct NewModAction<'a> {
+    pub actor_user_id: Id,
+    pub kind: Kind,
+    pub target_type: TargetType,
+    pub target_id: Id,
+    pub target_label: &'a str,
+    pub reason: &'a Reason,
+    pub details: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModAction {
+    pub id: Id,
+    pub actor: Option<UserPreview>,
+    pub action: &'static str,
+    pub target_type: &'static str,
+    pub target_id: Id,
+    pub target_label: String,
+    pub target_url: Option<String>,
+    pub reason: String,
+    pub details: String,
+    pub created_at: String,
+}
+
+impl ModAction {
+    pub fn from_raw(raw: RawModAction) -> Self {
+        let kind = Kind::from_str(&raw.action).ok();
+        let target_type = TargetType::from_str(&raw.target_type).ok();
+
+        let target_url = match target_type {
+            Some(TargetType::Post) => Some(format!("/post/{}", raw.target_id)),
+            Some(TargetType::User) => Some(format!("/user/{}", raw.target_label)),
+            Some(TargetType::Tag) => Some(format!("/tag/{}", raw.target_label)),
+            Some(TargetType::Comment) => raw.target_post_id.map(|post_id| {
+                format!(
+                    "/post/{post_id}?start_id={}#{}",
+                    raw.target_id, raw.target_id
+                )
+            }),
+            Some(TargetType::Role) | None => None,
+        };
+
+        Self {
+            id: raw.id,
+            actor: raw
+                .actor_user_id
+                .zip(raw.actor_username)
+                .map(|(id, username)| UserPreview {
+                    id,
+                    username,
+                    mini_pfp: Pfp::from_variants(raw.actor_mini_pfp, default_mini_pfp()),
+                }),
+            action: kind.map(|kind| kind.label()).unwrap_or("Unknown action"),
+            target_type: target_type
+                .map(|target_type| target_type.as_str())
+                .unwrap_or("Unknown"),
+            target_id: raw.target_id,
+            target_label: raw.target_label,
+            target_url,
+            reason: raw.reason,
+            details: raw.details,
+            created_at: format!("{}", raw.created_at.format("%B %d · %Y %H:%M")),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RawModAction {
+    pub id: Id,
+    pub actor_user_id: Option<Id>,
+    pub actor_username: Option<String>,
+    pub actor_mini_pfp: Option<String>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Id,
+    pub target_label: String,
+    // Comments are linked through their post, gone once the comment is purged
+    pub target_post_id: Option<Id>,
+    pub reason: String,
+    pub details: String,
+    pub created_at: NaiveDateTime,
+}
@@ -0,0 +1,23 @@
+pub const REASON_MAX_CHAR_COUNT: usize = 512;
+
+// Free form explanation of a moderation action, may be empty
+#[derive(Debug, Default)]
+pub struct Reason(String);
+
+impl AsRef<str> for Reason {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Reason {
+    pub fn parse(reason: impl Into<String>) -> Result<Self, ()> {
+        let reason = reason.into().trim().to_owned();
+
+        if reason.chars().count() > REASON_MAX_CHAR_COUNT {
+            return Err(());
+        }
+
+        Ok(Self(reason))
+    }
+}
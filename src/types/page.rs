@@ -1,7 +1,8 @@
+use crate::config::PageConfig;
 use serde::Deserialize;
 
 pub const MIN_LIMIT: u64 = 1;
-pub const MAX_LIMIT: u64 = 100;
+pub const DEFAULT_MAX_LIMIT: u64 = 100;
 
 pub const DEFAULT_LIMIT: u64 = 40;
 
@@ -34,8 +35,8 @@ pub fn clamp(n: u64, min: u64, max: u64) -> u64 {
     std::cmp::max(min, std::cmp::min(n, max))
 }
 
-pub fn clamp_limit(limit: impl Into<Option<u64>>) -> u64 {
-    let limit = limit.into().unwrap_or(DEFAULT_LIMIT);
+pub fn clamp_limit(limit: impl Into<Option<u64>>, config: &PageConfig) -> u64 {
+    let limit = limit.into().unwrap_or(config.default_limit);
 
-    clamp(limit, MIN_LIMIT, MAX_LIMIT)
+    clamp(limit, MIN_LIMIT, config.max_limit)
 }
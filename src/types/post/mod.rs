@@ -1,6 +1,8 @@
-use super::default_mini_pfp;
 use super::id::Id;
-use super::user::Preview as UserPreview;
+use super::user::{pfp::Pfp, Preview as UserPreview};
+use super::{default_mini_pfp, REMOVED_PLACEHOLDER};
+use crate::config::Config;
+use crate::policy::{self, Action, Authored, Resource};
 use crate::session::User;
 use chrono::NaiveDateTime;
 use serde::Serialize;
@@ -18,6 +20,9 @@ pub struct Preview {
     pub tags: Vec<String>,
     pub posted_by: Option<UserPreview>,
     pub posted_at: String,
+
+    // Only ever listed for the author
+    pub is_pending: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -31,10 +36,23 @@ pub struct Post {
     pub posted_at: String,
     pub modified_at: Option<String>,
 
+    pub is_deleted: bool,
+    pub is_pending: bool,
+    // Only set for those who can view held posts, the author must not find out
+    pub is_shadowed: bool,
+    // Held posts are only visible to their author and moderators
+    pub is_visible: bool,
+    // Only counted for those who can resolve the reports
+    pub open_report_count: i64,
+
     pub is_commentable: bool,
     pub is_editable: bool,
     pub is_anonymisable: bool,
     pub is_deletable: bool,
+    pub is_restorable: bool,
+    pub is_reportable: bool,
+    pub is_resolvable: bool,
+    pub is_approvable: bool,
 }
 
 impl Post {
@@ -42,50 +60,76 @@ impl Post {
         raw: RawPost,
         tags: Vec<String>,
         user: impl Into<Option<&'a User>>,
+        config: &Config,
     ) -> Self {
         let user = user.into();
 
+        let authored = Authored {
+            posted_by_user_id: raw.posted_by_user_id,
+            posted_at: raw.posted_at,
+        };
+        let can = |action, resource| {
+            user.map(|user| policy::can(user, action, resource, config))
+                .unwrap_or(false)
+        };
+
+        let is_deleted = raw.deleted_at.is_some();
+        let is_author = user
+            .zip(raw.posted_by_user_id)
+            .map(|(user, posted_by_user_id)| user.id == posted_by_user_id)
+            .unwrap_or(false);
+        let can_view_held = can(Action::View, Resource::Held);
+        let is_resolvable = can(Action::Resolve, Resource::Post(authored));
+        let is_restorable = is_deleted && can(Action::Restore, Resource::Post(authored));
+
+        // Only those who can restore a post get to see what was removed
+        let is_hidden = is_deleted && !is_restorable;
+        let unless_hidden = |s: String| if is_hidden { String::new() } else { s };
+
         Self {
             id: raw.id,
-            title: raw.title,
-            description: raw.description,
-            tags,
-            content: raw.content,
+            title: if is_hidden {
+                REMOVED_PLACEHOLDER.to_owned()
+            } else {
+                raw.title
+            },
+            description: unless_hidden(raw.description),
+            tags: if is_hidden { Vec::new() } else { tags },
+            content: unless_hidden(raw.content),
             posted_at: format_posted_at(raw.posted_at),
-            modified_at: raw.modified_at.map(format_posted_at),
+            modified_at: raw.modified_at.filter(|_| !is_hidden).map(format_posted_at),
             posted_by: raw
                 .posted_by_user_id
                 .zip(raw.posted_by_username)
+                .filter(|_| !is_hidden)
                 .map(|(id, username)| UserPreview {
                     id,
                     username,
-                    mini_pfp: raw.posted_by_mini_pfp.unwrap_or(default_mini_pfp()),
+                    mini_pfp: Pfp::from_variants(raw.posted_by_mini_pfp, default_mini_pfp()),
                 }),
 
-            is_commentable: user.map(|user| user.role.can_comment()).unwrap_or(false),
-            // TODO: move out the functions and reuse them in API
-            is_editable: user
-                .map(|user| {
-                    let hours_since_posted_at =
-                        (chrono::Utc::now().naive_utc() - raw.posted_at).num_hours();
-
-                    // TODO: Make edit time window configurable
-                    raw.posted_by_user_id
-                        .map(|id| user.id == id && user.role.can_edit_posts())
-                        .unwrap_or(false)
-                        && hours_since_posted_at < 2
-                })
-                .unwrap_or(false),
-            is_anonymisable: user
-                .map(|user| {
-                    raw.posted_by_user_id
-                        .map(|id| user.id == id && user.role.can_anonymise_posts())
-                        .unwrap_or(false)
-                })
-                .unwrap_or(false),
-            is_deletable: user
-                .map(|user| user.role.can_delete_posts())
-                .unwrap_or(false),
+            is_deleted,
+            is_pending: raw.is_pending,
+            is_shadowed: raw.is_shadowed && can_view_held,
+            is_visible: !(raw.is_pending || raw.is_shadowed) || is_author || can_view_held,
+            open_report_count: if is_resolvable {
+                raw.open_report_count
+            } else {
+                0
+            },
+
+            is_commentable: !is_deleted
+                && !raw.is_pending
+                && can(Action::Create, Resource::Comments),
+            is_editable: !is_deleted && can(Action::Edit, Resource::Post(authored)),
+            is_anonymisable: !is_deleted && can(Action::Anonymise, Resource::Post(authored)),
+            is_deletable: !is_deleted && can(Action::Delete, Resource::Post(authored)),
+            is_restorable,
+            is_reportable: !is_deleted && can(Action::Create, Resource::Reports),
+            is_resolvable,
+            is_approvable: raw.is_pending
+                && !is_deleted
+                && can(Action::Approve, Resource::Post(authored)),
         }
     }
 }
@@ -103,9 +147,11 @@ impl Preview {
                 .map(|(id, username)| UserPreview {
                     id,
                     username,
-                    mini_pfp: raw.posted_by_mini_pfp.unwrap_or(default_mini_pfp()),
+                    mini_pfp: Pfp::from_variants(raw.posted_by_mini_pfp, default_mini_pfp()),
                 }),
             posted_at: format_posted_at(raw.posted_at),
+
+            is_pending: raw.is_pending,
         }
     }
 }
@@ -121,6 +167,10 @@ pub struct RawPost {
     pub posted_by_mini_pfp: Option<String>,
     pub posted_at: NaiveDateTime,
     pub modified_at: Option<NaiveDateTime>,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub is_pending: bool,
+    pub is_shadowed: bool,
+    pub open_report_count: i64,
 }
 
 #[derive(Debug)]
@@ -132,6 +182,7 @@ pub struct RawPreview {
     pub posted_by_username: Option<String>,
     pub posted_by_mini_pfp: Option<String>,
     pub posted_at: NaiveDateTime,
+    pub is_pending: bool,
 }
 
 pub fn format_posted_at(posted_at: NaiveDateTime) -> String {
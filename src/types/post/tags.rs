@@ -2,11 +2,14 @@ use lazy_static::lazy_static;
 use regex::Regex;
 use std::collections::HashSet;
 
-pub const TAGS_MAX_CHAR_COUNT: usize = TAG_MAX_CHAR_COUNT * TAG_MAX_COUNT + TAG_MAX_COUNT - 1;
 pub const TAG_MAX_CHAR_COUNT: usize = 64;
 
 pub const TAG_MIN_COUNT: usize = 1;
-pub const TAG_MAX_COUNT: usize = 10;
+pub const DEFAULT_TAG_MAX_COUNT: usize = 10;
+
+pub fn tags_max_char_count(max_count: usize) -> usize {
+    TAG_MAX_CHAR_COUNT * max_count + max_count - 1
+}
 
 #[derive(Debug, Copy, Clone)]
 pub enum Error {
@@ -25,7 +28,7 @@ impl AsRef<Vec<String>> for Tags {
 }
 
 impl Tags {
-    pub fn parse(tags: impl Into<String>) -> Result<Self, Error> {
+    pub fn parse(tags: impl Into<String>, max_count: usize) -> Result<Self, Error> {
         let tags = tags.into();
 
         let tags = tags.split_whitespace();
@@ -36,7 +39,7 @@ impl Tags {
             return Err(Error::NotEnoughTags);
         }
 
-        if tag_count > TAG_MAX_COUNT {
+        if tag_count > max_count {
             return Err(Error::TooManyTags);
         }
 
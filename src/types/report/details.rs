@@ -0,0 +1,23 @@
+pub const DETAILS_MAX_CHAR_COUNT: usize = 512;
+
+// What the reporter has to add to the category, may be empty
+#[derive(Debug)]
+pub struct Details(String);
+
+impl AsRef<str> for Details {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Details {
+    pub fn parse(details: impl Into<String>) -> Result<Self, ()> {
+        let details = details.into().trim().to_owned();
+
+        if details.chars().count() > DETAILS_MAX_CHAR_COUNT {
+            return Err(());
+        }
+
+        Ok(Self(details))
+    }
+}
@@ -0,0 +1,131 @@
+use super::default_mini_pfp;
+use super::id::Id;
+use super::post::format_posted_at;
+use super::user::{pfp::Pfp, Preview as UserPreview};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+pub mod details;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Category {
+    Spam,
+    Abuse,
+    OffTopic,
+    Illegal,
+    Other,
+}
+
+// For the category `<select>`
+#[derive(Debug, Serialize)]
+pub struct CategoryOption {
+    pub value: &'static str,
+    pub label: &'static str,
+}
+
+// What a moderator did about the reports on an item
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    Resolved,
+    Dismissed,
+}
+
+impl Category {
+    pub const ALL: [Self; 5] = [
+        Self::Spam,
+        Self::Abuse,
+        Self::OffTopic,
+        Self::Illegal,
+        Self::Other,
+    ];
+
+    pub fn from_str(s: &str) -> Result<Self, ()> {
+        Self::ALL
+            .into_iter()
+            .find(|category| category.as_str() == s)
+            .ok_or(())
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Spam => "Spam",
+            Self::Abuse => "Abuse",
+            Self::OffTopic => "OffTopic",
+            Self::Illegal => "Illegal",
+            Self::Other => "Other",
+        }
+    }
+
+    pub fn options() -> Vec<CategoryOption> {
+        Self::ALL
+            .into_iter()
+            .map(|category| CategoryOption {
+                value: category.as_str(),
+                label: category.label(),
+            })
+            .collect()
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Spam => "Spam",
+            Self::Abuse => "Harassment or abuse",
+            Self::OffTopic => "Off-topic",
+            Self::Illegal => "Illegal content",
+            Self::Other => "Something else",
+        }
+    }
+}
+
+impl Resolution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Resolved => "Resolved",
+            Self::Dismissed => "Dismissed",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub id: Id,
+    pub category: &'static str,
+    pub details: String,
+    pub reported_by: Option<UserPreview>,
+    pub reported_at: String,
+    // Filed by the spam filter rather than a user
+    pub is_automatic: bool,
+}
+
+impl Report {
+    pub fn from_raw(raw: RawReport) -> Self {
+        Self {
+            id: raw.id,
+            category: Category::from_str(&raw.category)
+                .map(|category| category.label())
+                .unwrap_or("Unknown"),
+            details: raw.details,
+            reported_by: raw.reported_by_user_id.zip(raw.reported_by_username).map(
+                |(id, username)| UserPreview {
+                    id,
+                    username,
+                    mini_pfp: Pfp::from_variants(raw.reported_by_mini_pfp, default_mini_pfp()),
+                },
+            ),
+            reported_at: format_posted_at(raw.reported_at),
+            is_automatic: raw.is_automatic,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RawReport {
+    pub id: Id,
+    pub category: String,
+    pub details: String,
+    pub reported_by_user_id: Option<Id>,
+    pub reported_by_username: Option<String>,
+    pub reported_by_mini_pfp: Option<String>,
+    pub reported_at: NaiveDateTime,
+    pub is_automatic: bool,
+}
@@ -0,0 +1,271 @@
+use super::default_mini_pfp;
+use super::id::Id;
+use super::user::{pfp::Pfp, Preview as UserPreview};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+
+// Unchanged lines kept around each change, longer unchanged stretches are cut
+pub const CONTEXT_LINE_COUNT: usize = 3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Revision {
+    pub id: Id,
+    // Counting from 1 for the oldest version
+    pub number: usize,
+    pub edited_by: Option<UserPreview>,
+    pub created_at: String,
+    // Compared to the version before, everything is inserted for the first one
+    pub lines: Vec<Line>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Line {
+    pub kind: LineKind,
+    pub text: String,
+}
+
+#[derive(Debug, Copy, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineKind {
+    Equal,
+    Insert,
+    Delete,
+    // Stands in for unchanged lines that were cut
+    Skip,
+}
+
+#[derive(Debug)]
+pub struct RawRevision {
+    pub id: Id,
+    // Posts put their title, description and content together so all of them get diffed
+    pub text: String,
+    pub edited_by_user_id: Option<Id>,
+    pub edited_by_username: Option<String>,
+    pub edited_by_mini_pfp: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl Revision {
+    // Takes the revisions oldest first and returns them newest first.
+    // Editors are left out where they'd give away who wrote anonymised content
+    pub fn history(raw_revisions: Vec<RawRevision>, are_editors_visible: bool) -> Vec<Self> {
+        let mut previous_text = String::new();
+
+        let mut revisions = raw_revisions
+            .into_iter()
+            .enumerate()
+            .map(|(i, raw)| {
+                let lines = diff(&previous_text, &raw.text);
+
+                previous_text = raw.text;
+
+                Self {
+                    id: raw.id,
+                    number: i + 1,
+                    edited_by: raw
+                        .edited_by_user_id
+                        .zip(raw.edited_by_username)
+                        .filter(|_| are_editors_visible)
+                        .map(|(id, username)| UserPreview {
+                            id,
+                            username,
+                            mini_pfp: Pfp::from_variants(
+                                raw.edited_by_mini_pfp,
+                                default_mini_pfp(),
+                            ),
+                        }),
+                    created_at: format_created_at(raw.created_at),
+                    lines,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        revisions.reverse();
+
+        revisions
+    }
+}
+
+pub fn diff(old: &str, new: &str) -> Vec<Line> {
+    let diff = TextDiff::from_lines(old, new);
+
+    let mut lines = Vec::new();
+
+    for (i, group) in diff.grouped_ops(CONTEXT_LINE_COUNT).iter().enumerate() {
+        if i > 0 {
+            lines.push(Line {
+                kind: LineKind::Skip,
+                text: String::new(),
+            });
+        }
+
+        for op in group {
+            for change in diff.iter_changes(op) {
+                let kind = match change.tag() {
+                    ChangeTag::Equal => LineKind::Equal,
+                    ChangeTag::Insert => LineKind::Insert,
+                    ChangeTag::Delete => LineKind::Delete,
+                };
+
+                lines.push(Line {
+                    kind,
+                    text: change.value().trim_end_matches('\n').to_owned(),
+                });
+            }
+        }
+    }
+
+    lines
+}
+
+fn format_created_at(created_at: NaiveDateTime) -> String {
+    format!("{}", created_at.format("%B %d · %Y %H:%M"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(id: Id, text: &str, edited_by: &str) -> RawRevision {
+        RawRevision {
+            id,
+            text: text.to_owned(),
+            edited_by_user_id: Some(id * 10),
+            edited_by_username: Some(edited_by.to_owned()),
+            edited_by_mini_pfp: None,
+            created_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+
+    // `+`, `-` or ` ` before the text, `...` for cut lines
+    fn show(lines: &[Line]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| match line.kind {
+                LineKind::Equal => format!(" {}", line.text),
+                LineKind::Insert => format!("+{}", line.text),
+                LineKind::Delete => format!("-{}", line.text),
+                LineKind::Skip => "...".to_owned(),
+            })
+            .collect()
+    }
+
+    fn numbered_lines(count: usize) -> Vec<String> {
+        (1..=count).map(|i| i.to_string()).collect()
+    }
+
+    #[test]
+    fn first_version_is_all_inserted() {
+        assert_eq!(show(&diff("", "one\ntwo\n")), ["+one", "+two"]);
+    }
+
+    #[test]
+    fn unchanged_text_has_no_lines() {
+        assert!(diff("one\ntwo\n", "one\ntwo\n").is_empty());
+    }
+
+    #[test]
+    fn keeps_context_around_a_change() {
+        let old = numbered_lines(10);
+        let mut new = old.clone();
+        new[4] = "five".to_owned();
+
+        assert_eq!(
+            show(&diff(&old.join("\n"), &new.join("\n"))),
+            [" 2", " 3", " 4", "-5", "+five", " 6", " 7", " 8"]
+        );
+    }
+
+    #[test]
+    fn cuts_long_unchanged_stretches() {
+        let old = numbered_lines(20);
+        let mut new = old.clone();
+        new[1] = "two".to_owned();
+        new[17] = "eighteen".to_owned();
+
+        assert_eq!(
+            show(&diff(&old.join("\n"), &new.join("\n"))),
+            [
+                " 1",
+                "-2",
+                "+two",
+                " 3",
+                " 4",
+                " 5",
+                "...",
+                " 15",
+                " 16",
+                " 17",
+                "-18",
+                "+eighteen",
+                " 19",
+                " 20"
+            ]
+        );
+    }
+
+    #[test]
+    fn close_changes_share_their_context() {
+        let old = numbered_lines(20);
+        let mut new = old.clone();
+        new[1] = "two".to_owned();
+        new[7] = "eight".to_owned();
+
+        let lines = show(&diff(&old.join("\n"), &new.join("\n")));
+
+        assert!(!lines.contains(&"...".to_owned()));
+        assert_eq!(lines.first().map(String::as_str), Some(" 1"));
+        assert_eq!(lines.last().map(String::as_str), Some(" 11"));
+    }
+
+    #[test]
+    fn history_is_newest_first() {
+        let history = Revision::history(
+            vec![
+                raw(1, "one\n", "first"),
+                raw(2, "one\ntwo\n", "second"),
+                raw(3, "two\n", "third"),
+            ],
+            true,
+        );
+
+        let ids = history
+            .iter()
+            .map(|revision| revision.id)
+            .collect::<Vec<_>>();
+        let numbers = history
+            .iter()
+            .map(|revision| revision.number)
+            .collect::<Vec<_>>();
+
+        assert_eq!(ids, [3, 2, 1]);
+        assert_eq!(numbers, [3, 2, 1]);
+
+        // Each one is compared to the version before it
+        assert_eq!(show(&history[0].lines), ["-one", " two"]);
+        assert_eq!(show(&history[1].lines), [" one", "+two"]);
+        assert_eq!(show(&history[2].lines), ["+one"]);
+    }
+
+    #[test]
+    fn hides_editors() {
+        let raw_revisions = || vec![raw(1, "one\n", "first"), raw(2, "two\n", "second")];
+
+        let visible = Revision::history(raw_revisions(), true);
+        let hidden = Revision::history(raw_revisions(), false);
+
+        let editors = visible
+            .iter()
+            .map(|revision| {
+                revision
+                    .edited_by
+                    .as_ref()
+                    .map(|user| user.username.as_str())
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(editors, [Some("second"), Some("first")]);
+        assert!(hidden.iter().all(|revision| revision.edited_by.is_none()));
+    }
+}
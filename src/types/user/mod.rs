@@ -1,4 +1,6 @@
 use super::{default_pfp, id::Id, post::format_posted_at};
+use crate::config::Config;
+use crate::policy::{self, Account, Action, Resource};
 use crate::session::User as AuthUser;
 use chrono::NaiveDateTime;
 use serde::Serialize;
@@ -10,25 +12,27 @@ pub mod hashed_password;
 pub mod name;
 pub mod password;
 pub mod password_pair;
+pub mod pfp;
 pub mod session;
 pub mod username;
 
 pub mod role;
-use role::Role;
+use pfp::Pfp;
+use role::{Permissions, Role};
 
 #[derive(Debug, Clone, Serialize)]
 pub struct Preview {
     pub id: Id,
     pub username: String,
-    pub mini_pfp: String,
+    pub mini_pfp: Pfp,
 }
 
 #[derive(Debug, Serialize)]
 pub struct User {
     pub id: Id,
     pub username: String,
-    pub role: Role,
-    pub pfp: String,
+    pub roles: Vec<String>,
+    pub pfp: Pfp,
     pub name: String,
     pub description: String,
     pub joined_at: String,
@@ -39,37 +43,50 @@ pub struct User {
 }
 
 impl User {
-    pub fn from_raw<'a>(raw: RawUser, user: impl Into<Option<&'a AuthUser>>) -> Self {
+    pub fn from_raw<'a>(
+        raw: RawUser,
+        roles: Vec<Role>,
+        user: impl Into<Option<&'a AuthUser>>,
+        config: &Config,
+    ) -> Self {
         let user = user.into();
 
-        // TODO: Error handling?
-        let role = Role::from_str(&raw.role).unwrap();
+        let account = Account {
+            id: raw.id,
+            permissions: Permissions::of(&roles),
+        };
+        let can = |action| {
+            user.map(|user| policy::can(user, action, Resource::User(account), config))
+                .unwrap_or(false)
+        };
 
         Self {
             id: raw.id,
             username: raw.username,
-            role,
-            pfp: raw.pfp.unwrap_or(default_pfp()),
+            roles: roles.into_iter().map(|role| role.name).collect(),
+            pfp: Pfp::from_variants(raw.pfp, default_pfp()),
             name: raw.name,
             description: raw.description,
             joined_at: format_posted_at(raw.joined_at),
 
-            is_editable: user
-                .map(|user| user.role.can_edit_self() && user.id == raw.id)
-                .unwrap_or(false),
+            is_editable: can(Action::Edit),
             is_sign_outable: user.map(|user| user.id == raw.id).unwrap_or(false),
-            is_adminable: user
-                .map(|user| user.role.can_admin() && !role.can_admin())
-                .unwrap_or(false),
+            is_adminable: can(Action::Admin),
         }
     }
 }
 
+// Moderation flags that are never shown to the user themselves
+#[derive(Debug, Copy, Clone, Serialize)]
+pub struct Restrictions {
+    pub is_shadow_banned: bool,
+    pub requires_approval: bool,
+}
+
 #[derive(Debug)]
 pub struct RawUser {
     pub id: Id,
     pub username: String,
-    pub role: String,
     pub pfp: Option<String>,
     pub name: String,
     pub description: String,
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+pub const MINI: &str = "mini";
+pub const NORMAL: &str = "normal";
+
+pub const SIZE_NAME_MAX_CHAR_COUNT: usize = 32;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Size {
+    pub name: String,
+    pub width: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Pfp {
+    pub src: String,
+    pub srcset: String,
+}
+
+impl Pfp {
+    // `variants` is a comma separated list of `density:filename`
+    // pairs as aggregated by the `user_pfps` view
+    pub fn from_variants(variants: Option<String>, default: String) -> Self {
+        let mut variants = variants
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|variant| {
+                let (density, filename) = variant.split_once(':')?;
+                let density = density.parse::<u32>().ok()?;
+
+                Some((density, filename.to_owned()))
+            })
+            .collect::<Vec<_>>();
+
+        if variants.is_empty() {
+            variants.push((1, default));
+        }
+
+        variants.sort_by_key(|(density, _)| *density);
+
+        let src = format!("/files/{}", variants[0].1);
+
+        let srcset = variants
+            .iter()
+            .map(|(density, filename)| format!("/files/{filename} {density}x"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        Self { src, srcset }
+    }
+}
+
+pub fn default_sizes() -> Vec<Size> {
+    vec![
+        Size {
+            name: MINI.to_owned(),
+            width: 32,
+        },
+        Size {
+            name: NORMAL.to_owned(),
+            width: 128,
+        },
+    ]
+}
+
+pub fn default_densities() -> Vec<u32> {
+    vec![1, 2]
+}
+
+// Parses `name:width` pairs, e.g. `mini:32,normal:128`
+pub fn parse_sizes(sizes: &str) -> Result<Vec<Size>, ()> {
+    let sizes = sizes
+        .split(',')
+        .map(|size| {
+            let (name, width) = size.trim().split_once(':').ok_or(())?;
+            let width = width.parse::<u32>().map_err(|_| ())?;
+
+            Ok(Size {
+                name: name.to_owned(),
+                width,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    validate_sizes(&sizes)?;
+
+    Ok(sizes)
+}
+
+// Parses pixel densities, e.g. `1,2`
+pub fn parse_densities(densities: &str) -> Result<Vec<u32>, ()> {
+    let densities = densities
+        .split(',')
+        .map(|density| density.trim().parse::<u32>().map_err(|_| ()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    validate_densities(&densities)?;
+
+    Ok(densities)
+}
+
+pub fn validate_sizes(sizes: &[Size]) -> Result<(), ()> {
+    for (i, size) in sizes.iter().enumerate() {
+        if size.name.is_empty() || size.name.chars().count() > SIZE_NAME_MAX_CHAR_COUNT {
+            return Err(());
+        }
+
+        if size.width == 0 {
+            return Err(());
+        }
+
+        if sizes[..i].iter().any(|other| other.name == size.name) {
+            return Err(());
+        }
+    }
+
+    // Templates always render these two
+    for required in [MINI, NORMAL] {
+        if !sizes.iter().any(|size| size.name == required) {
+            return Err(());
+        }
+    }
+
+    Ok(())
+}
+
+pub fn validate_densities(densities: &[u32]) -> Result<(), ()> {
+    if !densities.contains(&1) || densities.contains(&0) {
+        return Err(());
+    }
+
+    for (i, density) in densities.iter().enumerate() {
+        if densities[..i].contains(density) {
+            return Err(());
+        }
+    }
+
+    Ok(())
+}
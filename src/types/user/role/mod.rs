@@ -0,0 +1,132 @@
+use crate::types::id::Id;
+use serde::Serialize;
+
+pub mod name;
+
+// Roles the code relies on, they are seeded by `db/scheme.sql`
+// and can't be renamed or deleted
+pub const ADMIN: &str = "Admin";
+pub const MOD: &str = "Mod";
+pub const MEMBER: &str = "Member";
+pub const BANNED: &str = "Banned";
+pub const UNVERIFIED: &str = "Unverified";
+
+pub const BUILTIN_ROLES: [&str; 5] = [ADMIN, MOD, MEMBER, BANNED, UNVERIFIED];
+
+// Roles that exist to take permissions away, granting them any
+// would undo every ban or the email check
+pub const FIXED_ROLES: [&str; 2] = [BANNED, UNVERIFIED];
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Permissions {
+    pub can_post: bool,
+    pub can_comment: bool,
+    pub can_reply: bool,
+    pub can_edit_posts: bool,
+    pub can_edit_comments: bool,
+    pub can_edit_self: bool,
+    pub can_anonymise_posts: bool,
+    pub can_anonymise_comments: bool,
+    pub can_delete_posts: bool,
+    pub can_delete_comments: bool,
+    pub can_admin: bool,
+}
+
+impl Permissions {
+    // A user with several roles can do anything any of them allows
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            can_post: self.can_post || other.can_post,
+            can_comment: self.can_comment || other.can_comment,
+            can_reply: self.can_reply || other.can_reply,
+            can_edit_posts: self.can_edit_posts || other.can_edit_posts,
+            can_edit_comments: self.can_edit_comments || other.can_edit_comments,
+            can_edit_self: self.can_edit_self || other.can_edit_self,
+            can_anonymise_posts: self.can_anonymise_posts || other.can_anonymise_posts,
+            can_anonymise_comments: self.can_anonymise_comments || other.can_anonymise_comments,
+            can_delete_posts: self.can_delete_posts || other.can_delete_posts,
+            can_delete_comments: self.can_delete_comments || other.can_delete_comments,
+            can_admin: self.can_admin || other.can_admin,
+        }
+    }
+
+    // Columns of `roles` that are set, e.g. for the moderation log
+    pub fn names(&self) -> Vec<&'static str> {
+        [
+            ("can_post", self.can_post),
+            ("can_comment", self.can_comment),
+            ("can_reply", self.can_reply),
+            ("can_edit_posts", self.can_edit_posts),
+            ("can_edit_comments", self.can_edit_comments),
+            ("can_edit_self", self.can_edit_self),
+            ("can_anonymise_posts", self.can_anonymise_posts),
+            ("can_anonymise_comments", self.can_anonymise_comments),
+            ("can_delete_posts", self.can_delete_posts),
+            ("can_delete_comments", self.can_delete_comments),
+            ("can_admin", self.can_admin),
+        ]
+        .into_iter()
+        .filter(|&(_, is_set)| is_set)
+        .map(|(name, _)| name)
+        .collect()
+    }
+
+    pub fn of<'a>(roles: impl IntoIterator<Item = &'a Role>) -> Self {
+        roles
+            .into_iter()
+            .fold(Self::default(), |permissions, role| {
+                permissions.union(role.permissions)
+            })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Role {
+    pub id: Id,
+    pub name: String,
+    pub permissions: Permissions,
+
+    pub is_builtin: bool,
+    pub has_fixed_permissions: bool,
+}
+
+impl Role {
+    pub fn from_raw(raw: RawRole) -> Self {
+        Self {
+            id: raw.id,
+            is_builtin: BUILTIN_ROLES.contains(&raw.name.as_str()),
+            has_fixed_permissions: FIXED_ROLES.contains(&raw.name.as_str()),
+            name: raw.name,
+            permissions: Permissions {
+                can_post: raw.can_post,
+                can_comment: raw.can_comment,
+                can_reply: raw.can_reply,
+                can_edit_posts: raw.can_edit_posts,
+                can_edit_comments: raw.can_edit_comments,
+                can_edit_self: raw.can_edit_self,
+                can_anonymise_posts: raw.can_anonymise_posts,
+                can_anonymise_comments: raw.can_anonymise_comments,
+                can_delete_posts: raw.can_delete_posts,
+                can_delete_comments: raw.can_delete_comments,
+                can_admin: raw.can_admin,
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RawRole {
+    pub id: Id,
+    pub name: String,
+    pub can_post: bool,
+    pub can_comment: bool,
+    pub can_reply: bool,
+    pub can_edit_posts: bool,
+    pub can_edit_comments: bool,
+    pub can_edit_self: bool,
+    pub can_anonymise_posts: bool,
+    pub can_anonymise_comments: bool,
+    pub can_delete_posts: bool,
+    pub can_delete_comments: bool,
+    pub can_admin: bool,
+}
@@ -0,0 +1,33 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+pub const NAME_MAX_CHAR_COUNT: usize = 64;
+
+#[derive(Debug)]
+pub struct Name(String);
+
+impl AsRef<str> for Name {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Name {
+    pub fn parse(name: impl Into<String>) -> Result<Self, ()> {
+        let name = name.into();
+
+        lazy_static! {
+            static ref REGEX: Regex = {
+                let regex = format!("^[A-Za-z0-9 \\-_]{{1,{}}}$", NAME_MAX_CHAR_COUNT);
+
+                Regex::new(&regex).expect("Failed to compile regex")
+            };
+        }
+
+        if !REGEX.is_match(&name) || name.trim() != name {
+            return Err(());
+        }
+
+        Ok(Self(name))
+    }
+}
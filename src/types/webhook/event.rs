@@ -0,0 +1,130 @@
+use serde::Serialize;
+
+// What a webhook can be subscribed to, sent as `X-Neor-Event`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Event {
+    PostCreated,
+    CommentCreated,
+    PostDeleted,
+    UserBanned,
+    // Sent on demand from the admin page, whatever the webhook is subscribed to
+    Ping,
+}
+
+// For the event checkboxes
+#[derive(Debug, Serialize)]
+pub struct EventOption {
+    pub value: &'static str,
+    pub key: &'static str,
+    pub label: &'static str,
+}
+
+// Stored as a comma separated list, e.g. `post.created,user.banned`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Events(Vec<Event>);
+
+impl Event {
+    // Everything but `Ping`, which can't be subscribed to
+    pub const ALL: [Self; 4] = [
+        Self::PostCreated,
+        Self::CommentCreated,
+        Self::PostDeleted,
+        Self::UserBanned,
+    ];
+
+    pub fn from_str(s: &str) -> Result<Self, ()> {
+        Self::ALL
+            .into_iter()
+            .chain([Self::Ping])
+            .find(|event| event.as_str() == s)
+            .ok_or(())
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PostCreated => "post.created",
+            Self::CommentCreated => "comment.created",
+            Self::PostDeleted => "post.deleted",
+            Self::UserBanned => "user.banned",
+            Self::Ping => "ping",
+        }
+    }
+
+    // Name of the checkbox in the webhook form
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::PostCreated => "event_post_created",
+            Self::CommentCreated => "event_comment_created",
+            Self::PostDeleted => "event_post_deleted",
+            Self::UserBanned => "event_user_banned",
+            Self::Ping => "event_ping",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::PostCreated => "Post created",
+            Self::CommentCreated => "Comment created",
+            Self::PostDeleted => "Post deleted",
+            Self::UserBanned => "User banned",
+            Self::Ping => "Ping",
+        }
+    }
+
+    pub fn options() -> Vec<EventOption> {
+        Self::ALL
+            .into_iter()
+            .map(|event| EventOption {
+                value: event.as_str(),
+                key: event.key(),
+                label: event.label(),
+            })
+            .collect()
+    }
+}
+
+impl Events {
+    pub fn parse(events: impl IntoIterator<Item = Event>) -> Result<Self, ()> {
+        let mut events = events
+            .into_iter()
+            .filter(|event| *event != Event::Ping)
+            .collect::<Vec<_>>();
+
+        events.sort_by_key(|event| Event::ALL.iter().position(|e| e == event));
+        events.dedup();
+
+        if events.is_empty() {
+            return Err(());
+        }
+
+        Ok(Self(events))
+    }
+
+    // Unknown names are skipped, so renaming an event can't break old rows
+    pub fn from_stored(s: &str) -> Self {
+        Self(
+            s.split(',')
+                .filter_map(|event| Event::from_str(event).ok())
+                .collect(),
+        )
+    }
+
+    pub fn contains(&self, event: Event) -> bool {
+        self.0.contains(&event)
+    }
+
+    pub fn as_string(&self) -> String {
+        self.0
+            .iter()
+            .map(Event::as_str)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn labels(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .map(|event| event.label().to_owned())
+            .collect()
+    }
+}
@@ -0,0 +1,150 @@
+use super::id::Id;
+use chrono::NaiveDateTime;
+use rand::RngCore;
+use serde::Serialize;
+
+pub mod event;
+pub mod url;
+use event::Events;
+
+// Outgoing `POST`s to other services on forum events,
+// signed with a per-webhook secret so the receiver can check where they came from
+
+const SECRET_BYTE_COUNT: usize = 32;
+
+// Where a delivery is at, `Pending` ones are picked up by the delivery worker
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Status {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Webhook {
+    pub id: Id,
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<String>,
+    pub tag: Option<String>,
+    pub is_active: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug)]
+pub struct RawWebhook {
+    pub id: Id,
+    pub url: String,
+    pub secret: String,
+    pub events: String,
+    pub tag: Option<String>,
+    pub is_active: bool,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Delivery {
+    pub id: Id,
+    pub webhook_id: Id,
+    pub url: String,
+    pub event: String,
+    pub payload: String,
+    pub status: &'static str,
+    pub attempt_count: u32,
+    pub response_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub created_at: String,
+    pub next_attempt_at: Option<String>,
+    pub finished_at: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct RawDelivery {
+    pub id: Id,
+    pub webhook_id: Id,
+    pub url: String,
+    pub event: String,
+    pub payload: String,
+    pub status: String,
+    pub attempt_count: u32,
+    pub response_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub next_attempt_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+impl Status {
+    pub const ALL: [Self; 3] = [Self::Pending, Self::Succeeded, Self::Failed];
+
+    pub fn from_str(s: &str) -> Result<Self, ()> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or(())
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Pending => "Pending",
+            Self::Succeeded => "Delivered",
+            Self::Failed => "Failed",
+        }
+    }
+}
+
+impl Webhook {
+    pub fn from_raw(raw: RawWebhook) -> Self {
+        Self {
+            id: raw.id,
+            url: raw.url,
+            secret: raw.secret,
+            events: Events::from_stored(&raw.events).labels(),
+            tag: raw.tag,
+            is_active: raw.is_active,
+            created_at: format_webhook_date(raw.created_at),
+        }
+    }
+}
+
+impl Delivery {
+    pub fn from_raw(raw: RawDelivery) -> Self {
+        let status = Status::from_str(&raw.status).unwrap_or(Status::Failed);
+
+        Self {
+            id: raw.id,
+            webhook_id: raw.webhook_id,
+            url: raw.url,
+            event: raw.event,
+            payload: raw.payload,
+            status: status.label(),
+            attempt_count: raw.attempt_count,
+            response_status: raw.response_status,
+            last_error: raw.last_error,
+            created_at: format_webhook_date(raw.created_at),
+            // Only means something while it's still being retried
+            next_attempt_at: (status == Status::Pending)
+                .then(|| format_webhook_date(raw.next_attempt_at)),
+            finished_at: raw.finished_at.map(format_webhook_date),
+        }
+    }
+}
+
+pub fn generate_secret() -> String {
+    let mut bytes = [0; SECRET_BYTE_COUNT];
+    rand::thread_rng().fill_bytes(&mut bytes);
+
+    hex::encode(bytes)
+}
+
+fn format_webhook_date(date: NaiveDateTime) -> String {
+    format!("{} UTC", date.format("%B %d · %Y %H:%M:%S"))
+}
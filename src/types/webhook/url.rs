@@ -0,0 +1,29 @@
+pub const URL_MAX_CHAR_COUNT: usize = 512;
+
+#[derive(Debug)]
+pub struct Url(String);
+
+impl AsRef<str> for Url {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Url {
+    // Plain `http` is allowed so a stand-in on `localhost` can be used for testing
+    pub fn parse(url: impl Into<String>) -> Result<Self, ()> {
+        let url = url.into().trim().to_owned();
+
+        if url.chars().count() > URL_MAX_CHAR_COUNT {
+            return Err(());
+        }
+
+        let parsed = reqwest::Url::parse(&url).map_err(|_| ())?;
+
+        if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+            return Err(());
+        }
+
+        Ok(Self(url))
+    }
+}
@@ -0,0 +1,383 @@
+use crate::session::User;
+use crate::types::id::Id;
+use crate::types::lifetime::Lifetime;
+use crate::types::mod_action::reason::Reason;
+use crate::types::webhook::event::{Event, Events};
+use crate::types::webhook::Status;
+use chrono::{SecondsFormat, Utc};
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::mysql::MySqlConnection;
+
+// Events are queued as `webhook_deliveries` rows on the same connection as the change
+// they describe, so inside its transaction when there is one. `jobs::webhooks` sends them.
+// Nothing is sent for pending or shadowed posts and comments, nobody else can see them
+
+pub const SIGNATURE_HEADER: &str = "X-Neor-Signature";
+pub const EVENT_HEADER: &str = "X-Neor-Event";
+pub const DELIVERY_HEADER: &str = "X-Neor-Delivery";
+
+#[derive(Debug)]
+struct RawPost {
+    id: Id,
+    title: String,
+    description: String,
+    posted_by_user_id: Option<Id>,
+    posted_by_username: Option<String>,
+    is_pending: bool,
+    is_shadowed: bool,
+}
+
+#[derive(Debug)]
+struct RawComment {
+    id: Id,
+    post_id: Id,
+    reply_to_comment_id: Option<Id>,
+    content: String,
+    posted_by_user_id: Option<Id>,
+    posted_by_username: Option<String>,
+    is_shadowed: bool,
+}
+
+#[derive(Debug)]
+struct RawTarget {
+    id: Id,
+    events: String,
+    tag: Option<String>,
+}
+
+pub async fn post_created(
+    conn: &mut MySqlConnection,
+    domain: &str,
+    post_id: Id,
+) -> sqlx::Result<()> {
+    let Some(post) = fetch_post(conn, post_id).await? else {
+        return Ok(());
+    };
+
+    if post.is_pending || post.is_shadowed {
+        return Ok(());
+    }
+
+    let tags = fetch_post_tags(conn, post.id).await?;
+
+    let data = json!({
+        "id": post.id,
+        "title": post.title,
+        "description": post.description,
+        "tags": tags,
+        "url": format!("https://{domain}/post/{}", post.id),
+        "posted_by": user_json(post.posted_by_user_id, post.posted_by_username),
+    });
+
+    enqueue(conn, Event::PostCreated, &tags, data).await
+}
+
+pub async fn comment_created(
+    conn: &mut MySqlConnection,
+    domain: &str,
+    comment_id: Id,
+) -> sqlx::Result<()> {
+    let Some(comment) = fetch_comment(conn, comment_id).await? else {
+        return Ok(());
+    };
+
+    if comment.is_shadowed {
+        return Ok(());
+    }
+
+    let Some(post) = fetch_post(conn, comment.post_id).await? else {
+        return Ok(());
+    };
+
+    let tags = fetch_post_tags(conn, post.id).await?;
+
+    let data = json!({
+        "id": comment.id,
+        "reply_to_comment_id": comment.reply_to_comment_id,
+        "content": comment.content,
+        "url": format!(
+            "https://{domain}/post/{}?start_id={}#{}",
+            post.id, comment.id, comment.id
+        ),
+        "posted_by": user_json(comment.posted_by_user_id, comment.posted_by_username),
+        "post": {
+            "id": post.id,
+            "title": post.title,
+            "tags": tags,
+        },
+    });
+
+    enqueue(conn, Event::CommentCreated, &tags, data).await
+}
+
+pub async fn post_deleted(
+    conn: &mut MySqlConnection,
+    domain: &str,
+    post_id: Id,
+    deleted_by: &User,
+    reason: &Reason,
+) -> sqlx::Result<()> {
+    let Some(post) = fetch_post(conn, post_id).await? else {
+        return Ok(());
+    };
+
+    // It was never announced in the first place
+    if post.is_pending || post.is_shadowed {
+        return Ok(());
+    }
+
+    let tags = fetch_post_tags(conn, post.id).await?;
+
+    let data = json!({
+        "id": post.id,
+        "title": post.title,
+        "tags": tags,
+        "url": format!("https://{domain}/post/{}", post.id),
+        "posted_by": user_json(post.posted_by_user_id, post.posted_by_username),
+        "deleted_by": user_json(Some(deleted_by.id), Some(deleted_by.username.clone())),
+        "reason": reason.as_ref(),
+    });
+
+    enqueue(conn, Event::PostDeleted, &tags, data).await
+}
+
+pub async fn user_banned(
+    conn: &mut MySqlConnection,
+    domain: &str,
+    user_id: Id,
+    username: &str,
+    banned_by: &User,
+    reason: &Reason,
+    duration: Lifetime,
+) -> sqlx::Result<()> {
+    let data = json!({
+        "id": user_id,
+        "username": username,
+        "url": format!("https://{domain}/user/{username}"),
+        "banned_by": user_json(Some(banned_by.id), Some(banned_by.username.clone())),
+        "reason": reason.as_ref(),
+        "days": duration.days(),
+    });
+
+    // Not about any tag, so webhooks limited to one never get it
+    enqueue(conn, Event::UserBanned, &[], data).await
+}
+
+// Queued for one webhook whatever it's subscribed to, to try it out
+pub async fn ping(conn: &mut MySqlConnection, domain: &str, webhook_id: Id) -> sqlx::Result<()> {
+    let data = json!({
+        "webhook_id": webhook_id,
+        "url": format!("https://{domain}"),
+    });
+
+    insert_delivery(conn, webhook_id, Event::Ping, &payload(Event::Ping, data)).await
+}
+
+// `sha256=` and the hex HMAC-SHA256 of the body, keyed with the webhook secret
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+
+    mac.update(body.as_bytes());
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+async fn enqueue(
+    conn: &mut MySqlConnection,
+    event: Event,
+    tags: &[String],
+    data: Value,
+) -> sqlx::Result<()> {
+    let targets = sqlx::query_as!(
+        RawTarget,
+        "
+        SELECT
+            id,
+            events,
+            tag
+        FROM webhooks
+        WHERE
+            is_active = 1
+        "
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let targets = targets.into_iter().filter(|target| {
+        let is_subscribed = Events::from_stored(&target.events).contains(event);
+        let is_tagged = match &target.tag {
+            Some(tag) => tags.contains(tag),
+            None => true,
+        };
+
+        is_subscribed && is_tagged
+    });
+
+    let payload = payload(event, data);
+
+    for target in targets {
+        insert_delivery(conn, target.id, event, &payload).await?;
+    }
+
+    Ok(())
+}
+
+fn payload(event: Event, data: Value) -> String {
+    json!({
+        "event": event.as_str(),
+        "created_at": Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        "data": data,
+    })
+    .to_string()
+}
+
+fn user_json(id: Option<Id>, username: Option<String>) -> Value {
+    match (id, username) {
+        (Some(id), Some(username)) => json!({ "id": id, "username": username }),
+        _ => Value::Null,
+    }
+}
+
+async fn insert_delivery(
+    conn: &mut MySqlConnection,
+    webhook_id: Id,
+    event: Event,
+    payload: &str,
+) -> sqlx::Result<()> {
+    sqlx::query!(
+        "
+        INSERT INTO webhook_deliveries
+        (
+            webhook_id,
+            event,
+            payload,
+            status,
+            attempt_count,
+            response_status,
+            last_error,
+            created_at,
+            next_attempt_at,
+            finished_at
+        )
+        VALUES
+        (
+            ?,
+            ?,
+            ?,
+            ?,
+            0,
+            NULL,
+            NULL,
+            NOW(),
+            NOW(),
+            NULL
+        )
+        ",
+        webhook_id,
+        event.as_str(),
+        payload,
+        Status::Pending.as_str()
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn fetch_post(conn: &mut MySqlConnection, post_id: Id) -> sqlx::Result<Option<RawPost>> {
+    sqlx::query_as!(
+        RawPost,
+        "
+        SELECT
+            posts.id,
+            posts.title,
+            posts.description,
+            users.id AS posted_by_user_id,
+            users.username AS posted_by_username,
+            posts.is_pending AS `is_pending: bool`,
+            posts.is_shadowed AS `is_shadowed: bool`
+        FROM posts
+            LEFT JOIN users ON posts.posted_by_user_id = users.id
+        WHERE
+            posts.id = ?
+        ",
+        post_id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+}
+
+async fn fetch_comment(
+    conn: &mut MySqlConnection,
+    comment_id: Id,
+) -> sqlx::Result<Option<RawComment>> {
+    sqlx::query_as!(
+        RawComment,
+        "
+        SELECT
+            comments.id,
+            comments.post_id,
+            comments.reply_to_comment_id,
+            comments.content,
+            users.id AS posted_by_user_id,
+            users.username AS posted_by_username,
+            comments.is_shadowed AS `is_shadowed: bool`
+        FROM comments
+            LEFT JOIN users ON comments.posted_by_user_id = users.id
+        WHERE
+            comments.id = ?
+        ",
+        comment_id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+}
+
+async fn fetch_post_tags(conn: &mut MySqlConnection, post_id: Id) -> sqlx::Result<Vec<String>> {
+    #[derive(Debug)]
+    struct Tag {
+        pub name: String,
+    }
+
+    sqlx::query_as!(
+        Tag,
+        "
+        SELECT
+            tags.name
+        FROM post_tags
+            JOIN tags on post_tags.tag_id = tags.id
+        WHERE
+            post_tags.post_id = ?
+        ",
+        post_id
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map(|result| result.into_iter().map(|tag| tag.name).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_with_hmac_sha256() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            sign("Jefe", "what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn signature_depends_on_secret_and_body() {
+        let body = r#"{"event":"ping"}"#;
+
+        assert_eq!(sign("secret", body), sign("secret", body));
+        assert_ne!(sign("secret", body), sign("other secret", body));
+        assert_ne!(sign("secret", body), sign("secret", r#"{"event":"pong"}"#));
+    }
+}